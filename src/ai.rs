@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, MAX_SIDE};
+use crate::game::{Game, GameAction};
+
+pub const DEFAULT_DEPTH: u32 = 3;
+
+/// Chance branches less likely than this are scored by the heuristic instead
+/// of being searched further, unless the limits say otherwise.
+pub const DEFAULT_MIN_PROBABILITY: f64 = 0.0001;
+
+const EMPTY_WEIGHT: f64 = 2.7;
+const MONOTONICITY_WEIGHT: f64 = 1.0;
+const SMOOTHNESS_WEIGHT: f64 = 0.1;
+const MAX_TILE_WEIGHT: f64 = 1.0;
+const LOSS_PENALTY: f64 = 1000.0;
+
+// Number of independently locked parts of the transposition table, so
+// threads searching different positions rarely wait on each other.
+const CACHE_SHARDS: usize = 64;
+
+/// The expected value of playing a move, or `None` if the move is illegal.
+#[derive(Clone, Copy, Debug)]
+pub struct Evaluation {
+    pub action: GameAction,
+    pub value: Option<f64>,
+}
+
+/// How much work the search puts into each move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchLimits {
+    /// The most player moves looked ahead.
+    pub depth: u32,
+    /// With a budget, the search deepens one move at a time from a single
+    /// move ahead, and once the budget is spent it stops and keeps the
+    /// deepest search it finished.
+    pub time_budget: Option<Duration>,
+    /// Chance branches less likely than this are scored by the heuristic
+    /// instead of being searched further.
+    pub min_probability: f64,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_DEPTH,
+            time_budget: None,
+            min_probability: DEFAULT_MIN_PROBABILITY,
+        }
+    }
+}
+
+/// How the latest search went.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchReport {
+    /// The moves ahead of the deepest search finished.
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl SearchReport {
+    /// The positions visited per second.
+    pub fn nodes_per_sec(&self) -> f64 {
+        self.nodes as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Expectimax search over player moves and random tile spawns, scoring leaf
+/// positions with a heuristic that rewards empty cells, monotonic rows and
+/// columns, smooth neighbors and a large max tile. The tile spawns after each
+/// root move are searched in parallel and share one transposition table.
+pub struct Searcher {
+    limits: SearchLimits,
+    nodes: AtomicU64,
+    cache: Cache,
+    // When the search in progress runs out of time, and whether it has.
+    deadline: Option<Instant>,
+    timed_out: AtomicBool,
+    report: Option<SearchReport>,
+}
+
+impl Searcher {
+    /// Creates a searcher that looks `depth` player moves ahead.
+    pub fn new(depth: u32) -> Self {
+        Self::with_limits(SearchLimits {
+            depth,
+            ..SearchLimits::default()
+        })
+    }
+
+    /// Creates a searcher that works within the given limits.
+    pub fn with_limits(limits: SearchLimits) -> Self {
+        Self {
+            limits: SearchLimits {
+                depth: limits.depth.max(1),
+                ..limits
+            },
+            nodes: AtomicU64::new(0),
+            cache: Cache::default(),
+            deadline: None,
+            timed_out: AtomicBool::new(false),
+            report: None,
+        }
+    }
+
+    /// Returns how the latest search went, if there was one.
+    pub fn report(&self) -> Option<SearchReport> {
+        self.report
+    }
+
+    /// Returns the number of positions visited so far.
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    pub fn evaluate(&mut self, board: &Board) -> [Evaluation; 4] {
+        self.cache.clear();
+        let start = Instant::now();
+        let nodes = self.nodes();
+        let (evaluations, depth) = match self.limits.time_budget {
+            None => (self.search(board, self.limits.depth), self.limits.depth),
+            Some(budget) => {
+                // A single move ahead is always searched in full, so there is
+                // a move to play however short the budget.
+                let mut deepest = (self.search(board, 1), 1);
+                self.deadline = Some(start + budget);
+                for depth in 2..=self.limits.depth {
+                    if self.out_of_time() {
+                        break;
+                    }
+                    let evaluations = self.search(board, depth);
+                    if self.timed_out.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    deepest = (evaluations, depth);
+                }
+                self.deadline = None;
+                self.timed_out.store(false, Ordering::Relaxed);
+                deepest
+            }
+        };
+        self.report = Some(SearchReport {
+            depth,
+            nodes: self.nodes() - nodes,
+            elapsed: start.elapsed(),
+        });
+        evaluations
+    }
+
+    // Searches every move from a position `depth` player moves ahead.
+    fn search(&self, board: &Board, depth: u32) -> [Evaluation; 4] {
+        let depth = depth - 1;
+        let previews =
+            GameAction::ALL.map(|action| Game::preview(board, action));
+
+        // Every spawn after every legal move is searched as its own task.
+        let mut tasks = Vec::new();
+        for (root, preview) in previews.iter().enumerate() {
+            if let Some((next, _)) = preview
+                && depth > 0
+            {
+                self.visit();
+                tasks.extend(spawns(next, 1.0).map(|spawn| (root, spawn)));
+            }
+        }
+        let values = parallel_map(&tasks, |(_, spawn)| {
+            spawn.weight * self.max(&spawn.board, depth - 1, spawn.probability)
+        });
+
+        let mut totals = [0.0; 4];
+        for ((root, _), value) in tasks.iter().zip(values) {
+            totals[*root] += value;
+        }
+        let mut evaluations = GameAction::ALL.map(|action| Evaluation {
+            action,
+            value: None,
+        });
+        for (i, preview) in previews.into_iter().enumerate() {
+            evaluations[i].value = preview.map(|(next, _)| match depth {
+                0 => self.chance(&next, 0, 1.0),
+                _ => totals[i],
+            });
+        }
+        evaluations
+    }
+
+    /// Returns the move with the highest expected value, or `None` if no move
+    /// is possible.
+    pub fn best_move(&mut self, board: &Board) -> Option<GameAction> {
+        preferred(&self.evaluate(board))
+    }
+
+    /// Follows the best move for `moves` turns, assuming the likeliest tile,
+    /// a 2 in the classic game, spawns in the first empty cell after each
+    /// move.
+    pub fn line(&mut self, board: &Board, moves: usize) -> Vec<GameAction> {
+        let mut board = *board;
+        let tile = likeliest_spawn(&board);
+        let mut line = Vec::new();
+        while line.len() < moves
+            && let Some(action) = self.best_move(&board)
+        {
+            let (next, _) = Game::preview(&board, action)
+                .expect("the best move is always legal");
+            board = next;
+            let first_empty = board.spawn_cells().next();
+            if let Some((row, col)) = first_empty {
+                board
+                    .set_cell(row, col, Some(tile))
+                    .expect("the variant's own tiles always fit its board");
+            }
+            line.push(action);
+        }
+        line
+    }
+
+    fn visit(&self) {
+        self.nodes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // The value of the player's best move from this position.
+    fn max(&self, board: &Board, depth: u32, probability: f64) -> f64 {
+        self.visit();
+        GameAction::ALL
+            .into_iter()
+            .filter_map(|action| Game::preview(board, action))
+            .map(|(next, _)| self.chance(&next, depth, probability))
+            .max_by(f64::total_cmp)
+            .unwrap_or_else(|| heuristic(board) - LOSS_PENALTY)
+    }
+
+    // The expected value over all possible tile spawns on this position.
+    fn chance(&self, board: &Board, depth: u32, probability: f64) -> f64 {
+        self.visit();
+        let probability = pruning_class(probability);
+        if depth == 0
+            || probability < self.limits.min_probability
+            || self.out_of_time()
+        {
+            return heuristic(board);
+        }
+        if let Some(value) = self.cache.get(board, depth, probability) {
+            return value;
+        }
+
+        let value = spawns(board, probability)
+            .map(|spawn| {
+                spawn.weight
+                    * self.max(&spawn.board, depth - 1, spawn.probability)
+            })
+            .sum();
+        // A value cut short by the deadline would spoil later searches.
+        if !self.timed_out.load(Ordering::Relaxed) {
+            self.cache.insert(*board, depth, probability, value);
+        }
+        value
+    }
+
+    // Whether the search has run past its deadline, which gives up on it.
+    fn out_of_time(&self) -> bool {
+        let Some(deadline) = self.deadline else {
+            return false;
+        };
+        if self.timed_out.load(Ordering::Relaxed) {
+            return true;
+        }
+        let late = Instant::now() >= deadline;
+        if late {
+            self.timed_out.store(true, Ordering::Relaxed);
+        }
+        late
+    }
+}
+
+// A tile appearing on a position, weighted by its share of the expected
+// value and carrying the probability of reaching it from the root.
+struct Spawn {
+    board: Board,
+    weight: f64,
+    probability: f64,
+}
+
+// The tile most likely to spawn on a board.
+fn likeliest_spawn(board: &Board) -> u32 {
+    let spawns = board.variant().rule().spawns();
+    spawns
+        .iter()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |&(tile, _)| tile)
+}
+
+// Every tile that can appear in an empty cell of the board, a 2 or a 4 in the
+// classic game.
+fn spawns(board: &Board, probability: f64) -> impl Iterator<Item = Spawn> {
+    let board = *board;
+    let empty: Vec<(usize, usize)> = board.spawn_cells().collect();
+    let cells = empty.len() as f64;
+    let tiles = board.variant().rule().spawns();
+    empty.into_iter().flat_map(move |(row, col)| {
+        tiles.iter().map(move |&(tile, tile_probability)| {
+            let mut next = board;
+            next.set_cell(row, col, Some(tile))
+                .expect("the variant's own tiles always fit its board");
+            Spawn {
+                board: next,
+                weight: tile_probability / cells,
+                probability: probability / cells * tile_probability,
+            }
+        })
+    })
+}
+
+// The probability of reaching a chance node rounded down to a power of two.
+// The node is searched as if reached with it, so its value depends only on
+// the position, the depth left and this class, and a cached value is the same
+// whichever path or search thread got there first.
+fn pruning_class(probability: f64) -> f64 {
+    const MANTISSA: u64 = (1 << 52) - 1;
+    f64::from_bits(probability.to_bits() & !MANTISSA)
+}
+
+// The key of a chance node in the transposition table: its position, the
+// depth left and the bits of its pruning class.
+type CacheKey = (Board, u32, u64);
+
+// Transposition table of chance node values, safe to share between search
+// threads.
+struct Cache {
+    shards: [Mutex<HashMap<CacheKey, f64>>; CACHE_SHARDS],
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            shards: std::array::from_fn(|_| Mutex::default()),
+        }
+    }
+}
+
+impl Cache {
+    fn shard(&self, board: &Board) -> &Mutex<HashMap<CacheKey, f64>> {
+        let mut hasher = DefaultHasher::new();
+        board.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % CACHE_SHARDS]
+    }
+
+    fn get(&self, board: &Board, depth: u32, probability: f64) -> Option<f64> {
+        let shard = self.shard(board).lock().unwrap();
+        shard.get(&(*board, depth, probability.to_bits())).copied()
+    }
+
+    fn insert(&self, board: Board, depth: u32, probability: f64, value: f64) {
+        let mut shard = self.shard(&board).lock().unwrap();
+        shard.insert((board, depth, probability.to_bits()), value);
+    }
+
+    fn clear(&mut self) {
+        for shard in &mut self.shards {
+            shard.get_mut().unwrap().clear();
+        }
+    }
+}
+
+// Maps `f` over `items` on every available core, in order. Where threads are
+// unavailable, as on the web, the items are mapped on the calling thread.
+fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let threads = thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(items.len());
+    if threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    // Workers take the next unclaimed item until none are left, so a few
+    // slow subtrees do not hold up the rest.
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Returns the legal move with the highest expected value among the
+/// evaluations, or `None` if none is legal.
+pub fn preferred(evaluations: &[Evaluation]) -> Option<GameAction> {
+    evaluations
+        .iter()
+        .filter_map(|e| e.value.map(|value| (e.action, value)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(action, _)| action)
+}
+
+/// Scores a position without searching further.
+pub fn heuristic(board: &Board) -> f64 {
+    // The ranks of the tiles by row, and by column.
+    let (rows, cols) = (board.rows(), board.cols());
+    let mut ranks = [[0.0; MAX_SIDE]; MAX_SIDE];
+    let mut transposed = [[0.0; MAX_SIDE]; MAX_SIDE];
+    let mut max_tile = 0.0;
+    for row in 0..rows {
+        for col in 0..cols {
+            let rank = f64::from(board.rank(row, col));
+            ranks[row][col] = rank;
+            transposed[col][row] = rank;
+            max_tile = f64::max(max_tile, rank);
+        }
+    }
+
+    let empty = board.empty_cells().count() as f64;
+
+    let mut monotonicity = 0.0;
+    let mut smoothness = 0.0;
+    let mut score_line = |line: &[f64]| {
+        // Penalize whichever direction the line is less ordered in.
+        let (mut increases, mut decreases) = (0.0, 0.0);
+        for pair in line.windows(2) {
+            if pair[0] > pair[1] {
+                decreases += pair[0] - pair[1];
+            } else {
+                increases += pair[1] - pair[0];
+            }
+            if pair[0] > 0.0 && pair[1] > 0.0 {
+                smoothness -= (pair[0] - pair[1]).abs();
+            }
+        }
+        monotonicity -= f64::min(increases, decreases);
+    };
+    for row in &ranks[..rows] {
+        score_line(&row[..cols]);
+    }
+    for col in &transposed[..cols] {
+        score_line(&col[..rows]);
+    }
+
+    EMPTY_WEIGHT * (empty + 1.0).ln()
+        + MONOTONICITY_WEIGHT * monotonicity
+        + SMOOTHNESS_WEIGHT * smoothness
+        + MAX_TILE_WEIGHT * max_tile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_marks_illegal_moves() {
+        // Everything is packed into the top-left corner, so only down and
+        // right can change the board.
+        let board: Board = "1200300000000000".parse().unwrap();
+
+        let evaluations = Searcher::new(2).evaluate(&board);
+
+        assert!(evaluations[0].value.is_none());
+        assert!(evaluations[1].value.is_some());
+        assert!(evaluations[2].value.is_none());
+        assert!(evaluations[3].value.is_some());
+    }
+
+    #[test]
+    fn best_move_takes_the_obvious_merge() {
+        // The board is full and only the two 1024s on the top row can merge.
+        let board: Board = "aa98121221211212".parse().unwrap();
+
+        let best = Searcher::new(2).best_move(&board);
+
+        assert!(matches!(best, Some(GameAction::Left | GameAction::Right)));
+    }
+
+    #[test]
+    fn best_move_is_none_when_game_is_over() {
+        let board: Board = "1212212112122121".parse().unwrap();
+        assert_eq!(Searcher::new(2).best_move(&board), None);
+    }
+
+    #[test]
+    fn line_follows_legal_moves() {
+        let board: Board = "1100000000000000".parse().unwrap();
+        let mut searcher = Searcher::new(2);
+
+        let line = searcher.line(&board, 3);
+
+        assert_eq!(line.len(), 3);
+        assert!(searcher.nodes() > 0);
+    }
+
+    #[test]
+    fn parallel_search_matches_sequential_search() {
+        let board: Board = "1121300000000000".parse().unwrap();
+        let mut searcher = Searcher::new(3);
+
+        let evaluations = searcher.evaluate(&board);
+
+        for evaluation in evaluations {
+            let sequential = Game::preview(&board, evaluation.action)
+                .map(|(next, _)| Searcher::new(3).chance(&next, 2, 1.0));
+            match (evaluation.value, sequential) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9),
+                (a, b) => assert_eq!(a.is_some(), b.is_some()),
+            }
+        }
+    }
+
+    #[test]
+    fn time_budget_keeps_the_deepest_search_finished() {
+        let board: Board = "1121300000000000".parse().unwrap();
+        let mut searcher = Searcher::with_limits(SearchLimits {
+            depth: 6,
+            time_budget: Some(Duration::ZERO),
+            ..SearchLimits::default()
+        });
+        assert!(searcher.best_move(&board).is_some());
+        assert_eq!(searcher.report().unwrap().depth, 1);
+
+        let mut searcher = Searcher::new(3);
+        searcher.best_move(&board);
+        assert_eq!(searcher.report().unwrap().depth, 3);
+    }
+
+    #[test]
+    fn pruning_more_visits_fewer_positions() {
+        let board: Board = "1121300000000000".parse().unwrap();
+        let nodes = |min_probability| {
+            let mut searcher = Searcher::with_limits(SearchLimits {
+                depth: 3,
+                min_probability,
+                ..SearchLimits::default()
+            });
+            searcher.best_move(&board);
+            searcher.report().unwrap().nodes
+        };
+        assert!(nodes(0.01) < nodes(DEFAULT_MIN_PROBABILITY));
+    }
+
+    #[test]
+    fn cached_values_do_not_depend_on_what_was_searched_first() {
+        let board: Board = "1121300000000000".parse().unwrap();
+        let searcher = || {
+            Searcher::with_limits(SearchLimits {
+                depth: 3,
+                min_probability: 0.01,
+                ..SearchLimits::default()
+            })
+        };
+        let fresh = searcher().chance(&board, 3, 0.1);
+
+        // The same position reached more likely prunes less, which must not
+        // leak into the value of the less likely one.
+        let warm = searcher();
+        warm.chance(&board, 3, 1.0);
+        assert_eq!(warm.chance(&board, 3, 0.1), fresh);
+        assert_eq!(pruning_class(0.1), pruning_class(0.07));
+        assert_eq!(pruning_class(0.5), 0.5);
+    }
+
+    #[test]
+    fn parallel_map_keeps_order() {
+        let items: Vec<u32> = (0..100).collect();
+        let doubled = parallel_map(&items, |i| i * 2);
+        assert_eq!(doubled, (0..200).step_by(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn heuristic_prefers_monotonic_boards() {
+        let ordered: Board = "4321000000000000".parse().unwrap();
+        let scattered: Board = "1000040000200003".parse().unwrap();
+        assert!(heuristic(&ordered) > heuristic(&scattered));
+    }
+}
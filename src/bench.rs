@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use rand::prelude::*;
+
+use crate::ai::Searcher;
+use crate::game::{Game, GameAction};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    pub moves_per_sec: f64,
+    pub nodes_per_sec: f64,
+}
+
+// Measures the engine for `duration`, split evenly between playing random
+// moves and searching positions at the given depth.
+pub fn run(duration: Duration, depth: u32) -> Result<Measurement> {
+    Ok(Measurement {
+        moves_per_sec: moves_per_sec(duration / 2)?,
+        nodes_per_sec: nodes_per_sec(duration / 2, depth)?,
+    })
+}
+
+// Plays random games back to back, counting every move applied.
+fn moves_per_sec(duration: Duration) -> Result<f64> {
+    let mut rng = rand::rng();
+    let mut game = Game::new();
+    let mut moves = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        if game.is_game_over() {
+            game.restart();
+        }
+        let action = *GameAction::ALL.choose(&mut rng).unwrap();
+        game.apply_move(action)?;
+        moves += 1;
+    }
+    Ok(moves as f64 / start.elapsed().as_secs_f64())
+}
+
+// Plays games with the search picking every move, counting the positions
+// it visits.
+fn nodes_per_sec(duration: Duration, depth: u32) -> Result<f64> {
+    let mut searcher = Searcher::new(depth);
+    let mut game = Game::new();
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        match searcher.best_move(game.board()) {
+            Some(action) => game.apply_move(action)?,
+            None => game.restart(),
+        };
+    }
+    Ok(searcher.nodes() as f64 / start.elapsed().as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_measures_moves_and_nodes() {
+        let measurement = run(Duration::from_millis(20), 1).unwrap();
+
+        assert!(measurement.moves_per_sec > 0.0);
+        assert!(measurement.nodes_per_sec > 0.0);
+    }
+}
@@ -1,38 +1,972 @@
-pub(crate) const BOARD_SIZE: usize = 4;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
-#[derive(Debug, Default)]
+use anyhow::{Error, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameAction;
+use crate::rules::{Settle, Slide, Variant};
+
+/// Number of rows and columns of the standard board.
+pub const BOARD_SIZE: usize = 4;
+
+/// Most rows or columns a board can have.
+pub const MAX_SIDE: usize = 8;
+
+/// Most cells a board can have.
+pub const MAX_CELLS: usize = 32;
+
+// Each cell takes 4 bits holding the rank of its tile, its place among the
+// tiles of the board's variant counting from 1, or 0 when empty. Cell
+// (row, col) is at nibble `row * cols + col` counting from the least
+// significant, so a row of the standard board takes 16 bits.
+const CELL_BITS: usize = 4;
+const CELL_MASK: u128 = 0xf;
+
+// Largest rank that fits in a cell.
+pub(crate) const MAX_RANK: u32 = 15;
+
+/// Largest tile a classic board can hold. Two of them do not merge.
+pub const MAX_TILE: u32 = 1 << MAX_RANK;
+
+// Lines of up to this many cells slide through the row moves table. Longer
+// ones, on boards wider or taller than the standard one, slide cell by cell.
+const TABLE_CELLS: usize = 4;
+
+// Stands for a wall in board codes.
+const WALL_DIGIT: char = 'x';
+
+// Stands for a void in board codes.
+const VOID_DIGIT: char = '~';
+
+// Separates the rows in the board codes of boards other than the standard
+// 4x4 one.
+const ROW_SEPARATOR: char = '/';
+
+// Separates the variant from the cells in the board codes of variants other
+// than the classic one.
+const VARIANT_SEPARATOR: char = ':';
+
+// Marks the board codes of boards whose lines wrap around.
+const WRAP_PREFIX: &str = "wrap";
+
+/// What a cell that is not empty holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Tile {
+    /// A numbered tile, which slides and merges.
+    Value(u32),
+    /// A wall, which never moves or merges. Tiles slide up against it.
+    Wall,
+    /// A void, a hole in the board that is not played on. Tiles slide past it
+    /// as if it were not there, so boards can be L-shaped or cross-shaped.
+    Void,
+}
+
+/// The number of rows and columns of a board, from 2 to `MAX_SIDE` each and
+/// at most `MAX_CELLS` cells in all, e.g. 4x6 or 5x3. Boards are 4x4 unless
+/// given another shape.
+///
+/// The lines of a board may also wrap around, as on a torus: tiles sliding
+/// off one edge come back in from the opposite one (see `Shape::with_wrap`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Shape {
+    rows: u8,
+    cols: u8,
+    wrap: bool,
+}
+
+impl Shape {
+    pub fn new(rows: usize, cols: usize) -> Result<Self> {
+        let sides = 2..=MAX_SIDE;
+        if !sides.contains(&rows) || !sides.contains(&cols) {
+            bail!(
+                "A board has 2 to {MAX_SIDE} rows and columns, found \
+                 {rows}x{cols}"
+            );
+        }
+        if rows * cols > MAX_CELLS {
+            bail!("A board has at most {MAX_CELLS} cells, found {rows}x{cols}");
+        }
+        Ok(Shape {
+            rows: rows as u8,
+            cols: cols as u8,
+            wrap: false,
+        })
+    }
+
+    /// The shape with lines that wrap around or not. A wrapping line with no
+    /// wall is a loop: its tiles keep their order around it, led by the tile
+    /// with the most room ahead, and pack against the edge they slide
+    /// towards, merging as usual. Lines with a wall, and the lines of variants
+    /// whose tiles move one cell at a time, do not wrap.
+    pub fn with_wrap(self, wrap: bool) -> Self {
+        Shape { wrap, ..self }
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows.into()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols.into()
+    }
+
+    pub fn cells(&self) -> usize {
+        self.rows() * self.cols()
+    }
+
+    /// Whether this is the size of the standard 4x4 board, wrapping or not.
+    pub fn is_standard(&self) -> bool {
+        self.with_wrap(false) == Shape::default()
+    }
+
+    fn transposed(&self) -> Shape {
+        Shape {
+            rows: self.cols,
+            cols: self.rows,
+            wrap: self.wrap,
+        }
+    }
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape {
+            rows: BOARD_SIZE as u8,
+            cols: BOARD_SIZE as u8,
+            wrap: false,
+        }
+    }
+}
+
+impl FromStr for Shape {
+    type Err = Error;
+
+    // Parses a shape written as rows by columns, e.g. "4x6".
+    fn from_str(shape: &str) -> Result<Self> {
+        let Some((rows, cols)) = shape.split_once(['x', 'X']) else {
+            bail!("Expected rows by columns, e.g. 4x6, found '{shape}'");
+        };
+        Shape::new(rows.trim().parse()?, cols.trim().parse()?)
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.rows, self.cols)
+    }
+}
+
+/// The grid of tiles, each cell holding a tile value or `None` when empty.
+/// Cells are addressed by `(row, col)` from the top-left corner. The tiles are
+/// packed into a single `u128`, so boards are cheap to copy, compare and hash.
+/// Some cells may be walls or voids instead (see `Tile`), which read as `None`
+/// from `cell` but are never empty. The board's variant decides which tiles it
+/// holds and how they merge, and its shape how many rows and columns it has.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Cells", into = "Cells")]
 pub struct Board {
-    cells: [[Option<u32>; BOARD_SIZE]; BOARD_SIZE],
+    bits: u128,
+    // One bit per wall, at bit `row * cols + col`. Wall cells have no tile.
+    walls: u32,
+    // One bit per void, like the walls. Void cells have no tile either.
+    voids: u32,
+    shape: Shape,
+    variant: Variant,
+}
+
+// Boards serialize as their grid of tiles, so the packing stays out of saved
+// games. Walls and voids are listed apart, and left out when there are none.
+#[derive(Serialize, Deserialize)]
+struct Cells {
+    cells: Vec<Vec<Option<u32>>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    walls: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    voids: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Variant::is_classic")]
+    variant: Variant,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    wrap: bool,
+}
+
+impl TryFrom<Cells> for Board {
+    type Error = Error;
+
+    fn try_from(cells: Cells) -> Result<Self> {
+        let cols = cells.cells.first().map_or(0, Vec::len);
+        if cells.cells.iter().any(|row| row.len() != cols) {
+            bail!("Every row of a board has the same number of cells");
+        }
+        let shape = Shape::new(cells.cells.len(), cols)?.with_wrap(cells.wrap);
+        let mut board = Board::with_shape(cells.variant, shape);
+        for (row, row_cells) in cells.cells.into_iter().enumerate() {
+            for (col, value) in row_cells.into_iter().enumerate() {
+                board.set_cell(row, col, value)?;
+            }
+        }
+        for (row, col) in cells.walls {
+            if row >= shape.rows() || col >= shape.cols() {
+                bail!("Wall ({row}, {col}) is off the board");
+            }
+            board.set_wall(row, col);
+        }
+        for (row, col) in cells.voids {
+            if row >= shape.rows() || col >= shape.cols() {
+                bail!("Void ({row}, {col}) is off the board");
+            }
+            board.set_void(row, col);
+        }
+        Ok(board)
+    }
+}
+
+impl From<Board> for Cells {
+    fn from(board: Board) -> Self {
+        let mut cells = vec![vec![None; board.cols()]; board.rows()];
+        let mut walls = Vec::new();
+        let mut voids = Vec::new();
+        for ((row, col), tile) in board.iter_tiles() {
+            match tile {
+                Some(Tile::Value(value)) => cells[row][col] = Some(value),
+                Some(Tile::Wall) => walls.push((row, col)),
+                Some(Tile::Void) => voids.push((row, col)),
+                None => {}
+            }
+        }
+        Cells {
+            cells,
+            walls,
+            voids,
+            variant: board.variant,
+            wrap: board.shape.wraps(),
+        }
+    }
+}
+
+// The tile of a rank in a variant, 0 being an empty cell.
+fn tile(variant: Variant, rank: u32) -> Option<u32> {
+    let tiles = variant.rule().tiles();
+    rank.checked_sub(1)
+        .and_then(|index| tiles.get(index as usize))
+        .copied()
+}
+
+// The rank of a tile in a variant, if a board of the variant can hold it.
+fn rank(variant: Variant, value: u32) -> Option<u32> {
+    let tiles = variant.rule().tiles();
+    let index = tiles.iter().position(|&tile| tile == value)?;
+    Some(index as u32 + 1).filter(|&rank| rank <= MAX_RANK)
+}
+
+// The points a variant scores for making the tile of a rank.
+fn points(variant: Variant, rank: u32) -> u32 {
+    tile(variant, rank).map_or(0, |tile| variant.rule().score(tile))
+}
+
+// The rank of the tile made by merging tiles of two ranks, if the variant
+// merges them and a board can hold the result.
+fn merge(variant: Variant, ahead: u32, behind: u32) -> Option<u32> {
+    let (ahead, behind) = (tile(variant, ahead)?, tile(variant, behind)?);
+    rank(variant, variant.rule().merge(ahead, behind)?)
+}
+
+// The rank of the cell of a line at `col`, counting from the line's first
+// cell.
+fn line_rank(line: u64, col: usize) -> u32 {
+    (line >> (col * CELL_BITS) & CELL_MASK as u64) as u32
+}
+
+// Moves the tiles of a line of `len` cells towards its first cell under the
+// rules of a variant: the line left behind and the points scored.
+fn move_line(variant: Variant, line: u64, len: usize) -> (u64, u32) {
+    match variant.rule().slide() {
+        Slide::ToWall => slide_to_wall(variant, line, len),
+        Slide::OneStep => shift(variant, line, len),
+    }
+}
+
+fn slide_to_wall(variant: Variant, line: u64, len: usize) -> (u64, u32) {
+    let mut ranks = (0..len)
+        .map(|col| line_rank(line, col))
+        .filter(|&rank| rank > 0)
+        .peekable();
+    let mut slid = 0;
+    let mut score = 0;
+    let mut col = 0;
+    while let Some(mut rank) = ranks.next() {
+        if let Some(&next) = ranks.peek()
+            && let Some(merged) = merge(variant, rank, next)
+        {
+            ranks.next();
+            rank = merged;
+            score += points(variant, rank);
+        }
+        slid |= u64::from(rank) << (col * CELL_BITS);
+        col += 1;
+    }
+    (slid, score)
+}
+
+// The cell a wrapping line of `len` cells starts from: the tile with the
+// most empty cells ahead of it, counting around the end of the line, and the
+// first such tile on a tie. A line with no empty cell or no tile starts from
+// its first cell.
+pub(crate) fn wrap_start(occupied: impl IntoIterator<Item = bool>) -> usize {
+    let mut first = None;
+    let (mut start, mut widest) = (0, 0);
+    let mut gap = 0;
+    for (col, occupied) in occupied.into_iter().enumerate() {
+        if !occupied {
+            gap += 1;
+            continue;
+        }
+        match first {
+            None => first = Some((col, gap)),
+            Some(_) if gap > widest => (start, widest) = (col, gap),
+            Some(_) => {}
+        }
+        gap = 0;
+    }
+    match first {
+        // The gap ahead of the first tile goes on past the line's last tile.
+        Some((col, ahead)) if ahead + gap >= widest => col,
+        Some(_) => start,
+        None => 0,
+    }
+}
+
+// Rotates a line of `len` cells so that it starts from the cell at `start`.
+fn rotate_line(line: u64, start: usize, len: usize) -> u64 {
+    if start == 0 {
+        return line;
+    }
+    let mask = (1 << (len * CELL_BITS)) - 1;
+    (line >> (start * CELL_BITS) | line << ((len - start) * CELL_BITS)) & mask
+}
+
+// Moves the first tile that can move one cell, into an empty cell or by
+// merging with the tile ahead, along with every tile behind it.
+fn shift(variant: Variant, line: u64, len: usize) -> (u64, u32) {
+    for col in 1..len {
+        let (ahead, behind) = (line_rank(line, col - 1), line_rank(line, col));
+        let (moved, score) = match ahead {
+            _ if behind == 0 => continue,
+            0 => (behind, 0),
+            _ => match merge(variant, ahead, behind) {
+                Some(merged) => (merged, points(variant, merged)),
+                None => continue,
+            },
+        };
+        let stays = line & ((1 << ((col - 1) * CELL_BITS)) - 1);
+        let shifted = line >> ((col + 1) * CELL_BITS) << (col * CELL_BITS);
+        let moved = u64::from(moved) << ((col - 1) * CELL_BITS);
+        return (stays | moved | shifted, score);
+    }
+    (line, 0)
+}
+
+// The outcome of moving every possible line of up to `TABLE_CELLS` cells
+// towards its first cell under the rules of a variant, indexed by the bits of
+// the line: the line left behind and the points scored. A shorter line reads
+// as a longer one ending in empty cells.
+struct RowMoves {
+    variant: Variant,
+    rows: Vec<u16>,
+    scores: Vec<u32>,
+}
+
+impl RowMoves {
+    fn new(variant: Variant) -> Self {
+        let (rows, scores) = (0..=u16::MAX)
+            .map(|row| {
+                let (slid, score) = move_line(variant, row.into(), TABLE_CELLS);
+                (slid as u16, score)
+            })
+            .unzip();
+        Self {
+            variant,
+            rows,
+            scores,
+        }
+    }
+
+    // Moves a line of `len` cells towards its first cell.
+    fn slide(&self, line: u64, len: usize) -> (u64, u32) {
+        if len > TABLE_CELLS {
+            return move_line(self.variant, line, len);
+        }
+        (self.rows[line as usize].into(), self.scores[line as usize])
+    }
+
+    // Moves a line of `len` cells towards its first cell when the cells set
+    // in `walls` are walls: the stretch of cells between two walls moves on
+    // its own, as a shorter line would.
+    fn slide_between_walls(
+        &self,
+        line: u64,
+        walls: u64,
+        len: usize,
+    ) -> (u64, u32) {
+        let mut slid = 0;
+        let mut score = 0;
+        let mut start = 0;
+        for end in 0..=len {
+            if end < len && walls >> end & 1 == 0 {
+                continue;
+            }
+            let shift = start * CELL_BITS;
+            let stretch =
+                line >> shift & ((1 << ((end - start) * CELL_BITS)) - 1);
+            let (stretch, points) = self.slide(stretch, end - start);
+            slid |= stretch << shift;
+            score += points;
+            start = end + 1;
+        }
+        (slid, score)
+    }
+
+    // Moves a line of `len` cells towards its first cell, between the walls
+    // set in `walls`, or around the line when it `wraps` and has no wall.
+    fn slide_line(
+        &self,
+        line: u64,
+        walls: u64,
+        len: usize,
+        wraps: bool,
+    ) -> (u64, u32) {
+        match walls {
+            0 if wraps => {
+                let occupied = (0..len).map(|col| line_rank(line, col) > 0);
+                let start = wrap_start(occupied);
+                self.slide(rotate_line(line, start, len), len)
+            }
+            0 => self.slide(line, len),
+            walls => self.slide_between_walls(line, walls, len),
+        }
+    }
+}
+
+// The row moves of each variant, built the first time it is played.
+static ROW_MOVES: [OnceLock<RowMoves>; Variant::ALL.len()] =
+    [const { OnceLock::new() }; Variant::ALL.len()];
+
+fn row_moves(variant: Variant) -> &'static RowMoves {
+    ROW_MOVES[variant as usize].get_or_init(|| RowMoves::new(variant))
+}
+
+// Reverses the order of the cells of a line of `len` cells.
+fn reverse_line(line: u64, len: usize) -> u64 {
+    if len == BOARD_SIZE {
+        return (line & 0xf) << 12
+            | (line & 0xf0) << 4
+            | (line >> 4) & 0xf0
+            | line >> 12;
+    }
+    (0..len).fold(0, |reversed, col| {
+        reversed
+            | u64::from(line_rank(line, col)) << ((len - 1 - col) * CELL_BITS)
+    })
+}
+
+// Reverses the order of the walls of a line of `len` cells, one bit per cell.
+fn reverse_walls(walls: u64, len: usize) -> u64 {
+    (0..len).fold(0, |reversed, col| {
+        reversed | (walls >> col & 1) << (len - 1 - col)
+    })
+}
+
+// Gathers the cells of a line of `len` cells that are not set in `voids`
+// into a shorter line, with its walls, so its tiles slide past the voids.
+// Returns the line, its walls and its length.
+fn gather_line(
+    line: u64,
+    walls: u64,
+    voids: u64,
+    len: usize,
+) -> (u64, u64, usize) {
+    let (mut gathered, mut gathered_walls, mut cells) = (0, 0, 0);
+    for col in (0..len).filter(|col| voids >> col & 1 == 0) {
+        gathered |= u64::from(line_rank(line, col)) << (cells * CELL_BITS);
+        gathered_walls |= (walls >> col & 1) << cells;
+        cells += 1;
+    }
+    (gathered, gathered_walls, cells)
+}
+
+// Spreads a line gathered by `gather_line` back over the cells of a line of
+// `len` cells that are not set in `voids`.
+fn scatter_line(gathered: u64, voids: u64, len: usize) -> u64 {
+    (0..len)
+        .filter(|col| voids >> col & 1 == 0)
+        .enumerate()
+        .fold(0, |line, (cell, col)| {
+            line | u64::from(line_rank(gathered, cell)) << (col * CELL_BITS)
+        })
 }
 
 impl Board {
-    // Returns an iterator over the board cells and coordinates in row major
-    // order in the form ((row, col), value).
+    /// An empty standard board of a variant.
+    pub fn new(variant: Variant) -> Self {
+        Board::with_shape(variant, Shape::default())
+    }
+
+    /// An empty board of a variant with the given rows and columns.
+    pub fn with_shape(variant: Variant, shape: Shape) -> Self {
+        Board {
+            bits: 0,
+            walls: 0,
+            voids: 0,
+            shape,
+            variant,
+        }
+    }
+
+    /// The board with its tiles taken off, keeping its walls and voids.
+    pub fn cleared(&self) -> Self {
+        Board { bits: 0, ..*self }
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn rows(&self) -> usize {
+        self.shape.rows()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.shape.cols()
+    }
+
+    /// The tile made by merging two tiles of the board, if its variant merges
+    /// them and the board can hold the result.
+    pub fn merge(&self, ahead: u32, behind: u32) -> Option<u32> {
+        let tile = self.variant.rule().merge(ahead, behind)?;
+        rank(self.variant, tile).map(|_| tile)
+    }
+
+    /// Returns an iterator over the board cells and coordinates in row major
+    /// order in the form ((row, col), value).
     pub fn iter_cells(
         &self,
-    ) -> impl Iterator<Item = ((usize, usize), &Option<u32>)> {
-        self.cells.iter().enumerate().flat_map(|(row, row_cells)| {
-            row_cells
-                .iter()
-                .enumerate()
-                .map(move |(col, col_cell)| ((row, col), col_cell))
-        })
+    ) -> impl Iterator<Item = ((usize, usize), Option<u32>)> {
+        let board = *self;
+        self.coords()
+            .map(move |(row, col)| ((row, col), board.cell(row, col)))
     }
 
+    /// Returns the board cells and coordinates in row major order like
+    /// `iter_cells`, telling walls and voids apart from empty cells.
+    pub fn iter_tiles(
+        &self,
+    ) -> impl Iterator<Item = ((usize, usize), Option<Tile>)> {
+        let board = *self;
+        self.coords()
+            .map(move |(row, col)| ((row, col), board.tile(row, col)))
+    }
+
+    /// Returns the tiles of a column from top to bottom, skipping empty cells.
     pub fn col(&self, col: usize) -> impl DoubleEndedIterator<Item = u32> {
-        self.cells.iter().filter_map(move |row| row[col])
+        let board = *self;
+        (0..self.rows()).filter_map(move |row| board.cell(row, col))
     }
 
+    /// Returns the tiles of a row from left to right, skipping empty cells.
     pub fn row(&self, row: usize) -> impl DoubleEndedIterator<Item = u32> {
-        self.cells[row].iter().copied().flatten()
+        let board = *self;
+        (0..self.cols()).filter_map(move |col| board.cell(row, col))
     }
 
     pub fn cell(&self, row: usize, col: usize) -> Option<u32> {
-        self.cells[row][col]
+        tile(self.variant, self.rank(row, col))
     }
 
-    pub fn cell_mut(&mut self, row: usize, col: usize) -> &mut Option<u32> {
-        &mut self.cells[row][col]
+    /// Returns the rank of a cell's tile, its place among the tiles of the
+    /// board's variant counting from 1, or 0 for an empty cell, a wall or a
+    /// void.
+    pub fn rank(&self, row: usize, col: usize) -> u32 {
+        (self.bits >> self.shift(row, col) & CELL_MASK) as u32
+    }
+
+    pub fn tile(&self, row: usize, col: usize) -> Option<Tile> {
+        if self.is_wall(row, col) {
+            Some(Tile::Wall)
+        } else if self.is_void(row, col) {
+            Some(Tile::Void)
+        } else {
+            self.cell(row, col).map(Tile::Value)
+        }
+    }
+
+    pub fn is_wall(&self, row: usize, col: usize) -> bool {
+        self.walls >> self.index(row, col) & 1 == 1
+    }
+
+    /// Turns a cell into a wall, removing its tile.
+    pub fn set_wall(&mut self, row: usize, col: usize) {
+        self.bits &= !(CELL_MASK << self.shift(row, col));
+        self.walls |= 1 << self.index(row, col);
+        self.voids &= !(1 << self.index(row, col));
+    }
+
+    pub fn is_void(&self, row: usize, col: usize) -> bool {
+        self.voids >> self.index(row, col) & 1 == 1
+    }
+
+    /// Turns a cell into a void, taking it out of play along with its tile.
+    pub fn set_void(&mut self, row: usize, col: usize) {
+        self.bits &= !(CELL_MASK << self.shift(row, col));
+        self.walls &= !(1 << self.index(row, col));
+        self.voids |= 1 << self.index(row, col);
+    }
+
+    /// Sets a cell, replacing a wall or a void, failing if the value is not a tile the
+    /// board can hold: for the classic variant, a power of two from 2 to
+    /// `MAX_TILE`.
+    pub fn set_cell(
+        &mut self,
+        row: usize,
+        col: usize,
+        value: Option<u32>,
+    ) -> Result<()> {
+        let rank = match value {
+            None => 0,
+            Some(value) => match rank(self.variant, value) {
+                Some(rank) => rank,
+                None => bail!("Unsupported tile value {value}"),
+            },
+        };
+        let shift = self.shift(row, col);
+        self.bits =
+            self.bits & !(CELL_MASK << shift) | u128::from(rank) << shift;
+        self.walls &= !(1 << self.index(row, col));
+        self.voids &= !(1 << self.index(row, col));
+        Ok(())
+    }
+
+    /// Returns the largest tile on the board, if any.
+    pub fn max_tile(&self) -> Option<u32> {
+        let rank = (0..self.shape.cells())
+            .map(|index| (self.bits >> (index * CELL_BITS) & CELL_MASK) as u32)
+            .max()
+            .unwrap_or_default();
+        tile(self.variant, rank)
+    }
+
+    /// Returns the coordinates of the empty cells in row major order. Walls
+    /// and voids are not empty.
+    pub fn empty_cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.iter_tiles()
+            .filter(|(_, tile)| tile.is_none())
+            .map(|(coord, _)| coord)
+    }
+
+    /// Encodes the board as a board code (see `Board::from_str`).
+    pub fn to_code(self) -> String {
+        let digit = |index: usize| {
+            if self.walls >> index & 1 == 1 {
+                return WALL_DIGIT;
+            }
+            if self.voids >> index & 1 == 1 {
+                return VOID_DIGIT;
+            }
+            let rank = self.bits >> (index * CELL_BITS) & CELL_MASK;
+            char::from_digit(rank as u32, 16).unwrap_or('0')
+        };
+        let rows: Vec<String> = (0..self.rows())
+            .map(|row| {
+                (0..self.cols())
+                    .map(|col| digit(row * self.cols() + col))
+                    .collect()
+            })
+            .collect();
+        let code = if self.shape.is_standard() {
+            rows.concat()
+        } else {
+            rows.join(&ROW_SEPARATOR.to_string())
+        };
+        let code = if self.shape.wraps() {
+            format!("{WRAP_PREFIX}{VARIANT_SEPARATOR}{code}")
+        } else {
+            code
+        };
+        if self.variant.is_classic() {
+            code
+        } else {
+            format!("{}{VARIANT_SEPARATOR}{code}", self.variant)
+        }
+    }
+
+    /// Returns the board after sliding its tiles in the given direction,
+    /// and letting them fall if its variant's tiles do (see `Settle`),
+    /// without spawning a tile, along with the points scored.
+    pub fn slide(&self, direction: GameAction) -> (Board, u32) {
+        let (board, score) = match direction {
+            GameAction::Left => self.slide_rows(false),
+            GameAction::Right => self.slide_rows(true),
+            GameAction::Up => {
+                let (board, score) = self.transpose().slide_rows(false);
+                (board.transpose(), score)
+            }
+            GameAction::Down => {
+                let (board, score) = self.transpose().slide_rows(true);
+                (board.transpose(), score)
+            }
+        };
+        match self.variant.rule().settle() {
+            Settle::InPlace => (board, score),
+            Settle::Fall => (board.fall(), score),
+        }
+    }
+
+    /// Returns the coordinates of the cells a tile can spawn on in row major
+    /// order: the empty cells, or on a board whose tiles fall, the empty
+    /// cells a tile would not fall from.
+    pub fn spawn_cells(&self) -> impl Iterator<Item = (usize, usize)> {
+        let board = *self;
+        let falls = self.variant.rule().settle() == Settle::Fall;
+        self.empty_cells()
+            .filter(move |&(row, col)| !falls || board.is_landing(row, col))
+    }
+
+    /// Returns whether any move would change the board.
+    pub fn can_move(&self) -> bool {
+        GameAction::ALL
+            .into_iter()
+            .any(|direction| self.slide(direction).0 != *self)
+    }
+
+    // The coordinates of every cell in row major order.
+    fn coords(&self) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let cols = self.cols();
+        (0..self.shape.cells()).map(move |index| (index / cols, index % cols))
+    }
+
+    fn shift(&self, row: usize, col: usize) -> usize {
+        self.index(row, col) * CELL_BITS
+    }
+
+    /// Whether the lines of the board wrap around when its tiles slide to
+    /// the wall (see `Shape::with_wrap`).
+    pub fn wraps(&self) -> bool {
+        self.shape.wraps() && self.variant.rule().slide() == Slide::ToWall
+    }
+
+    fn index(&self, row: usize, col: usize) -> usize {
+        assert!(row < self.rows() && col < self.cols(), "cell out of bounds");
+        row * self.cols() + col
+    }
+
+    // Whether a tile falling down a column stops at a cell: the next cell
+    // below that is not a void holds a tile or a wall, or there is none.
+    fn is_landing(&self, row: usize, col: usize) -> bool {
+        (row + 1..self.rows())
+            .find(|&below| !self.is_void(below, col))
+            .is_none_or(|below| self.tile(below, col).is_some())
+    }
+
+    // Drops every tile down its column as far as it goes, onto the bottom
+    // edge, a wall or another tile, past any void. Falling tiles keep their
+    // order and do not merge.
+    fn fall(&self) -> Board {
+        let mut board = *self;
+        for col in 0..self.cols() {
+            // The cells of the column from the last wall down, bottom first,
+            // and how many of them are filled.
+            let mut landing = [0; MAX_SIDE];
+            let (mut cells, mut filled) = (0, 0);
+            for row in (0..self.rows()).rev() {
+                if self.is_void(row, col) {
+                    continue;
+                }
+                if self.is_wall(row, col) {
+                    (cells, filled) = (0, 0);
+                    continue;
+                }
+                landing[cells] = row;
+                cells += 1;
+                let rank = self.bits >> self.shift(row, col) & CELL_MASK;
+                if rank > 0 {
+                    board.bits &= !(CELL_MASK << self.shift(row, col));
+                    board.bits |= rank << self.shift(landing[filled], col);
+                    filled += 1;
+                }
+            }
+        }
+        board
+    }
+
+    // Slides every row towards its first cell, or its last when `reversed`.
+    // The cells of a row with voids are gathered to slide as a shorter row.
+    fn slide_rows(&self, reversed: bool) -> (Board, u32) {
+        let moves = row_moves(self.variant);
+        let wraps = self.wraps();
+        let cols = self.cols();
+        let line_mask = (1 << (cols * CELL_BITS)) - 1;
+        let mut board = Board { bits: 0, ..*self };
+        let mut score = 0;
+        for row in 0..self.rows() {
+            let shift = row * cols * CELL_BITS;
+            let mut line = (self.bits >> shift) as u64 & line_mask;
+            let mut walls =
+                u64::from(self.walls) >> (row * cols) & ((1 << cols) - 1);
+            let mut voids =
+                u64::from(self.voids) >> (row * cols) & ((1 << cols) - 1);
+            if reversed {
+                line = reverse_line(line, cols);
+                walls = reverse_walls(walls, cols);
+                voids = reverse_walls(voids, cols);
+            }
+            let (mut slid, points) = if voids == 0 {
+                moves.slide_line(line, walls, cols, wraps)
+            } else {
+                let (line, walls, len) = gather_line(line, walls, voids, cols);
+                let (slid, points) = moves.slide_line(line, walls, len, wraps);
+                (scatter_line(slid, voids, cols), points)
+            };
+            if reversed {
+                slid = reverse_line(slid, cols);
+            }
+            board.bits |= u128::from(slid) << shift;
+            score += points;
+        }
+        (board, score)
+    }
+
+    // Swaps rows and columns. The standard board moves its cells across the
+    // diagonal in blocks; other shapes move them one at a time.
+    fn transpose(&self) -> Board {
+        if !self.shape.is_standard() {
+            return self.transpose_cells();
+        }
+        let x = self.bits as u64;
+        let a = x & 0xf0f0_0f0f_f0f0_0f0f
+            | (x & 0x0000_f0f0_0000_f0f0) << 12
+            | (x & 0x0f0f_0000_0f0f_0000) >> 12;
+        let bits = a & 0xff00_ff00_00ff_00ff
+            | (a & 0x00ff_00ff_0000_0000) >> 24
+            | (a & 0x0000_0000_ff00_ff00) << 24;
+        if self.walls == 0 && self.voids == 0 {
+            return Board {
+                bits: bits.into(),
+                ..*self
+            };
+        }
+        Board {
+            bits: bits.into(),
+            ..self.transpose_cells()
+        }
+    }
+
+    fn transpose_cells(&self) -> Board {
+        let (rows, cols) = (self.rows(), self.cols());
+        let mut board =
+            Board::with_shape(self.variant, self.shape.transposed());
+        for index in 0..rows * cols {
+            let (row, col) = (index / cols, index % cols);
+            let to = col * rows + row;
+            let rank = self.bits >> (index * CELL_BITS) & CELL_MASK;
+            board.bits |= rank << (to * CELL_BITS);
+            board.walls |= (self.walls >> index & 1) << to;
+            board.voids |= (self.voids >> index & 1) << to;
+        }
+        board
+    }
+}
+
+impl FromStr for Board {
+    type Err = Error;
+
+    // Parses a board code: one hex digit per cell in row major order holding
+    // the tile's rank, 0 for an empty cell, 'x' for a wall or '~' for a void,
+    // a hole in the board. For the classic variant the rank is the tile's
+    // exponent. The rows of boards other than the standard 4x4 one are
+    // separated by '/', e.g. "000/000/012" for a 3x3 board, or "~00/000/~00"
+    // for a T-shaped one. Boards of other variants are prefixed with the
+    // variant's name and ':', e.g. "fibonacci:11...", and boards whose lines
+    // wrap around with "wrap:", after the variant if any.
+    fn from_str(code: &str) -> Result<Self> {
+        let (variant, code) = match code.split_once(VARIANT_SEPARATOR) {
+            Some((variant, code)) if variant != WRAP_PREFIX => {
+                (variant.parse()?, code)
+            }
+            _ => (Variant::Classic, code),
+        };
+        let (wrap, code) = match code.split_once(VARIANT_SEPARATOR) {
+            Some((WRAP_PREFIX, code)) => (true, code),
+            _ => (false, code),
+        };
+
+        let rows: Vec<Vec<char>> = if code.contains(ROW_SEPARATOR) {
+            code.split(ROW_SEPARATOR)
+                .map(|row| row.chars().collect())
+                .collect()
+        } else if code.chars().count() == BOARD_SIZE * BOARD_SIZE {
+            let digits: Vec<char> = code.chars().collect();
+            digits.chunks(BOARD_SIZE).map(<[char]>::to_vec).collect()
+        } else {
+            bail!(
+                "A board code has {} hex digits, or rows separated by '/', \
+                 found '{code}'",
+                BOARD_SIZE * BOARD_SIZE
+            );
+        };
+        let cols = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != cols) {
+            bail!("The rows of board code '{code}' differ in length");
+        }
+
+        let shape = Shape::new(rows.len(), cols)?.with_wrap(wrap);
+        let mut board = Board::with_shape(variant, shape);
+        for (index, &digit) in rows.iter().flatten().enumerate() {
+            if digit.eq_ignore_ascii_case(&WALL_DIGIT) {
+                board.walls |= 1 << index;
+                continue;
+            }
+            if digit == VOID_DIGIT {
+                board.voids |= 1 << index;
+                continue;
+            }
+            let Some(rank) = digit.to_digit(16) else {
+                bail!("Invalid digit '{digit}' in board code '{code}'");
+            };
+            board.bits |= u128::from(rank) << (index * CELL_BITS);
+        }
+        Ok(board)
+    }
+}
+
+// Shows the board code rather than the packed bits.
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Board").field(&self.to_code()).finish()
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.rows() {
+            let line: Vec<String> = (0..self.cols())
+                .map(|col| match self.tile(row, col) {
+                    Some(Tile::Value(value)) => format!("{value:>6}"),
+                    Some(Tile::Wall) => format!("{:>6}", "#"),
+                    Some(Tile::Void) => format!("{:>6}", ""),
+                    None => format!("{:>6}", "."),
+                })
+                .collect();
+            writeln!(f, "{}", line.join(""))?;
+        }
+        Ok(())
     }
 }
 
@@ -44,7 +978,7 @@ mod tests {
         let mut board = Board::default();
         for (row, row_cells) in rows.iter().enumerate() {
             for (col, value) in row_cells.iter().enumerate() {
-                *board.cell_mut(row, col) = *value;
+                board.set_cell(row, col, *value).unwrap();
             }
         }
         board
@@ -59,10 +993,8 @@ mod tests {
             [None, None, None, Some(64)],
         ]);
 
-        let cells: Vec<((usize, usize), Option<u32>)> = board
-            .iter_cells()
-            .map(|(coord, value)| (coord, *value))
-            .collect();
+        let cells: Vec<((usize, usize), Option<u32>)> =
+            board.iter_cells().collect();
 
         let expected_coords: Vec<(usize, usize)> = (0..BOARD_SIZE)
             .flat_map(|row| (0..BOARD_SIZE).map(move |col| (row, col)))
@@ -105,13 +1037,328 @@ mod tests {
     }
 
     #[test]
-    fn cell_and_cell_mut_round_trip() {
+    fn cell_and_set_cell_round_trip() {
         let mut board = Board::default();
 
         assert_eq!(board.cell(1, 2), None);
-        *board.cell_mut(1, 2) = Some(32);
+        board.set_cell(1, 2, Some(32)).unwrap();
         assert_eq!(board.cell(1, 2), Some(32));
-        *board.cell_mut(1, 2) = None;
+        board.set_cell(1, 2, None).unwrap();
         assert_eq!(board.cell(1, 2), None);
     }
+
+    #[test]
+    fn max_tile_returns_largest_value() {
+        let mut board = Board::default();
+        assert_eq!(board.max_tile(), None);
+
+        board.set_cell(0, 3, Some(64)).unwrap();
+        board.set_cell(2, 1, Some(8)).unwrap();
+        assert_eq!(board.max_tile(), Some(64));
+    }
+
+    #[test]
+    fn empty_cells_lists_coordinates_in_row_major_order() {
+        let board = board_from_rows([
+            [Some(2), None, Some(4), Some(8)],
+            [Some(2), Some(4), Some(8), Some(16)],
+            [Some(2), Some(4), Some(8), None],
+            [Some(2), Some(4), Some(8), Some(16)],
+        ]);
+
+        assert_eq!(board.empty_cells().collect::<Vec<_>>(), [(0, 1), (2, 3)]);
+    }
+
+    #[test]
+    fn board_code_parses_exponents_in_row_major_order() {
+        let board = board_from_rows([
+            [Some(2), None, None, Some(32768)],
+            [None, Some(4), None, None],
+            [None, None, Some(1024), None],
+            [None, None, None, Some(2048)],
+        ]);
+
+        assert_eq!("100f020000a0000b".parse::<Board>().unwrap(), board);
+        assert_eq!(board.to_code(), "100f020000a0000b");
+    }
+
+    #[test]
+    fn board_code_rejects_bad_input() {
+        assert!("123".parse::<Board>().is_err());
+        assert!("000000000000000g".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn set_cell_rejects_tiles_the_board_cannot_hold() {
+        let mut board = Board::default();
+
+        assert!(board.set_cell(0, 0, Some(MAX_TILE)).is_ok());
+        for value in [1, 3, 2 * MAX_TILE] {
+            assert!(board.set_cell(0, 0, Some(value)).is_err(), "{value}");
+        }
+        assert_eq!(board.cell(0, 0), Some(MAX_TILE));
+    }
+
+    #[test]
+    fn slide_moves_and_merges_in_every_direction() {
+        let board: Board = "1102000020101100".parse().unwrap();
+
+        let slides = GameAction::ALL.map(|direction| {
+            let (board, score) = board.slide(direction);
+            (board.to_code(), score)
+        });
+
+        assert_eq!(
+            slides,
+            [
+                ("1212200010000000".to_string(), 4),
+                ("0000100020001212".to_string(), 4),
+                ("2200000021002000".to_string(), 8),
+                ("0022000000210002".to_string(), 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn slide_does_not_merge_the_largest_tiles() {
+        let board: Board = "ff00eeee00000000".parse().unwrap();
+
+        let (left, score) = board.slide(GameAction::Left);
+
+        assert_eq!(left.to_code(), "ff00ff0000000000");
+        assert_eq!(score, 2 * MAX_TILE);
+        assert!(!"ff12123423453456".parse::<Board>().unwrap().can_move());
+    }
+
+    #[test]
+    fn walls_split_lines_and_stay_put() {
+        let board: Board = "11x100x011110x00".parse().unwrap();
+
+        let slides = GameAction::ALL.map(|direction| {
+            let (board, score) = board.slide(direction);
+            (board.to_code(), score)
+        });
+
+        assert_eq!(
+            slides,
+            [
+                ("22x200x000100x00".to_string(), 12),
+                ("00x000x002002x12".to_string(), 12),
+                ("20x100x022000x00".to_string(), 12),
+                ("02x100x000220x00".to_string(), 12),
+            ]
+        );
+        assert_eq!(board.tile(0, 2), Some(Tile::Wall));
+        assert_eq!(board.cell(0, 2), None);
+        assert!(!board.empty_cells().any(|cell| cell == (0, 2)));
+    }
+
+    #[test]
+    fn gravity_boards_drop_their_tiles_after_sliding() {
+        let board: Board = "gravity:1010000001000000".parse().unwrap();
+
+        let (left, score) = board.slide(GameAction::Left);
+        assert_eq!(left.to_code(), "gravity:0000000020001000");
+        assert_eq!(score, 4);
+        let (up, _) = board.slide(GameAction::Up);
+        assert_eq!(up.to_code(), "gravity:0000000000001110");
+
+        // Tiles slid up fall back, so a bottom row with no merge is stuck.
+        let stuck: Board = "gravity:0000000000001212".parse().unwrap();
+        assert!(!stuck.can_move());
+        let spawns: Vec<_> = stuck.spawn_cells().collect();
+        assert_eq!(spawns, [(2, 0), (2, 1), (2, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn tiles_slide_past_voids() {
+        let board: Board = "~11~1~010000~10~".parse().unwrap();
+
+        let (up, up_score) = board.slide(GameAction::Up);
+        let (left, left_score) = board.slide(GameAction::Left);
+
+        assert_eq!((up.to_code().as_str(), up_score), ("~21~1~010000~00~", 4));
+        assert_eq!(
+            (left.to_code().as_str(), left_score),
+            ("~20~2~000000~10~", 8)
+        );
+        assert_eq!(board.tile(0, 0), Some(Tile::Void));
+        assert_eq!(board.empty_cells().count(), 6);
+        let json = serde_json::to_value(board).unwrap();
+        assert_eq!(
+            json["voids"],
+            serde_json::json!([[0, 0], [0, 3], [1, 1], [3, 0], [3, 3]])
+        );
+        assert_eq!(serde_json::from_value::<Board>(json).unwrap(), board);
+    }
+
+    #[test]
+    fn fibonacci_boards_merge_consecutive_tiles() {
+        let board: Board = "fibonacci:112333001300ef00".parse().unwrap();
+        assert_eq!(board.variant(), Variant::Fibonacci);
+        assert_eq!(board.cell(0, 3), Some(3));
+        assert_eq!(board.cell(3, 1), Some(987));
+
+        let (slid, score) = board.slide(GameAction::Left);
+
+        // 1 + 1 and 2 + 3 merge, while 3 and 3, 1 and 3, and 610 and 987,
+        // whose sum the board cannot hold, do not.
+        assert_eq!(slid.to_code(), "fibonacci:240033001300ef00");
+        assert_eq!(score, 2 + 5);
+        let mut board = board;
+        assert!(board.set_cell(0, 0, Some(4)).is_err());
+        assert!("fibonacci:1x".parse::<Board>().is_err());
+        assert!("hexagonal:0000000000000000".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn triples_boards_score_by_their_rule() {
+        let board: Board = "triples:1120330000000000".parse().unwrap();
+
+        let (slid, score) = board.slide(GameAction::Left);
+
+        assert_eq!(slid.to_code(), "triples:2200400000000000");
+        assert_eq!(score, 9 + 81 * 3);
+    }
+
+    #[test]
+    fn threes_boards_shift_one_cell() {
+        let board: Board = "threes:0120123333121122".parse().unwrap();
+
+        let (slid, score) = board.slide(GameAction::Left);
+
+        // Each row moves from its first tile that can move: into the empty
+        // first cell, by 1 + 2, by 3 + 3 and by the second 1 + 2.
+        assert_eq!(slid.to_code(), "threes:1200333041201320");
+        assert_eq!(score, 3 + 6 + 3);
+        let (slid, _) = board.slide(GameAction::Right);
+        assert_eq!(slid.to_code(), "threes:0012012403330132");
+        assert!(
+            !"threes:1111111111111111"
+                .parse::<Board>()
+                .unwrap()
+                .can_move()
+        );
+    }
+
+    #[test]
+    fn rectangular_boards_slide_in_every_direction() {
+        let board: Board = "110000/000000/000000/200021".parse().unwrap();
+        assert_eq!((board.rows(), board.cols()), (4, 6));
+
+        let slides = GameAction::ALL.map(|direction| {
+            let (board, score) = board.slide(direction);
+            (board.to_code(), score)
+        });
+
+        assert_eq!(
+            slides,
+            [
+                ("110021/200000/000000/000000".to_string(), 0),
+                ("000000/000000/100000/210021".to_string(), 0),
+                ("200000/000000/000000/310000".to_string(), 12),
+                ("000002/000000/000000/000031".to_string(), 12),
+            ]
+        );
+        assert_eq!(board.cell(3, 5), Some(2));
+        assert_eq!(board.empty_cells().count(), 19);
+        assert!("110/0000".parse::<Board>().is_err());
+        assert!("0/0".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn wrapping_lines_are_led_by_the_tile_with_most_room() {
+        let board: Board = "wrap:1002110001011021".parse().unwrap();
+        assert!(board.wraps());
+
+        let (left, score) = board.slide(GameAction::Left);
+        assert_eq!(
+            (left.to_code(), score),
+            ("wrap:2100200020002200".into(), 12)
+        );
+        let (right, score) = board.slide(GameAction::Right);
+        assert_eq!(
+            (right.to_code(), score),
+            ("wrap:0021000200020022".into(), 12)
+        );
+
+        let classic: Board = "1002110001011021".parse().unwrap();
+        assert_eq!(
+            classic.slide(GameAction::Left).0.to_code(),
+            "1200200020001210"
+        );
+        assert_eq!(wrap_start([true, false, true, true]), 2);
+        assert_eq!(wrap_start([false, true, false, true]), 1);
+        assert_eq!(wrap_start([false; 4]), 0);
+    }
+
+    #[test]
+    fn lines_with_walls_and_threes_boards_do_not_wrap() {
+        let walled: Board = "wrap:100x000000000000".parse().unwrap();
+        let (left, _) = walled.slide(GameAction::Left);
+        assert_eq!(left.to_code(), "wrap:100x000000000000");
+
+        let threes: Board = "threes:wrap:1002000000000000".parse().unwrap();
+        assert!(!threes.wraps());
+        assert_eq!(threes.to_code(), "threes:wrap:1002000000000000");
+        let (left, _) = threes.slide(GameAction::Left);
+        assert_eq!(left.to_code(), "threes:wrap:1020000000000000");
+    }
+
+    #[test]
+    fn set_cell_replaces_a_wall() {
+        let mut board = Board::default();
+
+        board.set_wall(1, 1);
+        assert!(board.is_wall(1, 1));
+        board.set_cell(1, 1, Some(2)).unwrap();
+
+        assert!(!board.is_wall(1, 1));
+        assert_eq!(board.tile(1, 1), Some(Tile::Value(2)));
+    }
+
+    #[test]
+    fn serializes_as_cells() {
+        let board: Board = "1000000000000002".parse().unwrap();
+
+        let json = serde_json::to_value(board).unwrap();
+        assert_eq!(json["cells"][3][3], 4);
+        assert!(json.get("walls").is_none());
+        assert_eq!(serde_json::from_value::<Board>(json).unwrap(), board);
+
+        let walled: Board = "100000x000000002".parse().unwrap();
+        let json = serde_json::to_value(walled).unwrap();
+        assert_eq!(json["walls"], serde_json::json!([[1, 2]]));
+        assert_eq!(serde_json::from_value::<Board>(json).unwrap(), walled);
+
+        let fibonacci: Board = "fibonacci:1000000000000004".parse().unwrap();
+        let json = serde_json::to_value(fibonacci).unwrap();
+        assert_eq!(json["variant"], "fibonacci");
+        assert_eq!(json["cells"][3][3], 5);
+        assert_eq!(serde_json::from_value::<Board>(json).unwrap(), fibonacci);
+
+        let wrapped: Board = "fibonacci:wrap:1000000000000004".parse().unwrap();
+        let json = serde_json::to_value(wrapped).unwrap();
+        assert_eq!(json["wrap"], true);
+        assert_eq!(serde_json::from_value::<Board>(json).unwrap(), wrapped);
+
+        let wide: Board = "10000/00000/00002".parse().unwrap();
+        let json = serde_json::to_value(wide).unwrap();
+        assert_eq!(json["cells"].as_array().unwrap().len(), 3);
+        assert_eq!(json["cells"][2][4], 4);
+        assert_eq!(serde_json::from_value::<Board>(json).unwrap(), wide);
+    }
+
+    #[test]
+    fn display_renders_grid() {
+        let board: Board = "1000000000000002".parse().unwrap();
+        let expected = [
+            "     2     .     .     .",
+            "     .     .     .     .",
+            "     .     .     .     .",
+            "     .     .     .     4",
+        ];
+
+        assert_eq!(board.to_string().lines().collect::<Vec<_>>(), expected);
+    }
 }
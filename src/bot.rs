@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use rhai::{AST, Array, Dynamic, Engine, INT, Scope};
+
+use crate::board::{Board, Tile};
+use crate::game::GameAction;
+
+// The function a bot script defines to pick each move.
+const CHOOSE_MOVE: &str = "choose_move";
+
+// Operations a script may take to choose one move, so a script stuck in a
+// loop stops with an error instead of freezing the game.
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+// A Rhai script that plays in place of the AI. It defines
+// `choose_move(board, score)`, which is given the board as an array of rows,
+// each an array of tiles with 0 for an empty cell, -1 for a wall and -2 for a
+// void, and returns "up", "down", "left" or "right".
+pub struct Bot {
+    engine: Engine,
+    ast: AST,
+}
+
+impl Bot {
+    pub fn load(path: &Path) -> Result<Self> {
+        let script = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Bot::new(&script)
+            .with_context(|| format!("Failed to load {}", path.display()))
+    }
+
+    pub fn new(script: &str) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile(script)?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == CHOOSE_MOVE && f.params.len() == 2)
+        {
+            bail!("The script does not define {CHOOSE_MOVE}(board, score)");
+        }
+        Ok(Self { engine, ast })
+    }
+
+    // Asks the script for its move in a position.
+    pub fn choose_move(&self, board: &Board, score: u32) -> Result<GameAction> {
+        let rows: Array = (0..board.rows())
+            .map(|row| {
+                let cells: Array = (0..board.cols())
+                    .map(|col| match board.tile(row, col) {
+                        Some(Tile::Value(value)) => INT::from(value),
+                        Some(Tile::Wall) => -1,
+                        Some(Tile::Void) => -2,
+                        None => 0,
+                    })
+                    .map(Dynamic::from)
+                    .collect();
+                Dynamic::from(cells)
+            })
+            .collect();
+        let choice: Dynamic = self.engine.call_fn(
+            &mut Scope::new(),
+            &self.ast,
+            CHOOSE_MOVE,
+            (rows, INT::from(score)),
+        )?;
+        let direction = choice.clone().into_string().unwrap_or_default();
+        serde_json::from_value(direction.to_ascii_lowercase().into()).map_err(
+            |_| {
+                anyhow!(
+                    "{CHOOSE_MOVE} returned {choice}, expected \"up\", \
+                     \"down\", \"left\" or \"right\""
+                )
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_see_the_board_and_choose_a_move() {
+        let bot = Bot::new(
+            r#"
+            fn choose_move(board, score) {
+                if board[0][1] == -1 && board[0][0] == 2 && score == 8 {
+                    "Left"
+                } else {
+                    "up"
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let board: Board = "1x00000000000000".parse().unwrap();
+
+        assert_eq!(bot.choose_move(&board, 8).unwrap(), GameAction::Left);
+        assert_eq!(bot.choose_move(&board, 0).unwrap(), GameAction::Up);
+    }
+
+    #[test]
+    fn bad_scripts_are_rejected() {
+        assert!(Bot::new("fn pick(board) { \"up\" }").is_err());
+        assert!(Bot::new("fn choose_move(board, score) {").is_err());
+
+        let board = Board::default();
+        let sideways =
+            Bot::new("fn choose_move(board, score) { \"sideways\" }").unwrap();
+        assert!(sideways.choose_move(&board, 0).is_err());
+        let endless =
+            Bot::new("fn choose_move(board, score) { loop {} }").unwrap();
+        assert!(endless.choose_move(&board, 0).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+use crate::replay::ReplayFrame;
+
+// Appended to a client's key before hashing it to accept a WebSocket
+// connection (RFC 6455).
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// Served to browsers that open the broadcast address: it connects back over
+// WebSocket and shows each frame as it arrives.
+const PAGE: &str = r#"<!doctype html>
+<meta charset="utf-8">
+<title>2048</title>
+<pre id="game">Waiting for the game to start</pre>
+<script>
+const game = document.getElementById("game");
+const socket = new WebSocket(`ws://${location.host}/`);
+socket.onmessage = (event) => {
+  const frame = JSON.parse(event.data);
+  const rows = frame.board.cells.map((row) =>
+    row.map((tile) => String(tile ?? ".").padStart(6)).join(""));
+  const state = frame.game_over ? "   Game over" : "";
+  game.textContent = `Score ${frame.score}${state}\n\n${rows.join("\n")}`;
+};
+socket.onclose = () => (game.textContent += "\n\nThe game has ended");
+</script>
+"#;
+
+// Sends the latest position of the game to every spectator connected to the
+// broadcast address, as the JSON of a replay frame. Spectators that fall
+// behind skip to the latest frame.
+pub struct Broadcaster {
+    frames: watch::Sender<String>,
+}
+
+impl Broadcaster {
+    // Serves spectators on `listener`, starting from `frame`.
+    pub fn serve(listener: TcpListener, frame: &ReplayFrame) -> Result<Self> {
+        let (frames, latest) = watch::channel(serde_json::to_string(frame)?);
+        tokio::spawn(accept(listener, latest));
+        Ok(Self { frames })
+    }
+
+    pub fn send(&self, frame: &ReplayFrame) -> Result<()> {
+        self.frames.send_replace(serde_json::to_string(frame)?);
+        Ok(())
+    }
+}
+
+// Accepts spectators until the listener fails.
+async fn accept(
+    listener: TcpListener,
+    latest: watch::Receiver<String>,
+) -> Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(spectate(stream, latest.clone()));
+    }
+}
+
+// Upgrades a connection to WebSocket and sends it every frame from the
+// latest one on, or serves the spectator page to a plain HTTP request.
+async fn spectate(mut stream: TcpStream, mut latest: watch::Receiver<String>) {
+    let mut request = [0; 4096];
+    let Ok(len) = stream.read(&mut request).await else {
+        return;
+    };
+
+    let request = String::from_utf8_lossy(&request[..len]);
+    let Some(key) = websocket_key(&request) else {
+        let response = format!(
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/html; charset=utf-8\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{PAGE}",
+            PAGE.len()
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        return;
+    };
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    );
+    if stream.write_all(response.as_bytes()).await.is_err() {
+        return;
+    }
+    loop {
+        let frame = text_frame(&latest.borrow_and_update());
+        if stream.write_all(&frame).await.is_err()
+            || latest.changed().await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+// The key of a WebSocket upgrade request, if it is one.
+fn websocket_key(request: &str) -> Option<&str> {
+    request.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("sec-websocket-key")
+            .then(|| value.trim())
+    })
+}
+
+// The answer to a client's key that accepts its connection.
+fn accept_key(key: &str) -> String {
+    STANDARD.encode(sha1(format!("{key}{WEBSOCKET_GUID}").as_bytes()))
+}
+
+// Wraps a message in an unmasked WebSocket text frame, as servers send them.
+fn text_frame(text: &str) -> Vec<u8> {
+    let len = text.len();
+    let mut frame = vec![0x81];
+    if len < 126 {
+        frame.push(len as u8);
+    } else if let Ok(len) = u16::try_from(len) {
+        frame.push(126);
+        frame.extend_from_slice(&len.to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(text.as_bytes());
+    frame
+}
+
+// SHA-1, which the WebSocket handshake needs and nothing else here does.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [
+        0x6745_2301,
+        0xefcd_ab89,
+        0x98ba_dcfe,
+        0x1032_5476,
+        0xc3d2_e1f0,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word =
+                u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            words[i] =
+                (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16])
+                    .rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.into_iter().enumerate() {
+            let (f, k) = match i {
+                0..20 => (b & c | !b & d, 0x5a82_7999),
+                20..40 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..60 => (b & c | b & d | c & d, 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            (a, b, c, d, e) = (next, a, b.rotate_left(30), c, d);
+        }
+        for (total, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *total = total.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::game::{Game, GameAction};
+
+    #[test]
+    fn accept_key_matches_the_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        let key = "GET / HTTP/1.1\r\nSec-WebSocket-Key: abc==\r\n\r\n";
+        assert_eq!(websocket_key(key), Some("abc=="));
+        assert_eq!(websocket_key("GET / HTTP/1.1\r\n\r\n"), None);
+    }
+
+    #[test]
+    fn text_frames_encode_their_length() {
+        assert_eq!(text_frame("hi"), [0x81, 2, b'h', b'i']);
+        assert_eq!(text_frame(&"a".repeat(300))[..4], [0x81, 126, 1, 44]);
+        assert_eq!(text_frame(&"a".repeat(70_000))[1], 127);
+    }
+
+    #[tokio::test]
+    async fn spectators_receive_each_frame() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut game = Game::with_seed(1);
+        let broadcaster =
+            Broadcaster::serve(listener, &ReplayFrame::new(&game, None))
+                .unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = "GET / HTTP/1.1\r\nHost: test\r\nUpgrade: websocket\r\n\
+                       Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut read = async || {
+            let mut buffer = vec![0; 4096];
+            let len = stream.read(&mut buffer).await.unwrap();
+            String::from_utf8_lossy(&buffer[..len]).into_owned()
+        };
+        let mut response = read().await;
+        if !response.contains("\"moves\":0") {
+            response += &read().await;
+        }
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("\"moves\":0"));
+
+        let action = GameAction::ALL
+            .into_iter()
+            .find(|&action| Game::preview(game.board(), action).is_some())
+            .unwrap();
+        game.apply_move(action).unwrap();
+        broadcaster
+            .send(&ReplayFrame::new(&game, Some(action)))
+            .unwrap();
+        assert!(read().await.contains("\"moves\":1"));
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use serde_json::json;
+
+// Clears the screen and homes the cursor, so a player starts from a blank
+// terminal and after the terminal is resized.
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+// Records the frames drawn during play so a run can be shared: as an
+// asciinema v2 cast, or as a plain text dump of each frame for files ending
+// in `.txt`. Only the cells that changed are written to a cast, like they
+// are to the terminal.
+pub struct CastRecorder {
+    file: BufWriter<File>,
+    plain: bool,
+    start: Instant,
+    // The frame last written, empty until the first one.
+    previous: Buffer,
+}
+
+impl CastRecorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Self {
+            file: BufWriter::new(file),
+            plain: path.extension().is_some_and(|ext| ext == "txt"),
+            start: Instant::now(),
+            previous: Buffer::empty(Rect::ZERO),
+        })
+    }
+
+    // Writes a frame that has just been drawn.
+    pub fn frame(&mut self, buffer: &Buffer) -> Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+        if self.plain {
+            self.write_text(time, buffer)?;
+        } else {
+            self.write_event(time, buffer)?;
+        }
+        self.previous = buffer.clone();
+        Ok(())
+    }
+
+    // Writes the frames still buffered to the file.
+    pub fn finish(mut self) -> Result<()> {
+        self.file.flush().context("Failed to write the cast")
+    }
+
+    fn write_event(&mut self, time: f64, buffer: &Buffer) -> Result<()> {
+        let area = buffer.area;
+        let mut output = String::new();
+        if area != self.previous.area {
+            // The header takes the size of the first frame, and later sizes
+            // are resize events redrawn from a blank screen.
+            let line = if self.previous.area.is_empty() {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs());
+                json!({
+                    "version": 2,
+                    "width": area.width,
+                    "height": area.height,
+                    "timestamp": timestamp,
+                })
+            } else {
+                json!([time, "r", format!("{}x{}", area.width, area.height)])
+            };
+            writeln!(self.file, "{line}")?;
+            self.previous = Buffer::empty(area);
+            output.push_str(CLEAR);
+        }
+
+        let updates = self.previous.diff(buffer);
+        if updates.is_empty() && output.is_empty() {
+            return Ok(());
+        }
+        // The changes are turned into the escape sequences the terminal was
+        // sent
+        let mut bytes = Vec::new();
+        CrosstermBackend::new(&mut bytes).draw(updates.into_iter())?;
+        output.push_str(&String::from_utf8_lossy(&bytes));
+        writeln!(self.file, "{}", json!([time, "o", output]))?;
+        Ok(())
+    }
+
+    fn write_text(&mut self, time: f64, buffer: &Buffer) -> Result<()> {
+        if buffer == &self.previous {
+            return Ok(());
+        }
+        let area = buffer.area;
+        writeln!(self.file, "--- {time:.3}s")?;
+        for y in area.top()..area.bottom() {
+            let line: String = (area.left()..area.right())
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            writeln!(self.file, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::persistence;
+
+    #[test]
+    fn cast_has_a_header_and_an_event_per_changed_frame() {
+        let dir = persistence::test_dir("cast-events");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run.cast");
+        let mut recorder = CastRecorder::create(&path).unwrap();
+        let mut buffer = Buffer::with_lines(["2048", "    "]);
+        recorder.frame(&buffer).unwrap();
+        recorder.frame(&buffer).unwrap();
+        buffer[(0, 1)].set_symbol("4");
+        recorder.frame(&buffer).unwrap();
+        recorder.finish().unwrap();
+
+        let cast = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = cast
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 4);
+        assert_eq!(lines[0]["height"], 2);
+        assert_eq!(lines[1][1], "o");
+        assert!(lines[1][2].as_str().unwrap().contains("2048"));
+        let last = lines[2][2].as_str().unwrap();
+        assert!(last.contains('4') && !last.contains("2048"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn text_dump_holds_each_frame() {
+        let dir = persistence::test_dir("cast-text");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run.txt");
+        let mut recorder = CastRecorder::create(&path).unwrap();
+        recorder.frame(&Buffer::with_lines(["2048 "])).unwrap();
+        recorder.frame(&Buffer::with_lines(["4096 "])).unwrap();
+        recorder.finish().unwrap();
+
+        let dump = fs::read_to_string(&path).unwrap();
+        let frames: Vec<&str> = dump
+            .lines()
+            .filter(|line| !line.starts_with("---"))
+            .collect();
+        assert_eq!(frames, ["2048", "4096"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+use clap::ValueEnum;
+use rand::prelude::*;
+use rust_2048::game::GameAction;
+
+// A challenge that changes what the move keys do, so that the usual controls
+// have to be unlearned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Challenge {
+    /// Left and right swap places, as in a mirror
+    Mirror,
+    /// The moves are dealt out to the move keys afresh after every move
+    Chaos,
+}
+
+impl Challenge {
+    // What the board's border calls the challenge while it is on.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Challenge::Mirror => "⇄ Mirrored controls",
+            Challenge::Chaos => "⁇ Chaos controls",
+        }
+    }
+}
+
+// Turns the move the player asked for into the move played, between the
+// commands decoded from input and the game.
+pub struct Controls {
+    challenge: Option<Challenge>,
+    // The move played for each move asked for, in the order of
+    // `GameAction::ALL`.
+    layout: [GameAction; 4],
+}
+
+impl Controls {
+    pub fn new(challenge: Option<Challenge>) -> Self {
+        let mut controls = Self {
+            challenge,
+            layout: GameAction::ALL,
+        };
+        if challenge == Some(Challenge::Mirror) {
+            controls.layout.swap(2, 3);
+        }
+        controls.shuffle();
+        controls
+    }
+
+    pub fn challenge(&self) -> Option<Challenge> {
+        self.challenge
+    }
+
+    pub fn map(&self, action: GameAction) -> GameAction {
+        let index = GameAction::ALL
+            .iter()
+            .position(|&other| other == action)
+            .unwrap_or_default();
+        self.layout[index]
+    }
+
+    // Deals the moves out again in chaos mode, called after each move that
+    // changed the board.
+    pub fn shuffle(&mut self) {
+        if self.challenge == Some(Challenge::Chaos) {
+            self.layout.shuffle(&mut rand::rng());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirror_swaps_left_and_right() {
+        let mut controls = Controls::new(Some(Challenge::Mirror));
+        controls.shuffle();
+        assert_eq!(controls.map(GameAction::Left), GameAction::Right);
+        assert_eq!(controls.map(GameAction::Right), GameAction::Left);
+        assert_eq!(controls.map(GameAction::Up), GameAction::Up);
+        assert_eq!(Controls::new(None).map(GameAction::Left), GameAction::Left);
+    }
+
+    #[test]
+    fn chaos_deals_out_every_move_once() {
+        let mut controls = Controls::new(Some(Challenge::Chaos));
+        for _ in 0..20 {
+            controls.shuffle();
+            let mut played = GameAction::ALL.map(|action| controls.map(action));
+            played.sort_by_key(GameAction::letter);
+            let mut all = GameAction::ALL;
+            all.sort_by_key(GameAction::letter);
+            assert_eq!(played, all);
+        }
+    }
+}
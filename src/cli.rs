@@ -0,0 +1,548 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::ai::DEFAULT_DEPTH;
+use crate::board::{Board, Shape};
+use crate::challenge::Challenge;
+use crate::keymap::Layout;
+use crate::label::LabelMode;
+use crate::profile::DEFAULT_PROFILE;
+use crate::replay::DEFAULT_SPEED;
+use crate::rules::{Difficulty, Variant};
+use crate::simulate::Format;
+use crate::strategy::{DEFAULT_ROLLOUTS, StrategyKind};
+use crate::theme::ThemeName;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "2048",
+    version,
+    about,
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    /// Player profile whose settings and history are used
+    #[arg(long, global = true, default_value = DEFAULT_PROFILE)]
+    pub profile: String,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    // Playing is the default, so its options are also accepted without a
+    // subcommand.
+    #[command(flatten)]
+    pub play: PlayArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Play a game (the default when no subcommand is given)
+    Play(Box<PlayArgs>),
+    /// Play in a native window instead of the terminal
+    #[cfg(feature = "gui")]
+    Gui,
+    /// Play today's daily game, dealt the same tiles for every player on a
+    /// given day
+    Daily,
+    /// Print statistics from the profile's game history
+    Stats,
+    /// List the player profiles with their games played and best score
+    Profiles,
+    /// Analyze a position and print the expected value of each move
+    Solve {
+        /// Board code: 16 hex digits in row-major order holding each tile's
+        /// exponent, 0 for an empty cell or x for a wall (e.g.
+        /// 1100000000000002)
+        board: Board,
+        /// Number of moves to search ahead, at least 1
+        #[arg(long, default_value_t = DEFAULT_DEPTH, value_parser = clap::value_parser!(u32).range(1..))]
+        depth: u32,
+    },
+    /// Measure how fast the engine plays moves and searches positions
+    Bench {
+        /// Number of seconds to spend measuring
+        #[arg(long, default_value_t = 4)]
+        seconds: u64,
+        /// Search depth used to measure nodes per second
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+    },
+    /// Play many games without the UI and print statistics of how they
+    /// ended
+    #[command(visible_alias = "sim")]
+    Simulate {
+        /// Number of games to play
+        #[arg(long, default_value_t = 100)]
+        games: usize,
+        /// How moves are chosen: random, greedy (the most points right away),
+        /// expectimax (the AI) or montecarlo (the move whose random games
+        /// score the most)
+        #[arg(long, default_value_t = StrategyKind::default())]
+        strategy: StrategyKind,
+        /// Search depth of the expectimax strategy
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+        /// Games played out after each move by the montecarlo strategy
+        #[arg(long, default_value_t = DEFAULT_ROLLOUTS, value_parser = clap::value_parser!(u32).range(1..))]
+        rollouts: u32,
+        /// Merge rules of the games played
+        #[arg(long, default_value_t = Variant::default())]
+        variant: Variant,
+        /// Seed of the first game, each following game using the next one
+        #[arg(long)]
+        seed: Option<u64>,
+        /// How the statistics are printed
+        #[arg(long, value_enum, default_value_t = Format::default())]
+        format: Format,
+    },
+    /// Follow a replay being recorded by another game and show it live
+    Watch {
+        /// Replay file written with `play --record`
+        file: PathBuf,
+    },
+    /// Play back a recorded game, with controls to pause, step and change
+    /// speed
+    Replay {
+        /// Replay file written with `play --record`
+        file: PathBuf,
+        /// Moves shown per second, from 0.5 to 32
+        #[arg(long, default_value_t = DEFAULT_SPEED)]
+        speed: f64,
+    },
+    /// Replay a game written in move notation, checking every move and the
+    /// tile it spawned, and print the position it ends in
+    Verify {
+        /// File in move notation, as saved with N after a game ends
+        file: PathBuf,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Default, Args)]
+pub struct PlayArgs {
+    /// How tile values are labeled, overriding the profile setting
+    #[arg(long, value_enum)]
+    pub labels: Option<LabelMode>,
+
+    /// Colors and borders the game is drawn with, overriding the profile
+    /// setting (switch with C while playing)
+    #[arg(long, value_enum)]
+    pub theme: Option<ThemeName>,
+
+    /// Mark tile borders with a pattern for each value, so tiles can be
+    /// told apart without relying on color
+    #[arg(long)]
+    pub markers: bool,
+
+    /// Keyboard layout preset, overriding the profile setting
+    #[arg(long, value_enum)]
+    pub layout: Option<Layout>,
+
+    /// Start a new game this many seconds after a game ends, overriding the
+    /// profile setting
+    #[arg(long, value_name = "SECS")]
+    pub auto_restart: Option<u64>,
+
+    /// Save the game and exit after this many minutes without input,
+    /// overriding the profile setting
+    #[arg(long, value_name = "MINS")]
+    pub idle_timeout: Option<u64>,
+
+    /// Quit right away, without asking first while a game is in progress
+    #[arg(long)]
+    pub no_confirm: bool,
+
+    /// Show the internal state of the game beside the board: the seed, the
+    /// raw board, the evaluation of the position and how long drawing takes
+    /// (toggle with F12)
+    #[arg(long)]
+    pub debug: bool,
+
+    /// Play against the clock: each game ends when this many minutes of
+    /// play have passed (3 if no value is given)
+    #[arg(
+        long,
+        value_name = "MINS",
+        num_args = 0..=1,
+        default_missing_value = "3"
+    )]
+    pub blitz: Option<u64>,
+
+    /// Challenge mode: drop a tile of the lowest value on an empty cell every
+    /// this many seconds of play, whether a move was made or not
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub garbage: Option<u64>,
+
+    /// Challenge mode: change what the move keys do, either mirror, where
+    /// left and right swap, or chaos, where the moves are dealt out to the
+    /// keys afresh after every move
+    #[arg(long, value_enum, conflicts_with = "headless")]
+    pub challenge: Option<Challenge>,
+
+    /// Tile that wins the game: in the classic game a power of two from 8 to
+    /// 32768 (2048 by default)
+    #[arg(long, value_name = "TILE", value_parser = parse_target)]
+    pub target: Option<u32>,
+
+    /// Start a new game with other merge rules: fibonacci, where consecutive
+    /// Fibonacci numbers merge, threes, where tiles move one cell at a time,
+    /// or triples, where equal tiles merge into three times their value
+    #[arg(long, value_name = "VARIANT", conflicts_with_all = ["import", "start"])]
+    pub variant: Option<Variant>,
+
+    /// Start a new game on a board of this many rows by columns, e.g. 4x6 or
+    /// 5x3, from 2 to 8 each and at most 32 cells (4x4 by default)
+    #[arg(long, value_name = "ROWSxCOLS", conflicts_with_all = ["import", "start"])]
+    pub size: Option<Shape>,
+
+    /// Start a new game whose tiles sliding off one edge come back in from
+    /// the opposite one, as on a torus
+    #[arg(long, conflicts_with_all = ["import", "start"])]
+    pub wrap: bool,
+
+    /// Start a new game in which each tile after a move is placed where it
+    /// hurts most, as chosen by a search, instead of at random (evil 2048)
+    #[arg(long, conflicts_with_all = ["import", "start"])]
+    pub evil: bool,
+
+    /// Start a new game in which a power-up is earned every 1000 points, in
+    /// turn: swap two tiles (1), delete a tile (2) or upgrade a tile (3)
+    #[arg(long, conflicts_with_all = ["import", "start", "headless"])]
+    pub power_ups: bool,
+
+    /// Start a new game in which merging moves in a row build a combo that
+    /// multiplies their points: x2, x3 and so on up to x8
+    #[arg(long, conflicts_with_all = ["import", "start"])]
+    pub combo: bool,
+
+    /// Start a new game at a difficulty: normal, hard, where only 4s spawn,
+    /// or expert, where 4s and now and then an 8 spawn
+    #[arg(long, value_name = "DIFFICULTY", conflicts_with_all = ["import", "start"])]
+    pub difficulty: Option<Difficulty>,
+
+    /// Start a new game instead of resuming the saved one
+    #[arg(long)]
+    pub new: bool,
+
+    /// Seed for the random tiles, so a game shown with the same seed can be
+    /// replayed exactly
+    #[arg(long, value_name = "SEED", conflicts_with = "import")]
+    pub seed: Option<u64>,
+
+    /// Let the AI play from the start (toggle it with P while playing)
+    #[arg(long)]
+    pub autoplay: bool,
+
+    /// Let the Rhai script at this path play in place of the AI, from the
+    /// start (toggle it with P): it defines `choose_move(board, score)`,
+    /// returning "up", "down", "left" or "right"
+    #[cfg(feature = "bot")]
+    #[arg(long, value_name = "SCRIPT", conflicts_with = "headless")]
+    pub bot: Option<PathBuf>,
+
+    /// How the AI picks its moves in autoplay: expectimax (the default),
+    /// montecarlo, greedy or random
+    #[arg(long, value_name = "STRATEGY")]
+    pub strategy: Option<StrategyKind>,
+
+    /// Games played out after each move by the montecarlo strategy
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub rollouts: Option<u32>,
+
+    /// Most moves the AI looks ahead for autoplay and hints, overriding the
+    /// profile setting (3 by default)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=8))]
+    pub ai_depth: Option<u32>,
+
+    /// Milliseconds the AI may search for each move, looking ahead one more
+    /// move at a time until they are spent, overriding the profile setting
+    #[arg(long, value_name = "MS")]
+    pub ai_time_budget: Option<u64>,
+
+    /// Probability below which the AI stops searching a run of tiles,
+    /// overriding the profile setting (0.0001 by default)
+    #[arg(long, value_name = "P", value_parser = parse_probability)]
+    pub ai_prune: Option<f64>,
+
+    /// Moves per second played by the AI, overriding the profile setting
+    #[arg(long, value_name = "MOVES")]
+    pub autoplay_speed: Option<f64>,
+
+    /// Play without the terminal UI: read moves (U, D, L or R) from stdin, one
+    /// per line, and write the board and score as JSON lines to stdout
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "autoplay",
+            "blitz",
+            "garbage",
+            "record",
+            "broadcast"
+        ]
+    )]
+    pub headless: bool,
+
+    /// Record every move to this file so the game can be followed with
+    /// `2048 watch`, or, if its name ends in .2048, as a compact log of the
+    /// seeds and moves that replays exactly with --play
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Record the frames drawn while playing as an asciinema cast to share
+    /// the run, or as a plain text dump of each frame if the file ends in
+    /// .txt
+    #[arg(long, value_name = "FILE", conflicts_with = "headless")]
+    pub record_cast: Option<PathBuf>,
+
+    /// Play back a move log recorded with `--record FILE.2048`, checking
+    /// that every move leads to the position recorded
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["headless", "record", "import", "start"]
+    )]
+    pub play: Option<PathBuf>,
+
+    /// Serve the game to spectators on this address, e.g. 127.0.0.1:9049:
+    /// open it in a browser, or connect over WebSocket to get each position
+    /// as JSON
+    #[arg(long, value_name = "ADDR")]
+    pub broadcast: Option<std::net::SocketAddr>,
+
+    /// Let another program drive the game through a Unix socket at this
+    /// path: it sends `move <up|down|left|right>`, `state` or `restart`, one
+    /// per line, and gets the game's state back as a JSON line
+    #[cfg(unix)]
+    #[arg(long, value_name = "SOCKET", conflicts_with = "headless")]
+    pub control: Option<PathBuf>,
+
+    /// Let other programs watch and play the game through a pipe, with the
+    /// messages of headless play: on Unix, moves are written to the FIFO
+    /// PATH.in and the state after every change read from PATH.out; on
+    /// Windows, both go through the named pipe PATH, e.g. \\.\pipe\2048
+    #[arg(long, value_name = "PATH", conflicts_with = "headless")]
+    pub pipe: Option<PathBuf>,
+
+    /// Start from a save exported by another 2048 implementation (browser
+    /// localStorage JSON or a plain-text grid) and import its best score
+    #[arg(long, value_name = "FILE")]
+    pub import: Option<PathBuf>,
+
+    /// Practice from a position, which restarting returns to: a layout with
+    /// rows separated by '/' and cells by ',' and empty cells left blank
+    /// (e.g. "2,2,,4/,,,/,8,,/,,,2"), a board code, or a file holding either
+    /// or any save --import reads
+    #[arg(long, value_name = "BOARD", conflicts_with_all = ["import", "seed"])]
+    pub start: Option<String>,
+
+    /// Play puzzles, each asking to reach a tile or a score from a set
+    /// position in a limited number of moves: the puzzles of a JSON file, or
+    /// the bundled starter pack if no file is given
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 0..=1,
+        conflicts_with_all = [
+            "import",
+            "start",
+            "seed",
+            "variant",
+            "size",
+            "wrap",
+            "evil",
+            "power_ups",
+            "combo",
+            "difficulty",
+            "blitz",
+            "garbage",
+            "headless"
+        ]
+    )]
+    pub puzzle: Option<Option<PathBuf>>,
+
+    /// Play the tutorial shown on the first launch again: a few lessons
+    /// showing how tiles merge and spawn and how the game ends
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "import",
+            "start",
+            "puzzle",
+            "seed",
+            "variant",
+            "size",
+            "wrap",
+            "evil",
+            "power_ups",
+            "combo",
+            "difficulty",
+            "blitz",
+            "garbage",
+            "autoplay",
+            "headless"
+        ]
+    )]
+    pub tutorial: bool,
+
+    /// File the current position is exported to with Ctrl-E and loaded from
+    /// with Ctrl-L
+    #[arg(long, value_name = "FILE", default_value = "2048-position.json")]
+    pub position: PathBuf,
+
+    /// Serve Prometheus metrics on this address, e.g. 127.0.0.1:9048
+    #[cfg(feature = "metrics")]
+    #[arg(long, value_name = "ADDR")]
+    pub metrics: Option<std::net::SocketAddr>,
+}
+
+// Accepts a winning tile of some variant that its starting tiles do not
+// already reach and its board can hold. Whether it is a tile of the variant
+// played is checked once the game is known.
+fn parse_target(value: &str) -> Result<u32, String> {
+    let target: u32 = value.parse().map_err(|e| format!("{e}"))?;
+    if !Variant::ALL.iter().any(|variant| variant.is_target(target)) {
+        return Err("expected a tile larger than the starting tiles, e.g. a \
+                    power of two from 8 to 32768"
+            .to_string());
+    }
+    Ok(target)
+}
+
+fn parse_probability(value: &str) -> Result<f64, String> {
+    let probability: f64 = value.parse().map_err(|e| format!("{e}"))?;
+    if !(0.0..1.0).contains(&probability) {
+        return Err("expected a probability from 0 up to 1".to_string());
+    }
+    Ok(probability)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_is_well_formed() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn play_options_are_accepted_without_subcommand() {
+        let cli = Cli::parse_from(["2048", "--labels", "superscript"]);
+        assert!(cli.command.is_none());
+        assert_eq!(cli.play.labels, Some(LabelMode::Superscript));
+    }
+
+    #[test]
+    fn solve_parses_board_code() {
+        let cli = Cli::parse_from(["2048", "solve", "1100000000000002"]);
+        let Some(Commands::Solve { board, depth }) = cli.command else {
+            panic!("expected the solve subcommand");
+        };
+        assert_eq!(board.cell(0, 1), Some(2));
+        assert_eq!(depth, DEFAULT_DEPTH);
+        assert!(Cli::try_parse_from(["2048", "solve", "xyz"]).is_err());
+        let no_depth = ["2048", "solve", "1100000000000002", "--depth", "0"];
+        assert!(Cli::try_parse_from(no_depth).is_err());
+    }
+
+    #[test]
+    fn simulate_parses_strategy() {
+        let cli = Cli::parse_from(["2048", "simulate", "--strategy", "greedy"]);
+        let Some(Commands::Simulate {
+            games, strategy, ..
+        }) = cli.command
+        else {
+            panic!("expected the simulate subcommand");
+        };
+        assert_eq!(games, 100);
+        assert_eq!(strategy, StrategyKind::Greedy);
+        assert!(
+            Cli::try_parse_from(["2048", "simulate", "--strategy", "minimax"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn daily_and_sim_are_subcommands() {
+        let cli = Cli::parse_from(["2048", "daily"]);
+        assert!(matches!(cli.command, Some(Commands::Daily)));
+        let cli = Cli::parse_from(["2048", "sim", "--games", "5"]);
+        let Some(Commands::Simulate { games, .. }) = cli.command else {
+            panic!("expected the simulate subcommand");
+        };
+        assert_eq!(games, 5);
+    }
+
+    #[test]
+    fn completions_list_the_subcommands() {
+        use clap::CommandFactory;
+        let mut script = Vec::new();
+        clap_complete::generate(
+            Shell::Bash,
+            &mut Cli::command(),
+            "2048",
+            &mut script,
+        );
+        let script = String::from_utf8(script).unwrap();
+        for name in ["play", "daily", "simulate", "sim", "completions"] {
+            assert!(script.contains(&format!("2048,{name})")), "{name}");
+        }
+    }
+
+    #[test]
+    fn play_conflicts_with_recording() {
+        let cli = Cli::parse_from(["2048", "--play", "run.2048"]);
+        assert_eq!(cli.play.play, Some(PathBuf::from("run.2048")));
+        assert!(
+            Cli::try_parse_from([
+                "2048", "--play", "run.2048", "--record", "out.2048"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn blitz_defaults_to_three_minutes() {
+        assert_eq!(Cli::parse_from(["2048", "--blitz"]).play.blitz, Some(3));
+        assert_eq!(
+            Cli::parse_from(["2048", "--blitz", "1"]).play.blitz,
+            Some(1)
+        );
+        assert_eq!(Cli::parse_from(["2048"]).play.blitz, None);
+    }
+
+    #[test]
+    fn target_must_be_a_tile_the_board_can_hold() {
+        let parse = |target| Cli::try_parse_from(["2048", "--target", target]);
+
+        assert_eq!(parse("4096").unwrap().play.target, Some(4096));
+        for target in ["4", "1000", "65536", "big"] {
+            assert!(parse(target).is_err(), "{target}");
+        }
+    }
+
+    #[test]
+    fn size_is_rows_by_columns() {
+        let parse = |size| Cli::try_parse_from(["2048", "--size", size]);
+
+        let shape = parse("4x6").unwrap().play.size.unwrap();
+        assert_eq!((shape.rows(), shape.cols()), (4, 6));
+        for size in ["4", "1x4", "9x2", "6x6", "4x"] {
+            assert!(parse(size).is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn profile_is_global() {
+        let cli = Cli::parse_from(["2048", "stats", "--profile", "alice"]);
+        assert!(matches!(cli.command, Some(Commands::Stats)));
+        assert_eq!(cli.profile, "alice");
+    }
+}
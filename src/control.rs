@@ -0,0 +1,168 @@
+#[cfg(unix)]
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use tokio::sync::{mpsc::Sender, oneshot};
+
+#[cfg(unix)]
+use crate::event::Event;
+use crate::game::{Game, GameAction};
+use crate::headless::State;
+
+// A command read from the control socket, one per line: `move <direction>`,
+// `state` or `restart`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlCommand {
+    Move(GameAction),
+    State,
+    Restart,
+}
+
+impl FromStr for ControlCommand {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let words: Vec<String> = line
+            .split_whitespace()
+            .map(str::to_ascii_lowercase)
+            .collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        Ok(match words[..] {
+            ["move", "up"] => ControlCommand::Move(GameAction::Up),
+            ["move", "down"] => ControlCommand::Move(GameAction::Down),
+            ["move", "left"] => ControlCommand::Move(GameAction::Left),
+            ["move", "right"] => ControlCommand::Move(GameAction::Right),
+            ["state"] => ControlCommand::State,
+            ["restart"] => ControlCommand::Restart,
+            _ => bail!(
+                "Unknown command '{line}', expected move up, down, left or \
+                 right, state or restart"
+            ),
+        })
+    }
+}
+
+// The reply to a command: the state of the game afterwards as a JSON line,
+// like headless output, with whether the board changed after a move.
+pub fn reply(game: &Game, moved: Option<bool>) -> Result<String> {
+    Ok(serde_json::to_string(&State::new(game, moved))?)
+}
+
+// Listens for agents on a Unix socket at `path`, replacing the socket a
+// previous game left behind.
+#[cfg(unix)]
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    use anyhow::Context;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path)
+        && metadata.file_type().is_socket()
+    {
+        std::fs::remove_file(path)?;
+    }
+    UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))
+}
+
+// Accepts agents until the listener fails. Their commands are handed to the
+// game as events, so they are played in turn with the keys pressed.
+#[cfg(unix)]
+pub async fn serve(
+    listener: UnixListener,
+    events: Sender<Event>,
+) -> Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(session(stream, events.clone()));
+    }
+}
+
+// Answers each command line of an agent with a JSON line, until the agent
+// disconnects or the game ends.
+#[cfg(unix)]
+async fn session(stream: UnixStream, events: Sender<Event>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut response = match line.parse() {
+            Ok(command) => {
+                let (reply, response) = oneshot::channel();
+                events.send(Event::Control { command, reply }).await?;
+                response.await?
+            }
+            Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+        };
+        response.push('\n');
+        writer.write_all(response.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse_case_insensitively() {
+        assert_eq!(
+            "move left".parse::<ControlCommand>().unwrap(),
+            ControlCommand::Move(GameAction::Left)
+        );
+        assert_eq!(
+            " MOVE  Up ".parse::<ControlCommand>().unwrap(),
+            ControlCommand::Move(GameAction::Up)
+        );
+        assert_eq!(
+            "state".parse::<ControlCommand>().unwrap(),
+            ControlCommand::State
+        );
+        for line in ["move", "move sideways", "restart now", "quit"] {
+            assert!(line.parse::<ControlCommand>().is_err(), "{line}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn agents_get_a_reply_to_each_command() {
+        let dir = std::env::temp_dir()
+            .join(format!("2048-control-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("control.sock");
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        tokio::spawn(serve(bind(&path).unwrap(), tx));
+        let game = Game::with_seed(1);
+        tokio::spawn(async move {
+            while let Some(Event::Control { command, reply }) = rx.recv().await
+            {
+                let moved = matches!(command, ControlCommand::Move(_));
+                let _ = reply.send(super::reply(&game, moved.then_some(true))?);
+            }
+            anyhow::Ok(())
+        });
+
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"state\nmove left\njump\n").await.unwrap();
+        let mut next = async || {
+            let line = lines.next_line().await.unwrap().unwrap();
+            serde_json::from_str::<serde_json::Value>(&line).unwrap()
+        };
+
+        assert!(next().await.get("moved").is_none());
+        assert_eq!(next().await["moved"], true);
+        assert!(next().await["error"].is_string());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,9 +1,44 @@
-#[derive(Debug, PartialEq)]
+use anyhow::Result;
+use rust_2048::ai::{Evaluation, SearchReport};
+use rust_2048::board::Board;
+use rust_2048::game::GameAction;
+use tokio::sync::oneshot;
+
+use crate::control::ControlCommand;
+use crate::keymap::Key;
+use crate::leaderboard::RemoteScore;
+
+#[derive(Debug)]
 pub enum Event {
-    MoveUp,
-    MoveDown,
-    MoveLeft,
-    MoveRight,
-    Quit,
-    Restart,
+    Key(Key),
+    Paste(String),
+    FocusGained,
+    FocusLost,
+    // The terminal changed size, so the frame is drawn again to fit.
+    Resize,
+    // The best move for a position, found off the UI thread, or `None` if no
+    // move is possible, and how the search went.
+    Hint {
+        board: Board,
+        action: Option<GameAction>,
+        report: Option<SearchReport>,
+    },
+    // The AI's evaluation of every move from a position, found off the UI
+    // thread while the evaluations are shown.
+    Analysis {
+        board: Board,
+        evaluations: [Evaluation; 4],
+        report: Option<SearchReport>,
+    },
+    // The global top scores fetched from the leaderboard server.
+    GlobalScores(Result<Vec<RemoteScore>>),
+    // A command from an agent on the control socket, answered with a JSON
+    // line once it has been carried out.
+    Control {
+        command: ControlCommand,
+        reply: oneshot::Sender<String>,
+    },
+    // A line from a tool on the control pipe: a move, or anything else to be
+    // answered with an error.
+    Pipe(String),
 }
@@ -1,16 +1,23 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
 use anyhow::{Result, bail};
 use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
 
-use crate::board::{BOARD_SIZE, Board};
+use crate::ai;
+use crate::board::{self, Board, Shape};
+use crate::rules::{Difficulty, MergeRule, Scoring, Settle, Slide, Variant};
+use crate::stats::GameStats;
 
-pub(crate) const TITLE: &str = " 2048 ";
+pub const TITLE: &str = " 2048 ";
 
 const STARTING_TILE_COUNT: usize = 2;
-const STARTING_TILE_TWO: u32 = 2;
-const STARTING_TILE_FOUR: u32 = 4;
-const STARTING_TILE_TWO_PROBABILITY: f64 = 0.9;
 
-#[derive(Debug)]
+/// A move: the direction every tile slides in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum GameAction {
     Up,
     Down,
@@ -18,21 +25,313 @@ pub enum GameAction {
     Right,
 }
 
+impl GameAction {
+    /// Every move, in the order up, down, left, right.
+    pub const ALL: [GameAction; 4] = [
+        GameAction::Up,
+        GameAction::Down,
+        GameAction::Left,
+        GameAction::Right,
+    ];
+
+    /// The arrow pointing in the move's direction.
+    pub fn arrow(&self) -> char {
+        match self {
+            GameAction::Up => '↑',
+            GameAction::Down => '↓',
+            GameAction::Left => '←',
+            GameAction::Right => '→',
+        }
+    }
+
+    /// The move's initial in upper case, e.g. 'U' for up.
+    pub fn letter(&self) -> char {
+        match self {
+            GameAction::Up => 'U',
+            GameAction::Down => 'D',
+            GameAction::Left => 'L',
+            GameAction::Right => 'R',
+        }
+    }
+
+    /// Parses a move's initial in either case.
+    pub fn from_letter(letter: char) -> Option<Self> {
+        GameAction::ALL
+            .into_iter()
+            .find(|action| action.letter() == letter.to_ascii_uppercase())
+    }
+}
+
+/// Creating a tile of this value wins the game, which can then continue.
+pub const WIN_TILE: u32 = 2048;
+
+/// Points a game with power-ups scores to earn each power-up.
+pub const POWER_UP_POINTS: u32 = 1000;
+
+/// A power-up played on the tiles in a game with power-ups. They are earned
+/// in turn, a swap first, as the score grows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUp {
+    /// Swaps two tiles.
+    Swap,
+    /// Removes a tile.
+    Delete,
+    /// Turns a tile into the next larger tile of the variant.
+    Upgrade,
+}
+
+impl PowerUp {
+    /// Every power-up, in the order they are earned.
+    pub const ALL: [PowerUp; 3] =
+        [PowerUp::Swap, PowerUp::Delete, PowerUp::Upgrade];
+
+    /// How many tiles the power-up is played on.
+    pub fn cells(&self) -> usize {
+        match self {
+            PowerUp::Swap => 2,
+            PowerUp::Delete | PowerUp::Upgrade => 1,
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+// Merges creating tiles of at least this value are logged.
+pub(crate) const NOTABLE_MERGE: u32 = 128;
+
+/// A notable merge: the tile it created and the move number that created it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Merge {
+    pub tile: u32,
+    pub move_number: usize,
+}
+
+/// A tile placed by the game after a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileSpawn {
+    pub row: usize,
+    pub col: usize,
+    pub tile: u32,
+}
+
+/// A move that changed the board and the points it scored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveRecord {
+    pub action: GameAction,
+    pub points: u32,
+}
+
+/// The board and score a move was played from, kept so a finished game can be
+/// reviewed move by move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub board: Board,
+    pub score: u32,
+}
+
+/// Chooses where the tile placed after each move goes and its value.
+pub trait Spawner {
+    /// Picks an empty cell of `board`, the position a move left, and one of
+    /// `tiles` to place there, given with their probabilities, or returns
+    /// `None` if no cell is empty.
+    fn spawn(
+        &mut self,
+        board: &Board,
+        tiles: &[(u32, f64)],
+    ) -> Option<TileSpawn>;
+}
+
+/// Source of the game's random tiles. Each spawn draws from its own
+/// generator keyed by the seed and the number of spawns so far, so the same
+/// seed and moves always reproduce the same game.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct RandomSpawner {
+    seed: u64,
+    spawns: u64,
+}
+
+impl RandomSpawner {
+    /// Creates a spawner drawing its tiles from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { seed, spawns: 0 }
+    }
+
+    fn next(&mut self) -> StdRng {
+        let mut key = [0; 32];
+        key[..8].copy_from_slice(&self.seed.to_le_bytes());
+        key[8..16].copy_from_slice(&self.spawns.to_le_bytes());
+        self.spawns += 1;
+        StdRng::from_seed(key)
+    }
+}
+
+impl Spawner for RandomSpawner {
+    fn spawn(
+        &mut self,
+        board: &Board,
+        tiles: &[(u32, f64)],
+    ) -> Option<TileSpawn> {
+        let mut rng = self.next();
+        let (row, col) = board.spawn_cells().choose(&mut rng)?;
+        let tile = Game::spawn_tile(&mut rng, tiles);
+        Some(TileSpawn { row, col, tile })
+    }
+}
+
+/// Places every tile where it hurts the player most, like "evil 2048": of
+/// the tiles it may place on each empty cell, it picks the one after
+/// which the player's best move leaves the worst position by the AI's
+/// heuristic, or a position with no move at all. It draws nothing at
+/// random, so a game against it replays from its moves alone.
 #[derive(Clone, Copy, Debug, Default)]
+pub struct AdversarialSpawner;
+
+impl Spawner for AdversarialSpawner {
+    fn spawn(
+        &mut self,
+        board: &Board,
+        tiles: &[(u32, f64)],
+    ) -> Option<TileSpawn> {
+        board
+            .spawn_cells()
+            .flat_map(|(row, col)| {
+                tiles
+                    .iter()
+                    .map(move |&(tile, _)| TileSpawn { row, col, tile })
+            })
+            .map(|spawn| (spawn, AdversarialSpawner::reply(board, spawn)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(spawn, _)| spawn)
+    }
+}
+
+impl AdversarialSpawner {
+    // Scores the position after `spawn` by the best position the player can
+    // reach from it in one move.
+    fn reply(board: &Board, spawn: TileSpawn) -> f64 {
+        let mut board = *board;
+        board
+            .set_cell(spawn.row, spawn.col, Some(spawn.tile))
+            .expect("the variant's own tiles always fit its board");
+        GameAction::ALL
+            .into_iter()
+            .filter_map(|action| Game::preview(&board, action))
+            .map(|(next, _)| ai::heuristic(&next))
+            .max_by(f64::total_cmp)
+            .unwrap_or(f64::NEG_INFINITY)
+    }
+}
+
+/// Number of moves that can be undone unless configured otherwise.
+pub const DEFAULT_UNDO_DEPTH: usize = 32;
+
+// A move that changed the board, with the positions before and after it so it
+// can be undone and redone exactly, spawned tile included.
+#[derive(Clone, Debug)]
+struct Step {
+    before: (Board, u32, u32, RandomSpawner, GameStats),
+    after: (Board, u32, u32, RandomSpawner, GameStats),
+    game_over: bool,
+    record: MoveRecord,
+    merges: Vec<Merge>,
+}
+
+// Moves that can be undone, most recent last, and the moves undone since the
+// last new move.
+#[derive(Debug)]
+struct Undo {
+    depth: usize,
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+}
+
+impl Default for Undo {
+    fn default() -> Self {
+        Self {
+            depth: DEFAULT_UNDO_DEPTH,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+impl Undo {
+    fn push(&mut self, step: Step) {
+        self.redo.clear();
+        self.undo.push_back(step);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// A cell of the board after a move.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub struct CellResult {
     pub value: Option<u32>,
+    /// Whether the tile was created by merging two tiles in this move.
     pub merged: bool,
+    /// Cells the tile came from before the move: one for a tile that slid (or
+    /// stayed put), two for a merge and none for a spawned tile.
+    pub from: [Option<(usize, usize)>; 2],
+    /// Whether the cell is a wall, which holds no tile.
+    pub wall: bool,
+    /// Whether the cell is a void, which is not part of the board.
+    pub void: bool,
 }
 
-#[derive(Debug, Default)]
+/// What a move, restart, undo or redo did to the game.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ActionOutcome {
+    /// The score of the game afterwards.
     pub score: u32,
+    /// The points scored by this move.
+    pub points: u32,
+    /// Whether the board changed; a move that changes nothing is not played.
     pub changed: bool,
     pub game_over: bool,
-    pub board: [[CellResult; BOARD_SIZE]; BOARD_SIZE],
+    /// Whether this move created the first winning tile of the game.
+    pub won: bool,
+    /// Every cell of the board afterwards, indexed by row and column.
+    pub board: Vec<Vec<CellResult>>,
+}
+
+// An outcome with every cell of a standard board empty.
+impl Default for ActionOutcome {
+    fn default() -> Self {
+        ActionOutcome::empty(Shape::default())
+    }
 }
 
 impl ActionOutcome {
+    // An outcome with every cell of a board of the given shape empty.
+    fn empty(shape: Shape) -> Self {
+        ActionOutcome {
+            score: 0,
+            points: 0,
+            changed: false,
+            game_over: false,
+            won: false,
+            board: vec![
+                vec![CellResult::default(); shape.cols()];
+                shape.rows()
+            ],
+        }
+    }
+
     fn iter_cells(
         &self,
     ) -> impl Iterator<Item = ((usize, usize), &CellResult)> {
@@ -50,40 +349,218 @@ impl From<&Game> for ActionOutcome {
         let mut outcome = ActionOutcome {
             score: game.score,
             game_over: game.game_over,
-            ..Default::default()
+            ..ActionOutcome::empty(game.board.shape())
         };
 
         for ((row, col), cell) in game.board.iter_cells() {
-            outcome.board[row][col].value = *cell;
+            outcome.board[row][col].value = cell;
+            outcome.board[row][col].wall = game.board.is_wall(row, col);
+            outcome.board[row][col].void = game.board.is_void(row, col);
         }
 
         outcome
     }
 }
 
-#[derive(Debug, Default)]
+/// A game of 2048: the board and score, the sequence of tiles still to
+/// spawn, and the history of moves that can be undone. It serializes with
+/// serde so a game can be saved and resumed.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Game {
     board: Board,
     score: u32,
+    #[serde(default)]
     game_over: bool,
+    // Whether a tile of `win_target` has been reached. Play continues after
+    // winning until no move is left.
+    #[serde(default)]
+    won: bool,
+    #[serde(default = "default_win_target")]
+    win_target: u32,
+    #[serde(default)]
+    spawner: RandomSpawner,
+    // Whether tiles are placed by an `AdversarialSpawner` rather than drawn
+    // from the seed.
+    #[serde(default)]
+    adversarial: bool,
+    // Whether power-ups are earned, and how many of each have been played.
+    #[serde(default)]
+    power_ups: bool,
+    #[serde(default)]
+    power_ups_used: [u32; 3],
+    // Whether the game started from the position its seed deals and only
+    // moves changed it since, so the seed and the moves replay it. A
+    // position set up or read from JSON, a power-up or a garbage tile rules
+    // that out.
+    #[serde(default)]
+    replayable: bool,
+    #[serde(default)]
+    scoring: Scoring,
+    #[serde(default)]
+    difficulty: Difficulty,
+    // The moves in a row, up to the latest, that merged tiles.
+    #[serde(default)]
+    combo: u32,
+    // Every move of the current game that changed the board, in order.
+    #[serde(default)]
+    moves: Vec<MoveRecord>,
+    // The position each of the latest moves was played from, in order. A
+    // game saved before positions were kept has them only for the moves
+    // played since it was resumed.
+    #[serde(default)]
+    positions: Vec<Position>,
+    // Notable merges of the current game, in order.
+    #[serde(default)]
+    merges: Vec<Merge>,
+    #[serde(default)]
+    stats: GameStats,
+    // Moves that can be undone and redone. They are not saved with the game.
+    #[serde(skip)]
+    undo: Undo,
+    // The tile spawned by the latest move, if it changed the board.
+    #[serde(skip)]
+    last_spawn: Option<TileSpawn>,
+}
+
+fn default_win_target() -> u32 {
+    WIN_TILE
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self {
+            board: Board::default(),
+            score: 0,
+            game_over: false,
+            won: false,
+            win_target: WIN_TILE,
+            spawner: RandomSpawner::default(),
+            adversarial: false,
+            power_ups: false,
+            power_ups_used: [0; 3],
+            replayable: false,
+            scoring: Scoring::Classic,
+            difficulty: Difficulty::Normal,
+            combo: 0,
+            moves: Vec::new(),
+            positions: Vec::new(),
+            merges: Vec::new(),
+            stats: GameStats::default(),
+            undo: Undo::default(),
+            last_spawn: None,
+        }
+    }
 }
 
 impl Game {
+    /// Starts a game with a random seed.
     pub fn new() -> Self {
+        Game::with_seed(rand::random())
+    }
+
+    /// Starts a game whose tiles are drawn from the given seed, so it can be
+    /// replayed exactly.
+    pub fn with_seed(seed: u64) -> Self {
+        Game::with_variant(Variant::Classic, seed)
+    }
+
+    /// Starts a game of a variant, with tiles drawn from the given seed.
+    pub fn with_variant(variant: Variant, seed: u64) -> Self {
+        Game::with_shape(variant, Shape::default(), seed)
+    }
+
+    /// Starts a game of a variant on a board of the given shape, with tiles
+    /// drawn from the given seed.
+    pub fn with_shape(variant: Variant, shape: Shape, seed: u64) -> Self {
+        let mut spawner = RandomSpawner::new(seed);
+        let board = Game::initialize_board(
+            &mut spawner.next(),
+            Board::with_shape(variant, shape),
+        );
         Self {
-            board: Game::initialize_board(),
+            board,
+            win_target: variant.rule().win_tile(),
+            spawner,
+            replayable: true,
+            stats: GameStats::new(&board),
             ..Default::default()
         }
     }
 
+    /// Starts a game from an existing position, e.g. one imported from another
+    /// implementation.
+    pub fn from_board(board: Board, score: u32) -> Self {
+        let mut game = Self {
+            board,
+            score,
+            win_target: board.variant().rule().win_tile(),
+            spawner: RandomSpawner::new(rand::random()),
+            stats: GameStats::from_board(&board),
+            ..Default::default()
+        };
+        game.won = game.max_tile() >= game.win_target;
+        let mut outcome = game.outcome();
+        game.check_game_over(&mut outcome);
+        game
+    }
+
+    /// Starts a game from a position whose next tiles are drawn from `seed`
+    /// after `spawns` tiles were, as a game with that seed and spawn count
+    /// would continue.
+    pub fn from_position(
+        board: Board,
+        score: u32,
+        seed: u64,
+        spawns: u64,
+    ) -> Self {
+        let mut game = Game::from_board(board, score);
+        game.spawner = RandomSpawner { seed, spawns };
+        game
+    }
+
+    /// Serializes the position, the seed of the tiles to come and the move
+    /// history as JSON, e.g. to share a position or keep it as a fixture.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Restores a game serialized with `Game::to_json`. The undo history is
+    /// not part of it, so the restored game starts without one. The JSON
+    /// may have been edited, so the game is not replayable.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut game: Self = serde_json::from_str(json)?;
+        game.replayable = false;
+        Ok(game)
+    }
+
+    /// Describes the current position without changing it.
     pub fn outcome(&self) -> ActionOutcome {
         ActionOutcome::from(self)
     }
 
+    /// Starts a new game, with a seed drawn from the current one.
     pub fn restart(&mut self) -> ActionOutcome {
         self.score = 0;
         self.game_over = false;
-        self.board = Game::initialize_board();
+        self.won = false;
+        self.moves.clear();
+        self.positions.clear();
+        self.merges.clear();
+        self.undo.clear();
+        self.last_spawn = None;
+        self.power_ups_used = [0; 3];
+        self.replayable = true;
+        self.combo = 0;
+        // Each new game continues the sequence of seeds, so a seeded session
+        // is reproducible from its first seed.
+        self.spawner = RandomSpawner::new(self.spawner.next().random());
+        // The new game is played on the same layout, walls and voids
+        // included.
+        self.board = Game::initialize_board(
+            &mut self.spawner.next(),
+            self.board.cleared(),
+        );
+        self.stats = GameStats::new(&self.board);
 
         // When restarting, we want to treat the new board as changed so that
         // the UI can update to show the new starting tiles.
@@ -96,6 +573,331 @@ impl Game {
         self.game_over
     }
 
+    pub fn is_won(&self) -> bool {
+        self.won
+    }
+
+    /// The tile that wins the game, 2048 unless set otherwise.
+    pub fn win_target(&self) -> u32 {
+        self.win_target
+    }
+
+    /// Sets the tile that wins the game, e.g. a lower one for a shorter game.
+    /// It carries over to the games that follow a restart.
+    pub fn set_win_target(&mut self, target: u32) {
+        self.win_target = target;
+        self.won = self.max_tile() >= target;
+    }
+
+    /// Whether tiles spawn where they hurt the player most rather than at
+    /// random.
+    pub fn is_adversarial(&self) -> bool {
+        self.adversarial
+    }
+
+    /// Spawns the tiles after each move with an [`AdversarialSpawner`], or
+    /// at random again. The starting tiles of a game are always random. It
+    /// carries over to the games that follow a restart.
+    pub fn set_adversarial(&mut self, adversarial: bool) {
+        self.adversarial = adversarial;
+    }
+
+    /// Whether power-ups are earned as the score grows.
+    pub fn has_power_ups(&self) -> bool {
+        self.power_ups
+    }
+
+    /// Lets the player earn a power-up every [`POWER_UP_POINTS`] points, or
+    /// stops them. It carries over to the games that follow a restart.
+    pub fn set_power_ups(&mut self, power_ups: bool) {
+        self.power_ups = power_ups;
+    }
+
+    /// How the points of the moves add up.
+    pub fn scoring(&self) -> Scoring {
+        self.scoring
+    }
+
+    /// Scores the moves of the game by another rule from the next move on.
+    /// It carries over to the games that follow a restart.
+    pub fn set_scoring(&mut self, scoring: Scoring) {
+        self.scoring = scoring;
+    }
+
+    /// How hard the tiles placed after each move are.
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Places the tiles after each move at another difficulty from the next
+    /// move on. The starting tiles are dealt as the variant spawns them. It
+    /// carries over to the games that follow a restart.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    /// The moves in a row, up to the latest, that merged tiles.
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    /// Sets the moves in a row that merged tiles so far, e.g. to resume a
+    /// game recorded in the middle of a combo.
+    pub fn set_combo(&mut self, combo: u32) {
+        self.combo = combo;
+    }
+
+    /// What the points of the next move are multiplied by if it merges.
+    pub fn multiplier(&self) -> u32 {
+        self.scoring.multiplier(self.combo + 1)
+    }
+
+    /// How many of a power-up the player has earned and not played yet.
+    pub fn power_ups_left(&self, power_up: PowerUp) -> u32 {
+        if !self.power_ups {
+            return 0;
+        }
+        // The earned power-ups take turns, so every third one is of a kind.
+        let earned = self.score / POWER_UP_POINTS;
+        let index = power_up.index() as u32;
+        let earned = (earned + 2 - index) / 3;
+        earned.saturating_sub(self.power_ups_used[power_up.index()])
+    }
+
+    /// Whether the game was dealt from its seed and played by moves alone,
+    /// so that replaying its moves from the seed gives the same game. Games
+    /// started from a position or changed by a power-up or a garbage tile
+    /// are not.
+    pub fn is_replayable(&self) -> bool {
+        self.replayable
+    }
+
+    /// The seed the current game's tiles are drawn from.
+    pub fn seed(&self) -> u64 {
+        self.spawner.seed
+    }
+
+    /// Returns the number of tiles drawn from the seed so far, which with the
+    /// seed determines the tiles to come.
+    pub fn spawns(&self) -> u64 {
+        self.spawner.spawns
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.board.variant()
+    }
+
+    /// The rule the tiles of this game merge by.
+    pub fn rule(&self) -> &'static dyn MergeRule {
+        self.variant().rule()
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn max_tile(&self) -> u32 {
+        self.board.max_tile().unwrap_or_default()
+    }
+
+    pub fn moves(&self) -> &[MoveRecord] {
+        &self.moves
+    }
+
+    /// The positions the latest moves were played from, in order, the last
+    /// one for the last of `moves`. There are fewer of them than moves only
+    /// in a game resumed from a save made before positions were kept.
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    pub fn merges(&self) -> &[Merge] {
+        &self.merges
+    }
+
+    pub fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    pub fn last_spawn(&self) -> Option<TileSpawn> {
+        self.last_spawn
+    }
+
+    /// Returns how many tiles the game has placed on a cell, counting the
+    /// starting tiles.
+    pub fn spawns_at(&self, row: usize, col: usize) -> u32 {
+        self.stats.spawned_at[row * self.board.cols() + col]
+    }
+
+    /// Sets how many moves can be undone, forgetting older ones beyond it.
+    pub fn set_undo_depth(&mut self, depth: usize) {
+        self.undo.depth = depth;
+        self.undo.trim();
+    }
+
+    /// Takes back the latest move, restoring the position before it. Returns
+    /// `None` if there is no move to undo.
+    pub fn undo(&mut self) -> Option<ActionOutcome> {
+        let step = self.undo.undo.pop_back()?;
+        (self.board, self.score, self.combo, self.spawner, self.stats) =
+            step.before;
+        self.game_over = false;
+        self.won = self.max_tile() >= self.win_target;
+        self.moves.pop();
+        self.positions.pop();
+        self.merges.truncate(self.merges.len() - step.merges.len());
+        self.undo.redo.push(step);
+        Some(self.restored())
+    }
+
+    /// Plays the latest undone move again, with the same tile spawn. Returns
+    /// `None` if there is no move to redo.
+    pub fn redo(&mut self) -> Option<ActionOutcome> {
+        let step = self.undo.redo.pop()?;
+        (self.board, self.score, self.combo, self.spawner, self.stats) =
+            step.after;
+        self.game_over = step.game_over;
+        self.won = self.max_tile() >= self.win_target;
+        self.moves.push(step.record);
+        self.positions.push(Position {
+            board: step.before.0,
+            score: step.before.1,
+        });
+        self.merges.extend(&step.merges);
+        self.undo.undo.push_back(step);
+        Some(self.restored())
+    }
+
+    /// Drops an extra tile of the lowest value the game spawns on a random
+    /// empty cell without a move, e.g. to press the player in a timed
+    /// challenge. The other tiles stay where they are, so a renderer sliding
+    /// the tiles of the outcome into place only shows the new tile appear.
+    /// The tile can end the game and cannot be undone. Returns `None` if the
+    /// game is over or no cell is empty.
+    pub fn drop_garbage(&mut self) -> Option<ActionOutcome> {
+        if self.is_game_over() {
+            return None;
+        }
+        let mut rng = self.spawner.next();
+        let (row, col) = self.board.spawn_cells().choose(&mut rng)?;
+        let tile = self.spawn_tiles().iter().map(|&(tile, _)| tile).min()?;
+        self.board.set_cell(row, col, Some(tile)).ok()?;
+        self.stats.record_spawn(row * self.board.cols() + col);
+        self.undo.clear();
+        self.replayable = false;
+        self.last_spawn = Some(TileSpawn { row, col, tile });
+
+        let mut outcome = self.outcome();
+        outcome.changed = true;
+        for (r, cells) in outcome.board.iter_mut().enumerate() {
+            for (c, cell) in cells.iter_mut().enumerate() {
+                if cell.value.is_some() && (r, c) != (row, col) {
+                    cell.from = [Some((r, c)), None];
+                }
+            }
+        }
+        self.check_game_over(&mut outcome);
+        Some(outcome)
+    }
+
+    /// Plays a power-up on the tiles of the given cells: two to swap, or one
+    /// to delete or upgrade. Only the cells involved change, so a renderer
+    /// animating the outcome slides swapped tiles into each other's place.
+    /// Playing a power-up scores nothing, can end the game and cannot be
+    /// undone. It fails if no such power-up is left, if a cell holds no tile
+    /// or if the tile is the largest of the variant.
+    pub fn use_power_up(
+        &mut self,
+        power_up: PowerUp,
+        cells: &[(usize, usize)],
+    ) -> Result<ActionOutcome> {
+        if self.is_game_over() {
+            bail!("The game is over");
+        }
+        if self.power_ups_left(power_up) == 0 {
+            bail!("No {power_up:?} power-up left");
+        }
+        if cells.len() != power_up.cells() {
+            bail!("A {power_up:?} is played on {} tiles", power_up.cells());
+        }
+        let mut tiles = Vec::with_capacity(cells.len());
+        for &(row, col) in cells {
+            let tile = (row < self.board.rows() && col < self.board.cols())
+                .then(|| self.board.cell(row, col))
+                .flatten();
+            let Some(tile) = tile else {
+                bail!("No tile at row {row}, column {col}");
+            };
+            tiles.push(tile);
+        }
+
+        let mut outcome = self.outcome();
+        match (power_up, cells) {
+            (PowerUp::Swap, &[first, second]) => {
+                if first == second {
+                    bail!("A tile cannot be swapped with itself");
+                }
+                self.board.set_cell(first.0, first.1, Some(tiles[1]))?;
+                self.board.set_cell(second.0, second.1, Some(tiles[0]))?;
+            }
+            (PowerUp::Delete, &[(row, col)]) => {
+                self.board.set_cell(row, col, None)?;
+            }
+            (PowerUp::Upgrade, &[(row, col)]) => {
+                let rank = self.board.rank(row, col) as usize;
+                let Some(&next) = self.rule().tiles().get(rank) else {
+                    bail!("The {} tile cannot be upgraded", tiles[0]);
+                };
+                self.board.set_cell(row, col, Some(next))?;
+                self.stats.highest_tile = self.stats.highest_tile.max(next);
+            }
+            _ => unreachable!("the number of cells was checked"),
+        }
+        self.power_ups_used[power_up.index()] += 1;
+        self.undo.clear();
+        self.replayable = false;
+        self.last_spawn = None;
+
+        for ((row, col), cell) in self.board.iter_cells() {
+            let result = &mut outcome.board[row][col];
+            result.value = cell;
+            result.from = [cell.map(|_| (row, col)), None];
+        }
+        if let (PowerUp::Swap, &[first, second]) = (power_up, cells) {
+            outcome.board[first.0][first.1].from = [Some(second), None];
+            outcome.board[second.0][second.1].from = [Some(first), None];
+        }
+        outcome.changed = true;
+        if !self.won && self.max_tile() >= self.win_target {
+            self.won = true;
+            outcome.won = true;
+        }
+        self.check_game_over(&mut outcome);
+        Ok(outcome)
+    }
+
+    /// Ends the game where it stands, e.g. when the time runs out, so its score
+    /// is final.
+    pub fn end(&mut self) -> ActionOutcome {
+        self.game_over = true;
+        self.last_spawn = None;
+        self.outcome()
+    }
+
+    fn restored(&mut self) -> ActionOutcome {
+        self.last_spawn = None;
+        let mut outcome = self.outcome();
+        outcome.changed = true;
+        outcome
+    }
+
+    /// Slides the tiles in the given direction and, if the board changed,
+    /// spawns a new tile. A move that changes nothing is not recorded, and
+    /// a game that is over ignores moves.
     pub fn apply_move(
         &mut self,
         direction: GameAction,
@@ -104,21 +906,73 @@ impl Game {
             return Ok(self.outcome());
         }
 
-        let mut outcome = ActionOutcome::default();
+        self.last_spawn = None;
+        let before =
+            (self.board, self.score, self.combo, self.spawner, self.stats);
+        let merges = self.merges.len();
+
+        let mut outcome = ActionOutcome::empty(self.board.shape());
         self.slide_and_merge(direction, &mut outcome);
 
         self.update_changed_flag(&mut outcome);
         if outcome.changed {
-            self.spawn_random_tile(&mut outcome)?;
-            self.commit_board(&outcome);
+            let merged = outcome.iter_cells().filter(|(_, c)| c.merged).count();
+            self.combo = if merged > 0 { self.combo + 1 } else { 0 };
+            outcome.score = self.scoring.points(outcome.score, self.combo);
+            let spawn = self.spawn_random_tile(&mut outcome)?;
+            self.commit_board(&outcome)?;
+            self.moves.push(MoveRecord {
+                action: direction,
+                points: outcome.score,
+            });
+            self.positions.push(Position {
+                board: before.0,
+                score: before.1,
+            });
+            self.log_merges(&outcome);
+            let spawned = spawn.row * self.board.cols() + spawn.col;
+            self.stats.record_move(merged as u32, spawned, &self.board);
+            if !self.won && self.max_tile() >= self.win_target {
+                self.won = true;
+                outcome.won = true;
+            }
         }
 
         self.update_score(&mut outcome);
         self.check_game_over(&mut outcome);
 
+        if outcome.changed
+            && let Some(&record) = self.moves.last()
+        {
+            self.undo.push(Step {
+                before,
+                after: (
+                    self.board,
+                    self.score,
+                    self.combo,
+                    self.spawner,
+                    self.stats,
+                ),
+                game_over: self.game_over,
+                record,
+                merges: self.merges[merges..].to_vec(),
+            });
+        }
+
         Ok(outcome)
     }
 
+    /// Returns the position after sliding the board in the given direction,
+    /// without spawning a tile, along with the points scored. Returns `None` if
+    /// the move would not change the board.
+    pub fn preview(
+        board: &Board,
+        direction: GameAction,
+    ) -> Option<(Board, u32)> {
+        let (next, score) = board.slide(direction);
+        (next != *board).then_some((next, score))
+    }
+
     fn update_changed_flag(&self, outcome: &mut ActionOutcome) {
         let changed = outcome
             .iter_cells()
@@ -127,179 +981,350 @@ impl Game {
     }
 
     fn update_score(&mut self, outcome: &mut ActionOutcome) {
+        outcome.points = outcome.score;
         self.score += outcome.score;
         outcome.score = self.score;
     }
 
     // Helper function that slides and merges a single line of tiles in the given
-    // direction, updating the board and score as necessary.
+    // direction, updating the board and score as necessary. The positions run
+    // from the edge the tiles slide towards. This follows `Board::slide` while
+    // also tracking where each tile came from. Walls split the line into
+    // stretches that slide on their own, and voids are skipped. On a board
+    // that wraps around, a line with no wall is a loop whose tiles are read
+    // from its leading tile.
     fn slide_and_merge_line(
         &self,
-        tiles: impl Iterator<Item = u32>,
-        positions: impl Iterator<Item = (usize, usize)>,
-        board: &mut [[CellResult; BOARD_SIZE]; BOARD_SIZE],
+        positions: impl Iterator<Item = (usize, usize)> + Clone,
+        board: &mut [Vec<CellResult>],
         score: &mut u32,
     ) {
-        let mut tiles = tiles.peekable();
-        for (row, col) in positions {
-            let Some(tile) = tiles.next() else {
-                break;
-            };
+        for (row, col) in positions.clone() {
+            board[row][col].void = self.board.is_void(row, col);
+        }
+        let positions =
+            positions.filter(|&(row, col)| !self.board.is_void(row, col));
+        let walled = positions
+            .clone()
+            .any(|(row, col)| self.board.is_wall(row, col));
+        if self.board.wraps() && !walled {
+            let start = board::wrap_start(
+                positions
+                    .clone()
+                    .map(|(row, col)| self.board.cell(row, col).is_some()),
+            );
+            let from = positions.clone().skip(start);
+            let tiles = from.chain(positions.clone().take(start));
+            self.slide_and_merge_stretch(positions, tiles, board, score);
+            return;
+        }
 
-            if let Some(&next_tile) = tiles.peek()
-                && tile == next_tile
-            {
-                let tile_sum = tile + next_tile;
-                board[row][col] = CellResult {
-                    value: Some(tile_sum),
-                    merged: true,
-                };
-                *score += tile_sum;
-                tiles.next();
-            } else {
-                board[row][col] = CellResult {
-                    value: Some(tile),
-                    merged: false,
-                };
+        let mut start = 0;
+        for (end, (row, col)) in positions.clone().enumerate() {
+            if self.board.is_wall(row, col) {
+                board[row][col].wall = true;
+                let stretch = positions.clone().skip(start).take(end - start);
+                self.move_stretch(stretch, board, score);
+                start = end + 1;
             }
         }
+        self.move_stretch(positions.skip(start), board, score);
     }
 
-    // Slides and merges the tiles in the given direction according to the game
-    // rules, updating the board and score as necessary.
-    fn slide_and_merge(
+    // Moves the tiles of a line between walls as far as the rule lets them.
+    fn move_stretch(
         &self,
-        direction: GameAction,
-        outcome: &mut ActionOutcome,
+        positions: impl Iterator<Item = (usize, usize)> + Clone,
+        board: &mut [Vec<CellResult>],
+        score: &mut u32,
     ) {
-        match direction {
-            GameAction::Up => {
-                for col in 0..BOARD_SIZE {
-                    self.slide_and_merge_line(
-                        self.board.col(col),
-                        (0..BOARD_SIZE).map(|row| (row, col)),
-                        &mut outcome.board,
-                        &mut outcome.score,
-                    );
+        match self.rule().slide() {
+            Slide::ToWall => self.slide_and_merge_stretch(
+                positions.clone(),
+                positions,
+                board,
+                score,
+            ),
+            Slide::OneStep => self.shift_stretch(positions, board, score),
+        }
+    }
+
+    // Shifts the tiles of a line between walls one cell: the first tile that
+    // can move, into an empty cell or by merging with the tile ahead, moves
+    // with every tile behind it, and the tiles ahead stay put.
+    fn shift_stretch(
+        &self,
+        positions: impl Iterator<Item = (usize, usize)> + Clone,
+        board: &mut [Vec<CellResult>],
+        score: &mut u32,
+    ) {
+        let cell = |(row, col): (usize, usize)| self.board.cell(row, col);
+        let stays = |from: (usize, usize)| CellResult {
+            value: cell(from),
+            from: [cell(from).map(|_| from), None],
+            ..Default::default()
+        };
+        let mut pairs = positions.clone().zip(positions.clone().skip(1));
+        let mut shifting = false;
+        for ((row, col), behind) in pairs.by_ref() {
+            let moved = match (cell((row, col)), cell(behind)) {
+                (None, Some(_)) => Some(stays(behind)),
+                (Some(tile), Some(next_tile)) => {
+                    self.board.merge(tile, next_tile).map(|merged| {
+                        *score += self.rule().score(merged);
+                        CellResult {
+                            value: Some(merged),
+                            merged: true,
+                            from: [Some((row, col)), Some(behind)],
+                            wall: false,
+                            void: false,
+                        }
+                    })
+                }
+                _ => None,
+            };
+            board[row][col] = moved.unwrap_or_else(|| stays((row, col)));
+            if moved.is_some() {
+                shifting = true;
+                break;
+            }
+        }
+        if shifting {
+            for ((row, col), behind) in pairs {
+                board[row][col] = stays(behind);
+            }
+        }
+        // The last cell is left empty by a shift.
+        if let Some((row, col)) = positions.last() {
+            board[row][col] = if shifting {
+                CellResult::default()
+            } else {
+                stays((row, col))
+            };
+        }
+    }
+
+    // Slides and merges the tiles of a line between walls into `positions`,
+    // taking them in the order of `from`: the same cells, unless the line
+    // wraps around.
+    fn slide_and_merge_stretch(
+        &self,
+        positions: impl Iterator<Item = (usize, usize)>,
+        from: impl Iterator<Item = (usize, usize)>,
+        board: &mut [Vec<CellResult>],
+        score: &mut u32,
+    ) {
+        let mut tiles = from
+            .filter_map(|(row, col)| {
+                self.board.cell(row, col).map(|tile| ((row, col), tile))
+            })
+            .peekable();
+        for (row, col) in positions {
+            let Some((from, tile)) = tiles.next() else {
+                break;
+            };
+
+            if let Some(&(next_from, next_tile)) = tiles.peek()
+                && let Some(merged) = self.board.merge(tile, next_tile)
+            {
+                board[row][col] = CellResult {
+                    value: Some(merged),
+                    merged: true,
+                    from: [Some(from), Some(next_from)],
+                    wall: false,
+                    void: false,
+                };
+                *score += self.rule().score(merged);
+                tiles.next();
+            } else {
+                board[row][col] = CellResult {
+                    value: Some(tile),
+                    merged: false,
+                    from: [Some(from), None],
+                    wall: false,
+                    void: false,
+                };
+            }
+        }
+    }
+
+    // Slides and merges the tiles in the given direction according to the game
+    // rules, updating the board and score as necessary.
+    fn slide_and_merge(
+        &self,
+        direction: GameAction,
+        outcome: &mut ActionOutcome,
+    ) {
+        let (rows, cols) = (self.board.rows(), self.board.cols());
+        match direction {
+            GameAction::Up => {
+                for col in 0..cols {
+                    self.slide_and_merge_line(
+                        (0..rows).map(|row| (row, col)),
+                        &mut outcome.board,
+                        &mut outcome.score,
+                    );
                 }
             }
             GameAction::Down => {
-                for col in 0..BOARD_SIZE {
+                for col in 0..cols {
                     self.slide_and_merge_line(
-                        self.board.col(col).rev(),
-                        (0..BOARD_SIZE).map(|row| (row, col)).rev(),
+                        (0..rows).map(|row| (row, col)).rev(),
                         &mut outcome.board,
                         &mut outcome.score,
                     );
                 }
             }
             GameAction::Left => {
-                for row in 0..BOARD_SIZE {
+                for row in 0..rows {
                     self.slide_and_merge_line(
-                        self.board.row(row),
-                        (0..BOARD_SIZE).map(|col| (row, col)),
+                        (0..cols).map(|col| (row, col)),
                         &mut outcome.board,
                         &mut outcome.score,
                     );
                 }
             }
             GameAction::Right => {
-                for row in 0..BOARD_SIZE {
+                for row in 0..rows {
                     self.slide_and_merge_line(
-                        self.board.row(row).rev(),
-                        (0..BOARD_SIZE).map(|col| (row, col)).rev(),
+                        (0..cols).map(|col| (row, col)).rev(),
                         &mut outcome.board,
                         &mut outcome.score,
                     );
                 }
             }
         }
+        if self.rule().settle() == Settle::Fall {
+            self.fall(&mut outcome.board);
+        }
     }
 
-    fn check_game_over(&mut self, outcome: &mut ActionOutcome) {
-        for row in 0..BOARD_SIZE {
-            for col in 0..BOARD_SIZE {
-                // If there is an empty cell, the game is not over.
-                let Some(current_tile) = self.board.cell(row, col) else {
-                    return;
-                };
-
-                // If there is a mergeable tile to the right.
-                if col + 1 < BOARD_SIZE
-                    && self.board.cell(row, col + 1) == Some(current_tile)
-                {
-                    return;
+    // Drops the tiles of a move's outcome down their columns like
+    // `Board::slide` does, keeping where each tile came from.
+    fn fall(&self, board: &mut [Vec<CellResult>]) {
+        for col in 0..self.board.cols() {
+            let mut landing = Vec::new();
+            let mut filled = 0;
+            for row in (0..self.board.rows()).rev() {
+                if self.board.is_void(row, col) {
+                    continue;
                 }
-
-                // If there is a mergeable tile below, the game is not over.
-                if row + 1 < BOARD_SIZE
-                    && self.board.cell(row + 1, col) == Some(current_tile)
-                {
-                    return;
+                if self.board.is_wall(row, col) {
+                    (landing, filled) = (Vec::new(), 0);
+                    continue;
+                }
+                landing.push(row);
+                if board[row][col].value.is_some() {
+                    let cell = std::mem::take(&mut board[row][col]);
+                    board[landing[filled]][col] = cell;
+                    filled += 1;
                 }
             }
         }
+    }
+
+    fn check_game_over(&mut self, outcome: &mut ActionOutcome) {
+        if self.board.can_move() {
+            return;
+        }
 
         outcome.game_over = true;
         self.game_over |= outcome.game_over;
     }
 
-    fn commit_board(&mut self, outcome: &ActionOutcome) {
+    // Logs the notable tiles created by the latest move.
+    fn log_merges(&mut self, outcome: &ActionOutcome) {
+        let move_number = self.moves.len();
+        self.merges.extend(
+            outcome
+                .iter_cells()
+                .filter(|(_, cell)| cell.merged)
+                .filter_map(|(_, cell)| cell.value)
+                .filter(|&tile| tile >= NOTABLE_MERGE)
+                .map(|tile| Merge { tile, move_number }),
+        );
+    }
+
+    fn commit_board(&mut self, outcome: &ActionOutcome) -> Result<()> {
+        Game::write_outcome(&mut self.board, outcome)
+    }
+
+    // Writes the tiles of a move's outcome to the board, leaving its walls.
+    fn write_outcome(board: &mut Board, outcome: &ActionOutcome) -> Result<()> {
         for ((row, col), cell) in outcome.iter_cells() {
-            if cell.value != self.board.cell(row, col) {
-                *self.board.cell_mut(row, col) = cell.value;
+            if cell.value != board.cell(row, col) {
+                board.set_cell(row, col, cell.value)?;
             }
         }
+        Ok(())
     }
 
-    // Spawns a new tile with the appropriate probability distribution.
-    fn spawn_tile() -> u32 {
-        let mut rng = rand::rng();
-        if rng.random_bool(STARTING_TILE_TWO_PROBABILITY) {
-            STARTING_TILE_TWO
-        } else {
-            STARTING_TILE_FOUR
+    // The tiles placed after each move, with their probabilities.
+    fn spawn_tiles(&self) -> Cow<'static, [(u32, f64)]> {
+        self.difficulty.spawns(self.rule())
+    }
+
+    // Draws a tile with the given probabilities. Each tile but the last is
+    // drawn with its share of the probability left, so two tiles take a
+    // single draw.
+    fn spawn_tile(rng: &mut StdRng, tiles: &[(u32, f64)]) -> u32 {
+        let (last, spawns) =
+            tiles.split_last().expect("a rule spawns at least one tile");
+        let mut left = 1.0;
+        for &(tile, probability) in spawns {
+            if rng.random_bool((probability / left).clamp(0.0, 1.0)) {
+                return tile;
+            }
+            left -= probability;
         }
+        last.0
     }
 
-    fn spawn_random_tile(&self, outcome: &mut ActionOutcome) -> Result<()> {
-        // Pick random coordinates on the board to place the starting tiles.
-        let Some((row, col)) = outcome
-            .iter_cells()
-            .filter(|(_, cell)| cell.value.is_none())
-            .map(|(pos, _)| pos)
-            .choose(&mut rand::rng())
-        else {
+    fn spawn_random_tile(
+        &mut self,
+        outcome: &mut ActionOutcome,
+    ) -> Result<TileSpawn> {
+        // The spawner picks a cell of the position the move left.
+        let mut board = self.board;
+        Game::write_outcome(&mut board, outcome)?;
+        let tiles = self.spawn_tiles();
+        let spawner: &mut dyn Spawner = if self.adversarial {
+            &mut AdversarialSpawner
+        } else {
+            &mut self.spawner
+        };
+        let Some(spawn) = spawner.spawn(&board, &tiles) else {
             bail!("No empty cell available to spawn a random tile");
         };
 
-        // Place the starting tiles on the board.
-        outcome.board[row][col] = CellResult {
-            value: Some(Game::spawn_tile()),
+        outcome.board[spawn.row][spawn.col] = CellResult {
+            value: Some(spawn.tile),
             ..Default::default()
         };
+        self.last_spawn = Some(spawn);
 
-        Ok(())
+        Ok(spawn)
     }
 
-    // Initializes the board with the starting tiles in random positions.
-    fn initialize_board() -> Board {
+    // Places the starting tiles in random empty cells of a board with no
+    // tiles yet, which sets the variant, shape, walls and voids played on.
+    fn initialize_board(rng: &mut StdRng, mut board: Board) -> Board {
+        let variant = board.variant();
         // Buffer that will be filled with random coordinates to place the
         // starting tiles.
         let mut cells: [Option<(usize, usize)>; STARTING_TILE_COUNT] =
             [None; STARTING_TILE_COUNT];
 
-        let mut board = Board::default();
-
-        // Pick random coordinates on the board to place the starting tiles.
-        board
-            .iter_cells()
-            .map(|(coord, _)| Some(coord))
-            .sample_fill(&mut rand::rng(), &mut cells);
+        // Pick random empty cells of the board to place the starting tiles,
+        // never a wall or a void.
+        board.empty_cells().map(Some).sample_fill(rng, &mut cells);
 
         // Place the starting tiles on the board.
         for (row, col) in cells.into_iter().flatten() {
-            *board.cell_mut(row, col) = Some(Game::spawn_tile());
+            let tile = Game::spawn_tile(rng, variant.rule().spawns());
+            board
+                .set_cell(row, col, Some(tile))
+                .expect("the variant's own tiles always fit its board");
         }
 
         board
@@ -309,12 +1334,13 @@ impl Game {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::board::BOARD_SIZE;
 
     fn board_from_rows(rows: [[Option<u32>; BOARD_SIZE]; BOARD_SIZE]) -> Board {
         let mut board = Board::default();
         for (row, row_cells) in rows.iter().enumerate() {
             for (col, value) in row_cells.iter().enumerate() {
-                *board.cell_mut(row, col) = *value;
+                board.set_cell(row, col, *value).unwrap();
             }
         }
         board
@@ -329,6 +1355,7 @@ mod tests {
             board: board_from_rows(rows),
             score,
             game_over,
+            ..Default::default()
         }
     }
 
@@ -354,12 +1381,21 @@ mod tests {
 
     #[test]
     fn slide_and_merge_line_merges_each_pair_once() {
-        let game = Game::default();
-        let mut board = [[CellResult::default(); BOARD_SIZE]; BOARD_SIZE];
+        let game = game_from_rows(
+            [
+                [Some(2), Some(2), Some(2), Some(2)],
+                [None, None, None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+            ],
+            0,
+            false,
+        );
+        let mut board =
+            vec![vec![CellResult::default(); BOARD_SIZE]; BOARD_SIZE];
         let mut score = 0;
 
         game.slide_and_merge_line(
-            vec![2, 2, 2, 2].into_iter(),
             (0..BOARD_SIZE).map(|col| (0, col)),
             &mut board,
             &mut score,
@@ -374,6 +1410,28 @@ mod tests {
         assert_eq!(board[0][3].value, None);
     }
 
+    #[test]
+    fn slide_and_merge_records_where_tiles_came_from() {
+        let game = game_from_rows(
+            [
+                [Some(2), None, Some(2), Some(8)],
+                [None, Some(4), None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+            ],
+            0,
+            false,
+        );
+        let mut outcome = ActionOutcome::default();
+
+        game.slide_and_merge(GameAction::Right, &mut outcome);
+
+        assert_eq!(outcome.board[0][3].from, [Some((0, 3)), None]);
+        assert_eq!(outcome.board[0][2].from, [Some((0, 2)), Some((0, 0))]);
+        assert_eq!(outcome.board[1][3].from, [Some((1, 1)), None]);
+        assert_eq!(outcome.board[1][2].from, [None, None]);
+    }
+
     #[test]
     fn slide_and_merge_up_merges_columns_correctly() {
         let game = game_from_rows(
@@ -502,7 +1560,7 @@ mod tests {
         outcome.board[0][0].value = Some(8);
         outcome.changed = false;
 
-        game.commit_board(&outcome);
+        game.commit_board(&outcome).unwrap();
 
         assert!(!outcome.changed);
         assert_eq!(game.board.cell(0, 0), Some(8));
@@ -535,7 +1593,7 @@ mod tests {
                 [Some(2), Some(4), Some(8), Some(16)],
                 [Some(32), Some(64), Some(128), Some(256)],
                 [Some(512), Some(1024), Some(2048), Some(4096)],
-                [Some(3), Some(6), Some(12), Some(12)],
+                [Some(2), Some(4), Some(16), Some(16)],
             ],
             0,
             false,
@@ -555,7 +1613,7 @@ mod tests {
                 [Some(2), Some(4), Some(8), Some(16)],
                 [Some(32), Some(64), Some(128), Some(16)],
                 [Some(1024), Some(2048), Some(4096), Some(512)],
-                [Some(8192), Some(16384), Some(32768), Some(65536)],
+                [Some(8192), Some(16384), Some(32768), Some(2)],
             ],
             0,
             false,
@@ -575,7 +1633,7 @@ mod tests {
                 [Some(2), Some(4), Some(8), Some(16)],
                 [Some(32), Some(64), Some(128), Some(256)],
                 [Some(512), Some(1024), Some(2048), Some(4096)],
-                [Some(3), Some(6), Some(12), Some(24)],
+                [Some(2), Some(4), Some(8), Some(16)],
             ],
             0,
             false,
@@ -590,13 +1648,13 @@ mod tests {
 
     #[test]
     fn spawn_random_tile_places_value_in_only_empty_slot() {
-        let game = Game::default();
+        let mut game = Game::default();
         let mut outcome = ActionOutcome::default();
         let mut values = [
             [Some(8), Some(16), Some(32), Some(64)],
             [Some(128), Some(256), None, Some(512)],
             [Some(1024), Some(2048), Some(4096), Some(8192)],
-            [Some(3), Some(6), Some(12), Some(24)],
+            [Some(2), Some(4), Some(8), Some(16)],
         ];
 
         for (row, row_values) in values.iter().enumerate() {
@@ -610,17 +1668,48 @@ mod tests {
 
         assert!(matches!(values[1][2], Some(2 | 4)));
         assert!(!outcome.board[1][2].merged);
+        let spawn = game.last_spawn().unwrap();
+        assert_eq!((spawn.row, spawn.col), (1, 2));
+        assert_eq!(Some(spawn.tile), values[1][2]);
+    }
+
+    #[test]
+    fn adversarial_spawner_picks_the_tile_that_ends_the_game() {
+        let board: Board = "1212212112122120".parse().unwrap();
+        assert_eq!(
+            AdversarialSpawner.spawn(&board, board.variant().rule().spawns()),
+            Some(TileSpawn {
+                row: 3,
+                col: 3,
+                tile: 2
+            })
+        );
+
+        // Without randomness, the seed makes no difference.
+        let start: Board = "1100000000000002".parse().unwrap();
+        let mut games = [1, 2].map(|seed| {
+            let mut game = Game::from_position(start, 0, seed, 0);
+            game.set_adversarial(true);
+            game
+        });
+        for game in &mut games {
+            for action in [GameAction::Left, GameAction::Up, GameAction::Right]
+            {
+                game.apply_move(action).unwrap();
+            }
+        }
+        assert_eq!(games[0].board(), games[1].board());
     }
 
     #[test]
     fn spawn_random_tile_returns_error_when_no_empty_cells() {
-        let game = Game::default();
+        let mut game = Game::default();
         let mut outcome = ActionOutcome::default();
         let values = [
             [Some(2), Some(4), Some(8), Some(16)],
             [Some(32), Some(64), Some(128), Some(256)],
             [Some(512), Some(1024), Some(2048), Some(4096)],
-            [Some(3), Some(6), Some(12), Some(24)],
+            [Some(2), Some(4), Some(8), Some(16)],
         ];
 
         for (row, row_values) in values.iter().enumerate() {
@@ -639,7 +1728,7 @@ mod tests {
                 [Some(2), Some(4), Some(8), Some(16)],
                 [Some(32), Some(64), Some(128), Some(256)],
                 [Some(512), Some(1024), Some(2048), Some(4096)],
-                [Some(3), Some(6), Some(12), Some(24)],
+                [Some(2), Some(4), Some(8), Some(16)],
             ],
             77,
             true,
@@ -722,7 +1811,7 @@ mod tests {
                 [None, Some(8), Some(16), Some(32)],
                 [Some(64), Some(128), Some(256), Some(512)],
                 [Some(1024), Some(2048), Some(4096), Some(8192)],
-                [Some(16384), Some(32768), Some(65536), Some(131072)],
+                [Some(16384), Some(32768), Some(2), Some(4)],
             ],
             0,
             false,
@@ -737,6 +1826,55 @@ mod tests {
         assert_eq!(count_filled(&values), BOARD_SIZE * BOARD_SIZE);
     }
 
+    #[test]
+    fn preview_slides_without_spawning_or_mutating() {
+        let board = board_from_rows([
+            [Some(2), Some(2), None, None],
+            [None, None, None, None],
+            [None, None, None, None],
+            [None, None, None, Some(4)],
+        ]);
+
+        let (next, score) = Game::preview(&board, GameAction::Left).unwrap();
+
+        assert_eq!(score, 4);
+        assert_eq!(next.cell(0, 0), Some(4));
+        assert_eq!(next.cell(3, 0), Some(4));
+        assert_eq!(next.iter_cells().filter(|(_, v)| v.is_some()).count(), 2);
+        assert!(Game::preview(&next, GameAction::Left).is_none());
+    }
+
+    #[test]
+    fn from_board_keeps_position_and_detects_game_over() {
+        let rows = [
+            [Some(2), Some(4), Some(8), Some(16)],
+            [Some(32), Some(64), Some(128), Some(256)],
+            [Some(512), Some(1024), Some(2048), Some(4096)],
+            [Some(2), Some(4), Some(8), Some(16)],
+        ];
+
+        let game = Game::from_board(board_from_rows(rows), 42);
+
+        assert_eq!(outcome_values(&game.outcome()), rows);
+        assert_eq!(game.score(), 42);
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn restart_keeps_the_walls_and_voids() {
+        let board = "1x0~00000000~x20".parse().unwrap();
+        let mut game = Game::from_position(board, 8, 7, 0);
+
+        for _ in 0..20 {
+            game.restart();
+            let board = game.board();
+            assert!(board.is_wall(0, 1) && board.is_wall(3, 1));
+            assert!(board.is_void(0, 3) && board.is_void(3, 0));
+            let tiles = board.iter_cells().filter(|(_, cell)| cell.is_some());
+            assert_eq!(tiles.count(), STARTING_TILE_COUNT);
+        }
+    }
+
     #[test]
     fn restart_resets_state_and_creates_starting_tiles() {
         let mut game = game_from_rows(
@@ -744,7 +1882,7 @@ mod tests {
                 [Some(2), Some(4), Some(8), Some(16)],
                 [Some(32), Some(64), Some(128), Some(256)],
                 [Some(512), Some(1024), Some(2048), Some(4096)],
-                [Some(3), Some(6), Some(12), Some(24)],
+                [Some(2), Some(4), Some(8), Some(16)],
             ],
             999,
             true,
@@ -763,5 +1901,626 @@ mod tests {
         assert_eq!(game.score, 0);
         assert_eq!(tiles.len(), STARTING_TILE_COUNT);
         assert!(tiles.iter().all(|value| matches!(value, 2 | 4)));
+        assert!(game.moves().is_empty());
+        assert!(game.merges().is_empty());
+        assert_eq!(
+            *game.stats(),
+            GameStats {
+                spawns: STARTING_TILE_COUNT as u32,
+                highest_tile: tiles.iter().copied().max().unwrap(),
+                ..*game.stats()
+            }
+        );
+        for ((row, col), value) in game.board().iter_cells() {
+            assert_eq!(game.spawns_at(row, col), u32::from(value.is_some()));
+        }
+    }
+
+    #[test]
+    fn ended_game_takes_no_more_moves() {
+        let mut game = Game::with_seed(7);
+        let board = *game.board();
+
+        let outcome = game.end();
+        assert!(outcome.game_over);
+        assert!(game.is_game_over());
+
+        for action in GameAction::ALL {
+            assert!(!game.apply_move(action).unwrap().changed);
+        }
+        assert_eq!(*game.board(), board);
+    }
+
+    #[test]
+    fn garbage_fills_an_empty_cell_and_can_end_the_game() {
+        let mut game = game_from_rows(
+            [
+                [Some(2), Some(4), Some(2), Some(4)],
+                [Some(4), Some(2), Some(4), Some(2)],
+                [Some(2), Some(4), Some(2), Some(4)],
+                [Some(4), Some(2), Some(8), None],
+            ],
+            0,
+            false,
+        );
+
+        let outcome = game.drop_garbage().unwrap();
+        assert!(outcome.changed);
+        assert!(outcome.game_over);
+        assert_eq!(game.board().cell(3, 3), Some(2));
+        assert_eq!(game.spawns_at(3, 3), 1);
+        assert_eq!(outcome.board[3][3].from, [None, None]);
+        assert_eq!(outcome.board[0][0].from, [Some((0, 0)), None]);
+        assert!(game.drop_garbage().is_none());
+    }
+
+    #[test]
+    fn only_games_dealt_from_their_seed_and_moved_are_replayable() {
+        let mut game = Game::with_seed(5);
+        game.apply_move(GameAction::Left).unwrap();
+        assert!(game.is_replayable());
+        let json = game.to_json().unwrap();
+        assert!(!Game::from_json(&json).unwrap().is_replayable());
+        let mut set_up = Game::from_board(*game.board(), game.score());
+        assert!(!set_up.is_replayable());
+        set_up.restart();
+        assert!(set_up.is_replayable());
+
+        game.drop_garbage().unwrap();
+        assert!(!game.is_replayable());
+    }
+
+    #[test]
+    fn power_ups_are_earned_in_turn_and_played_on_tiles() {
+        let mut game = game_from_rows(
+            [
+                [Some(2), Some(4), None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+                [None, None, None, Some(8)],
+            ],
+            2500,
+            false,
+        );
+        assert_eq!(game.power_ups_left(PowerUp::Swap), 0);
+        game.set_power_ups(true);
+        let left = PowerUp::ALL.map(|power_up| game.power_ups_left(power_up));
+        assert_eq!(left, [1, 1, 0]);
+
+        let outcome =
+            game.use_power_up(PowerUp::Swap, &[(0, 0), (0, 1)]).unwrap();
+        assert!(outcome.changed);
+        assert_eq!(game.board().cell(0, 0), Some(4));
+        assert_eq!(game.board().cell(0, 1), Some(2));
+        assert_eq!(outcome.board[0][0].from, [Some((0, 1)), None]);
+        assert_eq!(outcome.board[3][3].from, [Some((3, 3)), None]);
+        assert_eq!(game.power_ups_left(PowerUp::Swap), 0);
+        assert!(game.use_power_up(PowerUp::Swap, &[(0, 0), (0, 1)]).is_err());
+
+        assert!(game.use_power_up(PowerUp::Delete, &[(1, 1)]).is_err());
+        game.use_power_up(PowerUp::Delete, &[(3, 3)]).unwrap();
+        assert_eq!(game.board().cell(3, 3), None);
+        assert_eq!(game.score(), 2500);
+
+        game.score = 3000;
+        game.use_power_up(PowerUp::Upgrade, &[(0, 0)]).unwrap();
+        assert_eq!(game.board().cell(0, 0), Some(8));
+        assert_eq!(game.stats().highest_tile, 8);
+    }
+
+    #[test]
+    fn stats_count_moves_merges_and_spawns_and_follow_undo() {
+        let mut game = game_from_rows(
+            [
+                [Some(2), Some(2), Some(4), Some(4)],
+                [None, None, None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+            ],
+            0,
+            false,
+        );
+
+        game.apply_move(GameAction::Left).unwrap();
+        let after_move = *game.stats();
+        assert_eq!(
+            after_move,
+            GameStats {
+                moves: 1,
+                merges: 2,
+                spawns: 1,
+                highest_tile: 8,
+                ..after_move
+            }
+        );
+        let spawn = game.last_spawn().unwrap();
+        assert_eq!(game.spawns_at(spawn.row, spawn.col), 1);
+        assert_eq!(after_move.spawned_at.iter().sum::<u32>(), 1);
+
+        game.undo().unwrap();
+        assert_eq!(*game.stats(), GameStats::default());
+        game.redo().unwrap();
+        assert_eq!(*game.stats(), after_move);
+    }
+
+    #[test]
+    fn apply_move_records_moves_that_change_the_board() {
+        let mut game = game_from_rows(
+            [
+                [Some(2), Some(2), None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+            ],
+            0,
+            false,
+        );
+
+        game.apply_move(GameAction::Up).unwrap();
+        assert!(game.moves().is_empty());
+
+        game.apply_move(GameAction::Left).unwrap();
+        assert_eq!(
+            game.moves(),
+            [MoveRecord {
+                action: GameAction::Left,
+                points: 4,
+            }]
+        );
+    }
+
+    #[test]
+    fn apply_move_reports_the_points_scored() {
+        let mut game = game_from_rows(
+            [
+                [Some(2), Some(2), Some(8), Some(8)],
+                [None, None, None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+            ],
+            100,
+            false,
+        );
+
+        let outcome = game.apply_move(GameAction::Left).unwrap();
+        assert_eq!((outcome.points, outcome.score), (20, 120));
+
+        let outcome = game.apply_move(GameAction::Left).unwrap();
+        assert_eq!(outcome.points, 0);
+    }
+
+    #[test]
+    fn apply_move_logs_notable_merges() {
+        let mut game = game_from_rows(
+            [
+                [Some(64), Some(64), Some(2), Some(2)],
+                [Some(128), Some(128), None, None],
+                [None, None, None, None],
+                [None, None, None, None],
+            ],
+            0,
+            false,
+        );
+
+        game.apply_move(GameAction::Left).unwrap();
+
+        assert_eq!(
+            game.merges(),
+            [
+                Merge {
+                    tile: 128,
+                    move_number: 1,
+                },
+                Merge {
+                    tile: 256,
+                    move_number: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_restore_moves_exactly() {
+        let mut game = Game::new();
+        let start = *game.board();
+        let mut boards = vec![start];
+        for action in GameAction::ALL.into_iter().cycle().take(8) {
+            if game.apply_move(action).unwrap().changed {
+                boards.push(*game.board());
+            }
+        }
+        let end = (*game.board(), game.score(), game.moves().len());
+
+        while game.undo().is_some() {}
+        assert_eq!(*game.board(), start);
+        assert_eq!(game.score(), 0);
+        assert!(game.moves().is_empty());
+
+        let mut redone = vec![*game.board()];
+        while let Some(outcome) = game.redo() {
+            assert!(outcome.changed);
+            redone.push(*game.board());
+        }
+        assert_eq!(redone, boards);
+        assert_eq!((*game.board(), game.score(), game.moves().len()), end);
+        let played: Vec<Board> = game
+            .positions()
+            .iter()
+            .map(|position| position.board)
+            .collect();
+        assert_eq!(played, boards[..boards.len() - 1]);
+    }
+
+    #[test]
+    fn new_move_clears_redo_and_depth_limits_undo() {
+        let mut game = game_from_rows(
+            [
+                [Some(2), Some(2), None, None],
+                [None; BOARD_SIZE],
+                [None; BOARD_SIZE],
+                [None; BOARD_SIZE],
+            ],
+            0,
+            false,
+        );
+        game.set_undo_depth(1);
+
+        game.apply_move(GameAction::Left).unwrap();
+        game.apply_move(GameAction::Down).unwrap();
+        assert!(game.undo().is_some());
+        assert!(game.undo().is_none());
+
+        game.apply_move(GameAction::Right).unwrap();
+        assert!(game.redo().is_none());
+    }
+
+    #[test]
+    fn reaching_the_win_target_wins_once() {
+        let mut game = game_from_rows(
+            [
+                [Some(8), Some(8), Some(8), Some(8)],
+                [None; BOARD_SIZE],
+                [None; BOARD_SIZE],
+                [None; BOARD_SIZE],
+            ],
+            0,
+            false,
+        );
+        game.set_win_target(16);
+
+        let outcome = game.apply_move(GameAction::Left).unwrap();
+        assert!(outcome.won);
+        assert!(game.is_won());
+
+        let outcome = game.apply_move(GameAction::Left).unwrap();
+        assert!(outcome.changed);
+        assert!(!outcome.won);
+        assert!(game.is_won());
+
+        game.undo();
+        game.undo();
+        assert!(!game.is_won());
+        game.restart();
+        assert!(!game.is_won());
+        assert_eq!(game.win_target(), 16);
+    }
+
+    #[test]
+    fn from_board_with_a_winning_tile_is_already_won() {
+        let board: Board = "b000000000000000".parse().unwrap();
+        assert!(Game::from_board(board, 0).is_won());
+    }
+
+    #[test]
+    fn same_seed_and_moves_reproduce_the_game() {
+        let play = |seed| {
+            let mut game = Game::with_seed(seed);
+            let mut boards = vec![*game.board()];
+            for action in GameAction::ALL.into_iter().cycle().take(20) {
+                game.apply_move(action).unwrap();
+                boards.push(*game.board());
+            }
+            game.restart();
+            boards.push(*game.board());
+            boards
+        };
+
+        assert_eq!(play(7), play(7));
+        assert_ne!(play(7), play(8));
+        assert_eq!(Game::with_seed(7).seed(), 7);
+    }
+
+    #[test]
+    fn from_position_continues_with_the_same_spawns() {
+        let mut game = Game::with_seed(5);
+        game.apply_move(GameAction::Left).unwrap();
+        let mut resumed = Game::from_position(
+            *game.board(),
+            game.score(),
+            game.seed(),
+            game.spawns(),
+        );
+
+        for action in [GameAction::Up, GameAction::Right, GameAction::Down] {
+            game.apply_move(action).unwrap();
+            resumed.apply_move(action).unwrap();
+        }
+        assert_eq!(resumed.board(), game.board());
+        assert_eq!(resumed.score(), game.score());
+    }
+
+    #[test]
+    fn undo_rewinds_the_spawns() {
+        let mut game = Game::with_seed(3);
+        let action = GameAction::ALL
+            .into_iter()
+            .find(|&action| Game::preview(game.board(), action).is_some())
+            .unwrap();
+        game.apply_move(action).unwrap();
+        let board = *game.board();
+
+        assert!(game.undo().is_some());
+        game.apply_move(action).unwrap();
+
+        assert_eq!(*game.board(), board);
+    }
+
+    #[test]
+    fn preview_matches_the_tracked_slide() {
+        let mut game = Game::with_seed(11);
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let before = *game.board();
+            let score = game.score();
+            let preview = Game::preview(&before, action);
+            game.apply_move(action).unwrap();
+
+            let Some((next, points)) = preview else {
+                assert_eq!(*game.board(), before);
+                continue;
+            };
+            let spawn = game.last_spawn().unwrap();
+            let mut board = *game.board();
+            board.set_cell(spawn.row, spawn.col, None).unwrap();
+            assert_eq!(board, next);
+            assert_eq!(game.score(), score + points);
+        }
+    }
+
+    #[test]
+    fn fibonacci_games_spawn_ones_and_twos() {
+        let mut game = Game::with_variant(Variant::Fibonacci, 8);
+        assert_eq!(game.win_target(), 610);
+        assert!(game.board().iter_cells().all(|(_, tile)| {
+            tile.is_none_or(|tile| tile == 1 || tile == 2)
+        }));
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let preview = Game::preview(game.board(), action);
+            game.apply_move(action).unwrap();
+
+            if let (Some((next, _)), Some(spawn)) = (preview, game.last_spawn())
+            {
+                assert!(spawn.tile == 1 || spawn.tile == 2);
+                let mut board = *game.board();
+                board.set_cell(spawn.row, spawn.col, None).unwrap();
+                assert_eq!(board, next);
+            }
+        }
+        assert_eq!(game.variant(), Variant::Fibonacci);
+        game.restart();
+        assert_eq!(game.variant(), Variant::Fibonacci);
+    }
+
+    #[test]
+    fn threes_games_move_like_their_boards() {
+        let board: Board = "threes:120x003010201x12".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        assert_eq!(game.win_target(), 768);
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let preview = Game::preview(game.board(), action);
+            let outcome = game.apply_move(action).unwrap();
+
+            if let (Some((next, _)), Some(spawn)) = (preview, game.last_spawn())
+            {
+                assert!(outcome.iter_cells().all(|((row, col), cell)| {
+                    cell.from.iter().flatten().all(|&(from_row, from_col)| {
+                        from_row.abs_diff(row) + from_col.abs_diff(col) <= 1
+                    })
+                }));
+                let mut board = *game.board();
+                board.set_cell(spawn.row, spawn.col, None).unwrap();
+                assert_eq!(board, next);
+            }
+        }
+    }
+
+    #[test]
+    fn rectangular_games_fill_every_cell() {
+        let shape = "2x5".parse().unwrap();
+        let mut game = Game::with_shape(Variant::Classic, shape, 7);
+        assert_eq!(game.board().empty_cells().count(), 8);
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let preview = Game::preview(game.board(), action);
+            let Ok(outcome) = game.apply_move(action) else {
+                break;
+            };
+
+            assert_eq!(outcome.board.len(), 2);
+            assert!(outcome.board.iter().all(|row| row.len() == 5));
+            if let (Some((next, _)), Some(spawn)) = (preview, game.last_spawn())
+            {
+                let mut board = *game.board();
+                board.set_cell(spawn.row, spawn.col, None).unwrap();
+                assert_eq!(board, next);
+            }
+        }
+        assert_eq!(game.board().shape(), shape);
+    }
+
+    #[test]
+    fn wrapping_games_move_like_their_boards() {
+        let board: Board = "wrap:1002110001011021".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let preview = Game::preview(game.board(), action);
+            let Ok(outcome) = game.apply_move(action) else {
+                break;
+            };
+
+            assert!(outcome.iter_cells().all(|((row, col), cell)| {
+                cell.from.iter().flatten().all(|&(from_row, from_col)| {
+                    from_row == row || from_col == col
+                })
+            }));
+            if let (Some((next, _)), Some(spawn)) = (preview, game.last_spawn())
+            {
+                let mut board = *game.board();
+                board.set_cell(spawn.row, spawn.col, None).unwrap();
+                assert_eq!(board, next);
+            }
+        }
+        assert!(game.board().wraps());
+    }
+
+    #[test]
+    fn walls_never_move_and_never_get_a_tile() {
+        let board: Board = "1000x00000x01001".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let before = *game.board();
+            let preview = Game::preview(&before, action);
+            let outcome = game.apply_move(action).unwrap();
+
+            assert!(game.board().is_wall(1, 0) && game.board().is_wall(2, 2));
+            assert!(outcome.board[1][0].wall && outcome.board[2][2].wall);
+            if let (Some((next, _)), Some(spawn)) = (preview, game.last_spawn())
+            {
+                let mut board = *game.board();
+                board.set_cell(spawn.row, spawn.col, None).unwrap();
+                assert_eq!(board, next);
+            }
+        }
+    }
+
+    #[test]
+    fn hard_games_only_place_the_larger_tile() {
+        let mut game = Game::with_seed(3);
+        game.set_difficulty(Difficulty::Hard);
+        for action in GameAction::ALL.into_iter().cycle().take(40) {
+            if game.apply_move(action).unwrap().changed {
+                assert_eq!(game.last_spawn().unwrap().tile, 4);
+            }
+        }
+        assert!(game.moves().len() > 10);
+    }
+
+    #[test]
+    fn merging_moves_in_a_row_build_a_combo() {
+        let board: Board = "1111220000000000".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        game.set_scoring(Scoring::Combo);
+
+        assert_eq!(game.apply_move(GameAction::Left).unwrap().points, 16);
+        assert_eq!(game.combo(), 1);
+        // The fours merge again, twice over.
+        assert_eq!(game.apply_move(GameAction::Left).unwrap().points, 16);
+        assert_eq!((game.score(), game.multiplier()), (32, 3));
+        game.undo();
+        assert_eq!((game.score(), game.combo()), (16, 1));
+
+        let board: Board = "1000000000000000".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        game.set_scoring(Scoring::Combo);
+        game.set_combo(3);
+        assert!(game.apply_move(GameAction::Right).unwrap().changed);
+        assert_eq!(game.combo(), 0);
+    }
+
+    #[test]
+    fn gravity_tiles_fall_and_land_on_each_other() {
+        let board: Board = "gravity:1010000001000000".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        let outcome = game.apply_move(GameAction::Left).unwrap();
+        assert_eq!(outcome.board[2][0].from, [Some((0, 0)), Some((0, 2))]);
+        assert_eq!(outcome.board[3][0].from, [Some((2, 1)), None]);
+
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let before = *game.board();
+            let preview = Game::preview(&before, action);
+            game.apply_move(action).unwrap();
+
+            let board = game.board();
+            for ((row, col), _) in
+                board.iter_cells().filter(|(_, v)| v.is_some())
+            {
+                assert!(
+                    row + 1 == board.rows()
+                        || board.cell(row + 1, col).is_some()
+                );
+            }
+            if let (Some((next, _)), Some(spawn)) = (preview, game.last_spawn())
+            {
+                let mut board = *game.board();
+                board.set_cell(spawn.row, spawn.col, None).unwrap();
+                assert_eq!(board, next);
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_slide_past_voids_which_stay_blank() {
+        let board: Board = "~11~1~010000~10~".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        let outcome = game.apply_move(GameAction::Left).unwrap();
+        assert_eq!(outcome.board[1][0].from, [Some((1, 0)), Some((1, 3))]);
+
+        for action in GameAction::ALL.into_iter().cycle().take(200) {
+            let before = *game.board();
+            let preview = Game::preview(&before, action);
+            let outcome = game.apply_move(action).unwrap();
+
+            assert!(game.board().is_void(0, 0) && game.board().is_void(1, 1));
+            assert!(outcome.board[0][0].void && outcome.board[1][1].void);
+            if let (Some((next, _)), Some(spawn)) = (preview, game.last_spawn())
+            {
+                let mut board = *game.board();
+                board.set_cell(spawn.row, spawn.col, None).unwrap();
+                assert_eq!(board, next);
+            }
+        }
+    }
+
+    #[test]
+    fn json_round_trip_keeps_the_tiles_to_come() {
+        let mut game = Game::with_seed(5);
+        for action in GameAction::ALL.into_iter().cycle().take(8) {
+            game.apply_move(action).unwrap();
+        }
+
+        let mut restored = Game::from_json(&game.to_json().unwrap()).unwrap();
+        assert_eq!(restored.board(), game.board());
+        assert_eq!(restored.score(), game.score());
+        assert_eq!(restored.moves(), game.moves());
+        assert_eq!(restored.stats(), game.stats());
+        for action in GameAction::ALL.into_iter().cycle().take(8) {
+            game.apply_move(action).unwrap();
+            restored.apply_move(action).unwrap();
+            assert_eq!(restored.board(), game.board());
+        }
+
+        assert!(Game::from_json("{\"board\": 7}").is_err());
+    }
+
+    #[test]
+    fn outcome_round_trips_through_json() {
+        let mut game = Game::with_seed(5);
+        let outcome = game.apply_move(GameAction::Left).unwrap();
+
+        let json = serde_json::to_string(&outcome).unwrap();
+        let restored: ActionOutcome = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.score, outcome.score);
+        assert_eq!(restored.changed, outcome.changed);
+        assert_eq!(restored.board.len(), outcome.board.len());
+        assert_eq!(restored.board[0][0].from, outcome.board[0][0].from);
     }
 }
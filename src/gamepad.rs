@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use gilrs::{Axis, Button, EventType, Gilrs};
+use ratatui::crossterm::event::KeyCode;
+use tokio::sync::mpsc::Sender;
+
+use crate::event::Event;
+use crate::keymap::{Command, Key, Keymap};
+
+// How far the stick has to be pushed to move, and how far back it has to
+// come before it moves again.
+const STICK_PUSH: f32 = 0.6;
+const STICK_REST: f32 = 0.3;
+
+// How often the loop checks whether the game has ended.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Returns the key a button stands for. The d-pad presses the arrows, A
+// answers prompts and menus with Enter, and the other buttons press the keys
+// bound to their commands so remapped keys keep working.
+fn button_key(button: Button, keymap: &Keymap) -> Option<Key> {
+    let command = match button {
+        Button::DPadUp => return Some(Key::plain(KeyCode::Up)),
+        Button::DPadDown => return Some(Key::plain(KeyCode::Down)),
+        Button::DPadLeft => return Some(Key::plain(KeyCode::Left)),
+        Button::DPadRight => return Some(Key::plain(KeyCode::Right)),
+        Button::South => return Some(Key::plain(KeyCode::Enter)),
+        Button::East => Command::Undo,
+        Button::North | Button::West => Command::Restart,
+        Button::Start => Command::Pause,
+        Button::Select => Command::Quit,
+        _ => return None,
+    };
+    keymap.keys(command).first().copied()
+}
+
+// The left stick, which presses an arrow once when pushed and again only
+// after it has come back to rest.
+#[derive(Debug, Default)]
+struct Stick {
+    x: f32,
+    y: f32,
+    pushed: bool,
+}
+
+impl Stick {
+    fn update(&mut self, axis: Axis, value: f32) -> Option<Key> {
+        match axis {
+            Axis::LeftStickX => self.x = value,
+            Axis::LeftStickY => self.y = value,
+            _ => return None,
+        }
+        let deflection = self.x.abs().max(self.y.abs());
+        if deflection < STICK_REST {
+            self.pushed = false;
+            return None;
+        }
+        if self.pushed || deflection < STICK_PUSH {
+            return None;
+        }
+        self.pushed = true;
+        // The stick's Y axis points up.
+        let code = match (self.x.abs() > self.y.abs(), self.x, self.y) {
+            (true, x, _) if x > 0.0 => KeyCode::Right,
+            (true, _, _) => KeyCode::Left,
+            (false, _, y) if y > 0.0 => KeyCode::Up,
+            (false, _, _) => KeyCode::Down,
+        };
+        Some(Key::plain(code))
+    }
+}
+
+// Sends the keys that gamepad buttons and the left stick stand for, as if
+// they were typed, until the game ends.
+pub fn input_loop(tx: Sender<Event>, keymap: Keymap) -> Result<()> {
+    let mut gilrs =
+        Gilrs::new().map_err(|e| anyhow!("Failed to open gamepads: {e}"))?;
+    let mut stick = Stick::default();
+    // The receiver is dropped once the game ends, which stops the loop.
+    while !tx.is_closed() {
+        let Some(event) = gilrs.next_event_blocking(Some(POLL_INTERVAL)) else {
+            continue;
+        };
+        let key = match event.event {
+            EventType::ButtonPressed(button, _) => button_key(button, &keymap),
+            EventType::AxisChanged(axis, value, _) => stick.update(axis, value),
+            _ => None,
+        };
+        if let Some(key) = key
+            && tx.blocking_send(Event::Key(key)).is_err()
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stick_moves_once_per_push() {
+        let mut stick = Stick::default();
+        assert_eq!(stick.update(Axis::LeftStickX, 0.4), None);
+        assert_eq!(
+            stick.update(Axis::LeftStickX, 0.9),
+            Some(Key::plain(KeyCode::Right))
+        );
+        assert_eq!(stick.update(Axis::LeftStickX, 1.0), None);
+        assert_eq!(stick.update(Axis::LeftStickX, 0.1), None);
+        assert_eq!(
+            stick.update(Axis::LeftStickY, 0.8),
+            Some(Key::plain(KeyCode::Up))
+        );
+    }
+
+    #[test]
+    fn buttons_press_the_keys_of_their_commands() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            button_key(Button::DPadLeft, &keymap),
+            Some(Key::plain(KeyCode::Left))
+        );
+        assert_eq!(
+            button_key(Button::East, &keymap),
+            keymap.keys(Command::Undo).first().copied()
+        );
+        assert_eq!(button_key(Button::LeftTrigger, &keymap), None);
+    }
+}
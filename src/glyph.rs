@@ -0,0 +1,103 @@
+// Pixels of each digit, 3 wide and 5 tall, one row per byte from the top
+// with the leftmost pixel in the highest bit.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b011, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+const DIGIT_WIDTH: usize = 3;
+const DIGIT_HEIGHT: usize = 5;
+// Blank pixels between two digits.
+const DIGIT_GAP: usize = 1;
+
+// Each character shows a 2x2 block of pixels, indexed by the pixels set:
+// 1 for the top left, 2 for the top right, 4 for the bottom left and 8 for
+// the bottom right.
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟',
+    '█',
+];
+
+// Draws a number in block digits as large as fit in `width` by `height`
+// characters, scaling each pixel by the same whole factor. Returns `None` if
+// the text is not a number or does not fit even at the smallest size, so it
+// is shown as plain text instead.
+pub fn render(text: &str, width: usize, height: usize) -> Option<Vec<String>> {
+    let digits: Vec<usize> = text
+        .chars()
+        .map(|c| c.to_digit(10).map(|d| d as usize))
+        .collect::<Option<_>>()?;
+    if digits.is_empty() {
+        return None;
+    }
+    let pixels_wide = digits.len() * (DIGIT_WIDTH + DIGIT_GAP) - DIGIT_GAP;
+    // The largest scale whose pixels fit, two to a character each way.
+    let scale = (1..)
+        .take_while(|scale| {
+            (pixels_wide * scale).div_ceil(2) <= width
+                && (DIGIT_HEIGHT * scale).div_ceil(2) <= height
+        })
+        .last()?;
+
+    let pixel = |x: usize, y: usize| {
+        let (x, y) = (x / scale, y / scale);
+        let (digit, column) =
+            (x / (DIGIT_WIDTH + DIGIT_GAP), x % (DIGIT_WIDTH + DIGIT_GAP));
+        y < DIGIT_HEIGHT
+            && digit < digits.len()
+            && column < DIGIT_WIDTH
+            && DIGITS[digits[digit]][y] >> (DIGIT_WIDTH - 1 - column) & 1 == 1
+    };
+    let lines = (0..(DIGIT_HEIGHT * scale).div_ceil(2))
+        .map(|row| {
+            (0..(pixels_wide * scale).div_ceil(2))
+                .map(|col| {
+                    let (x, y) = (col * 2, row * 2);
+                    let index = usize::from(pixel(x, y))
+                        | usize::from(pixel(x + 1, y)) << 1
+                        | usize::from(pixel(x, y + 1)) << 2
+                        | usize::from(pixel(x + 1, y + 1)) << 3;
+                    QUADRANTS[index]
+                })
+                .collect()
+        })
+        .collect();
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digits_are_drawn_in_quadrant_blocks() {
+        assert_eq!(
+            render("2048", 9, 3).unwrap(),
+            ["▀▌▛▌▌▌▛▌", "▛▘▌▌▀▌▛▌", "▀▘▀▘ ▘▀▘"]
+        );
+    }
+
+    #[test]
+    fn digits_are_scaled_to_the_space_given() {
+        let small = render("2", 9, 3).unwrap();
+        assert_eq!((small.len(), small[0].chars().count()), (3, 2));
+        let large = render("2", 9, 5).unwrap();
+        assert_eq!((large.len(), large[0].chars().count()), (5, 3));
+        assert_eq!(large[0], "███");
+    }
+
+    #[test]
+    fn text_that_does_not_fit_is_left_plain() {
+        assert_eq!(render("16384", 9, 3), None);
+        assert_eq!(render("2", 9, 2), None);
+        assert_eq!(render("2¹¹", 9, 3), None);
+        assert_eq!(render("", 9, 3), None);
+    }
+}
@@ -0,0 +1,404 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use anyhow::{Error, Result};
+use macroquad::Window;
+use macroquad::color::Color;
+use macroquad::input::{self, KeyCode as WindowKey};
+use macroquad::shapes::draw_rectangle;
+use macroquad::text::{draw_text, measure_text};
+use macroquad::window::{
+    Conf, clear_background, next_frame, screen_height, screen_width,
+};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::game::{ActionOutcome, DEFAULT_UNDO_DEPTH, Game, GameAction, TITLE};
+use crate::history::{GameRecord, History};
+use crate::integrity::{SetAside, Signer};
+use crate::keymap::{Command, Key, Keymap};
+use crate::label::LabelMode;
+use crate::profile::Profile;
+use crate::save::{Autosave, SavedGame};
+use crate::settings::Settings;
+use crate::stopwatch::Stopwatch;
+use crate::sync::Remote;
+use crate::{load_history, metrics, pull_save};
+
+const WINDOW_WIDTH: i32 = 480;
+const WINDOW_HEIGHT: i32 = 560;
+// Height of the score line above the board.
+const HEADER_HEIGHT: f32 = 80.0;
+const MARGIN: f32 = 16.0;
+const GAP: f32 = 12.0;
+
+const BACKGROUND: Color = Color::from_rgba(250, 248, 239, 255);
+const BOARD_COLOR: Color = Color::from_rgba(187, 173, 160, 255);
+const EMPTY_COLOR: Color = Color::from_rgba(205, 193, 180, 255);
+const WALL_COLOR: Color = Color::from_rgba(119, 110, 101, 255);
+const DARK_TEXT: Color = Color::from_rgba(119, 110, 101, 255);
+const LIGHT_TEXT: Color = Color::from_rgba(249, 246, 242, 255);
+const WARNING_TEXT: Color = Color::from_rgba(205, 60, 40, 255);
+const OVERLAY: Color = Color::from_rgba(238, 228, 218, 186);
+const WIN_OVERLAY: Color = Color::from_rgba(237, 194, 46, 128);
+
+// Tile colors indexed by exponent, from 2 up to 2048. Larger tiles reuse the
+// last color.
+const TILE_COLORS: [Color; 11] = [
+    Color::from_rgba(238, 228, 218, 255),
+    Color::from_rgba(237, 224, 200, 255),
+    Color::from_rgba(242, 177, 121, 255),
+    Color::from_rgba(245, 149, 99, 255),
+    Color::from_rgba(246, 124, 95, 255),
+    Color::from_rgba(246, 94, 59, 255),
+    Color::from_rgba(237, 207, 114, 255),
+    Color::from_rgba(237, 204, 97, 255),
+    Color::from_rgba(237, 200, 80, 255),
+    Color::from_rgba(237, 197, 63, 255),
+    Color::from_rgba(237, 194, 46, 255),
+];
+
+// The windowed counterpart of the terminal `App`, sharing the profile's
+// keymap, history and save file.
+struct Gui {
+    game: Game,
+    outcome: ActionOutcome,
+    history: History,
+    labels: LabelMode,
+    keymap: Keymap,
+    clock: Stopwatch,
+    // Whether the current game has been recorded in the history.
+    recorded: bool,
+    // Whether the win is shown, until the player keeps playing or restarts.
+    celebrating: bool,
+    // Damaged files set aside at launch, warned about in place of the seed
+    // until the first key press.
+    warnings: Vec<String>,
+}
+
+impl Gui {
+    fn restart(&mut self) {
+        self.outcome = self.game.restart();
+        self.clock = Stopwatch::start();
+        self.recorded = false;
+        self.celebrating = false;
+    }
+
+    // Runs a command, returning false when the window should close.
+    fn handle(&mut self, command: Command) -> Result<bool> {
+        self.warnings.clear();
+        let (action, slam) = match command {
+            Command::Quit => return Ok(false),
+            Command::Restart => {
+                self.restart();
+                return Ok(true);
+            }
+            // As in the terminal, a finished game cannot be taken back.
+            Command::Undo | Command::Redo if !self.game.is_game_over() => {
+                let outcome = match command {
+                    Command::Undo => self.game.undo(),
+                    _ => self.game.redo(),
+                };
+                if let Some(outcome) = outcome {
+                    self.outcome = outcome;
+                }
+                return Ok(true);
+            }
+            Command::MoveUp => (GameAction::Up, false),
+            Command::MoveDown => (GameAction::Down, false),
+            Command::MoveLeft => (GameAction::Left, false),
+            Command::MoveRight => (GameAction::Right, false),
+            Command::SlamUp => (GameAction::Up, true),
+            Command::SlamDown => (GameAction::Down, true),
+            Command::SlamLeft => (GameAction::Left, true),
+            Command::SlamRight => (GameAction::Right, true),
+            // The panels and the gallery are only offered in the terminal.
+            _ => return Ok(true),
+        };
+        if self.game.is_game_over() {
+            return Ok(true);
+        }
+
+        loop {
+            let outcome = self.game.apply_move(action)?;
+            if !outcome.changed && !outcome.game_over {
+                break;
+            }
+            if outcome.changed {
+                metrics::moved(outcome.score);
+            }
+            self.celebrating |= outcome.won;
+            let done = !slam || outcome.game_over || outcome.won;
+            self.outcome = outcome;
+            if done {
+                break;
+            }
+        }
+        self.record_game_over()?;
+        Ok(true)
+    }
+
+    fn record_game_over(&mut self) -> Result<()> {
+        if !self.outcome.game_over || self.recorded {
+            return Ok(());
+        }
+        let run = GameRecord::new(
+            self.game.score(),
+            self.game.max_tile(),
+            self.clock.elapsed(),
+        )
+        .with_board(*self.game.board())
+        .with_win(self.game.is_won())
+        .with_difficulty(self.game.difficulty());
+        self.history.record(run);
+        self.recorded = true;
+        metrics::game_finished();
+        self.history.save()
+    }
+}
+
+// Translates the keys pressed since the last frame into keymap keys. Arrows
+// are read as key codes, everything else as typed characters so the layout
+// presets and custom bindings work as they do in the terminal.
+fn pressed_keys() -> Vec<Key> {
+    let shift = input::is_key_down(WindowKey::LeftShift)
+        || input::is_key_down(WindowKey::RightShift);
+    let mut keys: Vec<Key> = input::get_keys_pressed()
+        .into_iter()
+        .filter_map(|key| arrow_key(key, shift))
+        .collect();
+    while let Some(c) = input::get_char_pressed() {
+        if !c.is_control() {
+            keys.push(Key::char(c));
+        }
+    }
+    keys
+}
+
+fn arrow_key(key: WindowKey, shift: bool) -> Option<Key> {
+    let code = match key {
+        WindowKey::Up => KeyCode::Up,
+        WindowKey::Down => KeyCode::Down,
+        WindowKey::Left => KeyCode::Left,
+        WindowKey::Right => KeyCode::Right,
+        _ => return None,
+    };
+    let modifiers = if shift {
+        KeyModifiers::SHIFT
+    } else {
+        KeyModifiers::NONE
+    };
+    Some(Key::new(code, modifiers))
+}
+
+fn tile_color(value: u32) -> Color {
+    let index = value.trailing_zeros().saturating_sub(1) as usize;
+    TILE_COLORS[index.min(TILE_COLORS.len() - 1)]
+}
+
+fn draw_centered(text: &str, x: f32, y: f32, size: u16, color: Color) {
+    let dimensions = measure_text(text, None, size, 1.0);
+    draw_text(
+        text,
+        x - dimensions.width / 2.0,
+        y + dimensions.offset_y / 2.0,
+        f32::from(size),
+        color,
+    );
+}
+
+fn draw(gui: &Gui) {
+    clear_background(BACKGROUND);
+
+    let best = gui
+        .history
+        .best_score_at(gui.game.difficulty())
+        .max(gui.game.score());
+    draw_text(TITLE.trim(), MARGIN, 48.0, 48.0, DARK_TEXT);
+    let mut seed = format!("Seed {}", gui.game.seed());
+    if gui.game.board().wraps() {
+        seed.push_str("   Wrap");
+    }
+    match gui.warnings.first() {
+        Some(warning) => draw_text(warning, MARGIN, 68.0, 16.0, WARNING_TEXT),
+        None => draw_text(&seed, MARGIN, 68.0, 16.0, DARK_TEXT),
+    };
+    let score = format!("Score {}   Best {}", gui.game.score(), best);
+    let width = measure_text(&score, None, 24, 1.0).width;
+    draw_text(
+        &score,
+        screen_width() - MARGIN - width,
+        44.0,
+        24.0,
+        DARK_TEXT,
+    );
+
+    // The board is the largest that fits below the header with square
+    // cells, centered across the window.
+    let (rows, cols) = (gui.game.board().rows(), gui.game.board().cols());
+    let sides = |cells: usize| GAP * (cells + 1) as f32;
+    let cell = ((screen_width() - MARGIN * 2.0 - sides(cols)) / cols as f32)
+        .min(
+            (screen_height() - HEADER_HEIGHT - MARGIN - sides(rows))
+                / rows as f32,
+        );
+    let width = cols as f32 * cell + sides(cols);
+    let height = rows as f32 * cell + sides(rows);
+    let left = (screen_width() - width) / 2.0;
+    let top = HEADER_HEIGHT;
+    draw_rectangle(left, top, width, height, BOARD_COLOR);
+
+    let font_size = (cell * 0.4) as u16;
+    for (row, cells) in gui.outcome.board.iter().enumerate() {
+        for (col, result) in cells.iter().enumerate() {
+            let x = left + GAP + col as f32 * (cell + GAP);
+            let y = top + GAP + row as f32 * (cell + GAP);
+            if result.void {
+                continue;
+            }
+            let Some(value) = result.value else {
+                let color = if result.wall { WALL_COLOR } else { EMPTY_COLOR };
+                draw_rectangle(x, y, cell, cell, color);
+                continue;
+            };
+            draw_rectangle(x, y, cell, cell, tile_color(value));
+            let text = if value <= 4 { DARK_TEXT } else { LIGHT_TEXT };
+            let label = gui.labels.format(value);
+            // Long labels shrink so they stay inside the tile.
+            let size = font_size * 3 / label.chars().count().max(3) as u16;
+            draw_centered(&label, x + cell / 2.0, y + cell / 2.0, size, text);
+        }
+    }
+
+    let (center, middle) = (left + width / 2.0, top + height / 2.0);
+    if gui.game.is_game_over() {
+        draw_rectangle(left, top, width, height, OVERLAY);
+        draw_centered("Game over!", center, middle, 48, DARK_TEXT);
+        let hint = "Press the restart key to play again";
+        draw_centered(hint, center, middle + 48.0, 20, DARK_TEXT);
+    } else if gui.celebrating {
+        draw_rectangle(left, top, width, height, WIN_OVERLAY);
+        draw_centered("You win!", center, middle, 48, LIGHT_TEXT);
+        let hint = "Press C to keep playing or the restart key to start over";
+        draw_centered(hint, center, middle + 48.0, 18, LIGHT_TEXT);
+    }
+}
+
+async fn event_loop(gui: &mut Gui) -> Result<()> {
+    // Closing the window is handled here so an unfinished game is saved.
+    input::prevent_quit();
+    loop {
+        if input::is_quit_requested() {
+            return Ok(());
+        }
+        for key in pressed_keys() {
+            let command = gui.keymap.command(key);
+            if gui.celebrating
+                && !matches!(command, Some(Command::Restart | Command::Quit))
+            {
+                gui.celebrating = key != Key::char('c');
+                continue;
+            }
+            if let Some(command) = command
+                && !gui.handle(command)?
+            {
+                return Ok(());
+            }
+        }
+        draw(gui);
+        next_frame().await;
+    }
+}
+
+// Plays in a window using the profile's settings, history and save, so games
+// can be moved freely between the terminal and the window.
+pub fn run(profile: Profile) -> Result<()> {
+    let settings = Settings::load(&profile)?;
+    let mut sync = settings.sync.clone().map(Remote::new);
+    let signer = Signer::load()?;
+    let (history, history_set_aside) =
+        load_history(&profile, &signer, &mut sync)?;
+    pull_save(&profile, &signer, &mut sync);
+
+    let mut clock = Stopwatch::start();
+    let (save, save_set_aside) = SavedGame::take(&profile, &signer)?;
+    let warnings = history_set_aside
+        .iter()
+        .chain(&save_set_aside)
+        .map(SetAside::notice)
+        .collect();
+    let mut game = match save {
+        Some(save) => {
+            clock =
+                Stopwatch::resume_from(Duration::from_secs(save.duration_secs));
+            save.game
+        }
+        None => Game::new(),
+    };
+    game.set_undo_depth(settings.undo_depth.unwrap_or(DEFAULT_UNDO_DEPTH));
+    let mut gui = Gui {
+        outcome: game.outcome(),
+        game,
+        history,
+        labels: settings.labels,
+        keymap: Keymap::new(settings.layout, settings.keymap),
+        clock,
+        recorded: false,
+        celebrating: false,
+        warnings,
+    };
+
+    // The window runs the game on this thread and only hands back control
+    // once it closes, so the result is passed out through a shared cell.
+    let result: Rc<RefCell<Option<Result<Gui>>>> = Rc::default();
+    let output = Rc::clone(&result);
+    let conf = Conf {
+        window_title: TITLE.trim().to_string(),
+        window_width: WINDOW_WIDTH,
+        window_height: WINDOW_HEIGHT,
+        high_dpi: true,
+        ..Default::default()
+    };
+    Window::from_config(conf, async move {
+        let outcome = event_loop(&mut gui).await.map(|_| gui);
+        *output.borrow_mut() = Some(outcome);
+        macroquad::window::miniquad::window::order_quit();
+    });
+
+    let gui = result
+        .take()
+        .unwrap_or_else(|| Err(Error::msg("The window closed unexpectedly")))?;
+    // An unfinished game is saved to be resumed on the next launch.
+    Autosave::new(profile.clone(), signer.clone())
+        .write(&gui.game, gui.clock.elapsed())?;
+    if let Some(remote) = &sync {
+        if let Err(e) = SavedGame::push(&profile, remote, &signer) {
+            eprintln!("Failed to sync the save: {e:#}");
+        }
+        if let Err(e) = gui.history.push(remote, &signer) {
+            eprintln!("Failed to sync history: {e:#}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrows_keep_shift_for_slams() {
+        let keymap = Keymap::default();
+        let key = |code, shift| keymap.command(arrow_key(code, shift)?);
+        assert_eq!(key(WindowKey::Left, false), Some(Command::MoveLeft));
+        assert_eq!(key(WindowKey::Up, true), Some(Command::SlamUp));
+        assert_eq!(key(WindowKey::Q, false), None);
+    }
+
+    #[test]
+    fn large_tiles_reuse_the_last_color() {
+        assert_eq!(tile_color(2), TILE_COLORS[0]);
+        assert_eq!(tile_color(2048), TILE_COLORS[10]);
+        assert_eq!(tile_color(1 << 16), TILE_COLORS[10]);
+    }
+}
@@ -0,0 +1,174 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::board::Tile;
+use crate::game::{Game, GameAction};
+
+// A line written after each move: the board with 0 for empty cells, and
+// whether the move changed it. The first line, before any move, has no
+// `moved` field. Walls and voids, also 0 on the board, are listed when there
+// are any.
+#[derive(Debug, Serialize)]
+pub struct State {
+    board: Vec<Vec<u32>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    walls: Vec<(usize, usize)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    voids: Vec<(usize, usize)>,
+    score: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    moved: Option<bool>,
+    game_over: bool,
+    won: bool,
+}
+
+impl State {
+    pub fn new(game: &Game, moved: Option<bool>) -> Self {
+        let board = game.board();
+        let mut cells = vec![vec![0; board.cols()]; board.rows()];
+        let mut walls = Vec::new();
+        let mut voids = Vec::new();
+        for ((row, col), tile) in board.iter_tiles() {
+            match tile {
+                Some(Tile::Value(value)) => cells[row][col] = value,
+                Some(Tile::Wall) => walls.push((row, col)),
+                Some(Tile::Void) => voids.push((row, col)),
+                None => {}
+            }
+        }
+        Self {
+            board: cells,
+            walls,
+            voids,
+            score: game.score(),
+            moved,
+            game_over: game.is_game_over(),
+            won: game.is_won(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Error {
+    error: String,
+}
+
+impl Error {
+    // The error answering a line that is not a move.
+    pub fn unknown_move(line: &str) -> Self {
+        Self {
+            error: format!("Unknown move '{line}', expected U, D, L or R"),
+        }
+    }
+
+    // The error answering a move sent while the game is not being played,
+    // e.g. from the title screen or the pause menu.
+    pub fn not_playing() -> Self {
+        Self {
+            error: "The game is not being played, try again later".to_string(),
+        }
+    }
+}
+
+pub fn parse_move(line: &str) -> Option<GameAction> {
+    let mut chars = line.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) => GameAction::from_letter(letter),
+        _ => None,
+    }
+}
+
+fn write_line(output: &mut impl Write, value: &impl Serialize) -> Result<()> {
+    serde_json::to_writer(&mut *output, value)?;
+    writeln!(output)?;
+    output.flush()?;
+    Ok(())
+}
+
+// Plays the game with one move per input line (`U`, `D`, `L` or `R`) and
+// writes the state as a JSON line after each one, starting with the initial
+// state. Lines that are not a move get an error line instead. Input ends at
+// `Q` or the end of the stream.
+pub fn run(
+    mut game: Game,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> Result<()> {
+    write_line(&mut output, &State::new(&game, None))?;
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("q") {
+            break;
+        }
+        let Some(action) = parse_move(line) else {
+            write_line(&mut output, &Error::unknown_move(line))?;
+            continue;
+        };
+        let outcome = game.apply_move(action)?;
+        write_line(&mut output, &State::new(&game, Some(outcome.changed)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    fn play(input: &str) -> Vec<Value> {
+        let mut output = Vec::new();
+        run(Game::with_seed(1), input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn writes_the_state_before_and_after_each_move() {
+        let lines = play("L\nr\n\nU\n");
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].get("moved").is_none());
+        assert_eq!(lines[0]["score"], 0);
+        let tiles: Vec<u64> = lines[0]["board"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|row| row.as_array().unwrap())
+            .map(|tile| tile.as_u64().unwrap())
+            .filter(|&tile| tile > 0)
+            .collect();
+        assert_eq!(tiles.len(), 2);
+        assert!(lines[1]["moved"].is_boolean());
+        assert_eq!(lines[3]["game_over"], false);
+        assert!(lines[0].get("walls").is_none());
+    }
+
+    #[test]
+    fn lists_the_walls() {
+        let board = "1x00000000000000".parse().unwrap();
+        let mut output = Vec::new();
+        run(Game::from_board(board, 0), "q".as_bytes(), &mut output).unwrap();
+
+        let line: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(line["walls"], serde_json::json!([[0, 1]]));
+        assert_eq!(line["board"][0][1], 0);
+    }
+
+    #[test]
+    fn reports_unknown_moves_and_stops_at_quit() {
+        let lines = play("X\nq\nL\n");
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1]["error"].as_str().unwrap().contains("'X'"));
+    }
+}
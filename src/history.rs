@@ -0,0 +1,576 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::integrity::{SetAside, Signer};
+#[cfg(test)]
+use crate::persistence;
+use crate::profile::Profile;
+use crate::rules::Difficulty;
+use crate::sync::Remote;
+
+const HISTORY_FILE: &str = "history.json";
+
+// Score ranges are rounded up to a multiple of this.
+const SCORE_STEP: u32 = 1000;
+
+// Number of runs shown on the game-over scoreboard.
+pub const TOP_RUNS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub score: u32,
+    // Zero when unknown, e.g. for best scores imported from other games.
+    pub max_tile: u32,
+    pub finished_at: DateTime<Utc>,
+    pub duration_secs: u64,
+    // Whether the game reached the winning tile, false for runs recorded
+    // before wins were kept.
+    #[serde(default)]
+    pub won: bool,
+    // Board code of the final position, missing for runs recorded before
+    // snapshots were kept and for boards too large to encode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+    // The difficulty the game was played at, which has its own leaderboard.
+    #[serde(default, skip_serializing_if = "Difficulty::is_normal")]
+    pub difficulty: Difficulty,
+    // Where the player stood when they handed the game over to the AI to
+    // finish, for games the AI finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handoff: Option<Handoff>,
+    // Where the game stood when a tool on the control socket or pipe first
+    // played a move in it, for games tools played in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<Handoff>,
+}
+
+// The score, best tile and moves of a game when the player handed it over to
+// the AI to finish, or a tool first played in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handoff {
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: usize,
+}
+
+impl GameRecord {
+    pub fn new(score: u32, max_tile: u32, duration: Duration) -> Self {
+        Self {
+            score,
+            max_tile,
+            finished_at: Utc::now(),
+            duration_secs: duration.as_secs(),
+            won: false,
+            board: None,
+            difficulty: Difficulty::Normal,
+            handoff: None,
+            tool: None,
+        }
+    }
+
+    // Keeps a snapshot of the final board with the run.
+    pub fn with_board(mut self, board: Board) -> Self {
+        self.board = Some(board.to_code());
+        self
+    }
+
+    // Records whether the game reached the winning tile.
+    pub fn with_win(mut self, won: bool) -> Self {
+        self.won = won;
+        self
+    }
+
+    // Records the difficulty the game was played at.
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    // Records where the player handed the game over to the AI, if they did.
+    pub fn with_handoff(mut self, handoff: Option<Handoff>) -> Self {
+        self.handoff = handoff;
+        self
+    }
+
+    // Records where a tool first played a move in the game, if one did.
+    pub fn with_tool(mut self, tool: Option<Handoff>) -> Self {
+        self.tool = tool;
+        self
+    }
+
+    // Who played the game besides the player, if anyone: the AI that
+    // finished it or a tool.
+    pub fn helper(&self) -> Option<&'static str> {
+        if self.handoff.is_some() {
+            Some("AI")
+        } else if self.tool.is_some() {
+            Some("Tool")
+        } else {
+            None
+        }
+    }
+}
+
+// Aggregate statistics over all finished games.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub games: usize,
+    pub best_score: u32,
+    pub average_score: u32,
+    pub best_tile: u32,
+    pub total_secs: u64,
+    pub wins: usize,
+    // Number of games the player handed over to the AI to finish.
+    pub finished_by_ai: usize,
+    // Number of games that ended with each best tile, smallest tile first.
+    // Runs whose best tile is unknown are left out.
+    pub tiles: Vec<(u32, usize)>,
+}
+
+impl Summary {
+    // Share of the games won, from 0 to 1.
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games as f64
+    }
+}
+
+// All finished games, persisted as JSON in the profile directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    // Where the history is persisted and the signer protecting it.
+    #[serde(skip)]
+    file: Option<(PathBuf, Signer)>,
+    runs: Vec<GameRecord>,
+    // The best score brought over from another implementation with
+    // `--import`. It counts as the best score at the normal difficulty but,
+    // not being a run played here, is left out of the runs and their
+    // statistics.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    imported_best: Option<u32>,
+}
+
+impl History {
+    // Loads the profile's history. A missing file yields an empty history;
+    // without a data directory nothing is ever persisted.
+    // The file is signed so that edits made outside the game are detected,
+    // and one that fails the check or is corrupt is set aside for an empty
+    // history, returned alongside it to warn about.
+    pub fn load(
+        profile: &Profile,
+        signer: &Signer,
+    ) -> Result<(Self, Option<SetAside>)> {
+        let Some(path) = profile.path(HISTORY_FILE) else {
+            return Ok((Self::default(), None));
+        };
+
+        let (history, set_aside) = signer.recover_json(&path)?;
+        let mut history: History = history.unwrap_or_default();
+        history.file = Some((path, signer.clone()));
+        Ok((history, set_aside))
+    }
+
+    // Reads the profile's history like `load`, except that a damaged file is
+    // left where it is and reported as an error. Without a signing key, any
+    // history file cannot be checked and is reported too. The history read
+    // is never saved.
+    pub fn read(profile: &Profile, signer: Option<&Signer>) -> Result<Self> {
+        let Some(path) = profile.path(HISTORY_FILE) else {
+            return Ok(Self::default());
+        };
+        let Some(signer) = signer else {
+            if path.exists() {
+                bail!("{} cannot be checked without a key", path.display());
+            }
+            return Ok(Self::default());
+        };
+        Ok(signer.read_json(&path)?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        match &self.file {
+            Some((path, signer)) => signer.write_json(path, self),
+            None => Ok(()),
+        }
+    }
+
+    // Merges the remote copy of the history into this one. The copy is
+    // signed like the file, so it must have been pushed with the same key.
+    pub fn pull(&mut self, remote: &Remote, signer: &Signer) -> Result<()> {
+        if let Some(contents) = remote.pull(HISTORY_FILE)? {
+            let name = format!("the remote {HISTORY_FILE}");
+            self.merge(signer.verify_json(&contents, &name)?);
+        }
+        Ok(())
+    }
+
+    pub fn push(&self, remote: &Remote, signer: &Signer) -> Result<()> {
+        remote.push(HISTORY_FILE, &signer.sign_json(self)?)
+    }
+
+    // Adds the runs from another copy of the history that are missing from
+    // this one, keeping runs in the order they finished.
+    fn merge(&mut self, other: History) {
+        self.imported_best = self.imported_best.max(other.imported_best);
+        for run in other.runs {
+            if !self.runs.contains(&run) {
+                self.runs.push(run);
+            }
+        }
+        self.runs.sort_by_key(|run| run.finished_at);
+    }
+
+    // Keeps the best score imported from another implementation, unless one
+    // at least as high was imported before, and returns whether it was kept.
+    pub fn import_best(&mut self, score: u32) -> bool {
+        if self.imported_best >= Some(score) {
+            return false;
+        }
+        self.imported_best = Some(score);
+        true
+    }
+
+    pub fn imported_best(&self) -> Option<u32> {
+        self.imported_best
+    }
+
+    // Appends a finished run and returns its index in the history.
+    pub fn record(&mut self, run: GameRecord) -> usize {
+        self.runs.push(run);
+        self.runs.len() - 1
+    }
+
+    pub fn run(&self, index: usize) -> Option<&GameRecord> {
+        self.runs.get(index)
+    }
+
+    // Returns the runs with a final board snapshot, most recent first.
+    pub fn snapshots(&self) -> Vec<(&GameRecord, Board)> {
+        self.runs
+            .iter()
+            .rev()
+            .filter_map(|run| Some((run, run.board.as_deref()?.parse().ok()?)))
+            .collect()
+    }
+
+    // The highest score of any finished game or the imported best, or 0
+    // before either.
+    pub fn best_score(&self) -> u32 {
+        self.runs
+            .iter()
+            .map(|run| run.score)
+            .chain(self.imported_best)
+            .max()
+            .unwrap_or_default()
+    }
+
+    // The highest score of any game finished at a difficulty, counting the
+    // imported best at the normal one, or 0 before the first one.
+    pub fn best_score_at(&self, difficulty: Difficulty) -> u32 {
+        let imported = self.imported_best.filter(|_| difficulty.is_normal());
+        self.runs
+            .iter()
+            .filter(|run| run.difficulty == difficulty)
+            .map(|run| run.score)
+            .chain(imported)
+            .max()
+            .unwrap_or_default()
+    }
+
+    // Summarizes the history, or returns `None` if no games were played.
+    pub fn summary(&self) -> Option<Summary> {
+        if self.runs.is_empty() {
+            return None;
+        }
+
+        let total_score: u64 =
+            self.runs.iter().map(|r| u64::from(r.score)).sum();
+        let mut tiles = BTreeMap::new();
+        for run in self.runs.iter().filter(|r| r.max_tile > 0) {
+            *tiles.entry(run.max_tile).or_default() += 1;
+        }
+        Some(Summary {
+            games: self.runs.len(),
+            best_score: self.runs.iter().map(|r| r.score).max()?,
+            average_score: (total_score / self.runs.len() as u64) as u32,
+            best_tile: self.runs.iter().map(|r| r.max_tile).max()?,
+            total_secs: self.runs.iter().map(|r| r.duration_secs).sum(),
+            wins: self.runs.iter().filter(|r| r.won).count(),
+            finished_by_ai: self
+                .runs
+                .iter()
+                .filter(|r| r.handoff.is_some())
+                .count(),
+            tiles: tiles.into_iter().collect(),
+        })
+    }
+
+    // Counts the games scoring within each of `count` equal ranges from 0 up
+    // to the best score, returned with the lowest score of their range. The
+    // ranges are rounded to whole thousands so they are easy to read.
+    pub fn score_ranges(&self, count: usize) -> Vec<(u32, usize)> {
+        let step =
+            (self.best_score() / count as u32 / SCORE_STEP + 1) * SCORE_STEP;
+        let mut ranges: Vec<(u32, usize)> =
+            (0..count as u32).map(|i| (i * step, 0)).collect();
+        for run in &self.runs {
+            let index = (run.score / step) as usize;
+            ranges[index.min(count - 1)].1 += 1;
+        }
+        // Ranges past the best score are left out.
+        let used = ranges.iter().rposition(|&(_, games)| games > 0);
+        ranges.truncate(used.map_or(0, |i| i + 1));
+        ranges
+    }
+
+    // Returns up to `count` runs played at a difficulty, ordered by
+    // descending score along with their index in the history. Ties keep the
+    // earlier run first.
+    pub fn top(
+        &self,
+        count: usize,
+        difficulty: Difficulty,
+    ) -> Vec<(usize, &GameRecord)> {
+        let mut runs: Vec<(usize, &GameRecord)> = self
+            .runs
+            .iter()
+            .enumerate()
+            .filter(|(_, run)| run.difficulty == difficulty)
+            .collect();
+        runs.sort_by_key(|(_, run)| std::cmp::Reverse(run.score));
+        runs.truncate(count);
+        runs
+    }
+}
+
+// Formats a duration in seconds as "m:ss", or "h:mm:ss" past the hour.
+pub fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(score: u32) -> GameRecord {
+        GameRecord::new(score, 2, Duration::from_secs(1))
+    }
+
+    #[test]
+    fn top_orders_by_score_and_keeps_indices() {
+        let mut history = History::default();
+        history.record(run(100));
+        history.record(run(300));
+        history.record(run(200));
+
+        let top: Vec<(usize, u32)> = history
+            .top(2, Difficulty::Normal)
+            .into_iter()
+            .map(|(index, run)| (index, run.score))
+            .collect();
+
+        assert_eq!(top, vec![(1, 300), (2, 200)]);
+    }
+
+    #[test]
+    fn imported_best_counts_as_the_best_score_but_not_as_a_run() {
+        let mut history = History::default();
+        history.record(run(300));
+        assert!(history.import_best(1000));
+        assert!(!history.import_best(1000));
+        assert!(!history.import_best(500));
+
+        assert_eq!(history.best_score(), 1000);
+        assert_eq!(history.best_score_at(Difficulty::Normal), 1000);
+        assert_eq!(history.best_score_at(Difficulty::Hard), 0);
+        let summary = history.summary().unwrap();
+        assert_eq!((summary.games, summary.best_score), (1, 300));
+    }
+
+    #[test]
+    fn top_keeps_earlier_run_first_on_ties() {
+        let mut history = History::default();
+        history.record(run(100));
+        let latest = history.record(run(100));
+
+        let top = history.top(TOP_RUNS, Difficulty::Normal);
+
+        assert_eq!(top[1].0, latest);
+    }
+
+    #[test]
+    fn each_difficulty_has_its_own_leaderboard() {
+        let mut history = History::default();
+        history.record(run(100));
+        let hard = history.record(run(50).with_difficulty(Difficulty::Hard));
+        history.record(run(300));
+
+        let top = history.top(TOP_RUNS, Difficulty::Hard);
+
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].0, hard);
+        assert_eq!(history.best_score_at(Difficulty::Hard), 50);
+        assert_eq!(history.best_score_at(Difficulty::Expert), 0);
+        assert_eq!(history.best_score(), 300);
+    }
+
+    #[test]
+    fn merge_adds_missing_runs_in_finish_order() {
+        let first = run(100);
+        let second = run(200);
+        let mut local = History::default();
+        local.record(second.clone());
+        let mut remote = History::default();
+        remote.record(first.clone());
+        remote.record(second.clone());
+
+        local.merge(remote);
+
+        assert_eq!(local.runs, vec![first, second]);
+    }
+
+    #[test]
+    fn snapshots_list_runs_with_boards_newest_first() {
+        let board: Board = "1100000000000002".parse().unwrap();
+        let mut history = History::default();
+        history.record(run(100).with_board(board));
+        history.record(run(200));
+        history.record(run(300).with_board(board));
+
+        let scores: Vec<u32> = history
+            .snapshots()
+            .into_iter()
+            .map(|(run, snapshot)| {
+                assert_eq!(snapshot, board);
+                run.score
+            })
+            .collect();
+
+        assert_eq!(scores, [300, 100]);
+    }
+
+    #[test]
+    fn helper_names_who_played_besides_the_player() {
+        let handoff = Handoff {
+            score: 120,
+            max_tile: 8,
+            moves: 40,
+        };
+        let run = GameRecord::new(200, 16, Duration::from_secs(30));
+        assert_eq!(run.helper(), None);
+        assert_eq!(run.clone().with_tool(Some(handoff)).helper(), Some("Tool"));
+        let both = run.with_tool(Some(handoff)).with_handoff(Some(handoff));
+        assert_eq!(both.helper(), Some("AI"));
+    }
+
+    #[test]
+    fn summary_aggregates_all_runs() {
+        let mut history = History::default();
+        assert_eq!(history.summary(), None);
+        assert_eq!(history.best_score(), 0);
+
+        history.record(GameRecord::new(100, 16, Duration::from_secs(10)));
+        history.record(
+            GameRecord::new(300, 64, Duration::from_secs(20)).with_win(true),
+        );
+        let handoff = Handoff {
+            score: 120,
+            max_tile: 8,
+            moves: 40,
+        };
+        history.record(
+            GameRecord::new(200, 16, Duration::from_secs(30))
+                .with_handoff(Some(handoff)),
+        );
+        history.record(GameRecord::new(200, 0, Duration::ZERO));
+
+        assert_eq!(
+            history.summary(),
+            Some(Summary {
+                games: 4,
+                best_score: 300,
+                average_score: 200,
+                best_tile: 64,
+                total_secs: 60,
+                wins: 1,
+                finished_by_ai: 1,
+                tiles: vec![(16, 2), (64, 1)],
+            })
+        );
+        assert_eq!(history.summary().unwrap().win_rate(), 0.25);
+        assert_eq!(history.best_score(), 300);
+    }
+
+    #[test]
+    fn score_ranges_count_games_up_to_the_best_score() {
+        let mut history = History::default();
+        assert!(history.score_ranges(4).is_empty());
+        for score in [500, 1500, 2500, 3200, 3900] {
+            history.record(run(score));
+        }
+
+        assert_eq!(
+            history.score_ranges(8),
+            [(0, 1), (1000, 1), (2000, 1), (3000, 2)]
+        );
+        assert_eq!(history.score_ranges(2), [(0, 2), (2000, 3)]);
+    }
+
+    #[test]
+    fn save_without_path_is_a_no_op() {
+        let mut history = History::default();
+        history.record(run(100));
+        assert!(history.save().is_ok());
+    }
+
+    #[test]
+    fn save_and_reload_round_trip() {
+        let dir = persistence::test_dir("history");
+        let profile = Profile::at(dir.clone());
+        let signer = Signer::new(b"secret".to_vec());
+        let mut history = History::load(&profile, &signer).unwrap().0;
+        history.record(run(512));
+
+        history.save().unwrap();
+        let loaded = History::load(&profile, &signer).unwrap().0;
+
+        assert_eq!(loaded.runs, history.runs);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn read_leaves_a_damaged_history_in_place() {
+        let dir = persistence::test_dir("history-read");
+        let profile = Profile::at(dir.clone());
+        let signer = Signer::new(b"secret".to_vec());
+        assert!(History::read(&profile, None).is_ok());
+        let mut history = History::load(&profile, &signer).unwrap().0;
+        history.record(run(512));
+        history.save().unwrap();
+
+        let other = Signer::new(b"other".to_vec());
+        assert!(History::read(&profile, Some(&other)).is_err());
+        assert!(History::read(&profile, None).is_err());
+        assert!(profile.path(HISTORY_FILE).unwrap().exists());
+        let read = History::read(&profile, Some(&signer)).unwrap();
+        assert_eq!(read.runs, history.runs);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn format_duration_handles_minutes_and_hours() {
+        assert_eq!(format_duration(5), "0:05");
+        assert_eq!(format_duration(125), "2:05");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+}
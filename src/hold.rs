@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+// How long a key must be held to complete the gesture.
+pub const HOLD_DURATION: Duration = Duration::from_secs(1);
+
+// Terminals report a held key as a stream of presses: the first repeat
+// follows after the keyboard's repeat delay, the rest arrive quickly. A gap
+// longer than these means the key was released.
+const FIRST_REPEAT_GAP: Duration = Duration::from_millis(700);
+const REPEAT_GAP: Duration = Duration::from_millis(200);
+
+// Tracks a key being held down from the presses the terminal reports.
+#[derive(Clone, Copy, Debug)]
+pub struct Hold {
+    started: Instant,
+    last_press: Instant,
+    repeating: bool,
+}
+
+impl Hold {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            last_press: now,
+            repeating: false,
+        }
+    }
+
+    // Whether the key is considered released at `now`.
+    pub fn is_released(&self, now: Instant) -> bool {
+        let gap = if self.repeating {
+            REPEAT_GAP
+        } else {
+            FIRST_REPEAT_GAP
+        };
+        now.duration_since(self.last_press) > gap
+    }
+
+    // Registers another press of the key, returning true once it has been
+    // held for `HOLD_DURATION`. A press after the key was released starts
+    // the gesture over.
+    pub fn press(&mut self, now: Instant) -> bool {
+        if self.is_released(now) {
+            *self = Hold::new(now);
+            return false;
+        }
+        self.last_press = now;
+        self.repeating = true;
+        now.duration_since(self.started) >= HOLD_DURATION
+    }
+
+    // Fraction of `HOLD_DURATION` the key has been held, from 0 to 1.
+    pub fn progress(&self, now: Instant) -> f64 {
+        let held = now.duration_since(self.started).as_secs_f64();
+        (held / HOLD_DURATION.as_secs_f64()).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn repeated_presses_complete_after_hold_duration() {
+        let start = Instant::now();
+        let mut hold = Hold::new(start);
+
+        assert!(!hold.press(start + ms(500)));
+        let mut now = start + ms(500);
+        while now < start + HOLD_DURATION - ms(30) {
+            now += ms(30);
+            assert!(!hold.press(now));
+        }
+        assert!(hold.press(start + HOLD_DURATION));
+        assert_eq!(hold.progress(start + HOLD_DURATION), 1.0);
+    }
+
+    #[test]
+    fn slow_taps_do_not_complete() {
+        let start = Instant::now();
+        let mut hold = Hold::new(start);
+
+        assert!(!hold.press(start + ms(600)));
+        assert!(!hold.press(start + ms(1200)));
+        assert!(hold.progress(start + ms(1200)) < 0.1);
+    }
+
+    #[test]
+    fn release_is_detected_from_the_gap_between_presses() {
+        let start = Instant::now();
+        let mut hold = Hold::new(start);
+        assert!(!hold.is_released(start + ms(600)));
+        assert!(hold.is_released(start + ms(800)));
+
+        hold.press(start + ms(500));
+        assert!(!hold.is_released(start + ms(600)));
+        assert!(hold.is_released(start + ms(800)));
+    }
+}
@@ -0,0 +1,348 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+use crate::board::{Board, Shape};
+use crate::rules::Variant;
+
+// Marks a wall in text grids and layouts.
+const WALL: &str = "#";
+
+// Marks a void, a cell that is not part of the board, in text grids and
+// layouts.
+const VOID: &str = "~";
+
+// A position and best score imported from another 2048 implementation.
+#[derive(Debug, Default)]
+pub struct Import {
+    pub board: Option<Board>,
+    pub score: u32,
+    pub best_score: Option<u32>,
+}
+
+// Reads a save exported from another implementation.
+pub fn import_file(path: &Path) -> Result<Import> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    import_text(&contents)
+        .with_context(|| format!("Failed to import {}", path.display()))
+}
+
+// Reads the position a practice game starts from: the name of a file holding
+// any position `import_file` reads, or the position itself.
+pub fn start_position(arg: &str) -> Result<Board> {
+    let import = if Path::new(arg).is_file() {
+        import_file(Path::new(arg))?
+    } else {
+        import_text(arg)
+            .with_context(|| format!("Invalid starting position '{arg}'"))?
+    };
+    import.board.context("The starting position has no board")
+}
+
+// Parses a position, detecting the format from the contents: JSON is treated
+// as a browser localStorage export of the original web game, a single word
+// with '/' between rows and ',' between cells as a layout, any other single
+// word as a board code and anything else as a plain-text grid.
+pub fn import_text(contents: &str) -> Result<Import> {
+    let trimmed = contents.trim();
+    let import = if trimmed.starts_with('{') {
+        from_local_storage(trimmed)?
+    } else if !trimmed.contains(char::is_whitespace)
+        && trimmed.contains('/')
+        && trimmed.contains(',')
+    {
+        Import {
+            board: Some(from_layout(trimmed)?),
+            ..Default::default()
+        }
+    } else if !trimmed.contains(char::is_whitespace) {
+        Import {
+            board: Some(trimmed.parse()?),
+            ..Default::default()
+        }
+    } else {
+        from_text_grid(trimmed)?
+    };
+
+    if let Some(board) = &import.board {
+        validate(board)?;
+    }
+    Ok(import)
+}
+
+// The web game stores its values as strings, so the game state is JSON
+// encoded inside the exported JSON object.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalStorage {
+    best_score: Option<String>,
+    game_state: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WebGameState {
+    grid: WebGrid,
+    score: u32,
+}
+
+#[derive(Deserialize)]
+struct WebGrid {
+    size: usize,
+    // Indexed as cells[x][y], i.e. column-major.
+    cells: Vec<Vec<Option<WebTile>>>,
+}
+
+#[derive(Deserialize)]
+struct WebTile {
+    value: u32,
+}
+
+fn from_local_storage(contents: &str) -> Result<Import> {
+    let storage: LocalStorage = serde_json::from_str(contents)?;
+    let mut import = Import {
+        best_score: storage
+            .best_score
+            .map(|best| best.trim().parse())
+            .transpose()
+            .context("Invalid bestScore")?,
+        ..Default::default()
+    };
+
+    if let Some(state) = storage.game_state {
+        let state: WebGameState = serde_json::from_str(&state)?;
+        let size = state.grid.size;
+        let shape = Shape::new(size, size)?;
+
+        let mut board = Board::with_shape(Variant::Classic, shape);
+        for (col, cells) in state.grid.cells.iter().enumerate().take(size) {
+            for (row, tile) in cells.iter().enumerate().take(size) {
+                board.set_cell(row, col, tile.as_ref().map(|t| t.value))?;
+            }
+        }
+        import.board = Some(board);
+        import.score = state.score;
+    }
+
+    Ok(import)
+}
+
+// Parses a plain-text grid as written by several terminal clones: one row per
+// line with values separated by whitespace or '|', where '.', '-', '_' or '0'
+// mark an empty cell, '#' a wall and '~' a void. Optional "score: N" and
+// "best: N" lines are honored.
+fn from_text_grid(contents: &str) -> Result<Import> {
+    let mut import = Import::default();
+    let mut rows = Vec::new();
+
+    for line in contents.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let lower = line.to_ascii_lowercase();
+        if let Some(score) = lower.strip_prefix("score:") {
+            import.score = score.trim().parse().context("Invalid score")?;
+            continue;
+        }
+        if let Some(best) = lower.strip_prefix("best:") {
+            import.best_score =
+                Some(best.trim().parse().context("Invalid best score")?);
+            continue;
+        }
+
+        rows.push(
+            line.split(|c: char| c.is_whitespace() || c == '|')
+                .filter(|c| !c.is_empty())
+                .collect(),
+        );
+    }
+
+    import.board = Some(from_rows(&rows, |cell| {
+        matches!(cell, "." | "-" | "_" | "0")
+    })?);
+    Ok(import)
+}
+
+// Parses a one-line layout such as "2,2,,4/,#,,/,8,,/,,,2": rows are
+// separated by '/' and cells by ',', with empty cells left blank, '#' for
+// a wall and '~' for a void.
+fn from_layout(layout: &str) -> Result<Board> {
+    let rows: Vec<Vec<&str>> = layout
+        .split('/')
+        .map(|row| row.split(',').collect())
+        .collect();
+    from_rows(&rows, str::is_empty)
+}
+
+// Builds a board with as many rows as given and as many columns as the first
+// row has cells, failing if another row has a different number of cells.
+fn from_rows(rows: &[Vec<&str>], is_empty: fn(&str) -> bool) -> Result<Board> {
+    let cols = rows.first().map_or(0, Vec::len);
+    if let Some(row) = rows.iter().position(|cells| cells.len() != cols) {
+        bail!("Expected {cols} cells on row {}", row + 1);
+    }
+
+    let shape = Shape::new(rows.len(), cols)?;
+    let mut board = Board::with_shape(Variant::Classic, shape);
+    for (row, cells) in rows.iter().enumerate() {
+        for (col, &cell) in cells.iter().enumerate() {
+            let value = match cell {
+                WALL => {
+                    board.set_wall(row, col);
+                    continue;
+                }
+                VOID => {
+                    board.set_void(row, col);
+                    continue;
+                }
+                cell if is_empty(cell) => None,
+                value => Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid tile '{value}'"))?,
+                ),
+            };
+            board.set_cell(row, col, value)?;
+        }
+    }
+    Ok(board)
+}
+
+// Rejects positions this game cannot continue from. Tiles the board cannot
+// hold were already rejected when the board was read.
+fn validate(board: &Board) -> Result<()> {
+    if board.max_tile().is_none() {
+        bail!("The imported board has no tiles");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_storage_export_maps_columns_to_rows() {
+        let state = r#"{"grid":{"size":4,"cells":[
+            [{"position":{"x":0,"y":0},"value":2},null,null,null],
+            [null,{"position":{"x":1,"y":1},"value":4},null,null],
+            [null,null,null,null],
+            [null,null,null,{"position":{"x":3,"y":3},"value":8}]
+        ]},"score":36,"over":false,"won":false,"keepPlaying":false}"#;
+        let export = serde_json::json!({
+            "bestScore": "1024",
+            "gameState": state,
+        })
+        .to_string();
+
+        let import = from_local_storage(&export).unwrap();
+        let board = import.board.unwrap();
+
+        assert_eq!(import.score, 36);
+        assert_eq!(import.best_score, Some(1024));
+        assert_eq!(board.cell(0, 0), Some(2));
+        assert_eq!(board.cell(1, 1), Some(4));
+        assert_eq!(board.cell(3, 3), Some(8));
+        assert_eq!(board.iter_cells().filter(|(_, v)| v.is_some()).count(), 3);
+    }
+
+    #[test]
+    fn local_storage_export_without_game_only_imports_best_score() {
+        let import = from_local_storage(r#"{"bestScore":"512"}"#).unwrap();
+        assert!(import.board.is_none());
+        assert_eq!(import.best_score, Some(512));
+    }
+
+    #[test]
+    fn text_grid_parses_rows_and_score() {
+        let import = from_text_grid(
+            "Score: 20\n\
+             2 . . 4\n\
+             | 0 | 8 | # | _ |\n\
+             . . . .\n\
+             16 . ~ 2\n",
+        )
+        .unwrap();
+        let board = import.board.unwrap();
+
+        assert_eq!(import.score, 20);
+        assert_eq!(board.cell(0, 3), Some(4));
+        assert_eq!(board.cell(1, 1), Some(8));
+        assert_eq!(board.cell(1, 0), None);
+        assert!(board.is_wall(1, 2));
+        assert!(board.is_void(3, 2));
+        assert_eq!(board.cell(3, 0), Some(16));
+    }
+
+    #[test]
+    fn layout_parses_rows_with_blank_cells() {
+        let board = from_layout("2,2,,4/,#,,/,8,,/,,,2048").unwrap();
+
+        assert_eq!(board.cell(0, 1), Some(2));
+        assert_eq!(board.cell(0, 2), None);
+        assert_eq!(board.cell(0, 3), Some(4));
+        assert_eq!(board.cell(2, 1), Some(8));
+        assert_eq!(board.cell(3, 3), Some(2048));
+        assert!(board.is_wall(1, 1));
+        assert_eq!(board.iter_cells().filter(|(_, v)| v.is_some()).count(), 5);
+
+        assert!(from_layout("2,2,,4/,,,/,8,,/,,").is_err());
+        assert!(from_layout("2,2,,4").is_err());
+        assert!(from_layout("2,2,4/,,,/,8,,/,,,2").is_err());
+        assert!(from_layout("2,x,,4/,,,/,8,,/,,,2").is_err());
+    }
+
+    #[test]
+    fn import_text_detects_board_codes_and_grids() {
+        let code = import_text(" 1100000000000002\n").unwrap();
+        assert_eq!(code.board.unwrap().cell(3, 3), Some(4));
+
+        let grid = import_text("2 . . .\n. . . .\n. . . .\n. . . 4").unwrap();
+        assert_eq!(grid.board.unwrap().cell(3, 3), Some(4));
+
+        let layout = import_text(",,,/,,,/,,,/,,,4\n").unwrap();
+        assert_eq!(layout.board.unwrap().cell(3, 3), Some(4));
+        assert!(import_text(",,,/,,,/,,,/,,,").is_err());
+
+        assert!(import_text("0000000000000000").is_err());
+        assert!(import_text("hello").is_err());
+    }
+
+    #[test]
+    fn validate_rejects_empty_boards_and_odd_values() {
+        let mut board = Board::default();
+        assert!(validate(&board).is_err());
+
+        assert!(import_text("3 . . .\n. . . .\n. . . .\n. . . .").is_err());
+        assert!(import_text("65536 . . .\n. . . .\n. . . .\n. . . .").is_err());
+
+        board.set_cell(0, 0, Some(4)).unwrap();
+        assert!(validate(&board).is_ok());
+    }
+
+    #[test]
+    fn positions_take_the_shape_of_their_rows() {
+        let board = from_layout("2,2,,4/,,,/,8,,").unwrap();
+        assert_eq!((board.rows(), board.cols()), (3, 4));
+        assert_eq!(board.cell(2, 1), Some(8));
+
+        let grid = import_text(
+            "2 . . . . 4
+. . . . . .",
+        )
+        .unwrap();
+        let board = grid.board.unwrap();
+        assert_eq!((board.rows(), board.cols()), (2, 6));
+        assert_eq!(board.cell(0, 5), Some(4));
+
+        let code = import_text("010/000/002").unwrap().board.unwrap();
+        assert_eq!((code.rows(), code.cols()), (3, 3));
+        assert_eq!(code.cell(2, 2), Some(4));
+    }
+
+    #[test]
+    fn text_grid_rejects_wrong_dimensions() {
+        assert!(from_text_grid("2 2 2\n").is_err());
+        assert!(from_text_grid("2 2 2 2\n").is_err());
+        assert!(from_text_grid("2 x 2 2\n. . . .\n. . . .\n. . . .").is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::VecDeque;
+
+use crate::event::Event;
+
+// Keys and pastes held at most while the tiles slide. Keys pressed beyond
+// this are dropped rather than played long after they were pressed.
+const QUEUE_LIMIT: usize = 8;
+
+// Copies of the same key held at most one after another, so holding a key
+// down does not keep moving after it is released.
+const MAX_REPEATS: usize = 2;
+
+// Input received while the tiles of a move slide, handled in order once they
+// stop. Commands from the control socket are always kept, since their agent
+// waits for a reply.
+#[derive(Debug, Default)]
+pub struct InputQueue {
+    events: VecDeque<Event>,
+}
+
+impl InputQueue {
+    // Queues an event, returning whether it was kept.
+    pub fn push(&mut self, event: Event) -> bool {
+        if let Event::Key(key) = &event {
+            let repeats = self
+                .events
+                .iter()
+                .rev()
+                .take_while(
+                    |queued| matches!(queued, Event::Key(k) if k == key),
+                )
+                .count();
+            if repeats >= MAX_REPEATS {
+                return false;
+            }
+        }
+        let input =
+            |event: &Event| matches!(event, Event::Key(_) | Event::Paste(_));
+        if input(&event)
+            && self.events.iter().filter(|queued| input(queued)).count()
+                >= QUEUE_LIMIT
+        {
+            return false;
+        }
+        self.events.push_back(event);
+        true
+    }
+
+    pub fn pop(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::KeyCode;
+
+    use super::*;
+    use crate::keymap::Key;
+
+    fn keys(queue: &mut InputQueue) -> Vec<Key> {
+        std::iter::from_fn(|| queue.pop())
+            .filter_map(|event| match event {
+                Event::Key(key) => Some(key),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repeated_keys_are_coalesced() {
+        let (left, up) = (Key::plain(KeyCode::Left), Key::plain(KeyCode::Up));
+        let mut queue = InputQueue::default();
+        for key in [left, left, left, left, up, left] {
+            queue.push(Event::Key(key));
+        }
+        assert_eq!(keys(&mut queue), [left, left, up, left]);
+    }
+
+    #[test]
+    fn keys_beyond_the_limit_are_dropped() {
+        let mut queue = InputQueue::default();
+        let pressed: Vec<Key> = "abcdefghij".chars().map(Key::char).collect();
+        let kept: Vec<bool> = pressed
+            .iter()
+            .map(|&key| queue.push(Event::Key(key)))
+            .collect();
+        assert_eq!(kept.iter().filter(|&&kept| kept).count(), QUEUE_LIMIT);
+        assert!(queue.push(Event::Resize));
+        assert_eq!(keys(&mut queue), pressed[..QUEUE_LIMIT]);
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Error, Result, bail};
+use hmac::{Hmac, KeyInit, Mac};
+use rand::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sha2::Sha256;
+
+use crate::persistence;
+
+const KEY_FILE: &str = "key";
+const KEY_LEN: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Signs and verifies persisted files with an HMAC keyed by a secret stored
+// locally in the data directory, so hand-edited files can be detected.
+#[derive(Clone, Debug)]
+pub struct Signer {
+    key: Vec<u8>,
+    // Whether the key was generated on this launch, so files written before
+    // the game signed anything can still be unsigned.
+    fresh: bool,
+}
+
+// A signed file: the payload is kept as readable JSON next to its MAC.
+#[derive(Serialize, Deserialize)]
+struct Envelope<'a> {
+    #[serde(borrow)]
+    payload: &'a RawValue,
+    mac: String,
+}
+
+// Why a signed file, named by its path or where it came from, cannot be
+// trusted, as opposed to not being readable at all.
+#[derive(Debug)]
+enum Damaged {
+    // The file is not a signed file, or its payload is not what was signed.
+    Corrupt(String),
+    // The payload does not match its MAC.
+    Tampered(String),
+}
+
+impl fmt::Display for Damaged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Damaged::Corrupt(name) => write!(f, "Failed to parse {name}"),
+            Damaged::Tampered(name) => write!(
+                f,
+                "{name} failed its integrity check and may have been tampered \
+                 with"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Damaged {}
+
+// A damaged file that `recover_json` set aside, for the player to be told
+// about.
+#[derive(Debug)]
+pub struct SetAside {
+    path: PathBuf,
+    aside: PathBuf,
+    // Why the file could not be trusted.
+    error: String,
+}
+
+impl SetAside {
+    // A one-line notice naming the file, short enough for the status line.
+    pub fn notice(&self) -> String {
+        let name = self.path.file_name().unwrap_or(self.path.as_os_str());
+        format!("{} was damaged and set aside", name.display())
+    }
+}
+
+impl fmt::Display for SetAside {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}. It was moved to {} and a fresh one started.",
+            self.error,
+            self.aside.display()
+        )
+    }
+}
+
+impl Signer {
+    pub fn new(key: Vec<u8>) -> Self {
+        Self { key, fresh: false }
+    }
+
+    // Loads the local signing key, generating one on first use. Without a
+    // data directory an ephemeral key is used since nothing is persisted.
+    pub fn load() -> Result<Self> {
+        if let Some(signer) = Self::find()? {
+            return Ok(signer);
+        }
+        let key = random_key();
+        if let Some(path) = key_path() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, encode_hex(&key)).with_context(|| {
+                format!("Failed to write {}", path.display())
+            })?;
+        }
+        Ok(Self { key, fresh: true })
+    }
+
+    // Loads the local signing key if one was generated before, leaving the
+    // data directory untouched otherwise.
+    pub fn find() -> Result<Option<Self>> {
+        let Some(path) = key_path() else {
+            return Ok(None);
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Ok(Some(Self::new(decode_hex(contents.trim())?))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+                .with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.key)
+            .expect("HMAC accepts keys of any length")
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        encode_hex(&mac.finalize().into_bytes())
+    }
+
+    fn verify(&self, payload: &str, signature: &str) -> bool {
+        let Ok(signature) = decode_hex(signature) else {
+            return false;
+        };
+        let mut mac = self.mac();
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    // Reads a signed JSON file, failing if its contents do not match the MAC.
+    // A missing file yields `None`. A plain JSON file read with a key
+    // generated on this launch was written before files were signed, and is
+    // signed as it is, once, so it keeps loading on later launches.
+    pub fn read_json<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+    ) -> Result<Option<T>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read {}", path.display())
+                });
+            }
+        };
+
+        let name = path.display().to_string();
+        let envelope: Envelope = match serde_json::from_str(&contents) {
+            Ok(envelope) => envelope,
+            Err(_)
+                if self.fresh
+                    && let Ok(value) = serde_json::from_str(&contents) =>
+            {
+                let payload = RawValue::from_string(contents.trim().into())?;
+                persistence::write_json(path, &self.envelope(&payload))?;
+                return Ok(Some(value));
+            }
+            Err(e) => return Err(e).with_context(|| Damaged::Corrupt(name)),
+        };
+        self.open(&envelope, &name).map(Some)
+    }
+
+    // Checks the text of a signed file kept elsewhere, e.g. a synced copy,
+    // like `read_json` checks a file. `name` says where it came from.
+    pub fn verify_json<T: DeserializeOwned>(
+        &self,
+        contents: &str,
+        name: &str,
+    ) -> Result<T> {
+        let envelope: Envelope = serde_json::from_str(contents)
+            .with_context(|| Damaged::Corrupt(name.to_string()))?;
+        self.open(&envelope, name)
+    }
+
+    // Parses the payload of a signed file if it matches its MAC.
+    fn open<T: DeserializeOwned>(
+        &self,
+        envelope: &Envelope,
+        name: &str,
+    ) -> Result<T> {
+        if !self.verify(envelope.payload.get(), &envelope.mac) {
+            return Err(Error::new(Damaged::Tampered(name.to_string())));
+        }
+        serde_json::from_str(envelope.payload.get())
+            .with_context(|| Damaged::Corrupt(name.to_string()))
+    }
+
+    // Reads a signed JSON file like `read_json`, except that a file which is
+    // corrupt or fails its integrity check is set aside as `<file>.bad`, so
+    // it is neither loaded nor overwritten, and treated as missing so the
+    // game starts fresh. What was set aside is returned to warn about.
+    pub fn recover_json<T: DeserializeOwned>(
+        &self,
+        path: &Path,
+    ) -> Result<(Option<T>, Option<SetAside>)> {
+        match self.read_json(path) {
+            Err(e) if e.downcast_ref::<Damaged>().is_some() => {
+                let mut aside = path.as_os_str().to_owned();
+                aside.push(".bad");
+                fs::rename(path, &aside).with_context(|| {
+                    format!("{e:#}, and it could not be set aside")
+                })?;
+                let set_aside = SetAside {
+                    path: path.to_path_buf(),
+                    aside: aside.into(),
+                    error: format!("{e:#}"),
+                };
+                Ok((None, Some(set_aside)))
+            }
+            result => Ok((result?, None)),
+        }
+    }
+
+    // Signs a value and writes it atomically as a JSON envelope.
+    pub fn write_json<T: Serialize>(
+        &self,
+        path: &Path,
+        value: &T,
+    ) -> Result<()> {
+        let payload =
+            RawValue::from_string(serde_json::to_string_pretty(value)?)?;
+        persistence::write_json(path, &self.envelope(&payload))
+    }
+
+    // Signs a value as the text of a signed file, to keep elsewhere and check
+    // with `verify_json`.
+    pub fn sign_json<T: Serialize>(&self, value: &T) -> Result<String> {
+        let payload =
+            RawValue::from_string(serde_json::to_string_pretty(value)?)?;
+        Ok(serde_json::to_string_pretty(&self.envelope(&payload))?)
+    }
+
+    fn envelope<'a>(&self, payload: &'a RawValue) -> Envelope<'a> {
+        Envelope {
+            payload,
+            mac: self.sign(payload.get()),
+        }
+    }
+}
+
+fn key_path() -> Option<PathBuf> {
+    persistence::data_dir().map(|dir| dir.join(KEY_FILE))
+}
+
+fn random_key() -> Vec<u8> {
+    let mut key = vec![0; KEY_LEN];
+    rand::rng().fill_bytes(&mut key);
+    key
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    if !s.is_ascii() || !s.len().is_multiple_of(2) {
+        bail!("Invalid hex string");
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&s[i..i + 2], 16).context("Invalid hex string")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trips() {
+        let bytes = vec![0x00, 0x7f, 0xff, 0x10];
+        assert_eq!(encode_hex(&bytes), "007fff10");
+        assert_eq!(decode_hex("007fff10").unwrap(), bytes);
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+        assert!(decode_hex("é0").is_err());
+    }
+
+    #[test]
+    fn signed_file_round_trips() {
+        let dir = persistence::test_dir("signed-round-trip");
+        let path = dir.join("signed.json");
+        let signer = Signer::new(b"secret".to_vec());
+
+        signer.write_json(&path, &vec![2, 4, 8]).unwrap();
+        let value: Option<Vec<u32>> = signer.read_json(&path).unwrap();
+
+        assert_eq!(value, Some(vec![2, 4, 8]));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn signed_text_round_trips_and_edits_are_detected() {
+        let signer = Signer::new(b"secret".to_vec());
+        let signed = signer.sign_json(&vec![2, 4, 8]).unwrap();
+
+        let value: Vec<u32> = signer.verify_json(&signed, "remote").unwrap();
+        assert_eq!(value, vec![2, 4, 8]);
+        let edited = signed.replace('8', "2048");
+        let value: Result<Vec<u32>> = signer.verify_json(&edited, "remote");
+        assert!(value.is_err());
+        let value: Result<Vec<u32>> = signer.verify_json("[2, 4, 8]", "remote");
+        assert!(value.is_err());
+    }
+
+    #[test]
+    fn edited_payload_is_detected() {
+        let dir = persistence::test_dir("signed-tampered");
+        let path = dir.join("signed.json");
+        let signer = Signer::new(b"secret".to_vec());
+        signer.write_json(&path, &vec![2, 4, 8]).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::write(&path, contents.replace('8', "2048")).unwrap();
+        let value: Result<Option<Vec<u32>>> = signer.read_json(&path);
+
+        assert!(value.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn file_signed_with_another_key_is_rejected() {
+        let dir = persistence::test_dir("signed-other-key");
+        let path = dir.join("signed.json");
+        Signer::new(b"secret".to_vec())
+            .write_json(&path, &vec![2])
+            .unwrap();
+
+        let value: Result<Option<Vec<u32>>> =
+            Signer::new(b"other".to_vec()).read_json(&path);
+
+        assert!(value.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unsigned_file_is_signed_only_with_a_fresh_key() {
+        let dir = persistence::test_dir("signed-legacy");
+        let path = dir.join("legacy.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "[2, 4, 8]").unwrap();
+        let signer = Signer::new(b"secret".to_vec());
+
+        let value: Result<Option<Vec<u32>>> = signer.read_json(&path);
+        assert!(value.is_err());
+
+        let fresh = Signer {
+            fresh: true,
+            ..signer.clone()
+        };
+        let value: Option<Vec<u32>> = fresh.read_json(&path).unwrap();
+        assert_eq!(value, Some(vec![2, 4, 8]));
+        let value: Option<Vec<u32>> = signer.read_json(&path).unwrap();
+        assert_eq!(value, Some(vec![2, 4, 8]));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn damaged_file_is_set_aside() {
+        let dir = persistence::test_dir("signed-set-aside");
+        let signer = Signer::new(b"secret".to_vec());
+        for (name, damage) in [("tampered", "2048"), ("truncated", "")] {
+            let path = dir.join(format!("{name}.json"));
+            signer.write_json(&path, &vec![2, 4, 8]).unwrap();
+            let contents = fs::read_to_string(&path).unwrap();
+            let damaged = match damage {
+                "" => contents[..contents.len() / 2].to_string(),
+                value => contents.replace('8', value),
+            };
+            fs::write(&path, &damaged).unwrap();
+
+            let (value, set_aside): (Option<Vec<u32>>, _) =
+                signer.recover_json(&path).unwrap();
+
+            assert_eq!(value, None, "{name}");
+            assert_eq!(
+                set_aside.unwrap().notice(),
+                format!("{name}.json was damaged and set aside")
+            );
+            assert!(!path.exists(), "{name}");
+            let aside = dir.join(format!("{name}.json.bad"));
+            assert_eq!(fs::read_to_string(aside).unwrap(), damaged);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+use clap::ValueEnum;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+// Commands that can be bound to keys.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    // Repeat a move until the board stops changing.
+    SlamUp,
+    SlamDown,
+    SlamLeft,
+    SlamRight,
+    // Show or hide the move history panel and scroll through it.
+    ToggleMoves,
+    // Show or hide the log of notable merges.
+    ToggleMerges,
+    // Show or hide the stats of the current game.
+    ToggleStats,
+    // Show or hide the internal state of the game, for debugging.
+    ToggleDebug,
+    // Shade each cell by how often tiles spawned on it, or show the tiles.
+    ToggleHeatmap,
+    // Show or hide the AI's evaluation of each move.
+    ToggleAnalysis,
+    // Write a Markdown report of the game that just ended.
+    SaveReport,
+    // Copy a summary of the game that just ended to share it.
+    CopySummary,
+    // Step through the positions of the game that just ended, or stop.
+    ToggleReview,
+    // Write the moves of the game that just ended in move notation.
+    ExportNotation,
+    // Save the game under a name, or pick a named save to load.
+    SaveSlot,
+    LoadSlot,
+    // Write the current position to a JSON file, or load it back.
+    ExportPosition,
+    LoadPosition,
+    // Open or close the gallery of past final boards.
+    ToggleGallery,
+    // Open or close the leaderboard of the best finished games.
+    ToggleLeaderboard,
+    // Switch to the next built-in theme.
+    CycleTheme,
+    // Mute or unmute the sound effects.
+    ToggleSound,
+    ScrollUp,
+    ScrollDown,
+    // Take back the latest move, or play an undone move again.
+    Undo,
+    Redo,
+    // Let the AI play the game, or take back control.
+    ToggleAutoplay,
+    FinishWithAi,
+    // Show the move the AI recommends.
+    Hint,
+    // Play a power-up on tiles picked on the board.
+    SwapTiles,
+    DeleteTile,
+    UpgradeTile,
+    Restart,
+    // Pause the game and open the menu.
+    Pause,
+    // Show or hide the keybindings and rules.
+    Help,
+    Quit,
+}
+
+impl Command {
+    // Describes the command on the help screen.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Command::MoveUp => "Move up",
+            Command::MoveDown => "Move down",
+            Command::MoveLeft => "Move left",
+            Command::MoveRight => "Move right",
+            Command::SlamUp => "Slam up",
+            Command::SlamDown => "Slam down",
+            Command::SlamLeft => "Slam left",
+            Command::SlamRight => "Slam right",
+            Command::ToggleMoves => "Moves panel",
+            Command::ToggleMerges => "Merge log",
+            Command::ToggleStats => "Game stats",
+            Command::ToggleDebug => "Debug panel",
+            Command::ToggleHeatmap => "Spawn heatmap",
+            Command::ToggleAnalysis => "Move evaluations",
+            Command::SaveReport => "Save report",
+            Command::CopySummary => "Copy summary",
+            Command::ToggleReview => "Review game",
+            Command::ExportNotation => "Save moves",
+            Command::SaveSlot => "Save game",
+            Command::LoadSlot => "Load game",
+            Command::ExportPosition => "Export position",
+            Command::LoadPosition => "Load position",
+            Command::ToggleGallery => "Gallery",
+            Command::ToggleLeaderboard => "Leaderboard",
+            Command::CycleTheme => "Next theme",
+            Command::ToggleSound => "Sound on/off",
+            Command::ScrollUp => "Scroll moves up",
+            Command::ScrollDown => "Scroll moves down",
+            Command::Undo => "Undo",
+            Command::Redo => "Redo",
+            Command::ToggleAutoplay => "Autoplay",
+            Command::FinishWithAi => "Finish with AI",
+            Command::Hint => "Hint",
+            Command::SwapTiles => "Swap two tiles",
+            Command::DeleteTile => "Delete a tile",
+            Command::UpgradeTile => "Upgrade a tile",
+            Command::Restart => "Restart",
+            Command::Pause => "Pause menu",
+            Command::Help => "Help",
+            Command::Quit => "Quit",
+        }
+    }
+}
+
+// A key press with its modifiers, written as e.g. "k", "up" or "ctrl-r".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // The case of a character already reflects shift, so it is dropped to
+        // make "W" match regardless of how the terminal reports it.
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    pub const fn plain(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    pub const fn char(c: char) -> Self {
+        Self::plain(KeyCode::Char(c))
+    }
+
+    // The character typed by this key, if it is one typed without Ctrl or
+    // Alt.
+    pub fn typed(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => Some(c),
+            _ => None,
+        }
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+const MODIFIERS: [(&str, KeyModifiers); 3] = [
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+];
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, modifier) in MODIFIERS {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}-")?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            code => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, named)| *named == code)
+                    .map_or("?", |(name, _)| name);
+                write!(f, "{name}")
+            }
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+
+        // A trailing "-" is the minus key itself rather than a separator.
+        while let Some((prefix, tail)) = rest.split_once('-')
+            && !tail.is_empty()
+        {
+            let Some((_, modifier)) = MODIFIERS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(prefix))
+            else {
+                bail!("Unknown modifier '{prefix}' in key '{s}'");
+            };
+            modifiers |= *modifier;
+            rest = tail;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ if rest.eq_ignore_ascii_case("space") => KeyCode::Char(' '),
+            _ => {
+                let lower = rest.to_ascii_lowercase();
+                if let Some(n) = lower.strip_prefix('f')
+                    && let Ok(n) = n.parse::<u8>()
+                {
+                    KeyCode::F(n)
+                } else if let Some((_, code)) =
+                    NAMED_KEYS.iter().find(|(name, _)| *name == lower)
+                {
+                    *code
+                } else {
+                    bail!("Unknown key '{s}'");
+                }
+            }
+        };
+
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// Keys bound to each command. Commands missing from a user-provided set of
+// bindings keep the bindings of the selected layout.
+pub type Bindings = BTreeMap<Command, Vec<Key>>;
+
+// Built-in keymaps for keyboard layouts where the default letters are
+// awkward to reach.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layout {
+    /// WASD and vim keys (hjkl), restart with R and quit with Q
+    #[default]
+    Qwerty,
+    /// ZQSD and vim keys, quit with A
+    Azerty,
+    /// The QWERTY positions of WASD and vim keys: ,aoe and dhtn
+    Dvorak,
+    /// The QWERTY positions of WASD and vim keys: wars and hnei, restart
+    /// with P
+    Colemak,
+    /// Every control under the right hand: IJKL, restart with U and quit
+    /// with P
+    LeftHanded,
+}
+
+impl Layout {
+    // Letters for moving up, down, left and right, restarting and quitting.
+    fn letters(&self) -> [&'static str; 6] {
+        match self {
+            Layout::Qwerty => ["wk", "sj", "ah", "dl", "r", "q"],
+            Layout::Azerty => ["zk", "sj", "qh", "dl", "r", "a"],
+            Layout::Dvorak => [",t", "oh", "ad", "en", "r", "q"],
+            Layout::Colemak => ["we", "rn", "ah", "si", "p", "q"],
+            Layout::LeftHanded => ["i", "k", "j", "l", "u", "p"],
+        }
+    }
+
+    fn bindings(&self) -> Bindings {
+        let arrows = [
+            Some(KeyCode::Up),
+            Some(KeyCode::Down),
+            Some(KeyCode::Left),
+            Some(KeyCode::Right),
+            None,
+            None,
+        ];
+        let commands = [
+            Command::MoveUp,
+            Command::MoveDown,
+            Command::MoveLeft,
+            Command::MoveRight,
+            Command::Restart,
+            Command::Quit,
+        ];
+
+        let slams = [
+            Command::SlamUp,
+            Command::SlamDown,
+            Command::SlamLeft,
+            Command::SlamRight,
+        ];
+
+        let mut bindings: Bindings = commands
+            .into_iter()
+            .zip(arrows)
+            .zip(self.letters())
+            .map(|((command, arrow), letters)| {
+                let keys = arrow
+                    .map(Key::plain)
+                    .into_iter()
+                    .chain(letters.chars().map(Key::char))
+                    .collect();
+                (command, keys)
+            })
+            .collect();
+        bindings.extend(slams.into_iter().zip(arrows).filter_map(
+            |(command, arrow)| {
+                Some((command, vec![Key::new(arrow?, KeyModifiers::SHIFT)]))
+            },
+        ));
+        // The left-handed preset restarts with U, so undo moves next to it.
+        let (undo, redo) = match self {
+            Layout::LeftHanded => ('o', 'O'),
+            _ => ('u', 'U'),
+        };
+        // P is taken by the Colemak restart and the left-handed quit.
+        let autoplay = match self {
+            Layout::Colemak => 't',
+            Layout::LeftHanded => 'h',
+            _ => 'p',
+        };
+        // Dvorak moves up with T and Colemak toggles autoplay with it.
+        let stats = match self {
+            Layout::Dvorak | Layout::Colemak => 'y',
+            _ => 't',
+        };
+        bindings.extend([
+            (Command::Undo, vec![Key::char(undo)]),
+            (
+                Command::Redo,
+                vec![
+                    Key::char(redo),
+                    Key::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+                ],
+            ),
+            (Command::ToggleAutoplay, vec![Key::char(autoplay)]),
+            (Command::FinishWithAi, vec![Key::char('F')]),
+            (Command::Hint, vec![Key::char('?')]),
+            (Command::SwapTiles, vec![Key::char('1')]),
+            (Command::DeleteTile, vec![Key::char('2')]),
+            (Command::UpgradeTile, vec![Key::char('3')]),
+            (Command::Pause, vec![Key::plain(KeyCode::Esc)]),
+            (Command::Help, vec![Key::plain(KeyCode::F(1))]),
+            (Command::ToggleMoves, vec![Key::char('v')]),
+            (Command::ToggleMerges, vec![Key::char('m')]),
+            (Command::ToggleStats, vec![Key::char(stats)]),
+            (Command::ToggleDebug, vec![Key::plain(KeyCode::F(12))]),
+            (Command::ToggleHeatmap, vec![Key::char('H')]),
+            (Command::ToggleAnalysis, vec![Key::char('E')]),
+            (Command::SaveReport, vec![Key::char('x')]),
+            (Command::ToggleReview, vec![Key::char('R')]),
+            (Command::ExportNotation, vec![Key::char('N')]),
+            (
+                Command::CopySummary,
+                vec![Key::new(KeyCode::Char('y'), KeyModifiers::CONTROL)],
+            ),
+            (
+                Command::SaveSlot,
+                vec![Key::new(KeyCode::Char('s'), KeyModifiers::CONTROL)],
+            ),
+            (
+                Command::LoadSlot,
+                vec![Key::new(KeyCode::Char('o'), KeyModifiers::CONTROL)],
+            ),
+            (
+                Command::ExportPosition,
+                vec![Key::new(KeyCode::Char('e'), KeyModifiers::CONTROL)],
+            ),
+            (
+                Command::LoadPosition,
+                vec![Key::new(KeyCode::Char('l'), KeyModifiers::CONTROL)],
+            ),
+            (Command::ToggleGallery, vec![Key::char('g')]),
+            (Command::ToggleLeaderboard, vec![Key::char('b')]),
+            (Command::CycleTheme, vec![Key::char('c')]),
+            // M without Shift toggles the merge log.
+            (Command::ToggleSound, vec![Key::char('M')]),
+            (Command::ScrollUp, vec![Key::plain(KeyCode::PageUp)]),
+            (Command::ScrollDown, vec![Key::plain(KeyCode::PageDown)]),
+        ]);
+        bindings
+    }
+}
+
+// Maps commands to the keys that trigger them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap(Bindings);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(Layout::default(), Bindings::new())
+    }
+}
+
+impl Keymap {
+    // Builds the layout's keymap with the given bindings taking precedence.
+    pub fn new(layout: Layout, bindings: Bindings) -> Self {
+        let mut keymap = layout.bindings();
+        keymap.extend(bindings);
+        Keymap(keymap)
+    }
+
+    // Returns every command with the keys bound to it, in the order the
+    // commands are declared.
+    pub fn bindings(&self) -> impl Iterator<Item = (Command, &[Key])> {
+        self.0
+            .iter()
+            .map(|(command, keys)| (*command, keys.as_slice()))
+    }
+
+    // Returns the keys bound to a command.
+    pub fn keys(&self, command: Command) -> &[Key] {
+        self.0.get(&command).map_or(&[], Vec::as_slice)
+    }
+
+    // Returns the command bound to the given key, if any.
+    pub fn command(&self, key: Key) -> Option<Command> {
+        self.0
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(command, _)| *command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_parses_chars_names_and_modifiers() {
+        assert_eq!("k".parse::<Key>().unwrap(), Key::char('k'));
+        assert_eq!("Up".parse::<Key>().unwrap(), Key::plain(KeyCode::Up));
+        assert_eq!("f1".parse::<Key>().unwrap(), Key::plain(KeyCode::F(1)));
+        assert_eq!("space".parse::<Key>().unwrap(), Key::char(' '));
+        assert_eq!("-".parse::<Key>().unwrap(), Key::char('-'));
+        assert_eq!(
+            "ctrl-r".parse::<Key>().unwrap(),
+            Key::new(KeyCode::Char('r'), KeyModifiers::CONTROL)
+        );
+        assert!("hyper-r".parse::<Key>().is_err());
+        assert!("nope".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn key_display_round_trips() {
+        for name in ["k", "up", "f5", "space", "ctrl-r", "shift-left"] {
+            assert_eq!(name.parse::<Key>().unwrap().to_string(), name);
+        }
+    }
+
+    #[test]
+    fn key_ignores_shift_on_characters() {
+        assert_eq!(
+            Key::new(KeyCode::Char('W'), KeyModifiers::SHIFT),
+            Key::char('W')
+        );
+    }
+
+    #[test]
+    fn default_keymap_resolves_vim_and_arrow_keys() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.command(Key::char('k')), Some(Command::MoveUp));
+        assert_eq!(
+            keymap.command(Key::plain(KeyCode::Left)),
+            Some(Command::MoveLeft)
+        );
+        assert_eq!(keymap.command(Key::char('z')), None);
+        assert_eq!(
+            keymap.command(Key::char('p')),
+            Some(Command::ToggleAutoplay)
+        );
+    }
+
+    #[test]
+    fn shift_arrows_slam() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.command("shift-left".parse().unwrap()),
+            Some(Command::SlamLeft)
+        );
+    }
+
+    #[test]
+    fn partial_keymap_keeps_defaults_for_missing_commands() {
+        let bindings: Bindings =
+            serde_json::from_str(r#"{ "quit": ["f"] }"#).unwrap();
+        let keymap = Keymap::new(Layout::Qwerty, bindings);
+        assert_eq!(keymap.command(Key::char('f')), Some(Command::Quit));
+        assert_eq!(keymap.command(Key::char('q')), None);
+        assert_eq!(keymap.command(Key::char('r')), Some(Command::Restart));
+    }
+
+    #[test]
+    fn layouts_bind_every_command_without_conflicts() {
+        for layout in Layout::value_variants() {
+            let bindings = layout.bindings();
+            let keys: Vec<&Key> = bindings.values().flatten().collect();
+            assert_eq!(bindings.len(), 40, "{layout:?}");
+            for (i, key) in keys.iter().enumerate() {
+                assert!(!keys[i + 1..].contains(key), "{layout:?}: {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn bindings_reflect_remapped_keys() {
+        let bindings = Bindings::from([(Command::Help, vec![Key::char('f')])]);
+        let keymap = Keymap::new(Layout::Qwerty, bindings);
+        let help: Vec<_> = keymap
+            .bindings()
+            .filter(|(command, _)| *command == Command::Help)
+            .collect();
+        assert_eq!(help, [(Command::Help, &[Key::char('f')][..])]);
+    }
+
+    #[test]
+    fn layout_is_overridden_by_bindings() {
+        let keymap = Keymap::new(Layout::Azerty, Bindings::new());
+        assert_eq!(keymap.command(Key::char('z')), Some(Command::MoveUp));
+        assert_eq!(keymap.command(Key::char('q')), Some(Command::MoveLeft));
+        assert_eq!(keymap.command(Key::char('a')), Some(Command::Quit));
+
+        let bindings = Bindings::from([(Command::Quit, vec![Key::char('f')])]);
+        let keymap = Keymap::new(Layout::Azerty, bindings);
+        assert_eq!(keymap.command(Key::char('a')), None);
+        assert_eq!(keymap.command(Key::char('f')), Some(Command::Quit));
+    }
+}
@@ -0,0 +1,75 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+const SUPERSCRIPT_DIGITS: [char; 10] =
+    ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMode {
+    /// Plain decimal values, e.g. "2048"
+    #[default]
+    Decimal,
+    /// Powers of two with a superscript exponent, e.g. "2¹¹"
+    Superscript,
+    /// Decimal values in large block digits filling the tile, or plain ones
+    /// where they do not fit
+    Large,
+}
+
+impl LabelMode {
+    pub fn format(&self, value: u32) -> String {
+        match self {
+            LabelMode::Decimal | LabelMode::Large => value.to_string(),
+            LabelMode::Superscript if value.is_power_of_two() => {
+                superscript(value.trailing_zeros())
+            }
+            // Values that are not powers of two cannot be expressed as an
+            // exponent of two, so fall back to the decimal form.
+            LabelMode::Superscript => value.to_string(),
+        }
+    }
+}
+
+// Renders 2 raised to the given exponent using Unicode superscript digits.
+fn superscript(exponent: u32) -> String {
+    let digits = exponent.to_string();
+    let mut label = String::with_capacity(1 + digits.len() * 3);
+    label.push('2');
+    for digit in digits.bytes() {
+        label.push(SUPERSCRIPT_DIGITS[(digit - b'0') as usize]);
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_renders_plain_value() {
+        assert_eq!(LabelMode::Decimal.format(2048), "2048");
+    }
+
+    #[test]
+    fn superscript_renders_exponent_of_two() {
+        assert_eq!(LabelMode::Superscript.format(2), "2¹");
+        assert_eq!(LabelMode::Superscript.format(2048), "2¹¹");
+        assert_eq!(LabelMode::Superscript.format(1 << 17), "2¹⁷");
+    }
+
+    #[test]
+    fn superscript_falls_back_to_decimal_for_non_powers_of_two() {
+        assert_eq!(LabelMode::Superscript.format(3), "3");
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::task::{JoinHandle, spawn_blocking};
+
+use crate::game::Game;
+use crate::history::{GameRecord, TOP_RUNS};
+use crate::rules::Difficulty;
+
+// Server keeping a global leaderboard. Finished games are posted to
+// `{url}/scores` and the best scores read back from the same address.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardConfig {
+    // Base URL of the server, e.g. "https://2048.example.com/api".
+    pub url: String,
+    // Name the games are submitted under.
+    pub name: String,
+    // Bearer token sent with each submission, if the server asks for one.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl LeaderboardConfig {
+    // The address of the server's scores. Scores and tokens only travel over
+    // HTTPS, though a server on this machine may be reached over plain HTTP
+    // to try one out.
+    fn scores_url(&self) -> Result<String> {
+        let base = self.url.trim_end_matches('/');
+        let local = base.strip_prefix("http://").is_some_and(|address| {
+            let host = address.split([':', '/']).next().unwrap_or_default();
+            host == "localhost" || host == "127.0.0.1"
+        });
+        if !base.starts_with("https://") && !local {
+            bail!("The leaderboard URL must use HTTPS, found {base}");
+        }
+        Ok(format!("{base}/scores"))
+    }
+}
+
+// A finished game as submitted to the leaderboard. The seed and the hash of
+// the moves let the server tell games apart and check one replayed from its
+// seed, so only games that replay from it are submitted.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Submission {
+    pub name: String,
+    pub score: u32,
+    pub max_tile: u32,
+    pub seed: u64,
+    pub moves: usize,
+    // SHA-256 of the letters of the moves in order, as hex.
+    pub move_hash: String,
+    pub duration_secs: u64,
+    pub difficulty: Difficulty,
+    pub finished_by_ai: bool,
+    // Whether a tool on the control socket or pipe played any of the moves.
+    pub played_by_tool: bool,
+}
+
+impl Submission {
+    pub fn new(name: &str, game: &Game, run: &GameRecord) -> Self {
+        let letters: String =
+            game.moves().iter().map(|m| m.action.letter()).collect();
+        let move_hash = Sha256::digest(letters.as_bytes())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        Self {
+            name: name.to_string(),
+            score: run.score,
+            max_tile: run.max_tile,
+            seed: game.seed(),
+            moves: game.moves().len(),
+            move_hash,
+            duration_secs: run.duration_secs,
+            difficulty: run.difficulty,
+            finished_by_ai: run.handoff.is_some(),
+            played_by_tool: run.tool.is_some(),
+        }
+    }
+}
+
+// A score on the global leaderboard.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct RemoteScore {
+    pub name: String,
+    pub score: u32,
+    #[serde(default)]
+    pub max_tile: u32,
+}
+
+// The global leaderboard as the leaderboard screen shows it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GlobalScores {
+    Loading,
+    Loaded(Vec<RemoteScore>),
+    Failed,
+}
+
+fn post(config: &LeaderboardConfig, submission: &Submission) -> Result<()> {
+    let url = config.scores_url()?;
+    let body = serde_json::to_string(submission)?;
+    let mut request =
+        ureq::post(&url).header("Content-Type", "application/json");
+    if let Some(token) = &config.token {
+        request = request.header("Authorization", format!("Bearer {token}"));
+    }
+    request
+        .send(body)
+        .with_context(|| format!("Failed to submit the game to {url}"))?;
+    Ok(())
+}
+
+// Submits the game in the background so a slow server never stalls the game.
+pub fn submit(
+    config: LeaderboardConfig,
+    submission: Submission,
+) -> JoinHandle<Result<()>> {
+    spawn_blocking(move || post(&config, &submission))
+}
+
+// Reads the best scores at a difficulty from the server, best first.
+pub fn fetch(
+    config: &LeaderboardConfig,
+    difficulty: Difficulty,
+) -> Result<Vec<RemoteScore>> {
+    let url = format!(
+        "{}?difficulty={difficulty}&limit={TOP_RUNS}",
+        config.scores_url()?
+    );
+    let body = ureq::get(&url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .with_context(|| format!("Failed to fetch {url}"))?;
+    let mut scores: Vec<RemoteScore> = serde_json::from_str(&body)
+        .with_context(|| format!("Unexpected scores from {url}"))?;
+    scores.truncate(TOP_RUNS);
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::game::GameAction;
+
+    // Serves a single request with `response`, returning what was asked.
+    fn serve(response: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            // Read until the headers of a GET, or the JSON body of a POST,
+            // are in.
+            while !(request.starts_with(b"GET")
+                && request.ends_with(b"\r\n\r\n")
+                || request.ends_with(b"}"))
+            {
+                let len = stream.read(&mut buf).unwrap();
+                assert!(len > 0, "connection closed early");
+                request.extend_from_slice(&buf[..len]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (url, server)
+    }
+
+    fn config(url: &str) -> LeaderboardConfig {
+        LeaderboardConfig {
+            url: url.to_string(),
+            name: "alice".to_string(),
+            token: Some("secret".to_string()),
+        }
+    }
+
+    #[test]
+    fn scores_only_travel_over_https() {
+        let remote = config("https://2048.example.com/api/");
+        assert_eq!(
+            remote.scores_url().unwrap(),
+            "https://2048.example.com/api/scores"
+        );
+        assert!(config("http://2048.example.com").scores_url().is_err());
+        assert!(config("http://localhost.example.com").scores_url().is_err());
+        assert!(config("http://localhost:8080").scores_url().is_ok());
+    }
+
+    #[test]
+    fn post_sends_the_game_with_the_token() {
+        let (url, server) = serve("HTTP/1.1 204 No Content\r\n\r\n");
+        let mut game = Game::with_seed(7);
+        game.apply_move(GameAction::Left).unwrap();
+        let run = GameRecord::new(2048, 256, Duration::from_secs(90));
+        let submission = Submission::new("alice", &game, &run);
+
+        post(&config(&url), &submission).unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("POST /scores"));
+        assert!(request.contains("Bearer secret"));
+        assert!(request.contains(r#""score":2048"#));
+        assert!(request.contains(r#""seed":7"#));
+        assert_eq!(submission.move_hash.len(), 64);
+    }
+
+    #[test]
+    fn fetch_reads_the_best_scores() {
+        let (url, server) = serve(
+            "HTTP/1.1 200 OK\r\nContent-Length: 54\r\n\r\n\
+             [{\"name\":\"bob\",\"score\":9000},{\"name\":\"al\",\"score\":80}]",
+        );
+
+        let scores = fetch(&config(&url), Difficulty::Hard).unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("GET /scores?difficulty="));
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0].name, "bob");
+        assert_eq!(scores[0].score, 9000);
+    }
+}
@@ -0,0 +1,50 @@
+//! The game engine: the board, the rules, the expectimax search and other
+//! strategies that play it.
+//! Frontends such as the terminal game and the web bindings build on it.
+//!
+//! To embed the engine without the terminal game and its dependencies, turn
+//! off the default features:
+//!
+//! ```toml
+//! [dependencies]
+//! rust-2048 = { git = "https://github.com/freddiehaddad/2048", default-features = false }
+//! ```
+//!
+//! A [`Game`] is played by applying [`GameAction`]s, each returning an
+//! [`ActionOutcome`] that describes the new position:
+//!
+//! ```
+//! use rust_2048::{Game, GameAction};
+//!
+//! let mut game = Game::with_seed(2048);
+//! let outcome = game.apply_move(GameAction::Left)?;
+//! if outcome.changed {
+//!     println!("score {}, best tile {}", outcome.score, game.max_tile());
+//! }
+//! for ((row, col), tile) in game.board().iter_cells() {
+//!     if let Some(tile) = tile {
+//!         println!("{tile} at ({row}, {col})");
+//!     }
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! With the `widget` feature, [`widget::BoardWidget`] draws a board in any
+//! ratatui application.
+pub mod ai;
+pub mod board;
+pub mod game;
+pub mod notation;
+pub mod puzzle;
+pub mod rules;
+pub mod stats;
+pub mod strategy;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "widget")]
+pub mod widget;
+
+pub use board::{Board, Shape};
+pub use game::{ActionOutcome, Game, GameAction};
+pub use rules::{MergeRule, Variant};
+pub use strategy::{Strategy, StrategyKind};
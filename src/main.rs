@@ -1,217 +1,3185 @@
-mod board;
+mod bench;
+#[cfg(feature = "bot")]
+mod bot;
+mod broadcast;
+mod cast;
+mod challenge;
+mod cli;
+mod control;
 mod event;
-mod game;
-
-use anyhow::Result;
-use ratatui::crossterm::event::{KeyCode, read};
-use ratatui::layout::{Margin, Rect};
-use ratatui::style::Style;
-use ratatui::{DefaultTerminal, Frame};
-use ratatui::{
-    layout::{Constraint, Layout},
-    widgets::{Block, BorderType, Paragraph},
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod glyph;
+#[cfg(feature = "gui")]
+mod gui;
+mod headless;
+mod history;
+mod hold;
+mod import;
+mod input;
+mod integrity;
+mod keymap;
+mod label;
+mod leaderboard;
+mod menu;
+mod metrics;
+mod persistence;
+mod pipe;
+mod profile;
+mod replay;
+mod report;
+mod save;
+mod settings;
+mod share;
+mod simulate;
+mod slots;
+mod sound;
+mod stopwatch;
+mod sync;
+mod tas;
+mod theme;
+mod tutorial;
+mod ui;
+mod webhook;
+
+use std::fs;
+use std::io::{stdin, stdout};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use chrono::{Datelike, Utc};
+use clap::{CommandFactory, Parser};
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{
+    self as terminal_event, DisableBracketedPaste, DisableFocusChange,
+    EnableBracketedPaste, EnableFocusChange, KeyCode, poll, read,
+};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::style::Print;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use rust_2048::{
+    ai, board, game, notation, puzzle, rules, stats, strategy, widget,
 };
 use tokio::{
+    select,
     sync::mpsc::{Receiver, Sender, channel},
-    task::spawn_blocking,
+    task::{JoinHandle, spawn_blocking},
+    time::{sleep, sleep_until, timeout_at},
+};
+
+use crate::ai::{
+    DEFAULT_DEPTH, DEFAULT_MIN_PROBABILITY, Evaluation, SearchLimits,
+    SearchReport, Searcher,
+};
+use crate::board::Board;
+use crate::broadcast::Broadcaster;
+use crate::cast::CastRecorder;
+use crate::challenge::Controls;
+use crate::cli::{Cli, Commands, PlayArgs};
+use crate::control::ControlCommand;
+use crate::event::Event;
+use crate::game::{
+    ActionOutcome, DEFAULT_UNDO_DEPTH, Game, GameAction, Position, PowerUp,
 };
+use crate::history::{GameRecord, Handoff, History, TOP_RUNS, format_duration};
+use crate::hold::Hold;
+use crate::import::Import;
+use crate::input::InputQueue;
+use crate::integrity::{SetAside, Signer};
+use crate::keymap::{Command, Key, Keymap};
+use crate::label::LabelMode;
+use crate::leaderboard::{GlobalScores, LeaderboardConfig, Submission};
+use crate::menu::{PauseAction, PauseMenu, TitleItem, TitleMenu};
+use crate::notation::Notation;
+use crate::pipe::Pipe;
+use crate::profile::{DEFAULT_PROFILE, Profile};
+use crate::puzzle::{Puzzle, PuzzleStatus};
+use crate::replay::{DEFAULT_SPEED, Follower, Playback, Recorder, ReplayFrame};
+use crate::rules::{Difficulty, Scoring, Slide};
+use crate::save::{Autosave, SavedGame};
+use crate::settings::{RestartMode, Settings};
+use crate::share::{Clipboard, Shared};
+use crate::simulate::{Format, GameResult};
+use crate::slots::{SlotAction, SlotScreen, Slots};
+use crate::sound::{DEFAULT_VOLUME, Effect, Player};
+use crate::stopwatch::Stopwatch;
+use crate::strategy::{DEFAULT_ROLLOUTS, Strategy, StrategyKind};
+use crate::sync::Remote;
+use crate::theme::{Theme, ThemeName};
+use crate::tutorial::Tutorial;
+use crate::ui::{Analysis, GameView, Review};
+
+// Events waiting for the event loop. Input is buffered by the app's own
+// queue while tiles slide, so the input thread rarely waits on this.
+const BUFSIZE: usize = 64;
+
+// How often the restart progress is redrawn while the restart key is held.
+const HOLD_TICK: Duration = Duration::from_millis(50);
+
+// How often the play time in the stats panel and the blitz clock are redrawn.
+const STATS_TICK: Duration = Duration::from_secs(1);
+
+// How often `watch` checks the replay for new moves.
+const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+// How long tiles take to slide into place after a move, and how often the
+// board is redrawn meanwhile.
+const ANIMATION_DURATION: Duration = Duration::from_millis(100);
+const ANIMATION_FRAME: Duration = Duration::from_millis(20);
+
+// How long the border of the board flashes after a move that changes nothing.
+const FLASH_DURATION: Duration = Duration::from_millis(150);
+
+// How long the points scored by a move show next to the score, and how often
+// they are redrawn as they fade out.
+const POINTS_DURATION: Duration = Duration::from_secs(1);
+const POINTS_FRAME: Duration = Duration::from_millis(100);
+
+// How long notifications to the webhook still on their way are waited for
+// on exit.
+const WEBHOOK_GRACE: Duration = Duration::from_secs(2);
+
+// Moves per second played by the AI unless configured otherwise.
+const DEFAULT_AUTOPLAY_SPEED: f64 = 4.0;
+
+// How often the input thread checks whether the game has ended.
+const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// The move suggested for the position on the board when the hint was asked
+// for. It is only shown while that position is on the board.
+#[derive(Clone, Copy)]
+enum Hint {
+    // The search is still running.
+    Searching(Board),
+    Found(Board, GameAction),
+}
+
+impl Hint {
+    fn board(&self) -> &Board {
+        match self {
+            Hint::Searching(board) | Hint::Found(board, _) => board,
+        }
+    }
+}
+
+// Preferences resolved from the profile settings and the command line.
+struct Options {
+    #[cfg(feature = "bot")]
+    bot: Option<bot::Bot>,
+    recorder: Option<Recorder>,
+    broadcaster: Option<Broadcaster>,
+    // Settings listed in end-of-game reports.
+    report_settings: Vec<(&'static str, String)>,
+    // The player profile, named in the title unless it is the default one.
+    profile: Option<String>,
+    labels: LabelMode,
+    theme: ThemeName,
+    markers: bool,
+    sound: Player,
+    invalid_move_bell: bool,
+    invalid_move_flash: bool,
+    keymap: Keymap,
+    restart_mode: RestartMode,
+    confirm_quit: bool,
+    webhook: Option<String>,
+    leaderboard: Option<LeaderboardConfig>,
+    auto_restart: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    undo_depth: usize,
+    autoplay: bool,
+    autoplay_interval: Duration,
+    strategy: Box<dyn Strategy>,
+    search_limits: SearchLimits,
+    time_limit: Option<Duration>,
+    garbage_interval: Option<Duration>,
+    controls: Controls,
+    practice: Option<Board>,
+    puzzles: Option<Vec<Puzzle>>,
+    position_file: PathBuf,
+    slots: Slots,
+    autosave: Autosave,
+    warnings: Vec<String>,
+}
+
+// How a position was written or read: to or from the position file, or a
+// save slot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PositionIo {
+    Export,
+    Save,
+    Load,
+}
+
+// The latest position written or read, named after its file or slot, and
+// whether that worked.
+struct PositionStatus {
+    io: PositionIo,
+    name: String,
+    result: Result<()>,
+}
+
+// The pack of puzzles played in puzzle mode and the one on the board.
+struct Puzzles {
+    pack: Vec<Puzzle>,
+    index: usize,
+}
+
+impl Puzzles {
+    fn current(&self) -> &Puzzle {
+        &self.pack[self.index]
+    }
+}
+
+// A power-up waiting for the player to pick its tiles with a cursor moved by
+// the move keys.
+struct Targeting {
+    power_up: PowerUp,
+    cursor: (usize, usize),
+    picked: Option<(usize, usize)>,
+}
+
+impl Targeting {
+    // The cells picked so far, then the one under the cursor.
+    fn cells(&self) -> Vec<(usize, usize)> {
+        self.picked.into_iter().chain([self.cursor]).collect()
+    }
+}
+
+struct App {
+    game: Game,
+    outcome: ActionOutcome,
+    history: History,
+    labels: LabelMode,
+    theme: ThemeName,
+    markers: bool,
+    sound: Player,
+    keymap: Keymap,
+    webhook: Option<String>,
+    // Server that finished games are submitted to, and the global top scores
+    // read from it while the leaderboard is shown.
+    leaderboard: Option<LeaderboardConfig>,
+    global_scores: Option<GlobalScores>,
+    // Play time of the current run, paused while the terminal is unfocused.
+    clock: Stopwatch,
+    // How long each game may be played, in blitz mode.
+    time_limit: Option<Duration>,
+    // How often a tile drops without a move in garbage mode, and the play
+    // time at which the next one drops.
+    garbage_interval: Option<Duration>,
+    garbage_at: Duration,
+    // Turns the moves asked for into the moves played, under a challenge.
+    controls: Controls,
+    // The position a practice game started from, which restarting returns
+    // to. Practice runs are kept out of the history.
+    practice: Option<Board>,
+    // The puzzles played in puzzle mode, which are kept out of the history
+    // like practice runs.
+    puzzles: Option<Puzzles>,
+    // The lessons taught on the first launch, which are kept out of the
+    // history like practice runs.
+    tutorial: Option<Tutorial>,
+    // Index into the history of the run that just finished, if any.
+    finished_run: Option<usize>,
+    report_settings: Vec<(&'static str, String)>,
+    profile: Option<String>,
+    // Where the report of the finished run was written, or why it failed.
+    report: Option<Result<PathBuf>>,
+    // Whether the summary of the finished run was copied, the clipboard it
+    // was copied to and the summaries to print on exit for want of one.
+    shared: Option<Shared>,
+    // Where the moves of the finished run were written in move notation, or
+    // why they could not be.
+    notation: Option<Result<PathBuf>>,
+    clipboard: Clipboard,
+    unshared: Vec<String>,
+    // Where positions are exported to and loaded from, and the result of the
+    // latest export, save or load until the next key press.
+    position_file: PathBuf,
+    position_status: Option<PositionStatus>,
+    // Damaged files set aside at launch, warned about on the title screen
+    // and in place of the position status until the first key press in the
+    // game.
+    warnings: Vec<String>,
+    slots: Slots,
+    // The screen for saving to a slot or loading from one, while it is open.
+    slot_screen: Option<SlotScreen>,
+    // The power-up whose tiles are being picked, while one is.
+    targeting: Option<Targeting>,
+    restart_mode: RestartMode,
+    confirm_quit: bool,
+    // Number of moves of each game that can be undone.
+    undo_depth: usize,
+    // The restart key being held, in hold mode.
+    hold: Option<Hold>,
+    // Whether a restart is waiting for confirmation, in confirm mode.
+    confirming_restart: bool,
+    // Whether quitting is waiting for confirmation.
+    confirming_quit: bool,
+    // Whether the win is being celebrated, until the player chooses to keep
+    // playing or restart.
+    celebrating: bool,
+    // When the tiles of the latest move started sliding, while they still
+    // are.
+    animation: Option<Instant>,
+    // Input received during the animation, handled once it finishes.
+    queued: InputQueue,
+    // When the border started flashing after a move that changed nothing,
+    // while it still is.
+    flash: Option<Instant>,
+    // The points scored by the latest move and when, while they show next
+    // to the score.
+    points: Option<(u32, Instant)>,
+    // How moves that change nothing are signalled, if at all.
+    invalid_move_bell: bool,
+    invalid_move_flash: bool,
+    // When the AI plays its next move, while it is playing.
+    autoplay: Option<Instant>,
+    autoplay_interval: Duration,
+    // Where the player stood when they handed the game over to the AI to
+    // finish, while it does.
+    handoff: Option<Handoff>,
+    // Where the game stood when a tool on the control socket or pipe first
+    // played a move in it, if one has.
+    tool: Option<Handoff>,
+    // What picks the moves of the AI in autoplay.
+    strategy: Box<dyn Strategy>,
+    // How hard the AI searches, and how its latest search went, shown in the
+    // debug panel.
+    search_limits: SearchLimits,
+    search_report: Option<SearchReport>,
+    // Script that plays in place of the AI in autoplay, if any.
+    #[cfg(feature = "bot")]
+    bot: Option<bot::Bot>,
+    hint: Option<Hint>,
+    // Delay after a game ends before a new one starts automatically.
+    auto_restart: Option<Duration>,
+    // When the current game ended, if it has.
+    game_over_at: Option<Instant>,
+    // Whether the move history panel is shown, and how many moves it is
+    // scrolled back from the latest.
+    show_moves: bool,
+    moves_scroll: usize,
+    show_merges: bool,
+    show_stats: bool,
+    // Whether the debug panel is shown, and how long the latest frame took to
+    // draw.
+    show_debug: bool,
+    render_time: Option<Duration>,
+    // Whether the board is shaded by where tiles spawned instead of showing
+    // the tiles.
+    show_heatmap: bool,
+    // Whether the AI's evaluation of each move is shown, the position it was
+    // last asked about with the evaluations once found, and the move played
+    // from the previous position with the one the AI preferred there.
+    show_analysis: bool,
+    analysis: Option<(Board, Option<[Evaluation; 4]>)>,
+    last_choice: Option<(GameAction, GameAction)>,
+    // Whether the title screen or the game is shown.
+    screen: Screen,
+    // The menu opened with Esc, while the game is paused.
+    menu: Option<PauseMenu>,
+    // Whether the help screen is shown over the game.
+    show_help: bool,
+    // Whether the leaderboard of the best finished games is shown.
+    show_leaderboard: bool,
+    // Whether the all-time statistics of the profile are shown.
+    show_statistics: bool,
+    // Index of the snapshot shown while the gallery is open, most recent
+    // first.
+    gallery: Option<usize>,
+    // The position of the finished game shown while it is reviewed, counting
+    // from the first; one past the last move is the final board.
+    review: Option<usize>,
+    // Keeps the save of the game up to date, and the latest error writing it.
+    autosave: Autosave,
+    autosave_error: Option<anyhow::Error>,
+    // The notifications sent to the webhook, whose failures are reported on
+    // exit.
+    notifications: Vec<JoinHandle<Result<()>>>,
+    // Where moves are recorded for watching, and the error that stopped the
+    // recording, if any.
+    recorder: Option<Recorder>,
+    replay_error: Option<anyhow::Error>,
+    // Where the frames drawn are recorded as a cast, and the error that
+    // stopped the recording, if any.
+    cast: Option<CastRecorder>,
+    cast_error: Option<anyhow::Error>,
+    // Where the game is served to spectators, if anywhere.
+    broadcaster: Option<Broadcaster>,
+    // Where tools watch and play the game, if anywhere.
+    pipe: Option<Pipe>,
+    // How long the game may go without input before it saves and exits.
+    idle_timeout: Option<Duration>,
+    last_input: Instant,
+    // Position pasted into the terminal awaiting confirmation, or the reason
+    // it could not be read.
+    pasted: Option<Result<Import>>,
+}
+
+impl App {
+    fn new(mut game: Game, history: History, options: Options) -> Self {
+        let Options {
+            #[cfg(feature = "bot")]
+            bot,
+            recorder,
+            broadcaster,
+            report_settings,
+            profile,
+            labels,
+            theme,
+            markers,
+            sound,
+            invalid_move_bell,
+            invalid_move_flash,
+            keymap,
+            restart_mode,
+            confirm_quit,
+            webhook,
+            leaderboard,
+            auto_restart,
+            idle_timeout,
+            undo_depth,
+            autoplay,
+            autoplay_interval,
+            strategy,
+            search_limits,
+            time_limit,
+            garbage_interval,
+            controls,
+            practice,
+            puzzles,
+            position_file,
+            slots,
+            autosave,
+            warnings,
+        } = options;
+        game.set_undo_depth(undo_depth);
+        Self {
+            outcome: game.outcome(),
+            game,
+            history,
+            labels,
+            theme,
+            markers,
+            sound,
+            invalid_move_bell,
+            invalid_move_flash,
+            keymap,
+            restart_mode,
+            confirm_quit,
+            undo_depth,
+            hold: None,
+            confirming_restart: false,
+            confirming_quit: false,
+            celebrating: false,
+            animation: None,
+            flash: None,
+            points: None,
+            queued: InputQueue::default(),
+            autoplay: autoplay.then(Instant::now),
+            autoplay_interval,
+            handoff: None,
+            tool: None,
+            strategy,
+            search_limits,
+            search_report: None,
+            #[cfg(feature = "bot")]
+            bot,
+            hint: None,
+            webhook,
+            leaderboard,
+            global_scores: None,
+            clock: Stopwatch::start(),
+            time_limit,
+            garbage_interval,
+            garbage_at: garbage_interval.unwrap_or_default(),
+            controls,
+            practice,
+            puzzles: puzzles.map(|pack| Puzzles { pack, index: 0 }),
+            tutorial: None,
+            finished_run: None,
+            report_settings,
+            profile,
+            report: None,
+            shared: None,
+            notation: None,
+            clipboard: Clipboard::default(),
+            unshared: Vec::new(),
+            position_file,
+            position_status: None,
+            warnings,
+            slots,
+            slot_screen: None,
+            targeting: None,
+            auto_restart,
+            game_over_at: None,
+            show_moves: false,
+            moves_scroll: 0,
+            show_merges: false,
+            show_stats: false,
+            show_debug: false,
+            show_heatmap: false,
+            show_analysis: false,
+            analysis: None,
+            last_choice: None,
+            render_time: None,
+            screen: Screen::Game,
+            menu: None,
+            show_help: false,
+            show_leaderboard: false,
+            show_statistics: false,
+            gallery: None,
+            review: None,
+            autosave,
+            autosave_error: None,
+            notifications: Vec::new(),
+            recorder,
+            replay_error: None,
+            cast: None,
+            cast_error: None,
+            broadcaster,
+            pipe: None,
+            idle_timeout,
+            last_input: Instant::now(),
+            pasted: None,
+        }
+    }
+
+    fn restart(&mut self) {
+        if let Some(tutorial) = &mut self.tutorial {
+            let game = tutorial.restart();
+            return self.load_game(game);
+        }
+        if let Some(board) = self.practice {
+            return self.load(board, 0);
+        }
+        if let Some(puzzles) = &self.puzzles {
+            return self.load_game(puzzles.current().start());
+        }
+        let outcome = self.game.restart();
+        self.start_run(outcome);
+    }
+
+    fn apply_move(&mut self, direction: GameAction) -> Result<ActionOutcome> {
+        let previous_max = self.game.max_tile();
+        let preferred = self.preferred_move();
+        let outcome = self.game.apply_move(direction)?;
+        if outcome.changed {
+            self.last_choice = preferred.map(|best| (direction, best));
+            self.record(Some(direction));
+            self.schedule_autosave();
+        }
+        if let Some(effect) = Effect::of_move(&outcome, previous_max) {
+            self.sound.play(effect);
+        }
+        self.celebrating |= outcome.won;
+        Ok(outcome)
+    }
+
+    // Plays a move asked for by the player, signalling with the bell or a
+    // flash of the border if it changes nothing.
+    fn try_move(&mut self, direction: GameAction) -> Result<ActionOutcome> {
+        let outcome = self.apply_move(direction)?;
+        if outcome.changed {
+            self.controls.shuffle();
+        } else {
+            self.reject();
+        }
+        Ok(outcome)
+    }
+
+    // Signals with the bell or a flash of the border that the player's
+    // input changed nothing.
+    fn reject(&mut self) {
+        if self.invalid_move_flash {
+            self.flash = Some(Instant::now());
+        }
+        if self.invalid_move_bell {
+            let _ = execute!(stdout(), Print('\x07'));
+        }
+    }
+
+    // Starts picking the tiles to play a power-up on, from the middle of the
+    // board, if one is left.
+    fn start_targeting(&mut self, power_up: PowerUp) {
+        if self.game.power_ups_left(power_up) == 0 {
+            self.reject();
+            return;
+        }
+        let board = self.game.board();
+        self.targeting = Some(Targeting {
+            power_up,
+            cursor: (board.rows() / 2, board.cols() / 2),
+            picked: None,
+        });
+    }
+
+    // Moves the cursor with the move keys and picks the tile under it with
+    // Enter or Space, playing the power-up once it has all its tiles. Esc
+    // cancels, as does the game ending. Returns the outcome of the power-up
+    // once it is played.
+    fn answer_target(&mut self, key: Key) -> Option<ActionOutcome> {
+        if self.game.is_game_over() {
+            self.targeting = None;
+            return None;
+        }
+        let targeting = self.targeting.as_mut()?;
+        let board = self.game.board();
+        let (row, col) = &mut targeting.cursor;
+        match self.keymap.command(key) {
+            Some(Command::MoveUp) => *row = row.saturating_sub(1),
+            Some(Command::MoveDown) => *row = (*row + 1).min(board.rows() - 1),
+            Some(Command::MoveLeft) => *col = col.saturating_sub(1),
+            Some(Command::MoveRight) => *col = (*col + 1).min(board.cols() - 1),
+            Some(Command::Pause) => self.targeting = None,
+            _ if key.typed() == Some(' ')
+                || key == Key::plain(KeyCode::Enter) =>
+            {
+                return self.pick_target();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    // Picks the tile under the cursor, playing the power-up if it was the
+    // last tile it needs.
+    fn pick_target(&mut self) -> Option<ActionOutcome> {
+        let targeting = self.targeting.as_mut()?;
+        let (row, col) = targeting.cursor;
+        if self.game.board().cell(row, col).is_none()
+            || targeting.picked == Some(targeting.cursor)
+        {
+            self.reject();
+            return None;
+        }
+        let cells = targeting.cells();
+        if cells.len() < targeting.power_up.cells() {
+            targeting.picked = Some(targeting.cursor);
+            return None;
+        }
+        let power_up = targeting.power_up;
+        self.targeting = None;
+        let Ok(outcome) = self.game.use_power_up(power_up, &cells) else {
+            self.reject();
+            return None;
+        };
+        self.hint = None;
+        self.record(None);
+        self.schedule_autosave();
+        self.celebrating |= outcome.won;
+        Some(outcome)
+    }
+
+    // Carries out a command from the control socket, whatever is on screen,
+    // and returns the reply to send back.
+    fn control(&mut self, command: ControlCommand) -> Result<String> {
+        let moved = match command {
+            ControlCommand::State => None,
+            ControlCommand::Restart => {
+                self.restart();
+                None
+            }
+            ControlCommand::Move(direction) => {
+                self.note_tool();
+                let outcome = self.apply_move(direction)?;
+                let changed = outcome.changed;
+                self.finish_move(outcome)?;
+                Some(changed)
+            }
+        };
+        control::reply(&self.game, moved)
+    }
+
+    // Carries out a line from a tool on the control pipe as headless play
+    // does, while the game is being played. A move that changes nothing is
+    // answered with the state all the same, and any other line, or a move
+    // while something else is on screen, with an error.
+    fn pipe(&mut self, line: &str) -> Result<()> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+        let Some(pipe) = &self.pipe else {
+            return Ok(());
+        };
+        let Some(direction) = headless::parse_move(line) else {
+            pipe.send_error(headless::Error::unknown_move(line));
+            return Ok(());
+        };
+        if !self.is_playing() {
+            pipe.send_error(headless::Error::not_playing());
+            return Ok(());
+        }
+        self.note_tool();
+        let outcome = self.apply_move(direction)?;
+        let changed = outcome.changed;
+        self.finish_move(outcome)?;
+        if let Some(pipe) = &self.pipe
+            && !changed
+        {
+            pipe.send(&self.game, Some(false));
+        }
+        Ok(())
+    }
+
+    // Notes where the game stood when a tool first played a move in it, so
+    // the run is not recorded as the player's alone.
+    fn note_tool(&mut self) {
+        self.tool.get_or_insert_with(|| Handoff {
+            score: self.game.score(),
+            max_tile: self.game.max_tile(),
+            moves: self.game.moves().len(),
+        });
+    }
+
+    // Whether the game on the board is being played, with nothing over it
+    // that takes the keys instead.
+    fn is_playing(&self) -> bool {
+        self.screen == Screen::Game
+            && self.menu.is_none()
+            && self.slot_screen.is_none()
+            && self.pasted.is_none()
+            && self.targeting.is_none()
+            && self.gallery.is_none()
+            && self.review.is_none()
+            && self.tutorial.is_none()
+            && !(self.show_help
+                || self.show_statistics
+                || self.show_leaderboard
+                || self.confirming_restart
+                || self.confirming_quit
+                || self.celebrating)
+    }
+
+    // Shows the outcome of a move, starting the tiles sliding, and records
+    // the run if the move ended the game.
+    fn finish_move(&mut self, outcome: ActionOutcome) -> Result<()> {
+        if outcome.changed {
+            metrics::moved(outcome.score);
+            self.animation = Some(Instant::now());
+            self.show_points(&outcome);
+        }
+        if outcome.changed || outcome.game_over {
+            self.outcome = outcome;
+            self.end_puzzle();
+            self.record_game_over()?;
+        }
+        Ok(())
+    }
+
+    // Returns how the puzzle on the board stands, in puzzle mode.
+    fn puzzle_status(&self) -> Option<PuzzleStatus> {
+        let puzzles = self.puzzles.as_ref()?;
+        Some(puzzles.current().status(&self.game))
+    }
+
+    // Ends the game once its puzzle is solved or failed, keeping the tiles of
+    // the last move sliding into place.
+    fn end_puzzle(&mut self) {
+        if self.game.is_game_over()
+            || matches!(
+                self.puzzle_status(),
+                None | Some(PuzzleStatus::Playing { .. })
+            )
+        {
+            return;
+        }
+        self.game.end();
+        self.outcome.game_over = true;
+        // A puzzle reaching the winning tile is not celebrated on top of it
+        // being solved.
+        self.celebrating = false;
+    }
+
+    // Moves on to the next puzzle of the pack once the one on the board is
+    // solved.
+    fn next_puzzle(&mut self) {
+        if self.puzzle_status() != Some(PuzzleStatus::Solved) {
+            return;
+        }
+        let Some(puzzles) = &mut self.puzzles else {
+            return;
+        };
+        if puzzles.index + 1 < puzzles.pack.len() {
+            puzzles.index += 1;
+            let game = puzzles.current().start();
+            self.load_game(game);
+        }
+    }
+
+    // Whether the game is kept out of the history and the save: a practice
+    // run, a puzzle or the tutorial.
+    fn is_practice(&self) -> bool {
+        self.practice.is_some()
+            || self.puzzles.is_some()
+            || self.tutorial.is_some()
+    }
+
+    // Plays the move the lesson on the board asks for, rejecting any other,
+    // then moves on to the next lesson with Enter once it is played. Esc
+    // skips the rest of the tutorial.
+    fn answer_tutorial(&mut self, key: Key) -> Result<()> {
+        let Some(tutorial) = &mut self.tutorial else {
+            return Ok(());
+        };
+        let command = self.keymap.command(key);
+        if command == Some(Command::Pause) {
+            self.end_tutorial();
+        } else if tutorial.is_played() {
+            if key == Key::plain(KeyCode::Enter) {
+                match tutorial.next() {
+                    Some(game) => self.load_game(game),
+                    None => self.end_tutorial(),
+                }
+            }
+        } else if command == Some(tutorial.lesson().command()) {
+            let action = tutorial.lesson().action;
+            tutorial.play();
+            let outcome = self.apply_move(action)?;
+            self.finish_move(outcome)?;
+        } else {
+            self.reject();
+        }
+        Ok(())
+    }
+
+    // Ends the tutorial with a new game, noting that it need not be offered
+    // again. Failing to note it only offers it again on the next launch.
+    fn end_tutorial(&mut self) {
+        self.tutorial = None;
+        self.load_game(Game::new());
+        let _ = tutorial::mark_done();
+    }
+
+    // Shows the points scored by a move next to the score, if it scored any.
+    fn show_points(&mut self, outcome: &ActionOutcome) {
+        if outcome.points > 0 {
+            self.points = Some((outcome.points, Instant::now()));
+        }
+    }
+
+    // Returns the points scored by the latest move and how far they have
+    // faded, from 0 to 1, while they show.
+    fn points_progress(&self, now: Instant) -> Option<(u32, f64)> {
+        let (points, at) = self.points?;
+        let elapsed = now.saturating_duration_since(at);
+        (elapsed < POINTS_DURATION).then(|| {
+            (
+                points,
+                elapsed.as_secs_f64() / POINTS_DURATION.as_secs_f64(),
+            )
+        })
+    }
+
+    fn toggle_autoplay(&mut self) {
+        self.autoplay = match self.autoplay {
+            Some(_) => None,
+            None => Some(Instant::now()),
+        };
+    }
+
+    // Hands the game over to the AI, which plays it out and records how much
+    // further it got than the player.
+    fn finish_with_ai(&mut self) {
+        if self.game.is_game_over() || self.handoff.is_some() {
+            return;
+        }
+        self.handoff = Some(Handoff {
+            score: self.game.score(),
+            max_tile: self.game.max_tile(),
+            moves: self.game.moves().len(),
+        });
+        self.hint = None;
+        self.autoplay = Some(Instant::now());
+    }
+
+    // Returns when the AI should play its next move. It waits while the game
+    // is over, the win is being celebrated or the game is paused.
+    fn autoplay_deadline(&self) -> Option<Instant> {
+        if self.game.is_game_over()
+            || self.celebrating
+            || self.clock.is_paused()
+        {
+            return None;
+        }
+        self.autoplay
+    }
+
+    // Plays the move of the AI, or of the bot script if there is one, if it
+    // is due, returning its outcome.
+    fn autoplay_move(&mut self) -> Result<Option<ActionOutcome>> {
+        let now = Instant::now();
+        if self
+            .autoplay_deadline()
+            .is_none_or(|deadline| deadline > now)
+        {
+            return Ok(None);
+        }
+        self.autoplay = Some(now + self.autoplay_interval);
+        #[cfg(feature = "bot")]
+        if let Some(bot) = &self.bot {
+            let action = bot
+                .choose_move(self.game.board(), self.game.score())
+                .context("The bot script failed")?;
+            return self.apply_move(action).map(Some);
+        }
+        let action = self.strategy.choose_move(self.game.board());
+        if let Some(report) = self.strategy.search_report() {
+            self.search_report = Some(report);
+        }
+        match action {
+            Some(action) => self.apply_move(action).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    // Starts searching for the best move of the current position on a
+    // blocking thread, which sends it back as an event.
+    fn request_hint(&mut self, tx: &Sender<Event>) {
+        let board = *self.game.board();
+        if self.game.is_game_over()
+            || self.hint.is_some_and(|hint| *hint.board() == board)
+        {
+            return;
+        }
+        self.hint = Some(Hint::Searching(board));
+        let tx = tx.clone();
+        let limits = self.search_limits;
+        spawn_blocking(move || {
+            let mut searcher = Searcher::with_limits(limits);
+            let action = searcher.best_move(&board);
+            let _ = tx.blocking_send(Event::Hint {
+                board,
+                action,
+                report: searcher.report(),
+            });
+        });
+    }
+
+    // Starts evaluating every move of the current position on a blocking
+    // thread while the evaluations are shown, unless they already are for
+    // this position.
+    fn request_analysis(&mut self, tx: &Sender<Event>) {
+        // A reviewed position is searched for the move the AI prefers there,
+        // but the final board has no move to search.
+        let board = match self.reviewed_position() {
+            Some((position, Some(_))) => position.board,
+            Some((_, None)) => return,
+            None if self.show_analysis && !self.game.is_game_over() => {
+                *self.game.board()
+            }
+            None => return,
+        };
+        if self.analysis.is_some_and(|(analyzed, _)| analyzed == board) {
+            return;
+        }
+        self.analysis = Some((board, None));
+        let tx = tx.clone();
+        let limits = self.search_limits;
+        spawn_blocking(move || {
+            let mut searcher = Searcher::with_limits(limits);
+            let evaluations = searcher.evaluate(&board);
+            let _ = tx.blocking_send(Event::Analysis {
+                board,
+                evaluations,
+                report: searcher.report(),
+            });
+        });
+    }
+
+    // Fetches the global top scores while the leaderboard is shown, once each
+    // time it opens.
+    fn request_global_scores(&mut self, tx: &Sender<Event>) {
+        let Some(config) = &self.leaderboard else {
+            return;
+        };
+        if !self.show_leaderboard || self.global_scores.is_some() {
+            return;
+        }
+        self.global_scores = Some(GlobalScores::Loading);
+        let tx = tx.clone();
+        let config = config.clone();
+        let difficulty = self.game.difficulty();
+        spawn_blocking(move || {
+            let scores = leaderboard::fetch(&config, difficulty);
+            let _ = tx.blocking_send(Event::GlobalScores(scores));
+        });
+    }
+
+    // Keeps the evaluations found for a position if it is still the one
+    // being analyzed.
+    fn show_analysis(&mut self, board: Board, evaluations: [Evaluation; 4]) {
+        if let Some((analyzed, found)) = &mut self.analysis
+            && *analyzed == board
+        {
+            *found = Some(evaluations);
+        }
+    }
+
+    // The move the AI prefers from the position on the board, once its
+    // evaluations are in.
+    fn preferred_move(&self) -> Option<GameAction> {
+        ai::preferred(&self.evaluations_of(self.game.board())?)
+    }
+
+    // Shows the hint found for a position if it is still the one being
+    // searched.
+    fn show_hint(&mut self, board: Board, action: Option<GameAction>) {
+        if !matches!(self.hint, Some(Hint::Searching(searching)) if searching == board)
+        {
+            return;
+        }
+        self.hint = action.map(|action| Hint::Found(board, action));
+    }
+
+    // Returns the arrow of the hinted move, or an ellipsis while searching,
+    // if the hint is for the position on the board.
+    fn hint_arrow(&self) -> Option<char> {
+        match self.hint? {
+            hint if hint.board() != self.game.board() => None,
+            Hint::Searching(_) => Some('…'),
+            Hint::Found(_, action) => Some(action.arrow()),
+        }
+    }
+
+    // Saves the game shortly, so it can be resumed even if the game is not
+    // quit normally. Practice runs are not saved.
+    fn schedule_autosave(&mut self) {
+        if !self.is_practice() {
+            self.autosave.schedule();
+        }
+    }
+
+    // Appends the current position to the replay. Recording stops at the
+    // first error, which is reported when the game exits.
+    fn record(&mut self, action: Option<GameAction>) {
+        if let Some(pipe) = &self.pipe {
+            pipe.send(&self.game, action.map(|_| true));
+        }
+        if self.recorder.is_none() && self.broadcaster.is_none() {
+            return;
+        }
+        let frame = ReplayFrame::new(&self.game, action);
+        if let Some(broadcaster) = &self.broadcaster
+            && let Err(e) = broadcaster.send(&frame)
+        {
+            self.broadcaster = None;
+            self.replay_error = Some(e);
+        }
+        if let Some(recorder) = &mut self.recorder
+            && let Err(e) = recorder.record(&self.game, &frame)
+        {
+            self.recorder = None;
+            self.replay_error = Some(e);
+        }
+    }
+
+    // Writes a Markdown report of the run that just finished to the current
+    // directory.
+    fn save_report(&mut self) {
+        let Some(run) = self.finished_run.and_then(|i| self.history.run(i))
+        else {
+            return;
+        };
+        self.report = Some(report::write(
+            Path::new("."),
+            &self.game,
+            run,
+            &self.report_settings,
+        ));
+    }
+
+    // Writes the moves of the run that just finished in move notation, named
+    // after the time it finished.
+    fn export_notation(&mut self) {
+        let Some(run) = self.finished_run.and_then(|i| self.history.run(i))
+        else {
+            return;
+        };
+        let path = PathBuf::from(
+            run.finished_at.format("2048-%Y%m%d-%H%M%S.txt").to_string(),
+        );
+        self.notation =
+            Some(Notation::from_game(&self.game).and_then(|notation| {
+                fs::write(&path, notation.to_string()).with_context(|| {
+                    format!("Failed to write {}", path.display())
+                })?;
+                Ok(path)
+            }));
+    }
+
+    // Copies a summary of the run that just finished to share it, or keeps
+    // it to print on exit if there is no clipboard.
+    fn share_summary(&mut self) {
+        let Some(run) = self.finished_run.and_then(|i| self.history.run(i))
+        else {
+            return;
+        };
+        let summary = share::summary(&self.game, run);
+        self.shared = Some(match self.clipboard.copy(&summary) {
+            Ok(()) => Shared::Copied,
+            Err(_) => {
+                self.unshared.push(summary);
+                Shared::Deferred
+            }
+        });
+    }
+
+    // Handles the restart command according to the restart mode. A game that
+    // is over restarts right away.
+    fn request_restart(&mut self) {
+        let mode = if self.game.is_game_over() {
+            RestartMode::Instant
+        } else {
+            self.restart_mode
+        };
+        match mode {
+            RestartMode::Instant => self.restart(),
+            RestartMode::Confirm => self.confirming_restart = true,
+            RestartMode::Hold => {
+                let now = Instant::now();
+                let Some(hold) = &mut self.hold else {
+                    self.hold = Some(Hold::new(now));
+                    return;
+                };
+                if hold.press(now) {
+                    self.hold = None;
+                    self.restart();
+                }
+            }
+        }
+    }
+
+    // Restarts if the key confirms it; any other key cancels.
+    fn answer_restart(&mut self, key: Key) {
+        self.confirming_restart = false;
+        if confirms(key) {
+            self.restart();
+        }
+    }
+
+    // Asks before quitting while a game is in progress, unless turned off.
+    // Returns whether to quit right away.
+    fn request_quit(&mut self) -> bool {
+        if self.confirm_quit
+            && !self.game.is_game_over()
+            && !self.game.moves().is_empty()
+        {
+            self.confirming_quit = true;
+            return false;
+        }
+        true
+    }
+
+    // Quits if the key confirms it or is the quit key again; any other key
+    // cancels. Returns whether to quit.
+    fn answer_quit(&mut self, key: Key) -> bool {
+        self.confirming_quit = false;
+        confirms(key) || self.keymap.command(key) == Some(Command::Quit)
+    }
+
+    // Keeps playing past the win on `c`, or restarts on `r` or the restart
+    // key. Other keys leave the celebration up.
+    fn answer_win(&mut self, key: Key) {
+        if key == Key::char('c') {
+            self.celebrating = false;
+        } else if key == Key::char('r')
+            || self.keymap.command(key) == Some(Command::Restart)
+        {
+            self.restart();
+        }
+    }
+
+    // Takes back the latest move of the current game.
+    fn undo(&mut self) {
+        let outcome = self.game.undo();
+        self.restore(outcome);
+    }
+
+    // Plays the latest undone move again.
+    fn redo(&mut self) {
+        let outcome = self.game.redo();
+        self.restore(outcome);
+    }
+
+    fn restore(&mut self, outcome: Option<ActionOutcome>) {
+        let Some(outcome) = outcome else {
+            return;
+        };
+        self.outcome = outcome;
+        self.record(None);
+        self.schedule_autosave();
+        metrics::set_score(self.outcome.score);
+    }
+
+    // Writes the current game to the position file so it can be shared or
+    // loaded again later.
+    fn export_position(&mut self) {
+        let result = self.game.to_json().and_then(|json| {
+            fs::write(&self.position_file, json).with_context(|| {
+                format!("Failed to write {}", self.position_file.display())
+            })
+        });
+        self.set_position_status(PositionIo::Export, result);
+    }
+
+    // Continues from the game in the position file as a new run.
+    fn load_position(&mut self) {
+        let result = fs::read_to_string(&self.position_file)
+            .with_context(|| {
+                format!("Failed to read {}", self.position_file.display())
+            })
+            .and_then(|json| Game::from_json(&json));
+        let result = result.map(|game| self.load_game(game));
+        self.set_position_status(PositionIo::Load, result);
+    }
+
+    fn set_position_status(&mut self, io: PositionIo, result: Result<()>) {
+        let name = self.position_file.file_name().unwrap_or_default();
+        self.position_status = Some(PositionStatus {
+            io,
+            name: name.to_string_lossy().into_owned(),
+            result,
+        });
+    }
+
+    // Pauses the game and opens a slot screen.
+    fn open_slots(&mut self, screen: SlotScreen) {
+        self.slot_screen = Some(screen);
+        self.clock.pause();
+    }
+
+    // Acts on a key pressed on the slot screen: saves the game under the
+    // typed name or continues from the chosen slot with its play time.
+    fn answer_slots(&mut self, key: Key) {
+        let Some(screen) = &mut self.slot_screen else {
+            return;
+        };
+        let Some(action) = screen.press(key, self.slots.list().len()) else {
+            return;
+        };
+        self.slot_screen = None;
+        self.clock.resume();
+        let (io, name, result) = match action {
+            SlotAction::Save(name) => {
+                let result =
+                    self.slots.save(&name, &self.game, self.clock.elapsed());
+                (PositionIo::Save, name, result)
+            }
+            SlotAction::Load(index) => {
+                let name = self.slots.list()[index].name.clone();
+                let result = self.slots.open(index).map(|save| {
+                    self.load_game(save.game);
+                    self.clock = Stopwatch::resume_from(Duration::from_secs(
+                        save.duration_secs,
+                    ));
+                    self.schedule_garbage();
+                });
+                (PositionIo::Load, name, result)
+            }
+            SlotAction::Close => return,
+        };
+        self.position_status = Some(PositionStatus { io, name, result });
+    }
+
+    // Continues from a position as a new run, playing for the same tile if
+    // the position is of the same variant.
+    fn load(&mut self, board: Board, score: u32) {
+        let mut game = Game::from_board(board, score);
+        if game.variant() == self.game.variant() {
+            game.set_win_target(self.game.win_target());
+        }
+        self.load_game(game);
+    }
+
+    fn load_game(&mut self, game: Game) {
+        self.game = game;
+        self.game.set_undo_depth(self.undo_depth);
+        let mut outcome = self.game.outcome();
+        outcome.changed = true;
+        self.start_run(outcome);
+    }
+
+    fn start_run(&mut self, outcome: ActionOutcome) {
+        self.outcome = outcome;
+        self.celebrating = false;
+        self.animation = None;
+        self.points = None;
+        self.record(None);
+        self.schedule_autosave();
+        let paused = self.clock.is_paused();
+        self.clock = Stopwatch::start();
+        if paused {
+            self.clock.pause();
+        }
+        self.schedule_garbage();
+        metrics::set_score(self.outcome.score);
+        self.finished_run = None;
+        self.report = None;
+        self.shared = None;
+        self.notation = None;
+        self.targeting = None;
+        self.game_over_at = None;
+        self.moves_scroll = 0;
+        self.review = None;
+        // The player takes back the controls from the AI that finished the
+        // last game.
+        if self.handoff.take().is_some() {
+            self.autoplay = None;
+        }
+        self.tool = None;
+    }
+
+    // Moves through the gallery towards older (positive) or newer (negative)
+    // snapshots.
+    fn browse_gallery(&mut self, delta: isize) {
+        let oldest = self.history.snapshots().len().saturating_sub(1);
+        if let Some(index) = &mut self.gallery {
+            *index = index.saturating_add_signed(delta).min(oldest);
+        }
+    }
+
+    // Starts reviewing the game that just ended from its final board, or
+    // stops.
+    fn toggle_review(&mut self) {
+        self.review = match self.review {
+            None if self.game.is_game_over() => {
+                Some(self.game.positions().len())
+            }
+            _ => None,
+        };
+    }
+
+    // Steps through the reviewed game towards later (positive) or earlier
+    // (negative) positions.
+    fn step_review(&mut self, delta: isize) {
+        let last = self.game.positions().len();
+        if let Some(index) = &mut self.review {
+            *index = index.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    // The position under review and the move played from it, or the final
+    // position with no move.
+    fn reviewed_position(&self) -> Option<(Position, Option<GameAction>)> {
+        let index = self.review?;
+        let positions = self.game.positions();
+        // A game resumed from an older save has positions only for its
+        // latest moves.
+        let skipped = self.game.moves().len() - positions.len();
+        Some(match positions.get(index) {
+            Some(&position) => {
+                (position, Some(self.game.moves()[skipped + index].action))
+            }
+            None => (
+                Position {
+                    board: *self.game.board(),
+                    score: self.game.score(),
+                },
+                None,
+            ),
+        })
+    }
+
+    // The evaluations found for a board, once the AI's search of it is done.
+    fn evaluations_of(&self, board: &Board) -> Option<[Evaluation; 4]> {
+        self.analysis
+            .filter(|(analyzed, _)| analyzed == board)
+            .and_then(|(_, evaluations)| evaluations)
+    }
+
+    // Scrolls the move history panel towards older (positive) or newer
+    // (negative) moves.
+    fn scroll_moves(&mut self, delta: isize) {
+        let oldest = self.game.moves().len().saturating_sub(1);
+        self.moves_scroll =
+            self.moves_scroll.saturating_add_signed(delta).min(oldest);
+    }
+
+    // Returns when a new game should start automatically, if auto-restart is
+    // enabled and the game is over.
+    fn restart_deadline(&self) -> Option<Instant> {
+        Some(self.game_over_at? + self.auto_restart?)
+    }
+
+    // Returns when the game should save and exit for lack of input.
+    fn idle_deadline(&self) -> Option<Instant> {
+        Some(self.last_input + self.idle_timeout?)
+    }
+
+    // Returns how much play time is left in blitz mode.
+    fn time_left(&self) -> Option<Duration> {
+        Some(self.time_limit?.saturating_sub(self.clock.elapsed()))
+    }
+
+    // Ends the game once its time is up in blitz mode, returning the outcome.
+    fn time_up(&mut self) -> Option<ActionOutcome> {
+        if self.game.is_game_over() || self.time_left()? > Duration::ZERO {
+            return None;
+        }
+        self.hint = None;
+        Some(self.game.end())
+    }
+
+    // Schedules the next tile to drop one interval of play from now, in
+    // garbage mode.
+    fn schedule_garbage(&mut self) {
+        if let Some(interval) = self.garbage_interval {
+            self.garbage_at = self.clock.elapsed() + interval;
+        }
+    }
+
+    // Returns how much play time is left until the next tile drops in
+    // garbage mode.
+    fn garbage_left(&self) -> Option<Duration> {
+        self.garbage_interval?;
+        Some(self.garbage_at.saturating_sub(self.clock.elapsed()))
+    }
+
+    // Drops a tile once it is due in garbage mode, returning the outcome.
+    fn drop_garbage(&mut self) -> Option<ActionOutcome> {
+        if self.game.is_game_over() || self.garbage_left()? > Duration::ZERO {
+            return None;
+        }
+        self.schedule_garbage();
+        let outcome = self.game.drop_garbage()?;
+        self.hint = None;
+        self.record(None);
+        self.schedule_autosave();
+        Some(outcome)
+    }
+
+    fn is_idle(&self) -> bool {
+        self.idle_deadline()
+            .is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    // Returns how far the tiles have slid, from 0 to 1, while they are
+    // animating.
+    fn animation_progress(&self, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.animation?);
+        Some(
+            (elapsed.as_secs_f64() / ANIMATION_DURATION.as_secs_f64()).min(1.0),
+        )
+    }
+
+    // Returns when the app next needs to update without input: to restart
+    // automatically, to animate sliding tiles or a held restart key, to end
+    // the flash of the border, to fade out the points of a move, to play the
+    // AI's move, to count the play time in the stats panel or the blitz clock,
+    // to drop a tile in garbage mode or to exit when idle.
+    fn wake_at(&self) -> Option<Instant> {
+        let now = Instant::now();
+        let hold_tick = self.hold.map(|_| now + HOLD_TICK);
+        let animation_frame = self.animation.map(|_| now + ANIMATION_FRAME);
+        let flash_end = self.flash.map(|flash| flash + FLASH_DURATION);
+        let points_frame = self.points.map(|_| now + POINTS_FRAME);
+        let clock_tick = (self.show_stats || self.time_limit.is_some())
+            .then(|| now + STATS_TICK);
+        // The blitz clock stops with the game.
+        let clock_tick = clock_tick
+            .filter(|_| !self.clock.is_paused() && !self.game.is_game_over());
+        let time_up = self
+            .time_left()
+            .filter(|_| !self.clock.is_paused() && !self.game.is_game_over())
+            .map(|time_left| now + time_left);
+        let garbage = self
+            .garbage_left()
+            .filter(|_| !self.clock.is_paused() && !self.game.is_game_over())
+            .map(|garbage_left| now + garbage_left);
+        [
+            self.restart_deadline(),
+            hold_tick,
+            animation_frame,
+            flash_end,
+            points_frame,
+            clock_tick,
+            time_up,
+            garbage,
+            self.autoplay_deadline(),
+            self.idle_deadline(),
+            self.autosave.due(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    // Updates the app after waking up without input.
+    fn tick(&mut self) {
+        let now = Instant::now();
+        if self
+            .restart_deadline()
+            .is_some_and(|deadline| deadline <= now)
+        {
+            self.restart();
+        }
+        if self.hold.is_some_and(|hold| hold.is_released(now)) {
+            self.hold = None;
+        }
+        if self
+            .animation_progress(now)
+            .is_some_and(|progress| progress >= 1.0)
+        {
+            self.animation = None;
+        }
+        if self
+            .flash
+            .is_some_and(|flash| flash + FLASH_DURATION <= now)
+        {
+            self.flash = None;
+        }
+        if self.points_progress(now).is_none() {
+            self.points = None;
+        }
+        if self.autosave.due().is_some_and(|due| due <= now)
+            && let Err(e) =
+                self.autosave.write(&self.game, self.clock.elapsed())
+        {
+            self.autosave_error = Some(e);
+        }
+    }
+
+    // Records the finished run in the persistent history the first time the
+    // game is observed to be over.
+    fn record_game_over(&mut self) -> Result<()> {
+        if !self.outcome.game_over || self.game_over_at.is_some() {
+            return Ok(());
+        }
+        if self.is_practice() {
+            self.game_over_at = Some(Instant::now());
+            return Ok(());
+        }
+
+        let run = GameRecord::new(
+            self.game.score(),
+            self.game.max_tile(),
+            self.clock.elapsed(),
+        )
+        .with_board(*self.game.board())
+        .with_win(self.game.is_won())
+        .with_difficulty(self.game.difficulty())
+        .with_handoff(self.handoff)
+        .with_tool(self.tool);
+        if let Some(url) = &self.webhook {
+            let seed = self.game.seed();
+            let notification = webhook::notify(url.clone(), run.clone(), seed);
+            self.notifications.push(notification);
+        }
+        // Only a game its seed and moves replay can be checked by the server.
+        if let Some(config) = &self.leaderboard
+            && self.game.is_replayable()
+        {
+            let submission = Submission::new(&config.name, &self.game, &run);
+            leaderboard::submit(config.clone(), submission);
+        }
+        self.finished_run = Some(self.history.record(run));
+        self.game_over_at = Some(Instant::now());
+        metrics::game_finished();
+        self.history.save()
+    }
+
+    // Offers to load a pasted board code or text grid.
+    fn paste(&mut self, text: &str) {
+        let pasted = import::import_text(text).and_then(|import| {
+            if import.board.is_none() {
+                bail!("The pasted text has no board");
+            }
+            Ok(import)
+        });
+        self.pasted = Some(pasted);
+    }
+
+    // Loads the pasted position as a practice game, like `--start`, if the
+    // key confirms it; any other key dismisses the prompt.
+    fn answer_paste(&mut self, key: Key) {
+        if let Some(Ok(import)) = self.pasted.take()
+            && let Some(board) = import.board
+            && confirms(key)
+        {
+            self.practice = Some(board);
+            self.load(board, import.score);
+        }
+    }
+
+    // Describes what the game is doing, for the help screen.
+    fn mode(&self) -> &'static str {
+        if self.gallery.is_some() {
+            "Browsing the gallery"
+        } else if self.review.is_some() {
+            "Reviewing the game"
+        } else if self.tutorial.is_some() {
+            "Learning to play"
+        } else if self.game.is_game_over() {
+            "Game over"
+        } else if self.handoff.is_some() {
+            "AI finishing the game"
+        } else if self.autoplay.is_some() {
+            "Autoplay"
+        } else if self.game.is_won() {
+            "Playing past the win"
+        } else if self.practice.is_some() {
+            "Practicing"
+        } else if self.puzzles.is_some() {
+            "Solving a puzzle"
+        } else {
+            "Playing"
+        }
+    }
+
+    // Takes a snapshot of everything the next frame draws.
+    fn view(&self) -> GameView<'_> {
+        let now = Instant::now();
+        let title = match &self.screen {
+            Screen::Title(menu) => Some(menu),
+            Screen::Game => None,
+        };
+        GameView {
+            game: &self.game,
+            outcome: &self.outcome,
+            history: &self.history,
+            keymap: &self.keymap,
+            labels: self.labels,
+            theme: self.theme.theme().with_markers(self.markers),
+            report_settings: &self.report_settings,
+            profile: self.profile.as_deref(),
+            title,
+            mode: self.mode(),
+            elapsed: self.clock.elapsed(),
+            paused: self.clock.is_paused(),
+            time_left: self.time_left(),
+            hold_progress: self.hold.map(|hold| hold.progress(now)),
+            hint: self.hint_arrow(),
+            autoplay: self.autoplay.is_some(),
+            challenge: self.controls.challenge(),
+            flash: self.flash.is_some(),
+            animation: self.animation_progress(now),
+            points: self.points_progress(now),
+            position: self
+                .position_status
+                .as_ref()
+                .map(position_status)
+                .or_else(|| {
+                    let warning = self.warnings.first()?;
+                    Some(Line::from(format!(" {warning} ")).red())
+                }),
+            warnings: &self.warnings,
+            finished_run: self.finished_run,
+            report: &self.report,
+            shared: self.shared,
+            notation: self.notation.as_ref(),
+            // The tutorial explains its own game over.
+            practice_over: self.is_practice()
+                && self.tutorial.is_none()
+                && self.game_over_at.is_some(),
+            puzzle: self.puzzles.as_ref().map(|puzzles| {
+                let last = puzzles.index + 1 == puzzles.pack.len();
+                (puzzles.current(), last)
+            }),
+            tutorial: self.tutorial.as_ref(),
+            gallery: self.gallery,
+            review: self.reviewed_position().map(|(position, played)| Review {
+                index: self.review.unwrap_or_default(),
+                count: self.game.positions().len() + 1,
+                board: position.board,
+                score: position.score,
+                played,
+                best: self
+                    .evaluations_of(&position.board)
+                    .and_then(|evaluations| ai::preferred(&evaluations)),
+            }),
+            show_moves: self.show_moves,
+            moves_scroll: self.moves_scroll,
+            show_merges: self.show_merges,
+            show_stats: self.show_stats,
+            show_debug: self.show_debug,
+            show_heatmap: self.show_heatmap,
+            analysis: self.show_analysis.then(|| Analysis {
+                evaluations: self.evaluations_of(self.game.board()),
+                last: self.last_choice,
+            }),
+            render_time: self.render_time,
+            search: self.search_report,
+            show_help: self.show_help,
+            show_leaderboard: self.show_leaderboard,
+            global_scores: self.leaderboard.as_ref().map(|_| {
+                self.global_scores
+                    .as_ref()
+                    .unwrap_or(&GlobalScores::Loading)
+            }),
+            show_statistics: self.show_statistics,
+            confirming_restart: self.confirming_restart,
+            confirming_quit: self.confirming_quit,
+            celebrating: self.celebrating,
+            pasted: self.pasted.as_ref(),
+            menu: self.menu,
+            slot_screen: self.slot_screen.as_ref(),
+            targeting: self
+                .targeting
+                .as_ref()
+                .map(|targeting| (targeting.power_up, targeting.cells())),
+            slots: self.slots.list(),
+        }
+    }
+
+    // Pauses the game and opens the menu.
+    fn open_menu(&mut self) {
+        self.menu = Some(PauseMenu::default());
+        self.clock.pause();
+    }
+
+    // Acts on a key pressed in the menu, returning whether to quit.
+    fn answer_menu(&mut self, key: Key) -> bool {
+        let Some(menu) = &mut self.menu else {
+            return false;
+        };
+        let Some(action) = menu.press(key) else {
+            return false;
+        };
+        self.menu = None;
+        self.clock.resume();
+        match action {
+            PauseAction::Resume => {}
+            PauseAction::Restart => self.restart(),
+            PauseAction::Quit => return true,
+        }
+        false
+    }
+
+    // Acts on a key pressed on the title screen, leaving it for the game
+    // unless the settings, the leaderboard or the statistics are shown.
+    // Returns whether to quit.
+    fn answer_title(&mut self, key: Key) -> bool {
+        if self.keymap.command(key) == Some(Command::Quit) {
+            return true;
+        }
+        let Screen::Title(menu) = &mut self.screen else {
+            return false;
+        };
+        let Some(item) = menu.press(key) else {
+            return false;
+        };
+        match item {
+            TitleItem::Quit => return true,
+            TitleItem::Leaderboard => {
+                self.show_leaderboard = true;
+                return false;
+            }
+            TitleItem::Statistics => {
+                self.show_statistics = true;
+                return false;
+            }
+            TitleItem::NewGame => self.restart(),
+            // The saved game is already on the board.
+            TitleItem::Continue => {}
+            TitleItem::Daily => self.load_game(Game::with_seed(daily_seed())),
+            TitleItem::Settings => {
+                unreachable!("the title menu shows the settings itself")
+            }
+        }
+        self.screen = Screen::Game;
+        self.clock.resume();
+        false
+    }
+
+    // Pauses the clock and dims the board while the terminal is unfocused.
+    // The clock stays paused while the title screen, the menu or a slot
+    // screen is open.
+    fn set_focused(&mut self, focused: bool) {
+        if focused
+            && self.screen == Screen::Game
+            && self.menu.is_none()
+            && self.slot_screen.is_none()
+        {
+            self.clock.resume();
+        } else {
+            self.clock.pause();
+        }
+    }
+}
+
+// What fills the terminal: the title screen shown on launch, with its menu,
+// or the game.
+#[derive(Debug, PartialEq)]
+enum Screen {
+    Title(TitleMenu),
+    Game,
+}
+
+// The seed of the daily game, the same for every player on a given UTC day,
+// e.g. 20261016.
+fn daily_seed() -> u64 {
+    let today = Utc::now().date_naive();
+    today.year() as u64 * 10000 + u64::from(today.month() * 100 + today.day())
+}
+
+// Describes the latest position exported, saved or loaded.
+fn position_status(status: &PositionStatus) -> Line<'static> {
+    let name = &status.name;
+    let (done, verb) = match status.io {
+        PositionIo::Export => ("Exported", "export"),
+        PositionIo::Save => ("Saved", "save"),
+        PositionIo::Load => ("Loaded", "load"),
+    };
+    match status.result {
+        Ok(()) => Line::from(format!(" {done} {name} ")).green(),
+        Err(_) => Line::from(format!(" Failed to {verb} {name} ")).red(),
+    }
+}
+
+// Whether a key answers yes to a prompt.
+fn confirms(key: Key) -> bool {
+    key == Key::char('y') || key == Key::plain(KeyCode::Enter)
+}
+
+fn input_loop(tx: Sender<Event>) -> Result<()> {
+    // The receiver is dropped once the game ends, which stops the loop.
+    while !tx.is_closed() {
+        if !poll(INPUT_POLL_INTERVAL)? {
+            continue;
+        }
+
+        let event = match read()? {
+            terminal_event::Event::FocusGained => Event::FocusGained,
+            terminal_event::Event::FocusLost => Event::FocusLost,
+            terminal_event::Event::Paste(text) => Event::Paste(text),
+            terminal_event::Event::Resize(..) => Event::Resize,
+            event => match event.as_key_press_event() {
+                Some(key_event) => Event::Key(key_event.into()),
+                None => continue,
+            },
+        };
+
+        if tx.blocking_send(event).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Draws a frame and records how long it took.
+fn draw(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    let start = Instant::now();
+    let frame = terminal.draw(|frame| ui::draw(frame, &app.view()))?;
+    let elapsed = start.elapsed();
+    metrics::rendered(elapsed);
+    app.render_time = Some(elapsed);
+    if let Some(cast) = &mut app.cast
+        && let Err(e) = cast.frame(frame.buffer)
+    {
+        app.cast = None;
+        app.cast_error = Some(e);
+    }
+    Ok(())
+}
+
+// Repeats a move until the board stops changing, the game or its puzzle
+// ends or the blitz clock runs out, letting the tiles of each step slide into
+// place before the next.
+async fn slam(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    direction: GameAction,
+) -> Result<()> {
+    let mut outcome = app.try_move(direction)?;
+    loop {
+        let changed = outcome.changed;
+        app.finish_move(outcome)?;
+        if !changed || app.outcome.game_over || app.outcome.won {
+            return Ok(());
+        }
+        if let Some(outcome) = app.time_up() {
+            return app.finish_move(outcome);
+        }
+        let slid = Instant::now() + ANIMATION_DURATION;
+        while Instant::now() < slid {
+            draw(terminal, app)?;
+            sleep(ANIMATION_FRAME).await;
+        }
+        outcome = app.apply_move(direction)?;
+    }
+}
+
+async fn event_loop(
+    mut rx: Receiver<Event>,
+    tx: Sender<Event>,
+    mut terminal: DefaultTerminal,
+    app: &mut App,
+) -> Result<()> {
+    draw(&mut terminal, app)?;
+
+    loop {
+        // The evaluations follow the board after every move.
+        app.request_analysis(&tx);
+        app.request_global_scores(&tx);
+        // Input that arrived while tiles were sliding is handled in order
+        // once they have stopped.
+        let next = if app.animation.is_none()
+            && let Some(e) = app.queued.pop()
+        {
+            Some(e)
+        } else {
+            match app.wake_at() {
+                Some(deadline) => select! {
+                    e = rx.recv() => e,
+                    _ = sleep_until(deadline.into()) => {
+                        if app.is_idle() {
+                            break;
+                        }
+                        app.tick();
+                        if let Some(outcome) = app.time_up() {
+                            app.finish_move(outcome)?;
+                        } else if let Some(outcome) = app.drop_garbage() {
+                            app.finish_move(outcome)?;
+                        } else if let Some(outcome) = app.autoplay_move()? {
+                            app.finish_move(outcome)?;
+                        }
+                        draw(&mut terminal, app)?;
+                        continue;
+                    }
+                },
+                None => rx.recv().await,
+            }
+        };
+        let Some(e) = next else {
+            break;
+        };
+        if matches!(
+            e,
+            Event::Key(_)
+                | Event::Paste(_)
+                | Event::Control { .. }
+                | Event::Pipe(_)
+        ) {
+            app.last_input = Instant::now();
+            app.position_status = None;
+            if matches!(app.screen, Screen::Game) {
+                app.warnings.clear();
+            }
+            if app.animation.is_some() {
+                app.queued.push(e);
+                continue;
+            }
+        }
+
+        let command = match e {
+            Event::FocusGained | Event::FocusLost => {
+                app.set_focused(matches!(e, Event::FocusGained));
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Resize => {
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Hint {
+                board,
+                action,
+                report,
+            } => {
+                app.search_report = report.or(app.search_report);
+                app.show_hint(board, action);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::GlobalScores(scores) => {
+                if app.show_leaderboard {
+                    app.global_scores = Some(match scores {
+                        Ok(scores) => GlobalScores::Loaded(scores),
+                        Err(_) => GlobalScores::Failed,
+                    });
+                    draw(&mut terminal, app)?;
+                }
+                continue;
+            }
+            Event::Analysis {
+                board,
+                evaluations,
+                report,
+            } => {
+                app.search_report = report.or(app.search_report);
+                app.show_analysis(board, evaluations);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Paste(text) => {
+                app.paste(&text);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Control { command, reply } => {
+                // The agent may have gone away, which the game need not know.
+                let _ = reply.send(app.control(command)?);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Pipe(line) => {
+                app.pipe(&line)?;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key)
+                if matches!(app.screen, Screen::Title(_))
+                    && !app.show_leaderboard
+                    && !app.show_statistics =>
+            {
+                if app.answer_title(key) {
+                    break;
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key) if app.slot_screen.is_some() => {
+                app.answer_slots(key);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key) if app.menu.is_some() => {
+                if app.answer_menu(key) {
+                    break;
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            // Only the keys that toggle help, or Esc, close it.
+            Event::Key(key) if app.show_help => {
+                if matches!(
+                    app.keymap.command(key),
+                    Some(Command::Help | Command::Pause)
+                ) {
+                    app.show_help = false;
+                    draw(&mut terminal, app)?;
+                }
+                continue;
+            }
+            // Esc or Enter closes the statistics.
+            Event::Key(key) if app.show_statistics => {
+                if key == Key::plain(KeyCode::Enter)
+                    || app.keymap.command(key) == Some(Command::Pause)
+                {
+                    app.show_statistics = false;
+                    draw(&mut terminal, app)?;
+                }
+                continue;
+            }
+            // Only the leaderboard key, or Esc, closes the leaderboard.
+            Event::Key(key) if app.show_leaderboard => {
+                if matches!(
+                    app.keymap.command(key),
+                    Some(Command::ToggleLeaderboard | Command::Pause)
+                ) {
+                    app.show_leaderboard = false;
+                    app.global_scores = None;
+                    draw(&mut terminal, app)?;
+                }
+                continue;
+            }
+            Event::Key(key) if app.pasted.is_some() => {
+                app.answer_paste(key);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key) if app.confirming_quit => {
+                if app.answer_quit(key) {
+                    break;
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key)
+                if app.celebrating
+                    && app.keymap.command(key) != Some(Command::Quit) =>
+            {
+                app.answer_win(key);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key) if app.targeting.is_some() => {
+                if let Some(outcome) = app.answer_target(key) {
+                    app.finish_move(outcome)?;
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            // Only the move a lesson asks for, Enter once it is played, Esc
+            // or the quit key do anything in the tutorial.
+            Event::Key(key)
+                if app.tutorial.is_some()
+                    && app.keymap.command(key) != Some(Command::Quit) =>
+            {
+                app.answer_tutorial(key)?;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            // Enter moves on from a solved puzzle.
+            Event::Key(key)
+                if app.puzzle_status() == Some(PuzzleStatus::Solved)
+                    && key == Key::plain(KeyCode::Enter) =>
+            {
+                app.next_puzzle();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key) if app.confirming_restart => {
+                app.answer_restart(key);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Event::Key(key) => match app.keymap.command(key) {
+                Some(command) => command,
+                None => continue,
+            },
+        };
 
-use crate::board::BOARD_SIZE;
-use crate::event::Event;
-use crate::game::{ActionOutcome, CellResult, Game, GameAction, TITLE};
-
-const BUFSIZE: usize = 1;
-
-const CELL_WIDTH: u16 = 11;
-const CELL_HEIGHT: u16 = 5;
-const SCORE_HEIGHT: u16 = 1;
-const CELL_PADDING_X: u16 = 1;
-const CELL_PADDING_Y: u16 = 2;
-const BORDER_WIDTH: u16 = 1;
-
-fn calculate_game_dimensions() -> (u16, u16) {
-    let width = BOARD_SIZE as u16 * (CELL_WIDTH + CELL_PADDING_X)
-        + CELL_PADDING_X
-        + (BORDER_WIDTH * 2);
-    let height =
-        BOARD_SIZE as u16 * CELL_HEIGHT + SCORE_HEIGHT + (BORDER_WIDTH * 2);
-    (width, height)
-}
-
-// Render the border and title around the tiles area
-fn render_board(outcome: &ActionOutcome, area: Rect, frame: &mut Frame) {
-    let style = if outcome.game_over {
-        Style::new().red()
-    } else {
-        Style::new()
-    };
+        let outcome = match command {
+            Command::Quit => {
+                if app.request_quit() {
+                    break;
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::Help => {
+                app.show_help = true;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::Pause => {
+                app.open_menu();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::Restart => {
+                app.request_restart();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::Hint => {
+                app.request_hint(&tx);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleAutoplay => {
+                app.toggle_autoplay();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::FinishWithAi => {
+                app.finish_with_ai();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleMoves => {
+                app.show_moves = !app.show_moves;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleLeaderboard => {
+                app.show_leaderboard = true;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::CycleTheme => {
+                app.theme = app.theme.next();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleSound => {
+                app.sound.toggle_mute();
+                continue;
+            }
+            Command::ToggleGallery => {
+                app.gallery = match app.gallery {
+                    Some(_) => None,
+                    None => Some(0),
+                };
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            _ if app.gallery.is_some() => {
+                match command {
+                    Command::MoveLeft | Command::MoveUp => {
+                        app.browse_gallery(-1)
+                    }
+                    Command::MoveRight | Command::MoveDown => {
+                        app.browse_gallery(1)
+                    }
+                    _ => continue,
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleReview => {
+                app.toggle_review();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            _ if app.review.is_some() => {
+                match command {
+                    Command::MoveLeft => app.step_review(-1),
+                    Command::MoveRight => app.step_review(1),
+                    _ => continue,
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::SaveReport => {
+                app.save_report();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::CopySummary => {
+                app.share_summary();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ExportNotation => {
+                app.export_notation();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::SaveSlot => {
+                app.open_slots(SlotScreen::Naming(String::new()));
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::LoadSlot => {
+                app.open_slots(SlotScreen::Choosing(0));
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ExportPosition => {
+                app.export_position();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::LoadPosition => {
+                app.load_position();
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleMerges => {
+                app.show_merges = !app.show_merges;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleStats => {
+                app.show_stats = !app.show_stats;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleDebug => {
+                app.show_debug = !app.show_debug;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleHeatmap => {
+                app.show_heatmap = !app.show_heatmap;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ToggleAnalysis => {
+                app.show_analysis = !app.show_analysis;
+                app.last_choice = None;
+                app.request_analysis(&tx);
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::ScrollUp | Command::ScrollDown => {
+                app.scroll_moves(match command {
+                    Command::ScrollUp => 1,
+                    _ => -1,
+                });
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            // A finished game is already in the history, so it cannot be
+            // taken back.
+            _ if app.game.is_game_over() => continue,
+            Command::Undo | Command::Redo => {
+                match command {
+                    Command::Undo => app.undo(),
+                    _ => app.redo(),
+                }
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::SwapTiles | Command::DeleteTile | Command::UpgradeTile => {
+                app.start_targeting(match command {
+                    Command::SwapTiles => PowerUp::Swap,
+                    Command::DeleteTile => PowerUp::Delete,
+                    _ => PowerUp::Upgrade,
+                });
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            // A challenge may change which move the move keys play.
+            Command::MoveUp => {
+                app.try_move(app.controls.map(GameAction::Up))?
+            }
+            Command::MoveDown => {
+                app.try_move(app.controls.map(GameAction::Down))?
+            }
+            Command::MoveLeft => {
+                app.try_move(app.controls.map(GameAction::Left))?
+            }
+            Command::MoveRight => {
+                app.try_move(app.controls.map(GameAction::Right))?
+            }
+            Command::SlamUp => {
+                let direction = app.controls.map(GameAction::Up);
+                slam(&mut terminal, app, direction).await?;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::SlamDown => {
+                let direction = app.controls.map(GameAction::Down);
+                slam(&mut terminal, app, direction).await?;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::SlamLeft => {
+                let direction = app.controls.map(GameAction::Left);
+                slam(&mut terminal, app, direction).await?;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+            Command::SlamRight => {
+                let direction = app.controls.map(GameAction::Right);
+                slam(&mut terminal, app, direction).await?;
+                draw(&mut terminal, app)?;
+                continue;
+            }
+        };
 
-    frame.render_widget(
-        Block::bordered()
-            .border_type(BorderType::Thick)
-            .border_style(style)
-            .title(TITLE)
-            .title_style(Style::new().yellow()),
-        area,
-    );
+        app.finish_move(outcome)?;
+        draw(&mut terminal, app)?;
+    }
+    Ok(())
 }
 
-fn render_tiles(
-    board: &[[CellResult; BOARD_SIZE]; BOARD_SIZE],
-    area: Rect,
-    frame: &mut Frame,
-) {
-    // Split the tiles area into rows
-    let rows_layout = Layout::vertical([Constraint::Fill(1); BOARD_SIZE]);
-    let rows_rects: [Rect; BOARD_SIZE] = rows_layout.areas(
-        area.inner(Margin::new(BORDER_WIDTH + CELL_PADDING_X, BORDER_WIDTH)),
-    );
+// Loads the profile's history, merging in the remote copy when sync is
+// configured, and the damaged history set aside for it, if any. A remote
+// copy that cannot be pulled is not pushed over either, so sync is turned
+// off for the session.
+fn load_history(
+    profile: &Profile,
+    signer: &Signer,
+    sync: &mut Option<Remote>,
+) -> Result<(History, Option<SetAside>)> {
+    let (mut history, set_aside) = History::load(profile, signer)?;
+    if let Some(remote) = sync
+        && let Err(e) =
+            history.pull(remote, signer).and_then(|_| history.save())
+    {
+        eprintln!("Failed to sync history: {e:#}");
+        *sync = None;
+    }
+    Ok((history, set_aside))
+}
+
+// Replaces the profile's save with the remote copy when sync is configured.
+// As with the history, a copy that cannot be pulled turns sync off for the
+// session rather than being pushed over.
+fn pull_save(profile: &Profile, signer: &Signer, sync: &mut Option<Remote>) {
+    if let Some(remote) = sync
+        && let Err(e) = SavedGame::pull(profile, remote, signer)
+    {
+        eprintln!("Failed to sync the save: {e:#}");
+        *sync = None;
+    }
+}
+
+// Fetches a replay recorded on another machine from the remote when sync is
+// configured and the file is not on this one.
+fn pull_replay(profile: &Profile, path: &Path) -> Result<()> {
+    let Some(config) = Settings::load(profile)?.sync else {
+        return Ok(());
+    };
+    replay::pull(path, &Remote::new(config), &Signer::load()?)
+}
 
-    // Each row is split into columns, with spacing between them
-    let cols_layout = Layout::horizontal((0..BOARD_SIZE).flat_map(|i| {
-        if i < BOARD_SIZE - 1 {
-            [Constraint::Fill(1), Constraint::Length(1)].iter()
+// Prints every profile with its games played and best score, marking the
+// one given with `--profile`.
+fn print_profiles(current: &Profile) -> Result<()> {
+    let profiles = Profile::list()?;
+    if profiles.is_empty() {
+        println!("No profiles yet.");
+        return Ok(());
+    }
+    // Listing is read-only: a key is not generated just to check with, and
+    // a history that fails to read is reported rather than set aside.
+    let signer = Signer::find()?;
+    println!("  {:<24}  {:>5}  {:>7}", "Profile", "Games", "Best");
+    for profile in profiles {
+        let marker = if profile.name() == current.name() {
+            '*'
         } else {
-            [Constraint::Fill(1)].iter()
+            ' '
+        };
+        let Ok(history) = History::read(&profile, signer.as_ref()) else {
+            println!("{marker} {:<24}  {:>14}", profile.name(), "unreadable");
+            continue;
+        };
+        let games = history.summary().map_or(0, |summary| summary.games);
+        println!(
+            "{marker} {:<24}  {games:>5}  {:>7}",
+            profile.name(),
+            history.best_score()
+        );
+    }
+    Ok(())
+}
+
+fn print_stats(history: &History) {
+    let imported = history.imported_best();
+    let Some(summary) = history.summary() else {
+        println!("No games played yet.");
+        if let Some(best) = imported {
+            println!("Imported best: {best}");
         }
-    }));
+        return;
+    };
 
-    // Iterate over the row rectangles and render the tiles within each row
-    for (row, row_rect) in rows_rects.into_iter().enumerate() {
-        let col_rects: [Rect; BOARD_SIZE * 2 - 1] = cols_layout.areas(row_rect);
+    println!("Games played:  {}", summary.games);
+    println!("Best score:    {}", summary.best_score);
+    if let Some(best) = imported {
+        println!("Imported best: {best}");
+    }
+    println!("Average score: {}", summary.average_score);
+    println!("Win rate:      {:.1}%", summary.win_rate() * 100.0);
+    println!("AI finished:   {}", summary.finished_by_ai);
+    println!("Best tile:     {}", summary.best_tile);
+    println!("Time played:   {}", format_duration(summary.total_secs));
+    // Each difficulty played has its own table of the best runs.
+    for difficulty in Difficulty::ALL {
+        let top = history.top(TOP_RUNS, difficulty);
+        if top.is_empty() {
+            continue;
+        }
+        println!();
+        if !difficulty.is_normal() {
+            println!("{difficulty}:");
+        }
+        println!(
+            "{:>2}  {:>7}  {:>6}  {:<10}  {:<8}  By",
+            "#", "Score", "Tile", "Date", "Time"
+        );
+        for (rank, (_, run)) in top.into_iter().enumerate() {
+            println!(
+                "{:>2}  {:>7}  {:>6}  {:<10}  {:<8}  {}",
+                rank + 1,
+                run.score,
+                run.max_tile,
+                run.finished_at.format("%Y-%m-%d"),
+                format_duration(run.duration_secs),
+                run.helper().unwrap_or("You")
+            );
+        }
+    }
+}
 
-        // Filter out the spacing rectangles and render the tile rectangles
-        for (col, col_rect) in
-            col_rects.into_iter().enumerate().filter_map(|(idx, rect)| {
-                // Include the tile rectangles (skip the spacing)
-                if idx % 2 == 0 {
-                    Some((idx / 2, rect))
-                } else {
-                    None
-                }
-            })
-        {
-            // Get the cell result for the current coordinates
-            let result = &board[row][col];
+// Replays a game written in move notation, printing the position it ends in
+// once every move checks out.
+fn verify_notation(path: &Path) -> Result<()> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let game = text
+        .parse::<Notation>()
+        .and_then(|notation| notation.replay())
+        .with_context(|| format!("Failed to verify {}", path.display()))?;
+    println!("{}", game.board());
+    println!(
+        "Verified {} moves: score {}, best tile {}",
+        game.moves().len(),
+        game.score(),
+        game.max_tile()
+    );
+    Ok(())
+}
 
-            // Determine the style based on whether the cell was merged in the last move
-            let style = if result.merged {
-                Style::new().green()
-            } else {
-                Style::new().dim()
-            };
+// Prints the expected value of each move from a position and the line the
+// search prefers, as a post-mortem aid.
+fn print_solution(board: &Board, depth: u32) {
+    let mut searcher = Searcher::new(depth);
+    let evaluations = searcher.evaluate(board);
 
-            // Render the cell border with the appropriate style
-            frame
-                .render_widget(Block::bordered().border_style(style), col_rect);
+    println!("{board}");
+    println!("{:<6}  Evaluation", "Move");
+    for evaluation in evaluations {
+        match evaluation.value {
+            Some(value) => println!(
+                "{:<6}  {value:>10.2}",
+                format!("{:?}", evaluation.action)
+            ),
+            None => println!(
+                "{:<6}  {:>10}",
+                format!("{:?}", evaluation.action),
+                "illegal"
+            ),
+        }
+    }
 
-            // Render the cell value centered within the cell rectangle
-            let cell = col_rect.inner(Margin::new(0, CELL_PADDING_Y));
-            let cell_value =
-                result.value.map_or("".to_string(), |v| v.to_string());
-            frame.render_widget(Paragraph::new(cell_value).centered(), cell);
+    let line = searcher.line(board, depth as usize);
+    println!();
+    match line.first() {
+        Some(best) => {
+            let line: Vec<String> =
+                line.iter().map(|action| format!("{action:?}")).collect();
+            println!("Best move: {best:?}");
+            println!("Best line: {}", line.join(" "));
         }
+        None => println!("No legal moves: the game is over."),
     }
+    println!("Searched {} positions at depth {depth}.", searcher.nodes());
+}
+
+fn print_bench(seconds: u64, depth: u32) -> Result<()> {
+    println!("Measuring for {seconds}s at depth {depth}...");
+    let measurement = bench::run(Duration::from_secs(seconds), depth)?;
+
+    println!();
+    println!("Moves/s:  {:>12.0}", measurement.moves_per_sec);
+    println!("Nodes/s:  {:>12.0}", measurement.nodes_per_sec);
+    Ok(())
 }
 
-fn render_score(score: u32, area: Rect, frame: &mut Frame) {
-    const MIN_SCORE_WIDTH: usize = 6;
-    let score_text = format!("Score: {0:>1$} ", score, MIN_SCORE_WIDTH);
-    frame.render_widget(Paragraph::new(score_text).right_aligned(), area);
+fn print_simulation(results: &[GameResult], format: Format) {
+    let Some(summary) = simulate::summarize(results) else {
+        println!("No games played.");
+        return;
+    };
+
+    match format {
+        Format::Table => {
+            println!("Games played:  {}", summary.games);
+            println!("Mean score:    {:.1}", summary.mean_score);
+            println!("Median score:  {:.1}", summary.median_score);
+            println!("Best score:    {}", summary.best_score);
+            println!("Mean moves:    {:.1}", summary.mean_moves);
+            println!();
+            println!("{:>6}  {:>6}  {:>6}", "Tile", "Games", "Share");
+            for (tile, games) in summary.max_tiles.iter().rev() {
+                let share = *games as f64 / summary.games as f64 * 100.0;
+                println!("{tile:>6}  {games:>6}  {share:>5.1}%");
+            }
+        }
+        Format::Csv => {
+            println!("statistic,value");
+            println!("games,{}", summary.games);
+            println!("mean_score,{}", summary.mean_score);
+            println!("median_score,{}", summary.median_score);
+            println!("best_score,{}", summary.best_score);
+            println!("mean_moves,{}", summary.mean_moves);
+            for (tile, games) in &summary.max_tiles {
+                println!("max_tile_{tile},{games}");
+            }
+        }
+    }
 }
 
-fn render(outcome: &ActionOutcome, frame: &mut Frame) {
-    let (main_width, main_height) = calculate_game_dimensions();
+fn watch_loop(
+    mut follower: Follower,
+    terminal: &mut DefaultTerminal,
+    labels: LabelMode,
+    theme: Theme,
+) -> Result<()> {
+    let mut latest = None;
+    loop {
+        if let Some(replay) = follower.poll()?.pop() {
+            latest = Some(replay);
+        }
+        terminal.draw(|frame| {
+            ui::render_replay(latest.as_ref(), None, labels, &theme, frame)
+        })?;
 
-    // Center the game area within the terminal frame
-    let game_area = frame.area().centered(
-        Constraint::Length(main_width),
-        Constraint::Length(main_height),
-    );
+        if poll(WATCH_INTERVAL)?
+            && let Some(key) = read()?.as_key_press_event()
+            && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+        {
+            return Ok(());
+        }
+    }
+}
 
-    // Split the game area into the tiles area and the score area
-    let game_layout = Layout::vertical([
-        Constraint::Fill(1),
-        Constraint::Length(SCORE_HEIGHT),
-    ]);
-    let [tiles_area, scores_area] = game_layout.areas(game_area);
+// Follows a replay file as another instance writes it until `q` or Esc is
+// pressed.
+fn watch(path: &Path, labels: LabelMode, theme: Theme) -> Result<()> {
+    if tas::is_move_log(path) {
+        bail!(
+            "{} is a move log, which cannot be watched live: play it back \
+             with --play",
+            path.display()
+        );
+    }
+    let follower = Follower::open(path)?;
+    let mut terminal = ratatui::init();
+    let result = watch_loop(follower, &mut terminal, labels, theme);
+    ratatui::restore();
+    result
+}
 
-    render_board(outcome, tiles_area, frame);
-    render_tiles(&outcome.board, tiles_area, frame);
-    render_score(outcome.score, scores_area, frame);
+// Describes where playback is and how fast it runs, e.g. "▶ 4/s 12/80".
+fn playback_status(playback: &Playback) -> String {
+    let state = if playback.is_paused() { '⏸' } else { '▶' };
+    let (frame, frames) = playback.progress();
+    format!(" {state} {}/s {frame}/{frames}", playback.moves_per_sec())
 }
 
-fn input_loop(tx: Sender<Event>) -> Result<()> {
+fn replay_loop(
+    mut playback: Playback,
+    terminal: &mut DefaultTerminal,
+    labels: LabelMode,
+    theme: Theme,
+) -> Result<()> {
+    let mut next_step = Instant::now() + playback.interval();
     loop {
-        let event = read()?;
+        terminal.draw(|frame| {
+            let status = Some(playback_status(&playback));
+            ui::render_replay(
+                Some(playback.frame()),
+                status,
+                labels,
+                &theme,
+                frame,
+            )
+        })?;
 
-        let key = match event.as_key_press_event() {
-            Some(key_event) => key_event,
-            None => continue,
+        if !playback.is_paused()
+            && !poll(next_step.saturating_duration_since(Instant::now()))?
+        {
+            playback.step(1);
+            next_step = Instant::now() + playback.interval();
+            continue;
+        }
+        let Some(key) = read()?.as_key_press_event() else {
+            continue;
         };
-
         match key.code {
-            KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => {
-                tx.blocking_send(Event::MoveUp)?
-            }
-            KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => {
-                tx.blocking_send(Event::MoveDown)?
-            }
-            KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('h') => {
-                tx.blocking_send(Event::MoveLeft)?
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char(' ') => playback.toggle_pause(),
+            KeyCode::Right | KeyCode::Char('l') => {
+                playback.pause();
+                playback.step(1);
             }
-            KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('l') => {
-                tx.blocking_send(Event::MoveRight)?
-            }
-            KeyCode::Char('r') => tx.blocking_send(Event::Restart)?,
-            KeyCode::Char('q') => {
-                tx.blocking_send(Event::Quit)?;
-                break;
+            KeyCode::Left | KeyCode::Char('h') => {
+                playback.pause();
+                playback.step(-1);
             }
+            KeyCode::Char('+' | '=') => playback.faster(),
+            KeyCode::Char('-') => playback.slower(),
             _ => continue,
-        };
+        }
+        next_step = Instant::now() + playback.interval();
     }
-    Ok(())
 }
 
-async fn event_loop(
-    mut rx: Receiver<Event>,
-    mut terminal: DefaultTerminal,
+// Plays back a finished replay file until `q` or Esc is pressed.
+fn replay(
+    path: &Path,
+    speed: f64,
+    labels: LabelMode,
+    theme: Theme,
 ) -> Result<()> {
-    let mut game = Game::new();
-    terminal.draw(|frame| render(&game.outcome(), frame))?;
-
-    while let Some(e) = rx.recv().await {
-        let outcome = match e {
-            Event::Quit => break,
-            Event::Restart => game.restart(),
-            Event::MoveUp
-            | Event::MoveDown
-            | Event::MoveLeft
-            | Event::MoveRight
-                if game.is_game_over() =>
-            {
-                continue;
-            }
-            Event::MoveUp => game.apply_move(GameAction::Up)?,
-            Event::MoveDown => game.apply_move(GameAction::Down)?,
-            Event::MoveLeft => game.apply_move(GameAction::Left)?,
-            Event::MoveRight => game.apply_move(GameAction::Right)?,
-        };
+    let playback = Playback::open(path, speed)?;
+    let mut terminal = ratatui::init();
+    let result = replay_loop(playback, &mut terminal, labels, theme);
+    ratatui::restore();
+    result
+}
 
-        if outcome.changed || outcome.game_over {
-            terminal.draw(|frame| render(&outcome, frame))?;
+// Starts the new game asked for on the command line, of its variant and
+// size and with its seed if given.
+fn new_game(args: &PlayArgs) -> Result<Game> {
+    let variant = args.variant.unwrap_or_default();
+    if args.wrap && variant.rule().slide() != Slide::ToWall {
+        bail!("The lines of the {variant} variant do not wrap around");
+    }
+    let shape = args.size.unwrap_or_default().with_wrap(args.wrap);
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut game = Game::with_shape(variant, shape, seed);
+    game.set_adversarial(args.evil);
+    game.set_power_ups(args.power_ups);
+    if args.combo {
+        game.set_scoring(Scoring::Combo);
+    }
+    game.set_difficulty(args.difficulty.unwrap_or_default());
+    Ok(game)
+}
+
+// Reads a puzzle, or a pack of them, from a JSON file.
+fn load_puzzles(path: &Path) -> Result<Vec<Puzzle>> {
+    let json = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Puzzle::parse(&json)
+        .with_context(|| format!("Invalid puzzles in {}", path.display()))
+}
+
+// Sets the winning tile given on the command line, which must be one the
+// game's variant can reach.
+fn set_target(game: &mut Game, target: Option<u32>) -> Result<()> {
+    if let Some(target) = target {
+        if !game.variant().is_target(target) {
+            bail!("{target} is not a tile of the {} variant", game.variant());
         }
+        game.set_win_target(target);
     }
     Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+async fn play(profile: Profile, args: PlayArgs) -> Result<()> {
+    // A move log is checked and played back instead of a game being played.
+    if let Some(path) = &args.play {
+        pull_replay(&profile, path)?;
+        let settings = Settings::load(&profile)?;
+        let speed = args.autoplay_speed.unwrap_or(DEFAULT_SPEED);
+        let labels = args.labels.unwrap_or(settings.labels);
+        let theme = args.theme.unwrap_or(settings.theme).theme();
+        let theme = theme.with_markers(args.markers || settings.markers);
+        return replay(path, speed, labels, theme);
+    }
+    // Headless games are driven by another program and leave the profile
+    // alone.
+    if args.headless {
+        let mut game = match (&args.import, args.seed) {
+            _ if let Some(start) = &args.start => {
+                Game::from_board(import::start_position(start)?, 0)
+            }
+            (Some(path), _) => {
+                let import = import::import_file(path)?;
+                match import.board {
+                    Some(board) => Game::from_board(board, import.score),
+                    None => Game::new(),
+                }
+            }
+            (None, _) => new_game(&args)?,
+        };
+        set_target(&mut game, args.target)?;
+        game.set_adversarial(args.evil);
+        return headless::run(game, stdin().lock(), stdout().lock());
+    }
+
+    let settings = Settings::load(&profile)?;
+    let mut sync = settings.sync.clone().map(Remote::new);
+    let signer = Signer::load()?;
+    let (mut history, set_aside) = load_history(&profile, &signer, &mut sync)?;
+    pull_save(&profile, &signer, &mut sync);
+    let mut warnings: Vec<_> = set_aside.iter().map(SetAside::notice).collect();
+
+    let practice = args
+        .start
+        .as_deref()
+        .map(import::start_position)
+        .transpose()?;
+    let mut elapsed = Duration::ZERO;
+    // Launching without choosing a game shows the title screen, which offers
+    // to continue the saved game if there is one.
+    let mut title = None;
+    let mut first_run = false;
+    let puzzles = match &args.puzzle {
+        Some(Some(path)) => Some(load_puzzles(path)?),
+        Some(None) => Some(Puzzle::starter_pack()),
+        None => None,
+    };
+    let mut game = match &args.import {
+        None if let Some(board) = practice => Game::from_board(board, 0),
+        None if let Some(puzzles) = &puzzles => puzzles[0].start(),
+        Some(path) => {
+            let import = import::import_file(path)?;
+            if let Some(best_score) = import.best_score
+                && history.import_best(best_score)
+            {
+                history.save()?;
+            }
+            match import.board {
+                Some(board) => Game::from_board(board, import.score),
+                None => Game::new(),
+            }
+        }
+        None if args.seed.is_some()
+            || args.variant.is_some()
+            || args.size.is_some()
+            || args.wrap
+            || args.evil
+            || args.power_ups
+            || args.combo
+            || args.difficulty.is_some()
+            || args.tutorial =>
+        {
+            new_game(&args)?
+        }
+        None if args.new => {
+            let (_, set_aside) = SavedGame::take(&profile, &signer)?;
+            warnings.extend(set_aside.as_ref().map(SetAside::notice));
+            Game::new()
+        }
+        None => {
+            let (save, set_aside) = SavedGame::take(&profile, &signer)?;
+            warnings.extend(set_aside.as_ref().map(SetAside::notice));
+            title = Some(TitleMenu::new(save.as_ref().map(|s| s.game.score())));
+            // Nothing saved or played yet, the game is new to the player.
+            first_run = save.is_none()
+                && history.summary().is_none()
+                && !tutorial::is_done();
+            match save {
+                Some(save) => {
+                    elapsed = Duration::from_secs(save.duration_secs);
+                    save.game
+                }
+                None => Game::new(),
+            }
+        }
+    };
+    // A resumed game keeps the target it was started with unless another
+    // is given.
+    set_target(&mut game, args.target)?;
+
+    let labels = args.labels.unwrap_or(settings.labels);
+    let layout = args.layout.unwrap_or(settings.layout);
+    let keymap = Keymap::new(layout, settings.keymap);
+    let auto_restart = args
+        .auto_restart
+        .or(settings.auto_restart_secs)
+        .map(Duration::from_secs);
+    let idle_timeout = args
+        .idle_timeout
+        .or(settings.idle_timeout_mins)
+        .map(|mins| Duration::from_secs(mins * 60));
+    let autoplay_speed = args
+        .autoplay_speed
+        .or(settings.autoplay_speed)
+        .unwrap_or(DEFAULT_AUTOPLAY_SPEED);
+    let autoplay_interval = Duration::try_from_secs_f64(1.0 / autoplay_speed)
+        .with_context(|| {
+        format!("Invalid autoplay speed {autoplay_speed}")
+    })?;
+    let search_limits = SearchLimits {
+        depth: args.ai_depth.or(settings.ai_depth).unwrap_or(DEFAULT_DEPTH),
+        time_budget: args
+            .ai_time_budget
+            .or(settings.ai_time_budget_ms)
+            .map(Duration::from_millis),
+        min_probability: args
+            .ai_prune
+            .or(settings.ai_prune)
+            .unwrap_or(DEFAULT_MIN_PROBABILITY),
+    };
+    let mut report_settings = vec![
+        ("Profile", profile.name().to_string()),
+        ("Labels", format!("{labels:?}")),
+        ("Layout", format!("{layout:?}")),
+        ("Restart", format!("{:?}", settings.restart)),
+    ];
+    if let Some(mins) = args.blitz {
+        report_settings.push(("Blitz", format!("{mins} min")));
+    }
+    if let Some(secs) = args.garbage {
+        report_settings.push(("Garbage", format!("every {secs} s")));
+    }
+    match args.strategy {
+        Some(StrategyKind::MonteCarlo) => report_settings.push((
+            "Strategy",
+            format!(
+                "montecarlo, {} rollouts",
+                args.rollouts.unwrap_or(DEFAULT_ROLLOUTS)
+            ),
+        )),
+        Some(kind) => report_settings.push(("Strategy", kind.to_string())),
+        None => {}
+    }
+    if let Some(challenge) = args.challenge {
+        report_settings.push(("Challenge", format!("{challenge:?}")));
+    }
+    if let Some(board) = practice {
+        report_settings.push(("Start", board.to_code()));
+    }
+    if !game.variant().is_classic() {
+        report_settings.push(("Variant", game.variant().to_string()));
+    }
+    if !game.board().shape().is_standard() {
+        report_settings.push(("Size", game.board().shape().to_string()));
+    }
+    if game.board().wraps() {
+        report_settings.push(("Wrap", "on".to_string()));
+    }
+    if game.is_adversarial() {
+        report_settings.push(("Spawns", "adversarial".to_string()));
+    }
+    if game.has_power_ups() {
+        report_settings.push(("Power-ups", "on".to_string()));
+    }
+    if game.scoring() == Scoring::Combo {
+        report_settings.push(("Scoring", "combo".to_string()));
+    }
+    if !game.difficulty().is_normal() {
+        report_settings.push(("Difficulty", game.difficulty().to_string()));
+    }
+    #[cfg(feature = "bot")]
+    if let Some(path) = &args.bot {
+        report_settings.push(("Bot", path.display().to_string()));
+    }
+    if game.win_target() != game.rule().win_tile() {
+        report_settings.push(("Target", game.win_target().to_string()));
+    }
+    let recorder = args.record.as_deref().map(Recorder::create).transpose()?;
+    // A bot plays from the start.
+    #[cfg(feature = "bot")]
+    let bot = args.bot.as_deref().map(bot::Bot::load).transpose()?;
+    #[cfg(feature = "bot")]
+    let autoplay = args.autoplay || bot.is_some();
+    #[cfg(not(feature = "bot"))]
+    let autoplay = args.autoplay;
+    // The first launch teaches the game before it starts, in place of the
+    // title screen, unless the AI plays it or a clock would cut it short.
+    let first_run = first_run
+        && !autoplay
+        && args.blitz.is_none()
+        && args.garbage.is_none();
+    let tutorial = (args.tutorial || first_run).then(Tutorial::default);
+    if let Some(tutorial) = &tutorial {
+        game = tutorial.start();
+    }
+    let broadcaster = match args.broadcast {
+        Some(addr) => {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .with_context(|| format!("Failed to listen on {addr}"))?;
+            let frame = ReplayFrame::new(&game, None);
+            Some(Broadcaster::serve(listener, &frame)?)
+        }
+        None => None,
+    };
+    let options = Options {
+        #[cfg(feature = "bot")]
+        bot,
+        recorder,
+        broadcaster,
+        report_settings,
+        profile: (profile.name() != DEFAULT_PROFILE)
+            .then(|| profile.name().to_string()),
+        labels,
+        theme: args.theme.unwrap_or(settings.theme),
+        markers: args.markers || settings.markers,
+        sound: Player::new(
+            settings.volume.unwrap_or(DEFAULT_VOLUME),
+            settings.muted,
+        ),
+        invalid_move_bell: settings.invalid_move_bell,
+        invalid_move_flash: settings.invalid_move_flash,
+        keymap,
+        restart_mode: settings.restart,
+        confirm_quit: !(args.no_confirm || settings.no_confirm),
+        webhook: settings.webhook,
+        leaderboard: settings.leaderboard,
+        auto_restart,
+        idle_timeout,
+        undo_depth: settings.undo_depth.unwrap_or(DEFAULT_UNDO_DEPTH),
+        autoplay,
+        autoplay_interval,
+        strategy: args.strategy.unwrap_or_default().build(
+            rand::random(),
+            search_limits,
+            args.rollouts.unwrap_or(DEFAULT_ROLLOUTS),
+        ),
+        search_limits,
+        time_limit: args.blitz.map(|mins| Duration::from_secs(mins * 60)),
+        garbage_interval: args.garbage.map(Duration::from_secs),
+        controls: Controls::new(args.challenge),
+        practice,
+        puzzles,
+        position_file: args.position,
+        slots: Slots::load(&profile, signer.clone())?,
+        autosave: Autosave::new(profile.clone(), signer.clone()),
+        warnings,
+    };
+    let mut app = App::new(game, history, options);
+    app.clock = Stopwatch::resume_from(elapsed);
+    app.schedule_garbage();
+    app.show_debug = args.debug;
+    app.cast = args
+        .record_cast
+        .as_deref()
+        .map(CastRecorder::create)
+        .transpose()?;
+    let tutorial_started = tutorial.is_some();
+    app.tutorial = tutorial;
+    if let Some(menu) = title.filter(|_| !autoplay && !tutorial_started) {
+        app.screen = Screen::Title(menu);
+        app.clock.pause();
+    }
+    app.record(None);
+    metrics::set_score(app.outcome.score);
+
+    #[cfg(feature = "metrics")]
+    if let Some(addr) = args.metrics {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        tokio::spawn(metrics::serve(listener));
+    }
+
+    #[cfg(unix)]
+    let agents = args.control.as_deref().map(control::bind).transpose()?;
+    let tools = args.pipe.as_deref().map(pipe::bind).transpose()?;
+
     let terminal = ratatui::init();
+    // Focus reporting and bracketed paste are best effort; without them the
+    // game never pauses and pastes arrive as keystrokes.
+    let _ = execute!(stdout(), EnableFocusChange, EnableBracketedPaste);
     let (tx, rx): (Sender<Event>, Receiver<Event>) = channel(BUFSIZE);
-    spawn_blocking(move || input_loop(tx));
-    let result = event_loop(rx, terminal).await;
+    spawn_blocking({
+        let tx = tx.clone();
+        move || input_loop(tx)
+    });
+    #[cfg(feature = "gamepad")]
+    spawn_blocking({
+        let tx = tx.clone();
+        let keymap = app.keymap.clone();
+        move || gamepad::input_loop(tx, keymap)
+    });
+    #[cfg(unix)]
+    if let Some(listener) = agents {
+        tokio::spawn(control::serve(listener, tx.clone()));
+    }
+    if let Some(endpoint) = tools {
+        app.pipe = Some(Pipe::serve(endpoint, &app.game, tx.clone()));
+    }
+    let result = event_loop(rx, tx, terminal, &mut app).await;
+    let _ = execute!(stdout(), DisableFocusChange, DisableBracketedPaste);
     ratatui::restore();
+    #[cfg(unix)]
+    if let Some(path) = &args.control {
+        let _ = fs::remove_file(path);
+    }
+    #[cfg(unix)]
+    if let Some(path) = &args.pipe {
+        pipe::remove(path);
+    }
+
+    // The save is brought up to date on quit, so an unfinished game is
+    // resumed on the next launch, unless it is practice.
+    if result.is_ok() && !app.is_practice() {
+        let idle = app.is_idle();
+        let mut played = app.clock.elapsed();
+        if idle {
+            // The idle period itself is not counted as play time.
+            played =
+                played.saturating_sub(app.idle_timeout.unwrap_or_default());
+        }
+        app.autosave.write(&app.game, played)?;
+        if let Some(remote) = &sync
+            && let Err(e) = SavedGame::push(&profile, remote, &signer)
+        {
+            eprintln!("Failed to sync the save: {e:#}");
+        }
+        if idle {
+            println!("Saved the game after a period without input.");
+        }
+    } else if let Some(e) = &app.autosave_error {
+        eprintln!("Failed to autosave the game: {e:#}");
+    }
+
+    if let Some(e) = &app.replay_error {
+        eprintln!("Failed to record the replay: {e:#}");
+    } else if let Some(remote) = &sync
+        && let Some(path) = &args.record
+        && let Err(e) = replay::push(path, remote, &signer)
+    {
+        eprintln!("Failed to sync the replay: {e:#}");
+    }
+    let deadline = tokio::time::Instant::now() + WEBHOOK_GRACE;
+    for notification in app.notifications.drain(..) {
+        match timeout_at(deadline, notification).await {
+            Ok(Ok(Err(e))) => eprintln!("Failed to notify the webhook: {e:#}"),
+            Ok(_) => {}
+            Err(_) => eprintln!("Gave up waiting to notify the webhook"),
+        }
+    }
+    for summary in &app.unshared {
+        println!("{summary}\n");
+    }
+    if let Some(cast) = app.cast.take()
+        && let Err(e) = cast.finish()
+    {
+        app.cast_error = Some(e);
+    }
+    if let Some(e) = &app.cast_error {
+        eprintln!("Failed to record the cast: {e:#}");
+    }
+    if let Some(remote) = &sync
+        && let Err(e) = app.history.push(remote, &signer)
+    {
+        eprintln!("Failed to sync history: {e:#}");
+    }
     result
 }
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let profile = Profile::new(&cli.profile)?;
+
+    match cli.command {
+        None => play(profile, cli.play).await,
+        Some(Commands::Play(args)) => play(profile, *args).await,
+        // The daily game is the classic one, dealt from the day's seed.
+        Some(Commands::Daily) => {
+            let seed = Some(daily_seed());
+            play(profile, PlayArgs { seed, ..cli.play }).await
+        }
+        #[cfg(feature = "gui")]
+        Some(Commands::Gui) => gui::run(profile),
+        Some(Commands::Stats) => {
+            let settings = Settings::load(&profile)?;
+            let mut sync = settings.sync.map(Remote::new);
+            let signer = Signer::load()?;
+            let (history, set_aside) =
+                load_history(&profile, &signer, &mut sync)?;
+            if let Some(set_aside) = set_aside {
+                eprintln!("{set_aside}");
+            }
+            print_stats(&history);
+            Ok(())
+        }
+        Some(Commands::Profiles) => print_profiles(&profile),
+        Some(Commands::Solve { board, depth }) => {
+            print_solution(&board, depth);
+            Ok(())
+        }
+        Some(Commands::Bench { seconds, depth }) => print_bench(seconds, depth),
+        Some(Commands::Simulate {
+            games,
+            strategy,
+            depth,
+            rollouts,
+            variant,
+            seed,
+            format,
+        }) => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let results =
+                simulate::run(strategy, variant, games, seed, depth, rollouts)?;
+            print_simulation(&results, format);
+            Ok(())
+        }
+        Some(Commands::Watch { file }) => {
+            let settings = Settings::load(&profile)?;
+            let theme = settings.theme.theme().with_markers(settings.markers);
+            watch(&file, settings.labels, theme)
+        }
+        Some(Commands::Replay { file, speed }) => {
+            pull_replay(&profile, &file)?;
+            let settings = Settings::load(&profile)?;
+            let theme = settings.theme.theme().with_markers(settings.markers);
+            replay(&file, speed, settings.labels, theme)
+        }
+        Some(Commands::Verify { file }) => verify_notation(&file),
+        Some(Commands::Completions { shell }) => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut stdout());
+            Ok(())
+        }
+    }
+}
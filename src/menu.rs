@@ -0,0 +1,293 @@
+use ratatui::crossterm::event::KeyCode;
+
+use crate::keymap::Key;
+
+// Entries of the pause menu, in the order they are listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuItem {
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 4] = [
+        MenuItem::Resume,
+        MenuItem::Restart,
+        MenuItem::Settings,
+        MenuItem::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MenuItem::Resume => "Resume",
+            MenuItem::Restart => "Restart",
+            MenuItem::Settings => "Settings",
+            MenuItem::Quit => "Quit",
+        }
+    }
+}
+
+// What the game does when the pause menu closes. The settings entry is left
+// out since the menu shows the settings itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseAction {
+    Resume,
+    Restart,
+    Quit,
+}
+
+// The menu shown while the game is paused with Esc.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseMenu {
+    // Listing the entries, with the one at this index selected.
+    Choosing(usize),
+    // Showing the settings in use, until a key goes back to the entries.
+    Settings,
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        PauseMenu::Choosing(0)
+    }
+}
+
+impl PauseMenu {
+    // Handles a key press: the arrows move the selection and Enter picks it,
+    // while Esc resumes the game. Returns what the game should do once the
+    // menu closes; the settings are shown by the menu itself.
+    pub fn press(&mut self, key: Key) -> Option<PauseAction> {
+        let PauseMenu::Choosing(selected) = *self else {
+            *self = PauseMenu::Choosing(Self::index(MenuItem::Settings));
+            return None;
+        };
+
+        if key == Key::plain(KeyCode::Up) {
+            *self = PauseMenu::Choosing(selected.saturating_sub(1));
+        } else if key == Key::plain(KeyCode::Down) {
+            *self = PauseMenu::Choosing(
+                (selected + 1).min(MenuItem::ALL.len() - 1),
+            );
+        } else if key == Key::plain(KeyCode::Esc) {
+            return Some(PauseAction::Resume);
+        } else if key == Key::plain(KeyCode::Enter) {
+            return match MenuItem::ALL[selected] {
+                MenuItem::Resume => Some(PauseAction::Resume),
+                MenuItem::Restart => Some(PauseAction::Restart),
+                MenuItem::Settings => {
+                    *self = PauseMenu::Settings;
+                    None
+                }
+                MenuItem::Quit => Some(PauseAction::Quit),
+            };
+        }
+        None
+    }
+
+    fn index(item: MenuItem) -> usize {
+        MenuItem::ALL
+            .iter()
+            .position(|&i| i == item)
+            .unwrap_or_default()
+    }
+}
+
+// Entries of the title screen shown on launch, in the order they are listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TitleItem {
+    NewGame,
+    Continue,
+    Daily,
+    Settings,
+    Leaderboard,
+    Statistics,
+    Quit,
+}
+
+impl TitleItem {
+    pub const ALL: [TitleItem; 7] = [
+        TitleItem::NewGame,
+        TitleItem::Continue,
+        TitleItem::Daily,
+        TitleItem::Settings,
+        TitleItem::Leaderboard,
+        TitleItem::Statistics,
+        TitleItem::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TitleItem::NewGame => "New Game",
+            TitleItem::Continue => "Continue",
+            TitleItem::Daily => "Daily",
+            TitleItem::Settings => "Settings",
+            TitleItem::Leaderboard => "Leaderboard",
+            TitleItem::Statistics => "Statistics",
+            TitleItem::Quit => "Quit",
+        }
+    }
+}
+
+// The menu of the title screen. Continue is only listed when there is a
+// saved game to resume, and is then selected first, after a prompt asks
+// whether to resume it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TitleMenu {
+    items: Vec<TitleItem>,
+    selected: usize,
+    // Whether the settings in use are shown instead of the entries.
+    showing_settings: bool,
+    // Score of the saved game while the prompt to resume it is open.
+    resume_prompt: Option<u32>,
+}
+
+impl TitleMenu {
+    // Opens the menu, given the score of the saved game if there is one.
+    pub fn new(saved_score: Option<u32>) -> Self {
+        let items: Vec<TitleItem> = TitleItem::ALL
+            .into_iter()
+            .filter(|&item| {
+                saved_score.is_some() || item != TitleItem::Continue
+            })
+            .collect();
+        let selected = items
+            .iter()
+            .position(|&item| item == TitleItem::Continue)
+            .unwrap_or_default();
+        Self {
+            items,
+            selected,
+            showing_settings: false,
+            resume_prompt: saved_score,
+        }
+    }
+
+    pub fn items(&self) -> &[TitleItem] {
+        &self.items
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn is_showing_settings(&self) -> bool {
+        self.showing_settings
+    }
+
+    pub fn resume_prompt(&self) -> Option<u32> {
+        self.resume_prompt
+    }
+
+    // Handles a key press: the arrows move the selection and Enter picks it,
+    // while any key goes back from the settings to the entries. The resume
+    // prompt is answered with y or Enter to continue, n for a new game or
+    // Esc for the menu. Returns the entry the game should act on; the
+    // settings are shown by the menu itself.
+    pub fn press(&mut self, key: Key) -> Option<TitleItem> {
+        if self.resume_prompt.is_some() {
+            let item =
+                if key == Key::char('y') || key == Key::plain(KeyCode::Enter) {
+                    Some(TitleItem::Continue)
+                } else if key == Key::char('n') {
+                    Some(TitleItem::NewGame)
+                } else if key == Key::plain(KeyCode::Esc) {
+                    None
+                } else {
+                    return None;
+                };
+            self.resume_prompt = None;
+            return item;
+        }
+
+        if self.showing_settings {
+            self.showing_settings = false;
+            return None;
+        }
+
+        if key == Key::plain(KeyCode::Up) {
+            self.selected = self.selected.saturating_sub(1);
+        } else if key == Key::plain(KeyCode::Down) {
+            self.selected = (self.selected + 1).min(self.items.len() - 1);
+        } else if key == Key::plain(KeyCode::Enter) {
+            match self.items[self.selected] {
+                TitleItem::Settings => self.showing_settings = true,
+                item => return Some(item),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrows_move_the_selection_within_the_menu() {
+        let mut menu = PauseMenu::default();
+        assert_eq!(menu.press(Key::plain(KeyCode::Up)), None);
+        assert_eq!(menu, PauseMenu::Choosing(0));
+
+        for _ in 0..MenuItem::ALL.len() {
+            menu.press(Key::plain(KeyCode::Down));
+        }
+        assert_eq!(menu, PauseMenu::Choosing(MenuItem::ALL.len() - 1));
+        assert_eq!(
+            menu.press(Key::plain(KeyCode::Enter)),
+            Some(PauseAction::Quit)
+        );
+    }
+
+    #[test]
+    fn settings_are_shown_until_a_key_is_pressed() {
+        let mut menu = PauseMenu::Choosing(2);
+        assert_eq!(menu.press(Key::plain(KeyCode::Enter)), None);
+        assert_eq!(menu, PauseMenu::Settings);
+
+        assert_eq!(menu.press(Key::plain(KeyCode::Esc)), None);
+        assert_eq!(menu, PauseMenu::Choosing(2));
+        assert_eq!(
+            menu.press(Key::plain(KeyCode::Esc)),
+            Some(PauseAction::Resume)
+        );
+    }
+
+    #[test]
+    fn title_menu_offers_to_continue_a_saved_game() {
+        let mut menu = TitleMenu::new(Some(1234));
+        assert_eq!(menu.resume_prompt(), Some(1234));
+        assert_eq!(menu.press(Key::char('x')), None);
+        assert_eq!(menu.press(Key::plain(KeyCode::Esc)), None);
+        assert_eq!(menu.resume_prompt(), None);
+        assert_eq!(menu.items()[menu.selected()], TitleItem::Continue);
+        assert_eq!(
+            menu.press(Key::plain(KeyCode::Enter)),
+            Some(TitleItem::Continue)
+        );
+
+        let mut menu = TitleMenu::new(Some(1234));
+        assert_eq!(menu.press(Key::char('n')), Some(TitleItem::NewGame));
+
+        let mut menu = TitleMenu::new(None);
+        assert!(!menu.items().contains(&TitleItem::Continue));
+        menu.press(Key::plain(KeyCode::Down));
+        assert_eq!(
+            menu.press(Key::plain(KeyCode::Enter)),
+            Some(TitleItem::Daily)
+        );
+    }
+
+    #[test]
+    fn title_menu_shows_settings_until_a_key_is_pressed() {
+        let mut menu = TitleMenu::new(None);
+        for _ in 0..2 {
+            menu.press(Key::plain(KeyCode::Down));
+        }
+        assert_eq!(menu.press(Key::plain(KeyCode::Enter)), None);
+        assert!(menu.is_showing_settings());
+        assert_eq!(menu.press(Key::plain(KeyCode::Esc)), None);
+        assert!(!menu.is_showing_settings());
+        assert_eq!(menu.items()[menu.selected()], TitleItem::Settings);
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[cfg(feature = "metrics")]
+use anyhow::Result;
+#[cfg(feature = "metrics")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "metrics")]
+use tokio::net::TcpListener;
+
+// Usage counters. They are cheap enough to always update; the endpoint that
+// exposes them is only built with the `metrics` feature.
+static GAMES_PLAYED: AtomicU64 = AtomicU64::new(0);
+static MOVES: AtomicU64 = AtomicU64::new(0);
+static SCORE: AtomicU64 = AtomicU64::new(0);
+static RENDERS: AtomicU64 = AtomicU64::new(0);
+static RENDER_MICROS: AtomicU64 = AtomicU64::new(0);
+
+pub fn game_finished() {
+    GAMES_PLAYED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn moved(score: u32) {
+    MOVES.fetch_add(1, Ordering::Relaxed);
+    SCORE.store(score.into(), Ordering::Relaxed);
+}
+
+pub fn set_score(score: u32) {
+    SCORE.store(score.into(), Ordering::Relaxed);
+}
+
+pub fn rendered(latency: Duration) {
+    RENDERS.fetch_add(1, Ordering::Relaxed);
+    RENDER_MICROS.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+}
+
+// Formats the counters in the Prometheus text exposition format.
+#[cfg(feature = "metrics")]
+fn exposition() -> String {
+    let render_seconds = RENDER_MICROS.load(Ordering::Relaxed) as f64 / 1e6;
+    format!(
+        "# HELP game_2048_games_played_total Games that reached game over.\n\
+         # TYPE game_2048_games_played_total counter\n\
+         game_2048_games_played_total {}\n\
+         # HELP game_2048_moves_total Moves that changed the board.\n\
+         # TYPE game_2048_moves_total counter\n\
+         game_2048_moves_total {}\n\
+         # HELP game_2048_score Score of the current game.\n\
+         # TYPE game_2048_score gauge\n\
+         game_2048_score {}\n\
+         # HELP game_2048_render_seconds Time spent drawing frames.\n\
+         # TYPE game_2048_render_seconds summary\n\
+         game_2048_render_seconds_sum {}\n\
+         game_2048_render_seconds_count {}\n",
+        GAMES_PLAYED.load(Ordering::Relaxed),
+        MOVES.load(Ordering::Relaxed),
+        SCORE.load(Ordering::Relaxed),
+        render_seconds,
+        RENDERS.load(Ordering::Relaxed),
+    )
+}
+
+// Serves `/metrics` over plain HTTP until the listener fails.
+#[cfg(feature = "metrics")]
+pub async fn serve(listener: TcpListener) -> Result<()> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            let mut request = [0; 1024];
+            let Ok(len) = stream.read(&mut request).await else {
+                return;
+            };
+
+            let request = String::from_utf8_lossy(&request[..len]);
+            let path = request.split_whitespace().nth(1).unwrap_or_default();
+            let response = if path == "/metrics" {
+                let body = exposition();
+                format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: text/plain; version=0.0.4\r\n\
+                     Content-Length: {}\r\n\
+                     Connection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\
+                 Connection: close\r\n\r\n"
+                    .to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+
+    use tokio::net::TcpStream;
+
+    async fn get(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!("GET {path} HTTP/1.1\r\nHost: test\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn exposition_reports_recorded_values() {
+        moved(128);
+        rendered(Duration::from_millis(2));
+
+        let text = exposition();
+
+        assert!(text.contains("# TYPE game_2048_moves_total counter"));
+        assert!(text.contains("game_2048_score 128"));
+        assert!(text.contains("game_2048_render_seconds_count"));
+    }
+
+    #[tokio::test]
+    async fn serve_answers_metrics_and_rejects_other_paths() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener));
+
+        assert!(get(addr, "/metrics").await.starts_with("HTTP/1.1 200 OK"));
+        assert!(get(addr, "/").await.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Error, Result, bail};
+
+use crate::board::Board;
+use crate::game::{Game, GameAction, TileSpawn};
+use crate::rules::{Difficulty, Scoring};
+
+// Moves written on each line of the notation.
+const MOVES_PER_LINE: usize = 10;
+
+/// A game written in move notation, a plain text record of a game from its
+/// first move that people can read and other tools can replay. A header of
+/// tags in brackets gives the seed the tiles are drawn from, how many were
+/// drawn before the first move, the board code of the starting position and
+/// any setting that changes the tiles or the score, then the final score and
+/// best tile. The moves follow, each as its letter and the tile it spawned:
+/// the tile's value, its column as a letter from `a` and its row as a number
+/// from 1, counted from the top-left corner.
+///
+/// ```
+/// use rust_2048::GameAction;
+/// use rust_2048::notation::Notation;
+///
+/// let text = r#"
+/// [Seed "7"]
+/// [Start "1100000000000000"]
+///
+/// L2b3 R
+/// "#;
+/// let notation: Notation = text.parse()?;
+/// assert_eq!(notation.moves[1], (GameAction::Right, None));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// Unknown tags are ignored, so other tools can add their own. A move may
+/// leave out its spawn, which is then not checked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notation {
+    /// The seed the tiles are drawn from, and how many were drawn from it
+    /// before the first move.
+    pub seed: u64,
+    pub spawns: u64,
+    /// The position before the first move.
+    pub start: Board,
+    pub start_score: u32,
+    pub difficulty: Difficulty,
+    pub scoring: Scoring,
+    /// Whether tiles are placed by the adversarial spawner rather than drawn
+    /// from the seed.
+    pub adversarial: bool,
+    /// Each move, with the tile it spawned if given.
+    pub moves: Vec<(GameAction, Option<TileSpawn>)>,
+    /// The final score and best tile, if given.
+    pub score: Option<u32>,
+    pub max_tile: Option<u32>,
+}
+
+impl Notation {
+    /// Writes down a game from its first move. Fails if the game does not
+    /// follow from its moves alone: if it was resumed from a save that did
+    /// not keep its first positions, or if tiles were dropped or changed
+    /// without a move.
+    pub fn from_game(game: &Game) -> Result<Notation> {
+        let positions = game.positions();
+        if positions.len() < game.moves().len() {
+            bail!("The first positions of the game were not kept");
+        }
+        let (start, start_score) = positions
+            .first()
+            .map_or((*game.board(), game.score()), |first| {
+                (first.board, first.score)
+            });
+        // Each move draws a tile from the seed, unless the adversarial
+        // spawner places them.
+        let drawn = if game.is_adversarial() {
+            0
+        } else {
+            game.moves().len() as u64
+        };
+        let mut notation = Notation {
+            seed: game.seed(),
+            spawns: game.spawns().saturating_sub(drawn),
+            start,
+            start_score,
+            difficulty: game.difficulty(),
+            scoring: game.scoring(),
+            adversarial: game.is_adversarial(),
+            moves: game.moves().iter().map(|m| (m.action, None)).collect(),
+            score: Some(game.score()),
+            max_tile: Some(game.max_tile()),
+        };
+
+        // Replaying the moves finds the tile each spawned, and checks that
+        // they lead to the game's position.
+        let (replayed, spawns) = notation
+            .play()
+            .context("The game does not follow from its moves alone")?;
+        if replayed.board() != game.board() {
+            bail!(
+                "The game does not follow from its moves alone: tiles were \
+                 dropped or changed without a move"
+            );
+        }
+        for ((_, spawn), replayed) in notation.moves.iter_mut().zip(spawns) {
+            *spawn = Some(replayed);
+        }
+        Ok(notation)
+    }
+
+    /// Replays the game from its first position. Fails at the first move
+    /// that changes nothing or spawns another tile than the one given, or if
+    /// the game ends at another score or best tile than given.
+    pub fn replay(&self) -> Result<Game> {
+        Ok(self.play()?.0)
+    }
+
+    // Replays the game, returning it and the tile each move spawned.
+    fn play(&self) -> Result<(Game, Vec<TileSpawn>)> {
+        let mut game = Game::from_position(
+            self.start,
+            self.start_score,
+            self.seed,
+            self.spawns,
+        );
+        game.set_difficulty(self.difficulty);
+        game.set_scoring(self.scoring);
+        game.set_adversarial(self.adversarial);
+
+        let mut spawns = Vec::with_capacity(self.moves.len());
+        for (number, &(action, expected)) in (1..).zip(&self.moves) {
+            let letter = action.letter();
+            if !game.apply_move(action)?.changed {
+                bail!("Move {number} ({letter}) changes nothing");
+            }
+            let spawn = game
+                .last_spawn()
+                .with_context(|| format!("Move {number} spawned no tile"))?;
+            if let Some(expected) = expected
+                && expected != spawn
+            {
+                bail!(
+                    "Move {number} ({letter}) spawned {}, not {}",
+                    SpawnCode(spawn),
+                    SpawnCode(expected)
+                );
+            }
+            spawns.push(spawn);
+        }
+
+        if let Some(score) = self.score
+            && game.score() != score
+        {
+            bail!("The moves score {}, not {score}", game.score());
+        }
+        if let Some(tile) = self.max_tile
+            && game.max_tile() != tile
+        {
+            bail!("The moves reach {}, not {tile}", game.max_tile());
+        }
+        Ok((game, spawns))
+    }
+}
+
+// A spawned tile as written after a move, e.g. "2a3" for a 2 in the first
+// column of the third row.
+struct SpawnCode(TileSpawn);
+
+impl fmt::Display for SpawnCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let TileSpawn { row, col, tile } = self.0;
+        let col = char::from(b'a' + col as u8);
+        write!(f, "{tile}{col}{}", row + 1)
+    }
+}
+
+impl FromStr for SpawnCode {
+    type Err = Error;
+
+    fn from_str(code: &str) -> Result<Self> {
+        let bad = || format!("Bad spawn '{code}', expected e.g. 2a3");
+        let at = code
+            .find(|c: char| c.is_ascii_lowercase())
+            .with_context(bad)?;
+        let (tile, cell) = code.split_at(at);
+        let mut cell = cell.chars();
+        let col = cell.next().with_context(bad)? as usize - 'a' as usize;
+        let row: usize = cell.as_str().parse().with_context(bad)?;
+        if row == 0 {
+            bail!(bad());
+        }
+        Ok(SpawnCode(TileSpawn {
+            row: row - 1,
+            col,
+            tile: tile.parse().with_context(bad)?,
+        }))
+    }
+}
+
+impl fmt::Display for Notation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Seed \"{}\"]", self.seed)?;
+        writeln!(f, "[Spawns \"{}\"]", self.spawns)?;
+        writeln!(f, "[Start \"{}\"]", self.start.to_code())?;
+        if self.start_score > 0 {
+            writeln!(f, "[StartScore \"{}\"]", self.start_score)?;
+        }
+        if !self.difficulty.is_normal() {
+            writeln!(f, "[Difficulty \"{}\"]", self.difficulty)?;
+        }
+        if self.scoring == Scoring::Combo {
+            writeln!(f, "[Scoring \"combo\"]")?;
+        }
+        if self.adversarial {
+            writeln!(f, "[Spawner \"adversarial\"]")?;
+        }
+        if let Some(score) = self.score {
+            writeln!(f, "[Score \"{score}\"]")?;
+        }
+        if let Some(tile) = self.max_tile {
+            writeln!(f, "[MaxTile \"{tile}\"]")?;
+        }
+        for line in self.moves.chunks(MOVES_PER_LINE) {
+            writeln!(f)?;
+            for (i, &(action, spawn)) in line.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", action.letter())?;
+                if let Some(spawn) = spawn {
+                    write!(f, "{}", SpawnCode(spawn))?;
+                }
+            }
+        }
+        writeln!(f)
+    }
+}
+
+impl FromStr for Notation {
+    type Err = Error;
+
+    // Reads the tags up to the first line that is not one, then the moves.
+    fn from_str(text: &str) -> Result<Self> {
+        let mut seed = None;
+        let mut start = None;
+        let mut notation = Notation {
+            seed: 0,
+            spawns: 0,
+            start: Board::default(),
+            start_score: 0,
+            difficulty: Difficulty::Normal,
+            scoring: Scoring::Classic,
+            adversarial: false,
+            moves: Vec::new(),
+            score: None,
+            max_tile: None,
+        };
+
+        let mut lines = text.lines().map(str::trim).peekable();
+        while let Some(line) =
+            lines.next_if(|line| line.is_empty() || line.starts_with('['))
+        {
+            if line.is_empty() {
+                continue;
+            }
+            let Some((name, value)) = line
+                .strip_prefix('[')
+                .and_then(|tag| tag.strip_suffix(']'))
+                .and_then(|tag| tag.split_once(' '))
+            else {
+                bail!("Bad tag '{line}', expected e.g. [Seed \"7\"]");
+            };
+            let value = value.trim().trim_matches('"');
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .with_context(|| format!("Bad number in tag '{line}'"))
+            };
+            match name {
+                "Seed" => seed = Some(number()?),
+                "Spawns" => notation.spawns = number()?,
+                "Start" => {
+                    let board: Board = value.parse().with_context(|| {
+                        format!("Bad board in tag '{line}'")
+                    })?;
+                    start = Some(board);
+                }
+                "StartScore" => notation.start_score = number()?.try_into()?,
+                "Difficulty" => notation.difficulty = value.parse()?,
+                "Scoring" => {
+                    notation.scoring = match value {
+                        "classic" => Scoring::Classic,
+                        "combo" => Scoring::Combo,
+                        _ => bail!("Unknown scoring '{value}'"),
+                    };
+                }
+                "Spawner" => notation.adversarial = value == "adversarial",
+                "Score" => notation.score = Some(number()?.try_into()?),
+                "MaxTile" => notation.max_tile = Some(number()?.try_into()?),
+                _ => {}
+            }
+        }
+        let (Some(seed), Some(start)) = (seed, start) else {
+            bail!("The notation needs a Seed and a Start tag");
+        };
+        notation.seed = seed;
+        notation.start = start;
+
+        for token in lines.flat_map(str::split_whitespace) {
+            let mut letters = token.chars();
+            let Some(action) = letters.next().and_then(GameAction::from_letter)
+            else {
+                bail!("Unknown move '{token}'");
+            };
+            let spawn = match letters.as_str() {
+                "" => None,
+                code => Some(code.parse::<SpawnCode>()?.0),
+            };
+            notation.moves.push((action, spawn));
+        }
+        Ok(notation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notation_replays_the_game_it_was_written_from() {
+        let mut game = Game::with_seed(42);
+        for action in GameAction::ALL.into_iter().cycle().take(40) {
+            game.apply_move(action).unwrap();
+        }
+
+        let text = Notation::from_game(&game).unwrap().to_string();
+        let replayed = text.parse::<Notation>().unwrap().replay().unwrap();
+
+        assert!(text.starts_with("[Seed \"42\"]\n[Spawns \"1\"]\n"));
+        assert_eq!(replayed.board(), game.board());
+        assert_eq!(replayed.score(), game.score());
+        assert_eq!(replayed.moves(), game.moves());
+    }
+
+    #[test]
+    fn replay_fails_at_the_first_move_that_diverges() {
+        let mut game = Game::with_seed(42);
+        game.apply_move(GameAction::Left).unwrap();
+        game.apply_move(GameAction::Up).unwrap();
+        let mut notation = Notation::from_game(&game).unwrap();
+        let spawn = notation.moves[1].1.as_mut().unwrap();
+        spawn.tile = if spawn.tile == 2 { 4 } else { 2 };
+
+        let error = notation.replay().unwrap_err().to_string();
+
+        assert!(error.starts_with("Move 2 (U) spawned"), "{error}");
+    }
+
+    #[test]
+    fn game_changed_without_a_move_cannot_be_written() {
+        let mut game = Game::with_seed(42);
+        game.apply_move(GameAction::Left).unwrap();
+        game.drop_garbage().unwrap();
+        game.apply_move(GameAction::Up).unwrap();
+
+        assert!(Notation::from_game(&game).is_err());
+    }
+}
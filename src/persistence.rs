@@ -0,0 +1,101 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+const APP_DIR: &str = "2048";
+
+// Returns the directory where the game stores its persistent state, or `None`
+// when the platform does not provide a data directory.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+// Reads and deserializes a JSON file. A missing file is not an error and
+// yields `None`.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to read {}", path.display()));
+        }
+    };
+
+    let value = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(Some(value))
+}
+
+// Serializes a value to JSON and writes it atomically by writing to a
+// temporary file in the same directory and renaming it over the target.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("Failed to create {}", parent.display())
+        })?;
+    }
+
+    let contents = serde_json::to_string_pretty(value)?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "rust-2048-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_json_returns_none_for_missing_file() {
+        let dir = test_dir("missing");
+        let value: Option<Vec<u32>> =
+            read_json(&dir.join("missing.json")).unwrap();
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn write_json_round_trips_and_creates_parent_dirs() {
+        let dir = test_dir("round-trip");
+        let path = dir.join("nested").join("values.json");
+
+        write_json(&path, &vec![2, 4, 8]).unwrap();
+        let value: Option<Vec<u32>> = read_json(&path).unwrap();
+
+        assert_eq!(value, Some(vec![2, 4, 8]));
+        assert!(!path.with_extension("tmp").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn read_json_reports_parse_errors() {
+        let dir = test_dir("corrupt");
+        let path = dir.join("corrupt.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "not json").unwrap();
+
+        let value: Result<Option<Vec<u32>>> = read_json(&path);
+
+        assert!(value.is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,344 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+#[cfg(unix)]
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(windows)]
+use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc::Sender;
+
+use crate::event::Event;
+use crate::game::Game;
+use crate::headless;
+
+// How often the game looks for a tool opening the pipe it writes to.
+#[cfg(unix)]
+const CONNECT_INTERVAL: Duration = Duration::from_millis(100);
+
+// The FIFOs a tool opens: it writes moves to `PATH.in` and reads the states
+// from `PATH.out`.
+#[cfg(unix)]
+pub struct Endpoint {
+    input: PathBuf,
+    output: PathBuf,
+}
+
+// The named pipe a tool opens, to write moves and read the states.
+#[cfg(windows)]
+pub struct Endpoint {
+    path: PathBuf,
+    server: NamedPipeServer,
+}
+
+// Creates the FIFOs at `PATH.in` and `PATH.out`, reusing any a previous game
+// left behind, before the game starts so a path that cannot be used is
+// reported up front.
+#[cfg(unix)]
+pub fn bind(path: &Path) -> Result<Endpoint> {
+    let input = fifo(path, "in")?;
+    let output = fifo(path, "out")?;
+    Ok(Endpoint { input, output })
+}
+
+// Creates the named pipe at `path`, e.g. `\\.\pipe\2048`, failing if another
+// game has it.
+#[cfg(windows)]
+pub fn bind(path: &Path) -> Result<Endpoint> {
+    let server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(path)
+        .with_context(|| {
+            format!("Failed to create the pipe {}", path.display())
+        })?;
+    Ok(Endpoint {
+        path: path.to_path_buf(),
+        server,
+    })
+}
+
+#[cfg(unix)]
+fn fifo(path: &Path, extension: &str) -> Result<PathBuf> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::FileTypeExt;
+
+    let mut fifo = path.as_os_str().to_owned();
+    fifo.push(".");
+    fifo.push(extension);
+    let fifo = PathBuf::from(fifo);
+    if std::fs::symlink_metadata(&fifo).is_ok_and(|m| m.file_type().is_fifo()) {
+        return Ok(fifo);
+    }
+    let name = CString::new(fifo.as_os_str().as_bytes())?;
+    // SAFETY: `name` is a valid C string that outlives the call.
+    if unsafe { libc::mkfifo(name.as_ptr(), 0o600) } != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| {
+            format!("Failed to create the pipe {}", fifo.display())
+        });
+    }
+    Ok(fifo)
+}
+
+// Removes the FIFOs once the game is over.
+#[cfg(unix)]
+pub fn remove(path: &Path) {
+    for extension in ["in", "out"] {
+        let mut fifo = path.as_os_str().to_owned();
+        fifo.push(".");
+        fifo.push(extension);
+        let _ = std::fs::remove_file(fifo);
+    }
+}
+
+// Lines kept for a tool that falls behind, beyond which it skips ahead.
+const BACKLOG: usize = 256;
+
+// Lets tools watch and play the game through a pipe, with the messages of
+// headless play: they write one move per line, and read the state as a JSON
+// line after every change, whether it came from them or from the keys
+// pressed.
+pub struct Pipe {
+    lines: broadcast::Sender<String>,
+    latest: Arc<Mutex<String>>,
+}
+
+impl Pipe {
+    // Serves tools on `endpoint`, starting from the state of `game`. Their
+    // lines are handed to the game as events, so moves are played in turn
+    // with the keys pressed.
+    pub fn serve(
+        endpoint: Endpoint,
+        game: &Game,
+        events: Sender<Event>,
+    ) -> Self {
+        let latest = Arc::new(Mutex::new(state(game, None)));
+        let (lines, receiver) = broadcast::channel(BACKLOG);
+        let feed = Feed {
+            latest: Arc::clone(&latest),
+            lines: receiver,
+        };
+        #[cfg(unix)]
+        {
+            tokio::spawn(read_lines(endpoint.input, events));
+            tokio::spawn(write_lines(endpoint.output, feed));
+        }
+        #[cfg(windows)]
+        tokio::spawn(accept(endpoint, events, feed));
+        Self { lines, latest }
+    }
+
+    // Sends the state of the game, with whether the move played changed the
+    // board if it follows a move.
+    pub fn send(&self, game: &Game, moved: Option<bool>) {
+        let line = state(game, moved);
+        let mut latest = self.latest.lock().unwrap();
+        latest.clone_from(&line);
+        // No tool may be connected, which the game need not know.
+        let _ = self.lines.send(line);
+    }
+
+    // Answers a line that could not be carried out.
+    pub fn send_error(&self, error: headless::Error) {
+        let _ = self.lines.send(
+            serde_json::to_string(&error).expect("an error always serializes"),
+        );
+    }
+}
+
+fn state(game: &Game, moved: Option<bool>) -> String {
+    serde_json::to_string(&headless::State::new(game, moved))
+        .expect("a state always serializes")
+}
+
+// The lines for a tool: the latest state when it connects, then every line
+// sent after it.
+struct Feed {
+    latest: Arc<Mutex<String>>,
+    lines: broadcast::Receiver<String>,
+}
+
+impl Feed {
+    // Starts a tool off from the latest state, skipping the lines sent
+    // before it connected.
+    fn connect(&mut self) -> String {
+        let latest = self.latest.lock().unwrap();
+        self.lines = self.lines.resubscribe();
+        latest.clone()
+    }
+
+    // The next line, or `None` once the game has ended.
+    async fn next(&mut self) -> Option<String> {
+        loop {
+            match self.lines.recv().await {
+                Ok(line) => return Some(line),
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+impl Clone for Feed {
+    fn clone(&self) -> Self {
+        Self {
+            latest: Arc::clone(&self.latest),
+            lines: self.lines.resubscribe(),
+        }
+    }
+}
+
+// Hands each line written to the input FIFO to the game, until the game
+// ends. The game holds a writing end of its own, so the FIFO does not end
+// when a tool closes it and the next tool can open it.
+#[cfg(unix)]
+async fn read_lines(path: PathBuf, events: Sender<Event>) -> Result<()> {
+    use tokio::net::unix::pipe::OpenOptions;
+
+    let input = OpenOptions::new().open_receiver(&path)?;
+    let _writer = OpenOptions::new().open_sender(&path)?;
+    let mut lines = BufReader::new(input).lines();
+    while let Some(line) = lines.next_line().await? {
+        events.send(Event::Pipe(line)).await?;
+    }
+    Ok(())
+}
+
+// Writes the lines to the output FIFO while a tool has it open, starting
+// with the latest state, and waits for the next tool when it closes it.
+#[cfg(unix)]
+async fn write_lines(path: PathBuf, mut feed: Feed) -> Result<()> {
+    use tokio::net::unix::pipe::OpenOptions;
+
+    loop {
+        // The FIFO cannot be opened for writing until a tool opens it for
+        // reading.
+        let mut output = match OpenOptions::new().open_sender(&path) {
+            Ok(output) => output,
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => {
+                if feed.lines.is_closed() {
+                    return Ok(());
+                }
+                tokio::time::sleep(CONNECT_INTERVAL).await;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let mut line = feed.connect();
+        loop {
+            line.push('\n');
+            if output.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+            let Some(next) = feed.next().await else {
+                return Ok(());
+            };
+            line = next;
+        }
+    }
+}
+
+// Accepts tools until the pipe fails, making a new instance of it for the
+// next tool each time one connects.
+#[cfg(windows)]
+async fn accept(
+    endpoint: Endpoint,
+    events: Sender<Event>,
+    feed: Feed,
+) -> Result<()> {
+    let Endpoint { path, mut server } = endpoint;
+    loop {
+        server.connect().await?;
+        let next = ServerOptions::new().create(&path)?;
+        let client = std::mem::replace(&mut server, next);
+        tokio::spawn(session(client, events.clone(), feed.clone()));
+    }
+}
+
+// Hands each line of a tool to the game and writes it the lines, starting
+// with the latest state, until it disconnects or the game ends.
+#[cfg(windows)]
+async fn session(
+    client: NamedPipeServer,
+    events: Sender<Event>,
+    mut feed: Feed,
+) -> Result<()> {
+    let (reader, mut writer) = tokio::io::split(client);
+    let mut lines = BufReader::new(reader).lines();
+    let mut state = feed.connect();
+    state.push('\n');
+    writer.write_all(state.as_bytes()).await?;
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else {
+                    return Ok(());
+                };
+                events.send(Event::Pipe(line)).await?;
+            }
+            line = feed.next() => {
+                let Some(mut line) = line else {
+                    return Ok(());
+                };
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await?;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+    use crate::game::GameAction;
+
+    #[tokio::test]
+    async fn tools_watch_the_game_and_send_moves() {
+        use tokio::net::unix::pipe::OpenOptions;
+
+        let dir = std::env::temp_dir()
+            .join(format!("2048-pipe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("control");
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let mut game = Game::with_seed(1);
+        let pipe = Pipe::serve(bind(&path).unwrap(), &game, tx);
+
+        let output = OpenOptions::new()
+            .open_receiver(dir.join("control.out"))
+            .unwrap();
+        let mut states = BufReader::new(output).lines();
+        let mut next = async || {
+            let line = states.next_line().await.unwrap().unwrap();
+            serde_json::from_str::<Value>(&line).unwrap()
+        };
+        assert!(next().await.get("moved").is_none());
+
+        let mut input = OpenOptions::new()
+            .open_sender(dir.join("control.in"))
+            .unwrap();
+        input.write_all(b"L\njump\n").await.unwrap();
+        let Some(Event::Pipe(line)) = rx.recv().await else {
+            panic!("expected a line from the pipe");
+        };
+        assert_eq!(line, "L");
+        let Some(Event::Pipe(line)) = rx.recv().await else {
+            panic!("expected a line from the pipe");
+        };
+        assert_eq!(line, "jump");
+
+        // Every line is sent, however quickly they follow each other.
+        game.apply_move(GameAction::Left).unwrap();
+        pipe.send(&game, Some(true));
+        pipe.send_error(headless::Error::unknown_move("jump"));
+        assert_eq!(next().await["moved"], true);
+        assert!(next().await["error"].as_str().unwrap().contains("'jump'"));
+        remove(&path);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::persistence;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+const PROFILES_DIR: &str = "profiles";
+
+// A named player profile. Each profile keeps its settings and history in its
+// own directory under the data directory.
+#[derive(Clone, Debug)]
+pub struct Profile {
+    name: String,
+    dir: Option<PathBuf>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Result<Self> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            bail!(
+                "Invalid profile name '{name}': use letters, digits, '-' or '_'"
+            );
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            dir: persistence::data_dir()
+                .map(|dir| dir.join(PROFILES_DIR).join(name)),
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn at(dir: PathBuf) -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
+            dir: Some(dir),
+        }
+    }
+
+    // Lists the profiles that have been played, by name.
+    pub fn list() -> Result<Vec<Self>> {
+        match persistence::data_dir() {
+            Some(dir) => Self::list_in(&dir.join(PROFILES_DIR)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn list_in(dir: &Path) -> Result<Vec<Self>> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read {}", dir.display())
+                });
+            }
+        };
+        let mut profiles = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            // Directories whose names could not be given with `--profile`
+            // were not made by the game.
+            if let Some(name) = entry.file_name().to_str()
+                && entry.file_type()?.is_dir()
+                && let Ok(mut profile) = Self::new(name)
+            {
+                profile.dir = Some(entry.path());
+                profiles.push(profile);
+            }
+        }
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(profiles)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Returns the path of a file stored in this profile's directory, or `None`
+    // when there is no data directory to persist to.
+    pub fn path(&self, file: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_accepts_simple_names() {
+        assert!(Profile::new("alice_2").is_ok());
+        assert!(Profile::new("speed-run").is_ok());
+    }
+
+    #[test]
+    fn new_rejects_names_that_escape_the_profile_dir() {
+        assert!(Profile::new("").is_err());
+        assert!(Profile::new("../alice").is_err());
+        assert!(Profile::new("a/b").is_err());
+    }
+
+    #[test]
+    fn list_finds_the_profile_directories_by_name() {
+        let dir = persistence::test_dir("profile-list");
+        for name in ["bob", "alice", "not a profile"] {
+            fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let profiles = Profile::list_in(&dir).unwrap();
+
+        let names: Vec<&str> = profiles.iter().map(Profile::name).collect();
+        assert_eq!(names, ["alice", "bob"]);
+        assert_eq!(
+            profiles[0].path("save.json"),
+            Some(dir.join("alice/save.json"))
+        );
+        assert!(Profile::list_in(&dir.join("missing")).unwrap().is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn path_is_scoped_to_the_profile() {
+        let profile = Profile::at(PathBuf::from("/data/bob"));
+        assert_eq!(
+            profile.path("settings.json"),
+            Some(PathBuf::from("/data/bob/settings.json"))
+        );
+    }
+}
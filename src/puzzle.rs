@@ -0,0 +1,253 @@
+use std::fmt;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::game::Game;
+
+// The puzzles bundled with the game, from the easiest.
+const STARTER_PACK: &str = include_str!("../puzzles/starter.json");
+
+/// What a puzzle asks the player to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Objective {
+    /// Make a tile of at least this value.
+    Tile(u32),
+    /// Score at least this many points, counting the points the puzzle
+    /// starts with.
+    Score(u32),
+}
+
+impl Objective {
+    /// Whether the game has met the objective.
+    pub fn is_met(&self, game: &Game) -> bool {
+        match *self {
+            Objective::Tile(tile) => game.max_tile() >= tile,
+            Objective::Score(score) => game.score() >= score,
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Objective::Tile(tile) => write!(f, "Reach {tile}"),
+            Objective::Score(score) => write!(f, "Score {score}"),
+        }
+    }
+}
+
+/// How a puzzle being played stands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PuzzleStatus {
+    /// The objective is still to be met, with this many moves to spare.
+    Playing {
+        moves_left: usize,
+    },
+    Solved,
+    /// The moves ran out, or the board filled up, before the objective was
+    /// met.
+    Failed,
+}
+
+/// A position to play from with an objective to meet in a limited number of
+/// moves. Its tiles are drawn from a seed, so a puzzle plays out the same way
+/// every time it is tried.
+///
+/// Puzzles are written as JSON, the board as a board code:
+///
+/// ```
+/// use rust_2048::puzzle::{Objective, Puzzle};
+///
+/// let json = r#"{
+///     "name": "Corner",
+///     "board": "1100/0000/0000/0000",
+///     "objective": { "tile": 8 },
+///     "moves": 6
+/// }"#;
+/// let puzzles = Puzzle::parse(json)?;
+/// assert_eq!(puzzles[0].objective, Objective::Tile(8));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    pub name: String,
+    pub board: Board,
+    /// The score the puzzle starts with.
+    pub score: u32,
+    /// The seed the tiles spawned after each move are drawn from.
+    pub seed: u64,
+    pub objective: Objective,
+    /// The moves the player has to meet the objective.
+    pub moves: usize,
+}
+
+// A puzzle as it is written, with its board as a code.
+#[derive(Deserialize)]
+struct PuzzleFile {
+    name: String,
+    board: String,
+    #[serde(default)]
+    score: u32,
+    #[serde(default)]
+    seed: u64,
+    objective: Objective,
+    moves: usize,
+}
+
+// A file holds a single puzzle or a pack of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Pack {
+    One(PuzzleFile),
+    Many(Vec<PuzzleFile>),
+}
+
+impl Puzzle {
+    /// Parses a puzzle, or a pack of puzzles in a JSON array, failing if a
+    /// board code is invalid or a puzzle gives no moves.
+    pub fn parse(json: &str) -> Result<Vec<Puzzle>> {
+        let files = match serde_json::from_str(json)? {
+            Pack::One(file) => vec![file],
+            Pack::Many(files) => files,
+        };
+        if files.is_empty() {
+            bail!("The pack holds no puzzle");
+        }
+        files
+            .into_iter()
+            .map(|file| {
+                let board = file.board.parse().with_context(|| {
+                    format!("Invalid board in puzzle '{}'", file.name)
+                })?;
+                if file.moves == 0 {
+                    bail!("Puzzle '{}' gives no moves", file.name);
+                }
+                Ok(Puzzle {
+                    name: file.name,
+                    board,
+                    score: file.score,
+                    seed: file.seed,
+                    objective: file.objective,
+                    moves: file.moves,
+                })
+            })
+            .collect()
+    }
+
+    /// The puzzles bundled with the game, from the easiest.
+    pub fn starter_pack() -> Vec<Puzzle> {
+        Puzzle::parse(STARTER_PACK).expect("the starter pack is valid")
+    }
+
+    /// Starts a game from the puzzle's position.
+    pub fn start(&self) -> Game {
+        Game::from_position(self.board, self.score, self.seed, 0)
+    }
+
+    /// How a game started from the puzzle stands. Only the moves that
+    /// changed the board count.
+    pub fn status(&self, game: &Game) -> PuzzleStatus {
+        let played = game.moves().len();
+        if self.objective.is_met(game) && played <= self.moves {
+            PuzzleStatus::Solved
+        } else if played >= self.moves || game.is_game_over() {
+            PuzzleStatus::Failed
+        } else {
+            PuzzleStatus::Playing {
+                moves_left: self.moves - played,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameAction;
+
+    fn play(game: &mut Game, moves: &str) {
+        for letter in moves.chars() {
+            let action = GameAction::from_letter(letter).unwrap();
+            assert!(game.apply_move(action).unwrap().changed, "{moves}");
+        }
+    }
+
+    #[test]
+    fn puzzle_is_solved_or_failed_within_its_moves() {
+        let json = r#"[
+            {
+                "name": "Merge",
+                "board": "2200/0000/0000/0000",
+                "objective": { "tile": 8 },
+                "moves": 2
+            },
+            {
+                "name": "Far",
+                "board": "1100/0000/0000/0000",
+                "objective": { "score": 100 },
+                "moves": 2
+            }
+        ]"#;
+        let puzzles = Puzzle::parse(json).unwrap();
+        let mut game = puzzles[0].start();
+        assert_eq!(
+            puzzles[0].status(&game),
+            PuzzleStatus::Playing { moves_left: 2 }
+        );
+        play(&mut game, "L");
+        assert_eq!(puzzles[0].status(&game), PuzzleStatus::Solved);
+
+        let mut game = puzzles[1].start();
+        play(&mut game, "D");
+        assert_eq!(
+            puzzles[1].status(&game),
+            PuzzleStatus::Playing { moves_left: 1 }
+        );
+        play(&mut game, "U");
+        assert_eq!(puzzles[1].status(&game), PuzzleStatus::Failed);
+    }
+
+    #[test]
+    fn starter_pack_puzzles_can_be_solved() {
+        let solutions = [
+            "DLRU",
+            "ULL",
+            "UURULLL",
+            "LDLU",
+            "ULDLLDLU",
+            "RURURUL",
+            "DRURULDRU",
+            "DUDRDRRULDL",
+        ];
+        let pack = Puzzle::starter_pack();
+        assert_eq!(pack.len(), solutions.len());
+        for (puzzle, solution) in pack.iter().zip(solutions) {
+            let mut game = puzzle.start();
+            play(&mut game, solution);
+            assert_eq!(
+                puzzle.status(&game),
+                PuzzleStatus::Solved,
+                "{}",
+                puzzle.name
+            );
+        }
+    }
+
+    #[test]
+    fn pack_rejects_invalid_puzzles() {
+        assert!(Puzzle::parse("[]").is_err());
+        let bad_board = r#"[{
+            "name": "Bad", "board": "12", "objective": { "score": 8 },
+            "moves": 3
+        }]"#;
+        assert!(Puzzle::parse(bad_board).is_err());
+        let no_moves = r#"{
+            "name": "None", "board": "1100/0000/0000/0000",
+            "objective": { "score": 8 }, "moves": 0
+        }"#;
+        assert!(Puzzle::parse(no_moves).is_err());
+    }
+}
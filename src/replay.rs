@@ -0,0 +1,372 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::board::Board;
+use crate::game::{Game, GameAction, Merge, TileSpawn};
+use crate::integrity::Signer;
+use crate::sync::Remote;
+use crate::tas::{self, MoveLog};
+
+// Remote directory recorded replays are synced to, each under its file name.
+const REPLAYS_DIR: &str = "replays";
+
+// One line of a replay file: the position after a move, or after a game
+// started when there is no action. Frames hold the whole board so a replay
+// can be followed from any point without knowing which tiles spawned.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<GameAction>,
+    pub moves: usize,
+    pub board: Board,
+    pub score: u32,
+    pub game_over: bool,
+    // Notable merges made by this move.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub merges: Vec<Merge>,
+    // The tile spawned after this move.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawn: Option<TileSpawn>,
+}
+
+impl ReplayFrame {
+    // Captures the game after `action`, or at its start.
+    pub fn new(game: &Game, action: Option<GameAction>) -> Self {
+        let moves = game.moves().len();
+        let merges = match action {
+            Some(_) => game
+                .merges()
+                .iter()
+                .filter(|merge| merge.move_number == moves)
+                .copied()
+                .collect(),
+            None => Vec::new(),
+        };
+        Self {
+            action,
+            moves,
+            board: *game.board(),
+            score: game.score(),
+            game_over: game.is_game_over(),
+            merges,
+            spawn: action.and(game.last_spawn()),
+        }
+    }
+}
+
+// Appends the game to a replay file as it is played: as JSON lines of frames,
+// flushing each one so the file can be followed while it is written, or as a
+// move log for files ending in `.2048`.
+pub enum Recorder {
+    Frames(File),
+    Moves(MoveLog),
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        if tas::is_move_log(path) {
+            return MoveLog::create(path).map(Recorder::Moves);
+        }
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(Recorder::Frames(file))
+    }
+
+    // Records the frame of the game after `frame.action`.
+    pub fn record(&mut self, game: &Game, frame: &ReplayFrame) -> Result<()> {
+        match self {
+            Recorder::Frames(file) => {
+                let mut line = serde_json::to_string(frame)?;
+                line.push('\n');
+                file.write_all(line.as_bytes())
+                    .context("Failed to write the replay")
+            }
+            Recorder::Moves(log) => log.record(game, frame.action),
+        }
+    }
+}
+
+// Uploads a recorded replay, signed like the synced history, so it can be
+// played back on another machine under the same file name.
+pub fn push(path: &Path, remote: &Remote, signer: &Signer) -> Result<()> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    remote.push(&remote_name(path)?, &signer.sign_json(&contents)?)
+}
+
+// Downloads the replay of this file name when it was recorded on another
+// machine and is not on this one yet.
+pub fn pull(path: &Path, remote: &Remote, signer: &Signer) -> Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    let name = remote_name(path)?;
+    let Some(contents) = remote.pull(&name)? else {
+        return Ok(());
+    };
+    let contents: String =
+        signer.verify_json(&contents, &format!("the remote {name}"))?;
+    fs::write(path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn remote_name(path: &Path) -> Result<String> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("{} is not a file name", path.display()))?;
+    Ok(format!("{REPLAYS_DIR}/{name}"))
+}
+
+// Reads the frames appended to a replay file since the last poll.
+pub struct Follower {
+    reader: BufReader<File>,
+    // A line whose end has not been written yet.
+    partial: String,
+}
+
+impl Follower {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            partial: String::new(),
+        })
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<ReplayFrame>> {
+        let mut frames = Vec::new();
+        while self.reader.read_line(&mut self.partial)? > 0 {
+            if !self.partial.ends_with('\n') {
+                break;
+            }
+            let line = std::mem::take(&mut self.partial);
+            if line.trim().is_empty() {
+                continue;
+            }
+            frames.push(
+                serde_json::from_str(&line)
+                    .context("Failed to parse a replay frame")?,
+            );
+        }
+        Ok(frames)
+    }
+}
+
+// Moves shown per second when playback starts unless asked otherwise.
+pub const DEFAULT_SPEED: f64 = 4.0;
+
+// Playback speeds in moves per second, from slowest to fastest.
+pub const SPEEDS: [f64; 7] = [0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+
+// A finished replay being played back, which can be paused, stepped through
+// in either direction and sped up or slowed down.
+pub struct Playback {
+    frames: Vec<ReplayFrame>,
+    index: usize,
+    speed: usize,
+    paused: bool,
+}
+
+impl Playback {
+    // Reads every complete frame of a replay file, replaying a move log to
+    // recreate its frames.
+    pub fn open(path: &Path, moves_per_sec: f64) -> Result<Self> {
+        let frames = if tas::is_move_log(path) {
+            tas::replay(path)?
+        } else {
+            Follower::open(path)?.poll()?
+        };
+        if frames.is_empty() {
+            bail!("{} does not contain any moves", path.display());
+        }
+        Ok(Self::new(frames, moves_per_sec))
+    }
+
+    // Starts at the first frame, at the listed speed closest to the one
+    // requested.
+    pub fn new(frames: Vec<ReplayFrame>, moves_per_sec: f64) -> Self {
+        let speed = (0..SPEEDS.len())
+            .min_by(|&a, &b| {
+                let distance = |i: usize| (SPEEDS[i] - moves_per_sec).abs();
+                distance(a).total_cmp(&distance(b))
+            })
+            .unwrap_or_default();
+        Self {
+            frames,
+            index: 0,
+            speed,
+            paused: false,
+        }
+    }
+
+    pub fn frame(&self) -> &ReplayFrame {
+        &self.frames[self.index]
+    }
+
+    // Returns the position of the current frame and the number of frames.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.index + 1, self.frames.len())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn moves_per_sec(&self) -> f64 {
+        SPEEDS[self.speed]
+    }
+
+    // Time to show each frame while playing.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.moves_per_sec())
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    // Moves forward (positive) or back (negative) by `delta` frames,
+    // stopping at either end. Playback pauses on the last frame.
+    pub fn step(&mut self, delta: isize) {
+        let last = self.frames.len() - 1;
+        self.index = self.index.saturating_add_signed(delta).min(last);
+        if self.index == last {
+            self.paused = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{persistence, sync};
+
+    #[test]
+    fn follower_reads_frames_as_they_are_recorded() {
+        let dir = persistence::test_dir("replay-follow");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.jsonl");
+        let mut game = Game::from_board("7700000000000000".parse().unwrap(), 0);
+
+        let mut recorder = Recorder::create(&path).unwrap();
+        let mut follower = Follower::open(&path).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+
+        let start = ReplayFrame::new(&game, None);
+        recorder.record(&game, &start).unwrap();
+        assert_eq!(follower.poll().unwrap(), [start]);
+
+        game.apply_move(GameAction::Left).unwrap();
+        let moved = ReplayFrame::new(&game, Some(GameAction::Left));
+        recorder.record(&game, &moved).unwrap();
+        let frames = follower.poll().unwrap();
+
+        assert_eq!(frames, [moved]);
+        assert_eq!(frames[0].moves, 1);
+        assert_eq!(frames[0].merges[0].tile, 256);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn follower_waits_for_the_end_of_a_line() {
+        let dir = persistence::test_dir("replay-partial");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.jsonl");
+        let frame = ReplayFrame::new(&Game::new(), None);
+        let line = serde_json::to_string(&frame).unwrap();
+        let (head, tail) = line.split_at(10);
+
+        fs::write(&path, head).unwrap();
+        let mut follower = Follower::open(&path).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+
+        fs::write(&path, format!("{head}{tail}\n")).unwrap();
+        assert_eq!(follower.poll().unwrap(), [frame]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn replays_are_pulled_where_they_are_missing() {
+        let dir = persistence::test_dir("replay-sync");
+        fs::create_dir_all(dir.join("away")).unwrap();
+        let home = dir.join("game.jsonl");
+        let away = dir.join("away").join("game.jsonl");
+        let signer = Signer::new(b"secret".to_vec());
+        let remote = sync::serve(2);
+        let frame = ReplayFrame::new(&Game::with_seed(3), None);
+        Recorder::create(&home)
+            .unwrap()
+            .record(&Game::with_seed(3), &frame)
+            .unwrap();
+
+        push(&home, &remote, &signer).unwrap();
+        pull(&away, &remote, &signer).unwrap();
+        // The replay is on this machine now, so the remote is not asked
+        // again.
+        pull(&away, &remote, &signer).unwrap();
+        assert_eq!(
+            fs::read_to_string(away).unwrap(),
+            fs::read_to_string(home).unwrap()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn frames_record_the_spawned_tile() {
+        let mut game = Game::with_seed(1);
+        assert_eq!(ReplayFrame::new(&game, None).spawn, None);
+
+        let action = GameAction::ALL
+            .into_iter()
+            .find(|&action| Game::preview(game.board(), action).is_some())
+            .unwrap();
+        game.apply_move(action).unwrap();
+        let frame = ReplayFrame::new(&game, Some(action));
+
+        let spawn = frame.spawn.unwrap();
+        assert_eq!(frame.board.cell(spawn.row, spawn.col), Some(spawn.tile));
+    }
+
+    #[test]
+    fn playback_steps_within_the_replay_and_pauses_at_the_end() {
+        let frames: Vec<ReplayFrame> = (0..3)
+            .map(|_| ReplayFrame::new(&Game::new(), None))
+            .collect();
+        let mut playback = Playback::new(frames, 3.0);
+        assert_eq!(playback.moves_per_sec(), 2.0);
+
+        playback.step(-1);
+        assert_eq!(playback.progress(), (1, 3));
+        playback.step(1);
+        assert!(!playback.is_paused());
+        playback.step(5);
+        assert_eq!(playback.progress(), (3, 3));
+        assert!(playback.is_paused());
+
+        for _ in SPEEDS {
+            playback.faster();
+        }
+        assert_eq!(playback.moves_per_sec(), 32.0);
+    }
+}
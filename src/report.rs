@@ -0,0 +1,112 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::board::Tile;
+use crate::game::Game;
+use crate::history::{GameRecord, format_duration};
+
+// Renders a Markdown report of a finished game: the final board, stats, the
+// move at which each milestone tile was first created and the settings the
+// game was played with.
+pub fn render(
+    game: &Game,
+    run: &GameRecord,
+    settings: &[(&str, String)],
+) -> String {
+    let mut report = String::new();
+    let finished = run.finished_at.format("%Y-%m-%d %H:%M UTC");
+    // Writing to a String cannot fail.
+    let _ = writeln!(report, "# 2048 Game Report\n\nFinished {finished}.\n");
+
+    let _ = writeln!(report, "## Final Board\n");
+    let board = game.board();
+    let _ = writeln!(report, "|{}", "   |".repeat(board.cols()));
+    let _ = writeln!(report, "|{}", "---|".repeat(board.cols()));
+    for row in 0..board.rows() {
+        let cells: Vec<String> = (0..board.cols())
+            .map(|col| match board.tile(row, col) {
+                Some(Tile::Value(value)) => value.to_string(),
+                Some(Tile::Wall) => "#".to_string(),
+                Some(Tile::Void) => "~".to_string(),
+                None => String::new(),
+            })
+            .collect();
+        let _ = writeln!(report, "| {} |", cells.join(" | "));
+    }
+
+    let _ = writeln!(report, "\n## Stats\n");
+    let _ = writeln!(report, "- Score: {}", run.score);
+    let _ = writeln!(report, "- Best tile: {}", run.max_tile);
+    let _ = writeln!(report, "- Moves: {}", game.moves().len());
+    let _ = writeln!(report, "- Time: {}", format_duration(run.duration_secs));
+    let _ = writeln!(report, "- Seed: {}", game.seed());
+
+    let _ = writeln!(report, "\n## Milestones\n");
+    let mut best = 0;
+    let mut milestones = 0;
+    for merge in game.merges() {
+        if merge.tile > best {
+            best = merge.tile;
+            milestones += 1;
+            let _ = writeln!(
+                report,
+                "- Move {}: first {}",
+                merge.move_number, merge.tile
+            );
+        }
+    }
+    if milestones == 0 {
+        let _ = writeln!(report, "No milestone tiles were created.");
+    }
+
+    let _ = writeln!(report, "\n## Settings\n");
+    for (name, value) in settings {
+        let _ = writeln!(report, "- {name}: {value}");
+    }
+    report
+}
+
+// Writes the report into `dir`, named after the time the game finished, and
+// returns its path.
+pub fn write(
+    dir: &Path,
+    game: &Game,
+    run: &GameRecord,
+    settings: &[(&str, String)],
+) -> Result<PathBuf> {
+    let name = run.finished_at.format("2048-%Y%m%d-%H%M%S.md").to_string();
+    let path = dir.join(name);
+    fs::write(&path, render(game, run, settings))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::game::GameAction;
+
+    #[test]
+    fn render_includes_board_stats_milestones_and_settings() {
+        let board = "7700000000000000".parse().unwrap();
+        let mut game = Game::from_board(board, 100);
+        game.apply_move(GameAction::Left).unwrap();
+        let run = GameRecord::new(game.score(), 256, Duration::from_secs(75));
+        let settings = [("Labels", "Decimal".to_string())];
+
+        let report = render(&game, &run, &settings);
+
+        assert!(report.starts_with("# 2048 Game Report\n"));
+        assert!(report.contains("|---|---|---|---|\n| 256 |"));
+        assert!(report.contains("- Score: 356\n"));
+        assert!(report.contains("- Moves: 1\n"));
+        assert!(report.contains("- Time: 1:15\n"));
+        assert!(report.contains("- Move 1: first 256\n"));
+        assert!(report.contains("- Labels: Decimal\n"));
+    }
+}
@@ -0,0 +1,505 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::board::MAX_RANK;
+
+/// How the tiles of a variant of the game merge and which of them appear
+/// after a move. A board holds the first 15 tiles of a variant, so merges
+/// making a larger tile do not happen.
+///
+/// Sliding the tiles is left to the board and the game, which only ask the
+/// rule whether two tiles merge and how the tiles move and settle, so a
+/// variant is added by implementing this trait and listing it in `Variant`.
+pub trait MergeRule: Send + Sync {
+    /// Every tile of the variant, smallest first.
+    fn tiles(&self) -> &[u32];
+
+    /// The tile made by merging two touching tiles, or `None` if they do not
+    /// merge. `ahead` is the tile nearer the edge the tiles slide towards.
+    fn merge(&self, ahead: u32, behind: u32) -> Option<u32>;
+
+    /// The tiles that can appear after a move, each with its probability.
+    fn spawns(&self) -> &[(u32, f64)];
+
+    /// The tile that wins a game unless another target is chosen.
+    fn win_tile(&self) -> u32;
+
+    /// The points scored for making a tile by a merge.
+    fn score(&self, tile: u32) -> u32 {
+        tile
+    }
+
+    /// How far the tiles move in a move.
+    fn slide(&self) -> Slide {
+        Slide::ToWall
+    }
+
+    /// What the tiles do once they have slid, before a tile spawns.
+    fn settle(&self) -> Settle {
+        Settle::InPlace
+    }
+}
+
+/// How far the tiles of a line move towards the edge in a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slide {
+    /// Every tile slides as far as it can, and each pair of tiles that meet
+    /// merges once.
+    ToWall,
+    /// The tiles move one cell at most: the first tile from the edge that can
+    /// move, into an empty cell or by merging with the tile ahead, moves with
+    /// every tile behind it. At most one merge happens in a line.
+    OneStep,
+}
+
+/// What the tiles do after sliding in a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Settle {
+    /// The tiles stay where they slid.
+    InPlace,
+    /// Every tile then falls down its column onto the bottom edge, a wall or
+    /// another tile, without merging. New tiles land on top of a column too,
+    /// and a move only counts if the board changes once the tiles have
+    /// fallen, so a game is over when no move does.
+    Fall,
+}
+
+/// Most a combo multiplies the points of a move by.
+pub const MAX_COMBO: u32 = 8;
+
+/// How the points a move's merges score add up. Scoring applies to any
+/// variant, on top of the points its rule gives each merged tile.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Scoring {
+    /// A move scores the points of its merges.
+    #[default]
+    Classic,
+    /// Moves that merge in a row build a combo: the second scores twice its
+    /// points, the third three times and so on up to `MAX_COMBO` times. A
+    /// move without a merge breaks the combo.
+    Combo,
+}
+
+impl Scoring {
+    /// What the points of a move are multiplied by when it is the `combo`th
+    /// move in a row to merge, 0 for a move without a merge.
+    pub fn multiplier(&self, combo: u32) -> u32 {
+        match self {
+            Scoring::Classic => 1,
+            Scoring::Combo => combo.clamp(1, MAX_COMBO),
+        }
+    }
+
+    /// The points a move scores when its merges make `points` and it is the
+    /// `combo`th move in a row to merge.
+    pub fn points(&self, points: u32, combo: u32) -> u32 {
+        points.saturating_mul(self.multiplier(combo))
+    }
+}
+
+/// How often the tiles placed at expert difficulty are a step up from the
+/// tile placed at hard difficulty.
+const EXPERT_STEP_UP: f64 = 0.1;
+
+/// How hard the tiles placed after each move make a game. Difficulty applies
+/// to any variant: it leaves the starting tiles as they are dealt and raises
+/// the tiles placed after each move from the ones the variant's rule spawns.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// The tiles the rule spawns, e.g. 2s and a few 4s in the classic game.
+    #[default]
+    Normal,
+    /// Only the largest tile the rule spawns, 4s in the classic game.
+    Hard,
+    /// Mostly the largest tile the rule spawns and now and then the next tile
+    /// up: 4s and occasionally 8s in the classic game.
+    Expert,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] =
+        [Difficulty::Normal, Difficulty::Hard, Difficulty::Expert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    pub fn is_normal(&self) -> bool {
+        *self == Difficulty::Normal
+    }
+
+    /// The tiles placed after each move in a game by `rule`, each with its
+    /// probability.
+    pub fn spawns(
+        &self,
+        rule: &'static dyn MergeRule,
+    ) -> Cow<'static, [(u32, f64)]> {
+        let largest = rule.spawns().iter().map(|&(tile, _)| tile).max();
+        let (Some(largest), false) = (largest, self.is_normal()) else {
+            return Cow::Borrowed(rule.spawns());
+        };
+        let next = rule.tiles().iter().copied().find(|&tile| tile > largest);
+        match (self, next) {
+            (Difficulty::Expert, Some(next)) => Cow::Owned(vec![
+                (largest, 1.0 - EXPERT_STEP_UP),
+                (next, EXPERT_STEP_UP),
+            ]),
+            _ => Cow::Owned(vec![(largest, 1.0)]),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+        {
+            Some(difficulty) => Ok(difficulty),
+            None => bail!(
+                "Unknown difficulty '{name}', expected normal, hard or expert"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The original rules: equal tiles merge into their sum, a power of two.
+pub struct PowersOfTwo;
+
+const POWERS_OF_TWO: [u32; 15] = [
+    2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768,
+];
+
+impl MergeRule for PowersOfTwo {
+    fn tiles(&self) -> &[u32] {
+        &POWERS_OF_TWO
+    }
+
+    fn merge(&self, ahead: u32, behind: u32) -> Option<u32> {
+        (ahead == behind).then(|| ahead + behind)
+    }
+
+    fn spawns(&self) -> &[(u32, f64)] {
+        &[(2, 0.9), (4, 0.1)]
+    }
+
+    fn win_tile(&self) -> u32 {
+        2048
+    }
+}
+
+/// The original rules, but after each move every tile falls to the bottom of
+/// its column, as if the board stood upright.
+pub struct Gravity;
+
+impl MergeRule for Gravity {
+    fn tiles(&self) -> &[u32] {
+        &POWERS_OF_TWO
+    }
+
+    fn merge(&self, ahead: u32, behind: u32) -> Option<u32> {
+        PowersOfTwo.merge(ahead, behind)
+    }
+
+    fn spawns(&self) -> &[(u32, f64)] {
+        PowersOfTwo.spawns()
+    }
+
+    fn win_tile(&self) -> u32 {
+        PowersOfTwo.win_tile()
+    }
+
+    fn settle(&self) -> Settle {
+        Settle::Fall
+    }
+}
+
+/// Tiles are Fibonacci numbers, and two consecutive ones merge into the
+/// next: 1 + 1, 1 + 2, 2 + 3, 3 + 5 and so on.
+pub struct Fibonacci;
+
+const FIBONACCI: [u32; 15] =
+    [1, 2, 3, 5, 8, 13, 21, 34, 55, 89, 144, 233, 377, 610, 987];
+
+impl MergeRule for Fibonacci {
+    fn tiles(&self) -> &[u32] {
+        &FIBONACCI
+    }
+
+    fn merge(&self, ahead: u32, behind: u32) -> Option<u32> {
+        let (small, large) = (ahead.min(behind), ahead.max(behind));
+        let index = FIBONACCI.iter().position(|&tile| tile == small)?;
+        let consecutive = FIBONACCI.get(index + 1) == Some(&large);
+        (consecutive || small == 1 && large == 1).then(|| small + large)
+    }
+
+    fn spawns(&self) -> &[(u32, f64)] {
+        &[(1, 0.9), (2, 0.1)]
+    }
+
+    fn win_tile(&self) -> u32 {
+        610
+    }
+}
+
+/// Tiles are powers of three, and equal tiles merge into three times their
+/// value. A merge scores the tile times the number of times a 3 was tripled
+/// to make it, so high tiles are worth much more than in the classic game.
+pub struct PowersOfThree;
+
+const POWERS_OF_THREE: [u32; 15] = [
+    3, 9, 27, 81, 243, 729, 2187, 6561, 19683, 59049, 177147, 531441, 1594323,
+    4782969, 14348907,
+];
+
+impl MergeRule for PowersOfThree {
+    fn tiles(&self) -> &[u32] {
+        &POWERS_OF_THREE
+    }
+
+    fn merge(&self, ahead: u32, behind: u32) -> Option<u32> {
+        (ahead == behind).then(|| ahead * 3)
+    }
+
+    fn spawns(&self) -> &[(u32, f64)] {
+        &[(3, 0.9), (9, 0.1)]
+    }
+
+    fn win_tile(&self) -> u32 {
+        2187
+    }
+
+    fn score(&self, tile: u32) -> u32 {
+        tile * tile.ilog(3).saturating_sub(1)
+    }
+}
+
+/// Tiles shift one cell a move as in Threes: 1 and 2 merge into 3, and equal
+/// tiles from 3 up merge into their sum.
+pub struct Threes;
+
+const THREES: [u32; 15] = [
+    1, 2, 3, 6, 12, 24, 48, 96, 192, 384, 768, 1536, 3072, 6144, 12288,
+];
+
+impl MergeRule for Threes {
+    fn tiles(&self) -> &[u32] {
+        &THREES
+    }
+
+    fn merge(&self, ahead: u32, behind: u32) -> Option<u32> {
+        let ones_and_twos = ahead + behind == 3 && ahead != behind;
+        let equal = ahead == behind && ahead >= 3;
+        (ones_and_twos || equal).then(|| ahead + behind)
+    }
+
+    fn spawns(&self) -> &[(u32, f64)] {
+        &[(1, 0.4), (2, 0.4), (3, 0.2)]
+    }
+
+    fn win_tile(&self) -> u32 {
+        768
+    }
+
+    fn slide(&self) -> Slide {
+        Slide::OneStep
+    }
+}
+
+/// The variants of the game, each played by its own `MergeRule`.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    #[default]
+    Classic,
+    Fibonacci,
+    Threes,
+    Triples,
+    Gravity,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 5] = [
+        Variant::Classic,
+        Variant::Fibonacci,
+        Variant::Threes,
+        Variant::Triples,
+        Variant::Gravity,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Classic => "classic",
+            Variant::Fibonacci => "fibonacci",
+            Variant::Threes => "threes",
+            Variant::Triples => "triples",
+            Variant::Gravity => "gravity",
+        }
+    }
+
+    /// How the tiles of the variant merge, in a few words.
+    pub fn summary(&self) -> &'static str {
+        match self {
+            Variant::Classic => "equal tiles merge",
+            Variant::Fibonacci => "consecutive Fibonacci numbers merge",
+            Variant::Threes => "one-step moves, 1 + 2 and pairs merge",
+            Variant::Triples => "equal tiles merge into their triple",
+            Variant::Gravity => "equal tiles merge, then tiles fall",
+        }
+    }
+
+    pub fn rule(&self) -> &'static dyn MergeRule {
+        match self {
+            Variant::Classic => &PowersOfTwo,
+            Variant::Fibonacci => &Fibonacci,
+            Variant::Threes => &Threes,
+            Variant::Triples => &PowersOfThree,
+            Variant::Gravity => &Gravity,
+        }
+    }
+
+    /// Whether a tile can win a game of the variant: a tile larger than any
+    /// that spawns, among the 15 smallest, which a board can hold.
+    pub fn is_target(&self, tile: u32) -> bool {
+        let rule = self.rule();
+        let largest_spawn = rule.spawns().iter().map(|&(tile, _)| tile).max();
+        rule.tiles()
+            .iter()
+            .take(MAX_RANK as usize)
+            .any(|&t| t == tile)
+            && largest_spawn.is_none_or(|spawn| tile > spawn)
+    }
+
+    pub fn is_classic(&self) -> bool {
+        *self == Variant::Classic
+    }
+}
+
+impl FromStr for Variant {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match Variant::ALL
+            .into_iter()
+            .find(|variant| variant.name().eq_ignore_ascii_case(name))
+        {
+            Some(variant) => Ok(variant),
+            None => {
+                let names: Vec<&str> =
+                    Variant::ALL.iter().map(Variant::name).collect();
+                bail!(
+                    "Unknown variant '{name}', expected one of: {}",
+                    names.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consecutive_fibonacci_numbers_merge() {
+        let rule = Fibonacci;
+
+        assert_eq!(rule.merge(1, 1), Some(2));
+        assert_eq!(rule.merge(2, 1), Some(3));
+        assert_eq!(rule.merge(3, 5), Some(8));
+        assert_eq!(rule.merge(2, 2), None);
+        assert_eq!(rule.merge(3, 8), None);
+        assert_eq!(rule.merge(4, 5), None);
+    }
+
+    #[test]
+    fn powers_of_three_triple_and_score_by_merges() {
+        let rule = PowersOfThree;
+
+        assert_eq!(rule.merge(3, 3), Some(9));
+        assert_eq!(rule.merge(27, 27), Some(81));
+        assert_eq!(rule.merge(3, 9), None);
+        assert_eq!(rule.score(9), 9);
+        assert_eq!(rule.score(81), 3 * 81);
+        assert_eq!(PowersOfTwo.score(2048), 2048);
+    }
+
+    #[test]
+    fn threes_merges_one_and_two_then_equal_tiles() {
+        let rule = Threes;
+
+        assert_eq!(rule.merge(1, 2), Some(3));
+        assert_eq!(rule.merge(2, 1), Some(3));
+        assert_eq!(rule.merge(3, 3), Some(6));
+        assert_eq!(rule.merge(1, 1), None);
+        assert_eq!(rule.merge(2, 2), None);
+        assert_eq!(rule.merge(3, 6), None);
+    }
+
+    #[test]
+    fn every_variant_is_consistent() {
+        for variant in Variant::ALL {
+            let rule = variant.rule();
+            assert!(rule.tiles().is_sorted(), "{variant}");
+            assert!(rule.tiles().contains(&rule.win_tile()), "{variant}");
+            let total: f64 = rule.spawns().iter().map(|(_, p)| p).sum();
+            assert!((total - 1.0).abs() < 1e-9, "{variant}");
+            assert_eq!(variant.name().parse::<Variant>().unwrap(), variant);
+        }
+        assert!("hexagonal".parse::<Variant>().is_err());
+    }
+
+    #[test]
+    fn harder_difficulties_raise_the_spawned_tiles() {
+        let classic = Variant::Classic.rule();
+        assert_eq!(Difficulty::Normal.spawns(classic), classic.spawns());
+        assert_eq!(*Difficulty::Hard.spawns(classic), [(4, 1.0)]);
+        assert_eq!(*Difficulty::Expert.spawns(classic), [(4, 0.9), (8, 0.1)]);
+        assert_eq!(
+            *Difficulty::Hard.spawns(Variant::Threes.rule()),
+            [(3, 1.0)]
+        );
+        assert_eq!("Hard".parse::<Difficulty>().unwrap(), Difficulty::Hard);
+        assert!("impossible".parse::<Difficulty>().is_err());
+    }
+
+    #[test]
+    fn combos_multiply_points_up_to_the_cap() {
+        assert_eq!(Scoring::Classic.points(16, 5), 16);
+        assert_eq!(Scoring::Combo.points(16, 0), 16);
+        assert_eq!(Scoring::Combo.points(16, 1), 16);
+        assert_eq!(Scoring::Combo.points(16, 3), 48);
+        assert_eq!(Scoring::Combo.points(16, 20), 16 * MAX_COMBO);
+        assert_eq!(Scoring::Combo.points(u32::MAX, 2), u32::MAX);
+    }
+}
@@ -0,0 +1,382 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::game::Game;
+use crate::integrity::{SetAside, Signer};
+use crate::profile::Profile;
+use crate::sync::Remote;
+
+const SAVE_FILE: &str = "save.json";
+
+// Version of the save format written by this build, bumped with a migration
+// from the previous version whenever a change to it would otherwise misread
+// older saves. Saves written before the format was versioned have no version
+// and are read as version 0.
+const SAVE_VERSION: u64 = 1;
+
+// How long after the game changes it is autosaved, so a burst of moves is
+// written once.
+const AUTOSAVE_DELAY: Duration = Duration::from_secs(1);
+
+// A game in progress, saved so it can be resumed on the next launch with its
+// moves and tile seed. The file is signed like the history so a save cannot be
+// edited into a better game. Saves that only hold a board and score still
+// load.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    #[serde(flatten)]
+    pub game: Game,
+    pub duration_secs: u64,
+}
+
+// A save as written, borrowing the game it saves, with the version of its
+// format.
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u64,
+    #[serde(flatten)]
+    game: &'a Game,
+    duration_secs: u64,
+}
+
+impl SavedGame {
+    // Reads a save written in any version of the format up to this build's,
+    // migrating older ones. A missing file yields `None`.
+    pub fn read(signer: &Signer, path: &Path) -> Result<Option<Self>> {
+        Self::migrated(signer.read_json(path)?, path)
+    }
+
+    fn migrated(save: Option<Value>, path: &Path) -> Result<Option<Self>> {
+        save.map(migrate)
+            .transpose()
+            .with_context(|| format!("Failed to read {}", path.display()))
+    }
+
+    // Writes a game played for `duration` as a save in this build's format.
+    pub fn write(
+        signer: &Signer,
+        path: &Path,
+        game: &Game,
+        duration: Duration,
+    ) -> Result<()> {
+        let save = SaveFile {
+            version: SAVE_VERSION,
+            game,
+            duration_secs: duration.as_secs(),
+        };
+        signer.write_json(path, &save)
+    }
+
+    // Reads and removes the profile's save so a game is only resumed once.
+    // A save that is corrupt or fails its integrity check is set aside, and
+    // a new game started instead; what was set aside is returned to warn
+    // about.
+    pub fn take(
+        profile: &Profile,
+        signer: &Signer,
+    ) -> Result<(Option<Self>, Option<SetAside>)> {
+        let Some(path) = profile.path(SAVE_FILE) else {
+            return Ok((None, None));
+        };
+
+        let (save, set_aside) = signer.recover_json(&path)?;
+        let save = Self::migrated(save, &path)?;
+        if save.is_some() {
+            fs::remove_file(&path).with_context(|| {
+                format!("Failed to remove {}", path.display())
+            })?;
+        }
+        Ok((save, set_aside))
+    }
+
+    // Replaces the profile's save with the remote copy, the game left by the
+    // machine that quit last, or removes it if that game was over. The copy
+    // is signed like the file, so it must have been pushed with the same key.
+    pub fn pull(
+        profile: &Profile,
+        remote: &Remote,
+        signer: &Signer,
+    ) -> Result<()> {
+        let Some(path) = profile.path(SAVE_FILE) else {
+            return Ok(());
+        };
+        let Some(contents) = remote.pull(SAVE_FILE)? else {
+            return Ok(());
+        };
+        let name = format!("the remote {SAVE_FILE}");
+        let save: Option<Value> = signer.verify_json(&contents, &name)?;
+        match Self::migrated(save, Path::new(&name))? {
+            Some(save) => {
+                let played = Duration::from_secs(save.duration_secs);
+                Self::write(signer, &path, &save.game, played)
+            }
+            None => Self::discard(profile),
+        }
+    }
+
+    // Uploads the profile's save, or that there is none, so the next machine
+    // to launch resumes the same game.
+    pub fn push(
+        profile: &Profile,
+        remote: &Remote,
+        signer: &Signer,
+    ) -> Result<()> {
+        let Some(path) = profile.path(SAVE_FILE) else {
+            return Ok(());
+        };
+        let save: Option<Value> = signer.read_json(&path)?;
+        remote.push(SAVE_FILE, &signer.sign_json(&save)?)
+    }
+
+    // Removes the profile's save, if there is one.
+    fn discard(profile: &Profile) -> Result<()> {
+        let Some(path) = profile.path(SAVE_FILE) else {
+            return Ok(());
+        };
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            result => result.with_context(|| {
+                format!("Failed to remove {}", path.display())
+            }),
+        }
+    }
+}
+
+// Brings a save up to this build's format one version at a time. A save
+// from a newer build is refused rather than read without the fields it
+// added.
+fn migrate(save: Value) -> Result<SavedGame> {
+    let mut version = match save.get("version") {
+        Some(version) => version.as_u64().context("Bad save version")?,
+        None => 0,
+    };
+    if version > SAVE_VERSION {
+        bail!(
+            "The save is in format {version}, newer than the {SAVE_VERSION} \
+             this version of the game reads; update the game to resume it"
+        );
+    }
+    while version < SAVE_VERSION {
+        match version {
+            // Unversioned saves hold the fields of version 1, those added
+            // since being defaulted as the game is read.
+            0 => {}
+            _ => unreachable!("every older version has a migration"),
+        }
+        version += 1;
+    }
+    Ok(serde_json::from_value(save)?)
+}
+
+// Keeps the profile's save up to date with the game in progress, so a game
+// survives the terminal closing or the game crashing and is offered again on
+// the next launch.
+pub struct Autosave {
+    profile: Profile,
+    signer: Signer,
+    // When the latest changes to the game are due to be written.
+    due: Option<Instant>,
+}
+
+impl Autosave {
+    pub fn new(profile: Profile, signer: Signer) -> Self {
+        Self {
+            profile,
+            signer,
+            due: None,
+        }
+    }
+
+    // Schedules a save shortly after the game changed, unless one is
+    // already pending.
+    pub fn schedule(&mut self) {
+        self.due.get_or_insert(Instant::now() + AUTOSAVE_DELAY);
+    }
+
+    pub fn due(&self) -> Option<Instant> {
+        self.due
+    }
+
+    // Saves the game to be resumed, or removes the save once the game is
+    // over or has no moves to resume.
+    pub fn write(&mut self, game: &Game, played: Duration) -> Result<()> {
+        self.due = None;
+        if game.is_game_over() || game.moves().is_empty() {
+            return SavedGame::discard(&self.profile);
+        }
+        let Some(path) = self.profile.path(SAVE_FILE) else {
+            return Ok(());
+        };
+        SavedGame::write(&self.signer, &path, game, played)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameAction;
+    use crate::{persistence, sync};
+
+    #[test]
+    fn save_is_resumed_once() {
+        let dir = persistence::test_dir("save-resumed-once");
+        let profile = Profile::at(dir.clone());
+        let signer = Signer::new(b"secret".to_vec());
+        let mut game = Game::with_seed(5);
+        game.apply_move(GameAction::Left).unwrap();
+        game.apply_move(GameAction::Up).unwrap();
+
+        let mut autosave = Autosave::new(profile.clone(), signer.clone());
+        autosave.schedule();
+        assert!(autosave.due().is_some());
+        autosave.write(&game, Duration::from_secs(90)).unwrap();
+        assert!(autosave.due().is_none());
+
+        let resumed = SavedGame::take(&profile, &signer).unwrap().0.unwrap();
+        assert_eq!(resumed.game.board(), game.board());
+        assert_eq!(resumed.game.score(), game.score());
+        assert_eq!(resumed.game.seed(), 5);
+        assert_eq!(resumed.game.moves(), game.moves());
+        assert_eq!(resumed.duration_secs, 90);
+        assert!(SavedGame::take(&profile, &signer).unwrap().0.is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn autosave_is_removed_once_there_is_nothing_to_resume() {
+        let dir = persistence::test_dir("autosave-removed");
+        let profile = Profile::at(dir.clone());
+        let signer = Signer::new(b"secret".to_vec());
+        let mut autosave = Autosave::new(profile.clone(), signer.clone());
+        let mut game = Game::with_seed(5);
+        game.apply_move(GameAction::Left).unwrap();
+
+        autosave.write(&game, Duration::ZERO).unwrap();
+        autosave.write(&Game::with_seed(5), Duration::ZERO).unwrap();
+        assert!(SavedGame::take(&profile, &signer).unwrap().0.is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn resumed_game_spawns_the_same_tiles() {
+        let mut save = SavedGame {
+            game: Game::with_seed(9),
+            duration_secs: 0,
+        };
+        save.game.apply_move(GameAction::Left).unwrap();
+        let json = serde_json::to_string(&save).unwrap();
+        let mut resumed: SavedGame = serde_json::from_str(&json).unwrap();
+
+        for action in [GameAction::Right, GameAction::Down] {
+            save.game.apply_move(action).unwrap();
+            resumed.game.apply_move(action).unwrap();
+        }
+
+        assert_eq!(resumed.game.board(), save.game.board());
+    }
+
+    #[test]
+    fn tampered_save_is_set_aside_for_a_new_game() {
+        let dir = persistence::test_dir("save-tampered");
+        let profile = Profile::at(dir.clone());
+        let signer = Signer::new(b"secret".to_vec());
+        let mut game = Game::with_seed(5);
+        game.apply_move(GameAction::Left).unwrap();
+        let mut autosave = Autosave::new(profile.clone(), signer.clone());
+        autosave.write(&game, Duration::ZERO).unwrap();
+
+        let path = profile.path(SAVE_FILE).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let tampered =
+            contents.replace("\"duration_secs\": 0", "\"duration_secs\": 1");
+        assert_ne!(tampered, contents);
+        fs::write(&path, tampered).unwrap();
+
+        let (save, set_aside) = SavedGame::take(&profile, &signer).unwrap();
+        assert!(save.is_none());
+        assert!(set_aside.is_some());
+        assert!(dir.join("save.json.bad").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unversioned_saves_are_migrated_and_newer_ones_refused() {
+        let dir = persistence::test_dir("save-versions");
+        let path = dir.join(SAVE_FILE);
+        let signer = Signer::new(b"secret".to_vec());
+        let mut game = Game::with_seed(5);
+        game.apply_move(GameAction::Left).unwrap();
+        let mut save = serde_json::to_value(SaveFile {
+            version: SAVE_VERSION,
+            game: &game,
+            duration_secs: 30,
+        })
+        .unwrap();
+
+        save.as_object_mut().unwrap().remove("version");
+        signer.write_json(&path, &save).unwrap();
+        let migrated = SavedGame::read(&signer, &path).unwrap().unwrap();
+        assert_eq!(migrated.game.moves(), game.moves());
+        assert_eq!(migrated.duration_secs, 30);
+
+        save["version"] = (SAVE_VERSION + 1).into();
+        signer.write_json(&path, &save).unwrap();
+        let error = SavedGame::read(&signer, &path).unwrap_err();
+        assert!(
+            format!("{error:#}").contains("update the game"),
+            "{error:#}"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn save_follows_the_player_through_the_remote() {
+        let dir = persistence::test_dir("save-sync");
+        let (home, away) = (dir.join("home"), dir.join("away"));
+        let home = Profile::at(home);
+        let away = Profile::at(away);
+        let signer = Signer::new(b"secret".to_vec());
+        let remote = sync::serve(4);
+        let mut game = Game::with_seed(5);
+        game.apply_move(GameAction::Left).unwrap();
+        let mut autosave = Autosave::new(home.clone(), signer.clone());
+        autosave.write(&game, Duration::from_secs(30)).unwrap();
+
+        SavedGame::push(&home, &remote, &signer).unwrap();
+        SavedGame::pull(&away, &remote, &signer).unwrap();
+        let resumed = SavedGame::take(&away, &signer).unwrap().0.unwrap();
+        assert_eq!(resumed.game.board(), game.board());
+        assert_eq!(resumed.game.moves(), game.moves());
+        assert_eq!(resumed.duration_secs, 30);
+
+        // Once the save is removed at home, the one away goes too.
+        autosave.write(&Game::with_seed(5), Duration::ZERO).unwrap();
+        SavedGame::push(&home, &remote, &signer).unwrap();
+        Autosave::new(away.clone(), signer.clone())
+            .write(&game, Duration::ZERO)
+            .unwrap();
+        SavedGame::pull(&away, &remote, &signer).unwrap();
+        assert!(SavedGame::take(&away, &signer).unwrap().0.is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn board_only_saves_still_load() {
+        let json = r#"{
+            "board": { "cells": [[2, 2, null, null], [null, null, null, null],
+                [null, null, null, null], [null, null, null, 4]] },
+            "score": 4,
+            "duration_secs": 90
+        }"#;
+        let save: SavedGame = serde_json::from_str(json).unwrap();
+        assert_eq!(save.game.score(), 4);
+        assert_eq!(save.game.board().cell(0, 1), Some(2));
+        assert!(save.game.moves().is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::keymap::{Bindings, Layout};
+use crate::label::LabelMode;
+use crate::leaderboard::LeaderboardConfig;
+use crate::persistence;
+use crate::profile::Profile;
+use crate::sync::SyncConfig;
+use crate::theme::ThemeName;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// How the restart command guards against restarting by accident.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RestartMode {
+    // Restart as soon as the key is pressed.
+    #[default]
+    Instant,
+    // Ask for confirmation first.
+    Confirm,
+    // Restart once the key has been held for a second.
+    Hold,
+}
+
+// Per-profile preferences. Fields missing from the settings file keep their
+// defaults so older files continue to load.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub labels: LabelMode,
+    pub theme: ThemeName,
+    // Draw markers on the tile borders with every theme, not only those that
+    // draw them already.
+    pub markers: bool,
+    // Built-in keymap for the keyboard layout, with `keymap` overriding the
+    // bindings of individual commands.
+    pub layout: Layout,
+    pub keymap: Bindings,
+    // Restarting a game that is over never asks for confirmation or a hold.
+    pub restart: RestartMode,
+    // Quit without asking first, even with a game in progress.
+    pub no_confirm: bool,
+    // Remote endpoint for syncing history across machines. Syncing is off
+    // unless this is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncConfig>,
+    // Seconds to wait after a game ends before starting a new one. Games are
+    // only restarted by hand unless this is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_restart_secs: Option<u64>,
+    // Minutes without input after which the game is saved and the program
+    // exits. The saved game is resumed on the next launch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_mins: Option<u64>,
+    // Number of moves that can be undone, 0 to turn undo off. Defaults to
+    // `DEFAULT_UNDO_DEPTH`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undo_depth: Option<usize>,
+    // Moves per second played by the AI in autoplay. Defaults to
+    // `DEFAULT_AUTOPLAY_SPEED`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autoplay_speed: Option<f64>,
+    // How hard the AI searches for its moves in autoplay and hints: the most
+    // moves it looks ahead, the milliseconds it may take per move and the
+    // probability below which chance branches are pruned. Default to the
+    // `SearchLimits` defaults, with no time budget.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_depth: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_time_budget_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_prune: Option<f64>,
+    // Volume of the sound effects from 0 to 1, and whether they start muted.
+    // Defaults to `DEFAULT_VOLUME`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f32>,
+    pub muted: bool,
+    // Ring the terminal bell, or flash the border of the board, when a move
+    // leaves the board as it was.
+    pub invalid_move_bell: bool,
+    pub invalid_move_flash: bool,
+    // URL that receives a JSON summary of every finished game.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    // Server of a global leaderboard that finished games are submitted to.
+    // Nothing is submitted or fetched unless this is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaderboard: Option<LeaderboardConfig>,
+}
+
+impl Settings {
+    // Loads the profile's settings. On first use the defaults are written out
+    // so they can be edited by hand.
+    pub fn load(profile: &Profile) -> Result<Self> {
+        let Some(path) = profile.path(SETTINGS_FILE) else {
+            return Ok(Self::default());
+        };
+
+        match persistence::read_json(&path)? {
+            Some(settings) => Ok(settings),
+            None => {
+                let settings = Self::default();
+                persistence::write_json(&path, &settings)?;
+                Ok(settings)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_writes_defaults_on_first_use() {
+        let dir = persistence::test_dir("settings-defaults");
+        let profile = Profile::at(dir.clone());
+
+        let settings = Settings::load(&profile).unwrap();
+
+        assert_eq!(settings, Settings::default());
+        assert!(dir.join(SETTINGS_FILE).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_is_scoped_to_each_profile() {
+        let dir = persistence::test_dir("settings-profiles");
+        let alice = Profile::at(dir.join("alice"));
+        let bob = Profile::at(dir.join("bob"));
+        std::fs::create_dir_all(dir.join("alice")).unwrap();
+        std::fs::write(
+            dir.join("alice").join(SETTINGS_FILE),
+            r#"{ "labels": "superscript" }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Settings::load(&alice).unwrap().labels,
+            LabelMode::Superscript
+        );
+        assert_eq!(Settings::load(&bob).unwrap().labels, LabelMode::Decimal);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
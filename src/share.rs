@@ -0,0 +1,98 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::board::Tile;
+use crate::game::Game;
+use crate::history::{GameRecord, format_duration};
+
+// Squares standing for the tiles of a shared board, from the smallest tile
+// up. Larger tiles share the last square.
+const SQUARES: [&str; 6] = ["⬜", "🟨", "🟧", "🟥", "🟪", "🟩"];
+const EMPTY: &str = "⬛";
+const WALL: &str = "🔲";
+// Voids are left blank, two spaces as wide as a square.
+const VOID: &str = "  ";
+
+// Renders a summary of a finished game to share: the final board drawn in
+// colored squares, every two ranks of tiles a square brighter, then the score,
+// best tile, moves, time and the seed to play the same tiles.
+pub fn summary(game: &Game, run: &GameRecord) -> String {
+    let mut summary = String::new();
+    // Writing to a String cannot fail.
+    let _ = writeln!(summary, "2048: {} points", run.score);
+    let board = game.board();
+    for row in 0..board.rows() {
+        for col in 0..board.cols() {
+            let square = match board.tile(row, col) {
+                Some(Tile::Wall) => WALL,
+                Some(Tile::Void) => VOID,
+                Some(Tile::Value(_)) => {
+                    let index = (board.rank(row, col) as usize - 1) / 2;
+                    SQUARES[index.min(SQUARES.len() - 1)]
+                }
+                None => EMPTY,
+            };
+            summary.push_str(square);
+        }
+        summary.push('\n');
+    }
+    let _ = writeln!(
+        summary,
+        "Best tile {} · {} moves · {}",
+        run.max_tile,
+        game.moves().len(),
+        format_duration(run.duration_secs)
+    );
+    let _ = write!(summary, "Seed {}", game.seed());
+    summary
+}
+
+// What became of a shared summary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shared {
+    Copied,
+    // No clipboard could be reached, so the summary is printed once the game
+    // exits.
+    Deferred,
+}
+
+// The system clipboard, opened the first time something is copied and kept
+// open so what was copied stays available while the game runs.
+#[derive(Default)]
+pub struct Clipboard(Option<arboard::Clipboard>);
+
+impl Clipboard {
+    pub fn copy(&mut self, text: &str) -> Result<()> {
+        let clipboard = match &mut self.0 {
+            Some(clipboard) => clipboard,
+            None => self.0.insert(arboard::Clipboard::new()?),
+        };
+        clipboard.set_text(text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn summary_draws_the_board_in_squares() {
+        let board = "1234567800000000".parse().unwrap();
+        let game = Game::from_board(board, 0);
+        let run = GameRecord::new(1500, 256, Duration::from_secs(95));
+
+        let summary = summary(&game, &run);
+        let lines: Vec<&str> = summary.lines().collect();
+
+        assert_eq!(lines[0], "2048: 1500 points");
+        assert_eq!(lines[1], "⬜⬜🟨🟨");
+        assert_eq!(lines[2], "🟧🟧🟥🟥");
+        assert_eq!(lines[3], "⬛⬛⬛⬛");
+        assert_eq!(lines[5], "Best tile 256 · 0 moves · 1:35");
+        assert_eq!(lines[6], format!("Seed {}", game.seed()));
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use clap::ValueEnum;
+
+use crate::ai::SearchLimits;
+use crate::game::Game;
+use crate::rules::Variant;
+use crate::strategy::StrategyKind;
+
+// How the statistics of a simulation are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for reading
+    #[default]
+    Table,
+    /// A `statistic,value` line per statistic for spreadsheets and scripts
+    Csv,
+}
+
+// How one simulated game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub games: usize,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub best_score: u32,
+    pub mean_moves: f64,
+    // Number of games that ended with each max tile.
+    pub max_tiles: BTreeMap<u32, usize>,
+}
+
+// Plays `games` games of a variant to the end with moves chosen by the
+// strategy. Game `i` is drawn from `seed + i`, so a simulation with the same
+// seed plays the same games.
+pub fn run(
+    kind: StrategyKind,
+    variant: Variant,
+    games: usize,
+    seed: u64,
+    depth: u32,
+    rollouts: u32,
+) -> Result<Vec<GameResult>> {
+    (0..games as u64)
+        .map(|i| {
+            let seed = seed.wrapping_add(i);
+            let mut strategy = kind.build(
+                seed,
+                SearchLimits {
+                    depth,
+                    ..SearchLimits::default()
+                },
+                rollouts,
+            );
+            let mut game = Game::with_variant(variant, seed);
+            let mut moves = 0;
+            while let Some(action) = strategy.choose_move(game.board()) {
+                game.apply_move(action)?;
+                moves += 1;
+            }
+            Ok(GameResult {
+                score: game.score(),
+                max_tile: game.max_tile(),
+                moves,
+            })
+        })
+        .collect()
+}
+
+// Aggregates the results of a simulation, or returns `None` if no game was
+// played.
+pub fn summarize(results: &[GameResult]) -> Option<Summary> {
+    let games = results.len();
+    if games == 0 {
+        return None;
+    }
+    let mut scores: Vec<u32> = results.iter().map(|r| r.score).collect();
+    scores.sort_unstable();
+    let median_score = if games.is_multiple_of(2) {
+        (scores[games / 2 - 1] as f64 + scores[games / 2] as f64) / 2.0
+    } else {
+        scores[games / 2] as f64
+    };
+    let mut max_tiles = BTreeMap::new();
+    for result in results {
+        *max_tiles.entry(result.max_tile).or_insert(0) += 1;
+    }
+    Some(Summary {
+        games,
+        mean_score: scores.iter().map(|&s| s as f64).sum::<f64>()
+            / games as f64,
+        median_score,
+        best_score: scores[games - 1],
+        mean_moves: results.iter().map(|r| r.moves as f64).sum::<f64>()
+            / games as f64,
+        max_tiles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulations_with_the_same_seed_play_the_same_games() {
+        let first = run(StrategyKind::Random, Variant::Classic, 3, 9, 1, 1);
+        let second = run(StrategyKind::Random, Variant::Classic, 3, 9, 1, 1);
+
+        let first = first.unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first, second.unwrap());
+        assert!(first.iter().all(|result| result.moves > 0));
+    }
+
+    #[test]
+    fn summary_aggregates_the_results() {
+        let result = |score, max_tile, moves| GameResult {
+            score,
+            max_tile,
+            moves,
+        };
+        let results = [
+            result(100, 64, 50),
+            result(400, 128, 150),
+            result(200, 64, 100),
+            result(300, 128, 100),
+        ];
+
+        let summary = summarize(&results).unwrap();
+        assert_eq!(summary.games, 4);
+        assert_eq!(summary.mean_score, 250.0);
+        assert_eq!(summary.median_score, 250.0);
+        assert_eq!(summary.best_score, 400);
+        assert_eq!(summary.mean_moves, 100.0);
+        assert_eq!(summary.max_tiles, BTreeMap::from([(64, 2), (128, 2)]));
+        assert_eq!(summarize(&[]), None);
+    }
+}
@@ -0,0 +1,237 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use ratatui::crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::integrity::Signer;
+use crate::keymap::Key;
+use crate::persistence;
+use crate::profile::Profile;
+use crate::save::SavedGame;
+
+const SLOTS_FILE: &str = "slots.json";
+const SLOTS_DIR: &str = "slots";
+
+// Longest name a slot can be given.
+const MAX_NAME_LEN: usize = 24;
+
+// An entry of the slot index, describing a save well enough to pick it
+// without reading it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Slot {
+    pub name: String,
+    pub score: u32,
+    pub saved_at: DateTime<Utc>,
+    // Board code of the saved position, shown as a preview.
+    pub board: String,
+    // File holding the save, in the slots directory.
+    file: String,
+}
+
+// The named saves of a profile, most recently saved first. The index lists
+// them and each save is signed in its own file, like the save made on quit.
+pub struct Slots {
+    index: Option<PathBuf>,
+    dir: Option<PathBuf>,
+    signer: Signer,
+    slots: Vec<Slot>,
+}
+
+impl Slots {
+    pub fn load(profile: &Profile, signer: Signer) -> Result<Self> {
+        let index = profile.path(SLOTS_FILE);
+        let slots = match &index {
+            Some(path) => persistence::read_json(path)?.unwrap_or_default(),
+            None => Vec::new(),
+        };
+        Ok(Self {
+            index,
+            dir: profile.path(SLOTS_DIR),
+            signer,
+            slots,
+        })
+    }
+
+    pub fn list(&self) -> &[Slot] {
+        &self.slots
+    }
+
+    // Saves the game under a name, replacing the slot of that name if there
+    // is one.
+    pub fn save(
+        &mut self,
+        name: &str,
+        game: &Game,
+        duration: Duration,
+    ) -> Result<()> {
+        let (Some(index), Some(dir)) = (&self.index, &self.dir) else {
+            bail!("There is no data directory to save to");
+        };
+
+        let existing = self.slots.iter().position(|slot| slot.name == name);
+        let file = match existing {
+            Some(i) => self.slots[i].file.clone(),
+            None => self.unused_file(),
+        };
+        SavedGame::write(&self.signer, &dir.join(&file), game, duration)?;
+
+        if let Some(i) = existing {
+            self.slots.remove(i);
+        }
+        self.slots.insert(
+            0,
+            Slot {
+                name: name.to_string(),
+                score: game.score(),
+                saved_at: Utc::now(),
+                board: game.board().to_code(),
+                file,
+            },
+        );
+        persistence::write_json(index, &self.slots)
+    }
+
+    // Reads the save of the slot at `index` in the list. The slot is kept,
+    // so it can be loaded again.
+    pub fn open(&self, index: usize) -> Result<SavedGame> {
+        let (Some(dir), Some(slot)) = (&self.dir, self.slots.get(index)) else {
+            bail!("There is no slot {index}");
+        };
+        SavedGame::read(&self.signer, &dir.join(&slot.file))?
+            .with_context(|| format!("The save of '{}' is missing", slot.name))
+    }
+
+    fn unused_file(&self) -> String {
+        (1..)
+            .map(|n| format!("{n}.json"))
+            .find(|file| self.slots.iter().all(|slot| &slot.file != file))
+            .unwrap_or_default()
+    }
+}
+
+// The screens opened to save to a slot or load from one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SlotScreen {
+    // Typing the name to save under.
+    Naming(String),
+    // Listing the slots, with the one at this index selected.
+    Choosing(usize),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SlotAction {
+    Save(String),
+    Load(usize),
+    Close,
+}
+
+impl SlotScreen {
+    // Handles a key press on a screen listing `slots` slots. Enter saves
+    // under the typed name or loads the selected slot, and Esc closes the
+    // screen.
+    pub fn press(&mut self, key: Key, slots: usize) -> Option<SlotAction> {
+        if key == Key::plain(KeyCode::Esc) {
+            return Some(SlotAction::Close);
+        }
+
+        match self {
+            SlotScreen::Naming(name) => {
+                if key == Key::plain(KeyCode::Enter) {
+                    let name = name.trim();
+                    return (!name.is_empty())
+                        .then(|| SlotAction::Save(name.to_string()));
+                } else if key == Key::plain(KeyCode::Backspace) {
+                    name.pop();
+                } else if let Some(c) = key.typed()
+                    && name.chars().count() < MAX_NAME_LEN
+                {
+                    name.push(c);
+                }
+            }
+            SlotScreen::Choosing(selected) => {
+                if key == Key::plain(KeyCode::Up) {
+                    *selected = selected.saturating_sub(1);
+                } else if key == Key::plain(KeyCode::Down) {
+                    *selected = (*selected + 1).min(slots.saturating_sub(1));
+                } else if key == Key::plain(KeyCode::Enter) && *selected < slots
+                {
+                    return Some(SlotAction::Load(*selected));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::game::GameAction;
+
+    #[test]
+    fn slots_are_listed_newest_first_and_replaced_by_name() {
+        let dir = persistence::test_dir("slots-listed");
+        let profile = Profile::at(dir.clone());
+        let signer = Signer::new(b"secret".to_vec());
+        let mut slots = Slots::load(&profile, signer.clone()).unwrap();
+        let mut game = Game::with_seed(4);
+
+        slots.save("start", &game, Duration::ZERO).unwrap();
+        game.apply_move(GameAction::Left).unwrap();
+        game.apply_move(GameAction::Up).unwrap();
+        slots.save("later", &game, Duration::from_secs(30)).unwrap();
+        slots.save("start", &game, Duration::from_secs(40)).unwrap();
+
+        let slots = Slots::load(&profile, signer).unwrap();
+        let names: Vec<&str> =
+            slots.list().iter().map(|slot| slot.name.as_str()).collect();
+        assert_eq!(names, ["start", "later"]);
+        assert_eq!(slots.list()[0].board, game.board().to_code());
+        let save = slots.open(0).unwrap();
+        assert_eq!(save.game.moves(), game.moves());
+        assert_eq!(save.duration_secs, 40);
+        assert!(slots.open(2).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn naming_edits_the_name_until_enter() {
+        let mut screen = SlotScreen::Naming(String::new());
+        assert_eq!(screen.press(Key::plain(KeyCode::Enter), 0), None);
+
+        for c in " endgamex".chars() {
+            screen.press(Key::char(c), 0);
+        }
+        screen.press(Key::plain(KeyCode::Backspace), 0);
+
+        assert_eq!(
+            screen.press(Key::plain(KeyCode::Enter), 0),
+            Some(SlotAction::Save("endgame".to_string()))
+        );
+    }
+
+    #[test]
+    fn choosing_stays_within_the_slots() {
+        let mut screen = SlotScreen::Choosing(0);
+        assert_eq!(screen.press(Key::plain(KeyCode::Enter), 0), None);
+
+        for _ in 0..3 {
+            screen.press(Key::plain(KeyCode::Down), 2);
+        }
+        assert_eq!(screen, SlotScreen::Choosing(1));
+        assert_eq!(
+            screen.press(Key::plain(KeyCode::Enter), 2),
+            Some(SlotAction::Load(1))
+        );
+        assert_eq!(
+            screen.press(Key::plain(KeyCode::Esc), 2),
+            Some(SlotAction::Close)
+        );
+    }
+}
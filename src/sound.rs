@@ -0,0 +1,148 @@
+#[cfg(feature = "sound")]
+use std::time::Duration;
+
+#[cfg(feature = "sound")]
+use rodio::source::{SineWave, Source};
+#[cfg(feature = "sound")]
+use rodio::{OutputStream, OutputStreamBuilder};
+use rust_2048::game::ActionOutcome;
+
+// Volume used unless the settings choose another, from 0 (silent) to 1.
+pub const DEFAULT_VOLUME: f32 = 0.5;
+
+// Merges creating tiles of this size and up sound bigger.
+const BIG_MERGE: u32 = 128;
+
+// Something in the game that makes a sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Merge,
+    BigMerge,
+    // A merge created a tile larger than any on the board before it.
+    NewBest,
+    GameOver,
+}
+
+impl Effect {
+    // The sound of a move, given the largest tile before it, if it makes
+    // one. Only the most notable thing the move did is heard.
+    pub fn of_move(outcome: &ActionOutcome, previous_max: u32) -> Option<Self> {
+        if outcome.game_over {
+            return Some(Effect::GameOver);
+        }
+        let merged = outcome
+            .board
+            .iter()
+            .flatten()
+            .filter(|cell| cell.merged)
+            .filter_map(|cell| cell.value)
+            .max()?;
+        Some(if merged < BIG_MERGE {
+            Effect::Merge
+        } else if merged > previous_max {
+            Effect::NewBest
+        } else {
+            Effect::BigMerge
+        })
+    }
+
+    // The notes of the effect as frequencies in hertz and lengths in
+    // milliseconds, played one after another.
+    #[cfg_attr(not(feature = "sound"), allow(dead_code))]
+    fn notes(&self) -> &'static [(f32, u64)] {
+        match self {
+            Effect::Merge => &[(660.0, 40)],
+            Effect::BigMerge => &[(880.0, 60), (1320.0, 80)],
+            Effect::NewBest => &[(660.0, 70), (880.0, 70), (1320.0, 140)],
+            Effect::GameOver => &[(440.0, 150), (330.0, 150), (220.0, 300)],
+        }
+    }
+}
+
+// Plays effects on the default output device. Without the `sound` feature,
+// or when there is no device to play on, the game stays silent.
+pub struct Player {
+    #[cfg(feature = "sound")]
+    stream: Option<OutputStream>,
+    volume: f32,
+    muted: bool,
+}
+
+impl Player {
+    pub fn new(volume: f32, muted: bool) -> Self {
+        Self {
+            #[cfg(feature = "sound")]
+            stream: OutputStreamBuilder::open_default_stream().ok().map(
+                |mut stream| {
+                    // Closing the stream on exit is expected, so it should
+                    // not print anything.
+                    stream.log_on_drop(false);
+                    stream
+                },
+            ),
+            volume: volume.clamp(0.0, 1.0),
+            muted,
+        }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted || self.volume == 0.0
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+    }
+
+    pub fn play(&self, effect: Effect) {
+        if self.is_muted() {
+            return;
+        }
+        #[cfg(feature = "sound")]
+        if let Some(stream) = &self.stream {
+            let mut start = Duration::ZERO;
+            for &(freq, millis) in effect.notes() {
+                let length = Duration::from_millis(millis);
+                let note = SineWave::new(freq)
+                    .take_duration(length)
+                    .fade_out(length)
+                    .amplify(self.volume * 0.2)
+                    .delay(start);
+                stream.mixer().add(note);
+                start += length;
+            }
+        }
+        #[cfg(not(feature = "sound"))]
+        let _ = effect;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_2048::game::{Game, GameAction};
+
+    use super::*;
+
+    fn effect(board: &str, action: GameAction) -> Option<Effect> {
+        let mut game = Game::from_board(board.parse().unwrap(), 0);
+        let previous_max = game.max_tile();
+        let outcome = game.apply_move(action).unwrap();
+        Effect::of_move(&outcome, previous_max)
+    }
+
+    #[test]
+    fn moves_sound_like_their_largest_merge() {
+        assert_eq!(effect("1000000000000000", GameAction::Down), None);
+        assert_eq!(
+            effect("1100000000000000", GameAction::Left),
+            Some(Effect::Merge)
+        );
+        assert_eq!(
+            effect("6680000000000000", GameAction::Left),
+            Some(Effect::BigMerge)
+        );
+        assert_eq!(
+            effect("7700000000000000", GameAction::Left),
+            Some(Effect::NewBest)
+        );
+    }
+}
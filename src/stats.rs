@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, MAX_CELLS};
+
+/// Running counts of what has happened in a game so far.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct GameStats {
+    /// Moves that changed the board.
+    pub moves: u32,
+    /// Pairs of tiles merged into one.
+    pub merges: u32,
+    /// Tiles placed by the game, including the starting tiles.
+    pub spawns: u32,
+    pub highest_tile: u32,
+    /// Tiles placed by the game on each cell, in row-major order.
+    #[serde(default)]
+    pub spawned_at: [u32; MAX_CELLS],
+}
+
+impl GameStats {
+    // Stats of a new game, whose starting tiles count as spawned.
+    pub(crate) fn new(board: &Board) -> Self {
+        let mut stats = Self::from_board(board);
+        for (cell, (_, value)) in board.iter_cells().enumerate() {
+            if value.is_some() {
+                stats.spawns += 1;
+                stats.spawned_at[cell] += 1;
+            }
+        }
+        stats
+    }
+
+    // Stats of a game continued from a position reached elsewhere, whose
+    // tiles were not spawned by this game.
+    pub(crate) fn from_board(board: &Board) -> Self {
+        Self {
+            highest_tile: board.max_tile().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    // Counts a move that merged `merges` pairs and spawned a tile on the
+    // cell at index `spawned`, leaving `board`.
+    pub(crate) fn record_move(
+        &mut self,
+        merges: u32,
+        spawned: usize,
+        board: &Board,
+    ) {
+        self.moves += 1;
+        self.merges += merges;
+        self.record_spawn(spawned);
+        self.highest_tile =
+            self.highest_tile.max(board.max_tile().unwrap_or_default());
+    }
+
+    // Counts a tile the game placed on the cell at index `spawned` without a
+    // move.
+    pub(crate) fn record_spawn(&mut self, spawned: usize) {
+        self.spawns += 1;
+        self.spawned_at[spawned] += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_game_counts_its_starting_tiles() {
+        let board: Board = "1200000000000000".parse().unwrap();
+
+        let mut spawned_at = [0; MAX_CELLS];
+        spawned_at[..2].fill(1);
+        assert_eq!(
+            GameStats::new(&board),
+            GameStats {
+                spawns: 2,
+                highest_tile: 4,
+                spawned_at,
+                ..Default::default()
+            }
+        );
+        assert_eq!(GameStats::from_board(&board).spawns, 0);
+    }
+
+    #[test]
+    fn record_move_accumulates() {
+        let mut stats = GameStats::default();
+        stats.record_move(2, 1, &"3100000000000000".parse().unwrap());
+        stats.record_move(0, 2, &"3110000000000000".parse().unwrap());
+        let mut spawned_at = [0; MAX_CELLS];
+        spawned_at[1..3].fill(1);
+
+        assert_eq!(
+            stats,
+            GameStats {
+                moves: 2,
+                merges: 2,
+                spawns: 2,
+                highest_tile: 8,
+                spawned_at,
+            }
+        );
+    }
+}
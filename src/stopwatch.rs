@@ -0,0 +1,95 @@
+use std::time::{Duration, Instant};
+
+// Measures play time, excluding the periods it was paused, e.g. while the
+// terminal did not have focus.
+#[derive(Clone, Copy, Debug)]
+pub struct Stopwatch {
+    // Time accumulated before the current running period.
+    elapsed: Duration,
+    running_since: Option<Instant>,
+}
+
+impl Stopwatch {
+    // Creates a running stopwatch.
+    pub fn start() -> Self {
+        Self::resume_from(Duration::ZERO)
+    }
+
+    // Creates a running stopwatch that has already counted `elapsed`, e.g.
+    // for a resumed game.
+    pub fn resume_from(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            running_since: Some(Instant::now()),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    pub fn pause(&mut self) {
+        self.pause_at(Instant::now());
+    }
+
+    pub fn resume(&mut self) {
+        self.resume_at(Instant::now());
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_at(Instant::now())
+    }
+
+    // The same, reading the clock at `now`, which the tests choose.
+    fn pause_at(&mut self, now: Instant) {
+        if let Some(since) = self.running_since.take() {
+            self.elapsed += now.saturating_duration_since(since);
+        }
+    }
+
+    fn resume_at(&mut self, now: Instant) {
+        if self.running_since.is_none() {
+            self.running_since = Some(now);
+        }
+    }
+
+    fn elapsed_at(&self, now: Instant) -> Duration {
+        self.elapsed
+            + self.running_since.map_or(Duration::ZERO, |since| {
+                now.saturating_duration_since(since)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paused_time_is_not_counted() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut stopwatch = Stopwatch {
+            elapsed: Duration::ZERO,
+            running_since: Some(start),
+        };
+        stopwatch.pause_at(at(10));
+        assert!(stopwatch.is_paused());
+        assert_eq!(stopwatch.elapsed_at(at(30)), Duration::from_millis(10));
+
+        stopwatch.resume_at(at(30));
+        assert!(!stopwatch.is_paused());
+        assert_eq!(stopwatch.elapsed_at(at(35)), Duration::from_millis(15));
+    }
+
+    #[test]
+    fn pause_and_resume_are_idempotent() {
+        let mut stopwatch = Stopwatch::start();
+        stopwatch.pause();
+        stopwatch.pause();
+        let paused_at = stopwatch.elapsed();
+        stopwatch.resume();
+        stopwatch.resume();
+        assert!(stopwatch.elapsed() >= paused_at);
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::ai::{SearchLimits, SearchReport, Searcher};
+use crate::board::Board;
+use crate::game::{Game, GameAction};
+
+/// Games played out after each move by the Monte Carlo strategy unless told
+/// otherwise.
+pub const DEFAULT_ROLLOUTS: u32 = 100;
+
+// Moves after which a game played out by the Monte Carlo strategy stops, in
+// case a variant lets random moves go on without ever filling the board.
+const MAX_ROLLOUT_MOVES: usize = 10_000;
+
+/// Something that picks the moves of a game, such as the expectimax search.
+pub trait Strategy {
+    /// Returns the move to play from a position, or `None` if no move is
+    /// possible.
+    fn choose_move(&mut self, board: &Board) -> Option<GameAction>;
+
+    /// Returns how the search for the latest move went, for strategies that
+    /// search.
+    fn search_report(&self) -> Option<SearchReport> {
+        None
+    }
+}
+
+impl Strategy for Searcher {
+    fn choose_move(&mut self, board: &Board) -> Option<GameAction> {
+        self.best_move(board)
+    }
+
+    fn search_report(&self) -> Option<SearchReport> {
+        self.report()
+    }
+}
+
+/// Plays a legal move chosen at random.
+pub struct RandomMoves {
+    rng: StdRng,
+}
+
+impl RandomMoves {
+    /// Creates a player whose moves are drawn from the given seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for RandomMoves {
+    fn choose_move(&mut self, board: &Board) -> Option<GameAction> {
+        let legal: Vec<GameAction> = GameAction::ALL
+            .into_iter()
+            .filter(|&action| Game::preview(board, action).is_some())
+            .collect();
+        legal.choose(&mut self.rng).copied()
+    }
+}
+
+/// Plays the move that scores the most points right away, preferring the one
+/// that leaves the most empty cells when several score the same.
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn choose_move(&mut self, board: &Board) -> Option<GameAction> {
+        GameAction::ALL
+            .into_iter()
+            .filter_map(|action| {
+                Game::preview(board, action).map(|(next, score)| {
+                    (action, (score, next.empty_cells().count()))
+                })
+            })
+            .max_by_key(|&(_, key)| key)
+            .map(|(action, _)| action)
+    }
+}
+
+/// Plays the move whose games played out at random score the most on
+/// average: after each legal move, a number of games go on with random moves
+/// and tiles until no move is left.
+pub struct MonteCarlo {
+    rollouts: u32,
+    rng: StdRng,
+}
+
+impl MonteCarlo {
+    /// Creates a player that plays out `rollouts` games after each move, with
+    /// moves and tiles drawn from the given seed.
+    pub fn new(rollouts: u32, seed: u64) -> Self {
+        Self {
+            rollouts: rollouts.max(1),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // Plays a game out from a position the player just moved to, returning
+    // the points scored on the way.
+    fn rollout(&mut self, mut board: Board) -> u64 {
+        let mut score = 0;
+        for _ in 0..MAX_ROLLOUT_MOVES {
+            self.spawn(&mut board);
+            let moves: Vec<(Board, u32)> = GameAction::ALL
+                .into_iter()
+                .filter_map(|action| Game::preview(&board, action))
+                .collect();
+            let Some(&(next, points)) = moves.choose(&mut self.rng) else {
+                break;
+            };
+            board = next;
+            score += u64::from(points);
+        }
+        score
+    }
+
+    // Places a tile of the variant on a random cell where one can spawn.
+    fn spawn(&mut self, board: &mut Board) {
+        let cells: Vec<(usize, usize)> = board.spawn_cells().collect();
+        let Some(&(row, col)) = cells.choose(&mut self.rng) else {
+            return;
+        };
+        let spawns = board.variant().rule().spawns();
+        if let Ok(&(tile, _)) = spawns
+            .choose_weighted(&mut self.rng, |&(_, probability)| probability)
+        {
+            board
+                .set_cell(row, col, Some(tile))
+                .expect("the variant's own tiles always fit its board");
+        }
+    }
+}
+
+impl Strategy for MonteCarlo {
+    fn choose_move(&mut self, board: &Board) -> Option<GameAction> {
+        let mut best = None;
+        for action in GameAction::ALL {
+            let Some((next, points)) = Game::preview(board, action) else {
+                continue;
+            };
+            let total: u64 = (0..self.rollouts)
+                .map(|_| u64::from(points) + self.rollout(next))
+                .sum();
+            let average = total as f64 / f64::from(self.rollouts);
+            if best.is_none_or(|(_, value)| average > value) {
+                best = Some((action, average));
+            }
+        }
+        best.map(|(action, _)| action)
+    }
+}
+
+/// The strategies that can be chosen by name, e.g. on the command line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StrategyKind {
+    Random,
+    Greedy,
+    #[default]
+    Expectimax,
+    MonteCarlo,
+}
+
+impl StrategyKind {
+    pub const ALL: [StrategyKind; 4] = [
+        StrategyKind::Random,
+        StrategyKind::Greedy,
+        StrategyKind::Expectimax,
+        StrategyKind::MonteCarlo,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StrategyKind::Random => "random",
+            StrategyKind::Greedy => "greedy",
+            StrategyKind::Expectimax => "expectimax",
+            StrategyKind::MonteCarlo => "montecarlo",
+        }
+    }
+
+    /// Creates a player of this kind. `seed` draws the random moves, `limits`
+    /// bound the expectimax search and `rollouts` is the number of games the
+    /// Monte Carlo strategy plays out after each move.
+    pub fn build(
+        &self,
+        seed: u64,
+        limits: SearchLimits,
+        rollouts: u32,
+    ) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Random => Box::new(RandomMoves::with_seed(seed)),
+            StrategyKind::Greedy => Box::new(Greedy),
+            StrategyKind::Expectimax => Box::new(Searcher::with_limits(limits)),
+            StrategyKind::MonteCarlo => {
+                Box::new(MonteCarlo::new(rollouts, seed))
+            }
+        }
+    }
+}
+
+impl fmt::Display for StrategyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for StrategyKind {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match StrategyKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+        {
+            Some(kind) => Ok(kind),
+            None => {
+                let names: Vec<&str> =
+                    StrategyKind::ALL.iter().map(StrategyKind::name).collect();
+                bail!(
+                    "Unknown strategy '{name}', expected one of: {}",
+                    names.join(", ")
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_strategy_plays_a_legal_move() {
+        let board: Board = "1100000000000002".parse().unwrap();
+        let limits = SearchLimits {
+            depth: 1,
+            ..SearchLimits::default()
+        };
+        for kind in StrategyKind::ALL {
+            let action = kind.build(7, limits, 4).choose_move(&board).unwrap();
+            assert!(Game::preview(&board, action).is_some(), "{kind}");
+        }
+    }
+
+    #[test]
+    fn greedy_takes_the_most_points() {
+        let board: Board = "1100000000000000".parse().unwrap();
+        let action = Greedy.choose_move(&board).unwrap();
+        assert!(matches!(action, GameAction::Left | GameAction::Right));
+    }
+
+    #[test]
+    fn no_strategy_moves_on_a_full_board() {
+        let board: Board = "1212212112122121".parse().unwrap();
+        let limits = SearchLimits {
+            depth: 1,
+            ..SearchLimits::default()
+        };
+        for kind in StrategyKind::ALL {
+            assert_eq!(
+                kind.build(7, limits, 4).choose_move(&board),
+                None,
+                "{kind}"
+            );
+        }
+    }
+
+    #[test]
+    fn monte_carlo_keeps_away_from_the_move_that_loses() {
+        // Moving up leaves no move whatever tile spawns, while moving left
+        // keeps the game going.
+        let board: Board = "0121/3412/xxxx/xxxx".parse().unwrap();
+        let action = MonteCarlo::new(20, 7).choose_move(&board);
+        assert_eq!(action, Some(GameAction::Left));
+    }
+
+    #[test]
+    fn strategies_are_parsed_by_name() {
+        assert_eq!(
+            "Greedy".parse::<StrategyKind>().unwrap(),
+            StrategyKind::Greedy
+        );
+        assert!("minimax".parse::<StrategyKind>().is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use ureq::Agent;
+
+// How long a request to the remote may take, so an unreachable server holds
+// up the launch or the exit for no longer than this.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Remote endpoint that synced files are pushed to and pulled from. Any server
+// accepting plain HTTP GET and PUT works, e.g. a WebDAV share or an
+// S3-compatible bucket behind a gateway that handles request signing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncConfig {
+    // Base URL of the remote directory, e.g. "https://dav.example.com/2048".
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    // Bearer token, used instead of basic authentication when set.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl SyncConfig {
+    fn url(&self, name: &str) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), name)
+    }
+
+    fn authorization(&self) -> Option<String> {
+        if let Some(token) = &self.token {
+            return Some(format!("Bearer {token}"));
+        }
+
+        let username = self.username.as_deref()?;
+        let password = self.password.as_deref().unwrap_or_default();
+        let credentials = STANDARD.encode(format!("{username}:{password}"));
+        Some(format!("Basic {credentials}"))
+    }
+}
+
+// The remote copy of the synced files.
+pub struct Remote {
+    config: SyncConfig,
+    agent: Agent,
+}
+
+impl Remote {
+    pub fn new(config: SyncConfig) -> Self {
+        Self {
+            config,
+            agent: Agent::config_builder()
+                .timeout_global(Some(TIMEOUT))
+                .build()
+                .into(),
+        }
+    }
+
+    // Downloads a remote file. A file that does not exist remotely yet yields
+    // `None`.
+    pub fn pull(&self, name: &str) -> Result<Option<String>> {
+        let url = self.config.url(name);
+        let mut request = self.agent.get(&url);
+        if let Some(authorization) = self.config.authorization() {
+            request = request.header("Authorization", authorization);
+        }
+
+        match request.call() {
+            Ok(mut response) => {
+                let body = response
+                    .body_mut()
+                    .read_to_string()
+                    .with_context(|| format!("Failed to read {url}"))?;
+                Ok(Some(body))
+            }
+            Err(ureq::Error::StatusCode(404)) => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to pull {url}")),
+        }
+    }
+
+    // Uploads a file, replacing the remote copy.
+    pub fn push(&self, name: &str, contents: &str) -> Result<()> {
+        let url = self.config.url(name);
+        let mut request = self.agent.put(&url);
+        if let Some(authorization) = self.config.authorization() {
+            request = request.header("Authorization", authorization);
+        }
+
+        request
+            .send(contents)
+            .with_context(|| format!("Failed to push {url}"))?;
+        Ok(())
+    }
+}
+
+// Serves `requests` GET and PUT requests the way a remote directory would,
+// keeping what is put in memory. Returns the remote it serves.
+#[cfg(test)]
+pub(crate) fn serve(requests: usize) -> Remote {
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/2048", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let mut files = HashMap::new();
+        for _ in 0..requests {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            // Read until the headers are in, then until the body is.
+            let end = loop {
+                let len = stream.read(&mut buf).unwrap();
+                assert!(len > 0, "connection closed early");
+                request.extend_from_slice(&buf[..len]);
+                if let Some(i) =
+                    request.windows(4).position(|w| w == b"\r\n\r\n")
+                {
+                    break i + 4;
+                }
+            };
+            let head = String::from_utf8(request[..end].to_vec()).unwrap();
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            while request.len() < end + length {
+                let len = stream.read(&mut buf).unwrap();
+                assert!(len > 0, "connection closed early");
+                request.extend_from_slice(&buf[..len]);
+            }
+            let body = String::from_utf8(request[end..].to_vec()).unwrap();
+            let mut words = head.split(' ');
+            let (method, path) = (words.next().unwrap(), words.next().unwrap());
+            let (status, file) = match method {
+                "PUT" => {
+                    files.insert(path.to_string(), body);
+                    ("201 Created", "")
+                }
+                _ => match files.get(path) {
+                    Some(file) => ("200 OK", file.as_str()),
+                    None => ("404 Not Found", ""),
+                },
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nConnection: close\r\n\
+                 Content-Length: {}\r\n\r\n{file}",
+                file.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    Remote::new(SyncConfig {
+        url,
+        username: None,
+        password: None,
+        token: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SyncConfig {
+        SyncConfig {
+            url: "https://dav.example.com/2048/".to_string(),
+            username: None,
+            password: None,
+            token: None,
+        }
+    }
+
+    #[test]
+    fn url_joins_base_and_file_name() {
+        assert_eq!(
+            config().url("history.json"),
+            "https://dav.example.com/2048/history.json"
+        );
+    }
+
+    #[test]
+    fn authorization_prefers_token_over_basic_auth() {
+        let mut config = config();
+        assert_eq!(config.authorization(), None);
+
+        config.username = Some("alice".to_string());
+        config.password = Some("secret".to_string());
+        assert_eq!(
+            config.authorization().as_deref(),
+            Some("Basic YWxpY2U6c2VjcmV0")
+        );
+
+        config.token = Some("abc".to_string());
+        assert_eq!(config.authorization().as_deref(), Some("Bearer abc"));
+    }
+}
@@ -0,0 +1,271 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::board::Board;
+use crate::game::{Game, GameAction};
+use crate::replay::ReplayFrame;
+use crate::rules::Scoring;
+
+// Extension of move log files, which `--record` writes instead of frames.
+pub const EXTENSION: &str = "2048";
+
+// First line of a move log, naming the format and its version.
+const HEADER: &str = "2048-moves 1";
+
+// A move log: a compact text file holding the seeds and moves of a session,
+// from which it replays exactly. After the header, a `game` line starts a
+// segment from a position, giving the seed, the number of tiles already
+// drawn from it, the score and the board code, followed by `adversarial` if
+// the tiles were placed by the adversarial spawner, `combo:N` if moves
+// scored by combo, N being the merging moves in a row so far, and
+// `difficulty:NAME` if the game was not at normal difficulty. Each move of
+// the segment follows on its own line as the move's letter and a hash of the board and
+// score it led to, which playback checks to catch a divergence:
+//
+//     2048-moves 1
+//     game 5 1 0 0100000000000001
+//     L 7c3a91e2
+//     U 0b44d2f9
+//
+// A segment starts with each game and wherever the game did not go on by a
+// move, e.g. after an undo.
+pub struct MoveLog {
+    file: File,
+}
+
+impl MoveLog {
+    pub fn create(path: &Path) -> Result<Self> {
+        let mut file = File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        writeln!(file, "{HEADER}")?;
+        Ok(Self { file })
+    }
+
+    // Appends the move that led to the game's position, or starts a segment
+    // from the position when there is no move.
+    pub fn record(
+        &mut self,
+        game: &Game,
+        action: Option<GameAction>,
+    ) -> Result<()> {
+        let line = match action {
+            Some(action) => format!(
+                "{} {:08x}",
+                action.letter(),
+                hash(game.board(), game.score())
+            ),
+            None => {
+                let mut line = format!(
+                    "game {} {} {} {}",
+                    game.seed(),
+                    game.spawns(),
+                    game.score(),
+                    game.board().to_code(),
+                );
+                if game.is_adversarial() {
+                    line.push_str(" adversarial");
+                }
+                if game.scoring() == Scoring::Combo {
+                    line.push_str(&format!(" combo:{}", game.combo()));
+                }
+                if !game.difficulty().is_normal() {
+                    line.push_str(&format!(
+                        " difficulty:{}",
+                        game.difficulty()
+                    ));
+                }
+                line
+            }
+        };
+        writeln!(self.file, "{line}").context("Failed to write the move log")
+    }
+}
+
+// Whether a replay file is a move log rather than JSON lines of frames.
+pub fn is_move_log(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+// Replays a move log, returning the frame of each position. Fails at the
+// first move whose position does not hash to the one recorded.
+pub fn replay(path: &Path) -> Result<Vec<ReplayFrame>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    parse(&text).with_context(|| format!("Failed to replay {}", path.display()))
+}
+
+fn parse(text: &str) -> Result<Vec<ReplayFrame>> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+    match lines.next() {
+        Some((_, HEADER)) => {}
+        _ => bail!("Not a move log: the first line is not '{HEADER}'"),
+    }
+
+    let mut frames = Vec::new();
+    let mut game: Option<Game> = None;
+    for (number, line) in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            [] => continue,
+            ["game", seed, spawns, score, code, ref settings @ ..] => {
+                let board: Board = code
+                    .parse()
+                    .with_context(|| format!("Line {number}: bad board"))?;
+                let parse_number = |field: &str| {
+                    field.parse::<u64>().with_context(|| {
+                        format!("Line {number}: bad number '{field}'")
+                    })
+                };
+                let score = u32::try_from(parse_number(score)?)?;
+                let mut start = Game::from_position(
+                    board,
+                    score,
+                    parse_number(seed)?,
+                    parse_number(spawns)?,
+                );
+                for &setting in settings {
+                    match setting.split_once(':') {
+                        None if setting == "adversarial" => {
+                            start.set_adversarial(true);
+                        }
+                        Some(("combo", combo)) => {
+                            start.set_scoring(Scoring::Combo);
+                            start.set_combo(u32::try_from(parse_number(
+                                combo,
+                            )?)?);
+                        }
+                        Some(("difficulty", name)) => {
+                            start.set_difficulty(name.parse().with_context(
+                                || format!("Line {number}: '{line}'"),
+                            )?);
+                        }
+                        _ => bail!(
+                            "Line {number}: unknown setting '{setting}' in \
+                             '{line}'"
+                        ),
+                    }
+                }
+                frames.push(ReplayFrame::new(&start, None));
+                game = Some(start);
+            }
+            [letter, expected] => {
+                let Some(game) = &mut game else {
+                    bail!("Line {number}: a move before any game line");
+                };
+                let mut letters = letter.chars();
+                let Some(action) = letters
+                    .next()
+                    .filter(|_| letters.next().is_none())
+                    .and_then(GameAction::from_letter)
+                else {
+                    bail!("Line {number}: unknown move '{letter}'");
+                };
+                let outcome = game.apply_move(action)?;
+                let actual =
+                    format!("{:08x}", hash(game.board(), game.score()));
+                if !outcome.changed || actual != expected {
+                    bail!(
+                        "Line {number}: the replay diverges at move {}: \
+                         {action:?} led to board {} with hash {actual}, \
+                         expected {expected}",
+                        game.moves().len(),
+                        game.board().to_code()
+                    );
+                }
+                frames.push(ReplayFrame::new(game, Some(action)));
+            }
+            _ => bail!(
+                "Line {number}: expected a game or a move, found '{line}'"
+            ),
+        }
+    }
+    if frames.is_empty() {
+        bail!("The move log does not contain any game");
+    }
+    Ok(frames)
+}
+
+// A 32-bit FNV-1a hash of a position. Unlike the standard library's hashers
+// it is the same in every build, so a log verifies anywhere.
+fn hash(board: &Board, score: u32) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    let code = board.to_code();
+    for byte in code.bytes().chain(score.to_le_bytes()) {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence;
+    use crate::rules::Difficulty;
+
+    // Records a seeded game, undoing its second move, and returns the log.
+    fn record_game(
+        path: &Path,
+        scoring: Scoring,
+        difficulty: Difficulty,
+    ) -> Game {
+        let mut log = MoveLog::create(path).unwrap();
+        let mut game = Game::with_seed(11);
+        game.set_scoring(scoring);
+        game.set_difficulty(difficulty);
+        log.record(&game, None).unwrap();
+        for (i, action) in
+            GameAction::ALL.into_iter().cycle().take(24).enumerate()
+        {
+            if game.apply_move(action).unwrap().changed {
+                log.record(&game, Some(action)).unwrap();
+            }
+            if i == 1 && game.undo().is_some() {
+                log.record(&game, None).unwrap();
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn recorded_games_replay_exactly() {
+        let dir = persistence::test_dir("tas-replay");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.2048");
+        let settings = [
+            (Scoring::Classic, Difficulty::Normal),
+            (Scoring::Combo, Difficulty::Expert),
+        ];
+        for (scoring, difficulty) in settings {
+            let game = record_game(&path, scoring, difficulty);
+
+            let frames = replay(&path).unwrap();
+            let last = frames.last().unwrap();
+            assert!(is_move_log(&path));
+            assert_eq!(last.board, *game.board());
+            assert_eq!(last.score, game.score());
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn divergent_moves_are_reported() {
+        let dir = persistence::test_dir("tas-diverge");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("game.2048");
+        record_game(&path, Scoring::Classic, Difficulty::Normal);
+
+        let text = fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<String> = text.lines().map(String::from).collect();
+        lines[2] = format!("{} deadbeef", &lines[2][..1]);
+        let tampered = lines.join("\n");
+
+        let error = parse(&tampered).unwrap_err().to_string();
+        assert!(error.contains("diverges at move 1"), "{error}");
+        assert!(parse("game 1 1 0 0100000000000001\n").is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
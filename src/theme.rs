@@ -0,0 +1,395 @@
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::border;
+use ratatui::widgets::BorderType;
+use serde::{Deserialize, Serialize};
+
+// Foreground and background of a tile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileColors {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl TileColors {
+    const fn rgb(fg: (u8, u8, u8), bg: (u8, u8, u8)) -> Self {
+        Self {
+            fg: Color::Rgb(fg.0, fg.1, fg.2),
+            bg: Color::Rgb(bg.0, bg.1, bg.2),
+        }
+    }
+
+    // A tile in a shade of gray, with black or white text.
+    const fn gray(fg: u8, bg: u8) -> Self {
+        Self::rgb((fg, fg, fg), (bg, bg, bg))
+    }
+
+    pub fn style(&self) -> Style {
+        Style::new().fg(self.fg).bg(self.bg)
+    }
+}
+
+// Number of tile values with their own colors, 2 through 2048. Larger tiles
+// share the last entry of the palette.
+const TILE_VALUES: usize = 11;
+
+const DARK_TEXT: (u8, u8, u8) = (119, 110, 101);
+const LIGHT_TEXT: (u8, u8, u8) = (249, 246, 242);
+const NIGHT_TEXT: (u8, u8, u8) = (236, 236, 240);
+const INK_TEXT: (u8, u8, u8) = (40, 40, 50);
+
+// Markers drawn along the top and bottom edges of tiles from 2 to 2048 and
+// above, growing from sparse dots to dense hatches and a solid bar so the
+// size of a tile shows without telling its color apart.
+const MARKERS: [&str; TILE_VALUES + 1] =
+    ["·", "⁚", "⁖", "⁘", "░", "▒", "▓", "╱", "╳", "▚", "█", "◆"];
+
+const PLAIN: Style = Style::new();
+const BOLD: Style = Style::new().add_modifier(Modifier::BOLD);
+const DIM: Style = Style::new().add_modifier(Modifier::DIM);
+
+// The built-in themes, chosen by name in the settings or on the command line.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    /// The colors of the original game
+    #[default]
+    Classic,
+    /// Cool tiles that stand out on a dark terminal
+    Dark,
+    /// Pastel tiles with dark text for a light terminal
+    Light,
+    /// Shades of gray, telling tiles apart by brightness alone
+    Monochrome,
+    /// A palette safe for color vision deficiencies, with markers on the
+    /// tile borders
+    Colorblind,
+}
+
+impl ThemeName {
+    pub fn theme(&self) -> &'static Theme {
+        match self {
+            ThemeName::Classic => &Theme::CLASSIC,
+            ThemeName::Dark => &Theme::DARK,
+            ThemeName::Light => &Theme::LIGHT,
+            ThemeName::Monochrome => &Theme::MONOCHROME,
+            ThemeName::Colorblind => &Theme::COLORBLIND,
+        }
+    }
+
+    // The theme after this one, wrapping around to the first.
+    pub fn next(&self) -> ThemeName {
+        let themes = ThemeName::value_variants();
+        let index = themes.iter().position(|name| name == self).unwrap_or(0);
+        themes[(index + 1) % themes.len()]
+    }
+}
+
+// Colors and styles used to draw the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    // Colors of the tiles from 2 to 2048, indexed by exponent minus one.
+    tiles: [TileColors; TILE_VALUES],
+    // Colors of tiles larger than 2048.
+    super_tile: TileColors,
+    // Colors of the walls, which hold no tile.
+    wall: TileColors,
+    // The border around the board, and its style while the game goes on and
+    // once it is over.
+    pub border: BorderType,
+    pub board: Style,
+    pub board_over: Style,
+    // Borders of the cells, and of the cells the last move merged into.
+    pub cell: Style,
+    pub merged: Style,
+    // Titles on the borders of the board and of the overlays.
+    pub title: Style,
+    // The score, and the best score while the game in progress beats it.
+    pub score: Style,
+    pub best: Style,
+    // Borders of the overlays: prompts, menus and screens, those announcing
+    // the end of a game and the one announcing a win.
+    pub overlay: Style,
+    pub alert: Style,
+    pub win: Style,
+    // Whether tiles carry markers on their borders that tell their values
+    // apart without relying on color.
+    pub markers: bool,
+}
+
+impl Theme {
+    // The palette of the original game.
+    pub const CLASSIC: Theme = Theme {
+        tiles: [
+            TileColors::rgb(DARK_TEXT, (238, 228, 218)),
+            TileColors::rgb(DARK_TEXT, (237, 224, 200)),
+            TileColors::rgb(LIGHT_TEXT, (242, 177, 121)),
+            TileColors::rgb(LIGHT_TEXT, (245, 149, 99)),
+            TileColors::rgb(LIGHT_TEXT, (246, 124, 95)),
+            TileColors::rgb(LIGHT_TEXT, (246, 94, 59)),
+            TileColors::rgb(LIGHT_TEXT, (237, 207, 114)),
+            TileColors::rgb(LIGHT_TEXT, (237, 204, 97)),
+            TileColors::rgb(LIGHT_TEXT, (237, 200, 80)),
+            TileColors::rgb(LIGHT_TEXT, (237, 197, 63)),
+            TileColors::rgb(LIGHT_TEXT, (237, 194, 46)),
+        ],
+        super_tile: TileColors::rgb(LIGHT_TEXT, (60, 58, 50)),
+        wall: TileColors::rgb((187, 173, 160), DARK_TEXT),
+        border: BorderType::Thick,
+        board: PLAIN,
+        board_over: Style::new().fg(Color::Red),
+        cell: DIM,
+        merged: Style::new().fg(Color::Green),
+        title: Style::new().fg(Color::Yellow),
+        score: PLAIN,
+        best: Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        overlay: Style::new().fg(Color::Yellow),
+        alert: Style::new().fg(Color::Red),
+        win: Style::new().fg(Color::Green),
+        markers: false,
+    };
+
+    // Tiles climbing from slate through blue and green to red, for dark
+    // terminals.
+    pub const DARK: Theme = Theme {
+        tiles: [
+            TileColors::rgb(NIGHT_TEXT, (58, 58, 70)),
+            TileColors::rgb(NIGHT_TEXT, (64, 70, 96)),
+            TileColors::rgb(NIGHT_TEXT, (46, 84, 140)),
+            TileColors::rgb(NIGHT_TEXT, (36, 110, 170)),
+            TileColors::rgb(NIGHT_TEXT, (30, 140, 170)),
+            TileColors::rgb(NIGHT_TEXT, (34, 160, 130)),
+            TileColors::rgb(NIGHT_TEXT, (90, 170, 80)),
+            TileColors::rgb(NIGHT_TEXT, (160, 170, 60)),
+            TileColors::rgb(NIGHT_TEXT, (200, 150, 50)),
+            TileColors::rgb(NIGHT_TEXT, (210, 110, 50)),
+            TileColors::rgb(NIGHT_TEXT, (220, 70, 70)),
+        ],
+        super_tile: TileColors::rgb(NIGHT_TEXT, (160, 60, 160)),
+        wall: TileColors::rgb((90, 90, 100), (30, 30, 36)),
+        border: BorderType::Rounded,
+        board: Style::new().fg(Color::Gray),
+        board_over: Style::new().fg(Color::LightRed),
+        cell: Style::new().fg(Color::DarkGray),
+        merged: Style::new().fg(Color::LightCyan),
+        title: Style::new().fg(Color::LightCyan),
+        score: Style::new().fg(Color::Gray),
+        best: Style::new()
+            .fg(Color::LightCyan)
+            .add_modifier(Modifier::BOLD),
+        overlay: Style::new().fg(Color::LightBlue),
+        alert: Style::new().fg(Color::LightRed),
+        win: Style::new().fg(Color::LightGreen),
+        markers: false,
+    };
+
+    // Pastel tiles with dark text, for light terminals.
+    pub const LIGHT: Theme = Theme {
+        tiles: [
+            TileColors::rgb(INK_TEXT, (250, 250, 250)),
+            TileColors::rgb(INK_TEXT, (235, 242, 250)),
+            TileColors::rgb(INK_TEXT, (214, 234, 248)),
+            TileColors::rgb(INK_TEXT, (197, 225, 245)),
+            TileColors::rgb(INK_TEXT, (200, 240, 220)),
+            TileColors::rgb(INK_TEXT, (170, 230, 190)),
+            TileColors::rgb(INK_TEXT, (255, 240, 180)),
+            TileColors::rgb(INK_TEXT, (255, 224, 150)),
+            TileColors::rgb(INK_TEXT, (255, 200, 150)),
+            TileColors::rgb(INK_TEXT, (255, 180, 170)),
+            TileColors::rgb(INK_TEXT, (240, 160, 200)),
+        ],
+        super_tile: TileColors::rgb(INK_TEXT, (200, 170, 230)),
+        wall: TileColors::rgb((150, 150, 160), (225, 225, 230)),
+        border: BorderType::Plain,
+        board: Style::new().fg(Color::Black),
+        board_over: Style::new().fg(Color::Red),
+        cell: Style::new().fg(Color::Gray),
+        merged: Style::new().fg(Color::Magenta),
+        title: Style::new().fg(Color::Blue),
+        score: Style::new().fg(Color::Black),
+        best: Style::new().fg(Color::Blue).add_modifier(Modifier::BOLD),
+        overlay: Style::new().fg(Color::Blue),
+        alert: Style::new().fg(Color::Red),
+        win: Style::new().fg(Color::Magenta),
+        markers: false,
+    };
+
+    // Shades of gray that darken as tiles grow, with emphasis shown by
+    // weight instead of color.
+    pub const MONOCHROME: Theme = Theme {
+        tiles: [
+            TileColors::gray(0, 235),
+            TileColors::gray(0, 215),
+            TileColors::gray(0, 195),
+            TileColors::gray(0, 175),
+            TileColors::gray(0, 155),
+            TileColors::gray(255, 135),
+            TileColors::gray(255, 115),
+            TileColors::gray(255, 95),
+            TileColors::gray(255, 75),
+            TileColors::gray(255, 55),
+            TileColors::gray(255, 35),
+        ],
+        super_tile: TileColors::gray(255, 15),
+        wall: TileColors::gray(128, 60),
+        border: BorderType::Double,
+        board: PLAIN,
+        board_over: BOLD,
+        cell: DIM,
+        merged: BOLD,
+        title: BOLD,
+        score: PLAIN,
+        best: Style::new()
+            .add_modifier(Modifier::BOLD.union(Modifier::REVERSED)),
+        overlay: PLAIN,
+        alert: BOLD,
+        win: BOLD,
+        markers: false,
+    };
+
+    // Yellow through green and blue to purple, darkening as tiles grow, with
+    // orange above 2048. The colors stay apart with any kind of color
+    // blindness, and the markers on the borders set the values apart even
+    // where they do not.
+    pub const COLORBLIND: Theme = Theme {
+        tiles: [
+            TileColors::rgb(INK_TEXT, (253, 231, 37)),
+            TileColors::rgb(INK_TEXT, (181, 222, 43)),
+            TileColors::rgb(INK_TEXT, (110, 206, 88)),
+            TileColors::rgb(INK_TEXT, (53, 183, 121)),
+            TileColors::rgb(LIGHT_TEXT, (31, 158, 137)),
+            TileColors::rgb(LIGHT_TEXT, (38, 130, 142)),
+            TileColors::rgb(LIGHT_TEXT, (49, 104, 142)),
+            TileColors::rgb(LIGHT_TEXT, (62, 73, 137)),
+            TileColors::rgb(LIGHT_TEXT, (72, 40, 120)),
+            TileColors::rgb(LIGHT_TEXT, (68, 1, 84)),
+            TileColors::rgb(LIGHT_TEXT, (20, 20, 30)),
+        ],
+        super_tile: TileColors::rgb(INK_TEXT, (230, 159, 0)),
+        wall: TileColors::gray(128, 60),
+        border: BorderType::Thick,
+        board: PLAIN,
+        board_over: Style::new().fg(Color::Rgb(213, 94, 0)),
+        cell: DIM,
+        merged: Style::new().fg(Color::Rgb(86, 180, 233)),
+        title: Style::new().fg(Color::Rgb(240, 228, 66)),
+        score: PLAIN,
+        best: Style::new()
+            .fg(Color::Rgb(86, 180, 233))
+            .add_modifier(Modifier::BOLD),
+        overlay: Style::new().fg(Color::Rgb(86, 180, 233)),
+        alert: Style::new().fg(Color::Rgb(213, 94, 0)),
+        win: Style::new().fg(Color::Rgb(0, 114, 178)),
+        markers: true,
+    };
+
+    // The theme with markers on the tile borders, whether or not it draws
+    // them itself.
+    pub fn with_markers(self, markers: bool) -> Theme {
+        Theme {
+            markers: self.markers || markers,
+            ..self
+        }
+    }
+
+    // Returns the colors of a tile. Values that are not powers of two, which
+    // only come from imported boards, use the colors of the power below them.
+    pub fn tile(&self, value: u32) -> TileColors {
+        self.tiles
+            .get(exponent(value) - 1)
+            .copied()
+            .unwrap_or(self.super_tile)
+    }
+
+    pub fn wall(&self) -> TileColors {
+        self.wall
+    }
+
+    // Returns the border of a tile, marked along its top and bottom edges if
+    // the theme draws markers.
+    pub fn tile_border(&self, value: u32) -> border::Set<'static> {
+        if !self.markers {
+            return border::PLAIN;
+        }
+        let marker = MARKERS[(exponent(value) - 1).min(TILE_VALUES)];
+        border::Set {
+            horizontal_top: marker,
+            horizontal_bottom: marker,
+            ..border::PLAIN
+        }
+    }
+}
+
+// The power of two of a tile value, rounded down, 1 for values below 4.
+fn exponent(value: u32) -> usize {
+    value.max(2).ilog2() as usize
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::CLASSIC
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_value_up_to_2048_has_its_own_colors() {
+        for name in ThemeName::value_variants() {
+            let theme = name.theme();
+            let colors: Vec<TileColors> =
+                (1..=11).map(|exponent| theme.tile(1 << exponent)).collect();
+            for (i, color) in colors.iter().enumerate() {
+                assert!(
+                    !colors[i + 1..].contains(color),
+                    "{name:?}: {color:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tiles_above_2048_share_the_super_tile_colors() {
+        let theme = Theme::default();
+        assert_eq!(theme.tile(4096), theme.super_tile);
+        assert_eq!(theme.tile(1 << 17), theme.super_tile);
+        assert_eq!(theme.tile(3), theme.tile(2));
+    }
+
+    #[test]
+    fn markers_tell_each_value_apart() {
+        let theme = Theme::COLORBLIND;
+        let markers: Vec<&str> = (1..=12)
+            .map(|exponent| theme.tile_border(1 << exponent).horizontal_top)
+            .collect();
+        for (i, marker) in markers.iter().enumerate() {
+            assert!(!markers[i + 1..].contains(marker), "{marker}");
+        }
+        assert_eq!(theme.tile_border(1 << 17), theme.tile_border(1 << 12));
+        assert_eq!(Theme::CLASSIC.tile_border(2), border::PLAIN);
+        let marked = Theme::CLASSIC.with_markers(true);
+        assert_eq!(marked.tile_border(2).horizontal_top, "·");
+    }
+
+    #[test]
+    fn themes_cycle_through_every_built_in() {
+        let mut name = ThemeName::default();
+        for _ in ThemeName::value_variants() {
+            name = name.next();
+        }
+        assert_eq!(name, ThemeName::default());
+        assert_eq!(ThemeName::Classic.next(), ThemeName::Dark);
+    }
+}
@@ -0,0 +1,180 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+
+use crate::board::Board;
+use crate::game::{Game, GameAction};
+use crate::keymap::Command;
+use crate::persistence;
+
+// Noted in the data directory once the tutorial has been played through or
+// skipped, so it is only offered on the first launch.
+const DONE_FILE: &str = "tutorial-done";
+
+// The seed the tiles of every lesson spawn from, so each plays out the same
+// way every time.
+const SEED: u64 = 2048;
+
+// A lesson of the tutorial: a position set up to show one rule of the game,
+// the move that shows it, and what to say before and after the move.
+pub struct Lesson {
+    pub name: &'static str,
+    board: &'static str,
+    pub action: GameAction,
+    // What the move does, following "Press KEY to".
+    pub goal: &'static str,
+    pub intro: &'static str,
+    pub outro: &'static str,
+}
+
+impl Lesson {
+    // The command of the key the player is asked to press.
+    pub fn command(&self) -> Command {
+        match self.action {
+            GameAction::Up => Command::MoveUp,
+            GameAction::Down => Command::MoveDown,
+            GameAction::Left => Command::MoveLeft,
+            GameAction::Right => Command::MoveRight,
+        }
+    }
+
+    fn start(&self) -> Game {
+        let board: Board =
+            self.board.parse().expect("the lesson boards are valid");
+        Game::from_position(board, 0, SEED, 0)
+    }
+}
+
+const LESSONS: [Lesson; 3] = [
+    Lesson {
+        name: "Merging",
+        board: "1100/0000/0000/0000",
+        action: GameAction::Left,
+        goal: "slide the tiles left",
+        intro: "Every move slides all the tiles as far as they go. Two tiles \
+                of the same value pushed together merge into one.",
+        outro: "The two 2s merged into a 4, and the 4 was added to your \
+                score. Merge your way up to the 2048 tile to win.",
+    },
+    Lesson {
+        name: "Spawning",
+        board: "1000/0000/0000/0000",
+        action: GameAction::Down,
+        goal: "slide the tile down",
+        intro: "Watch the empty cells: something happens after every move \
+                that changes the board.",
+        outro: "A new tile appeared on an empty cell: a 2, or now and then a \
+                4, comes after every move. A move that changes nothing \
+                brings no tile.",
+    },
+    Lesson {
+        name: "Game over",
+        board: "1234/2345/3456/4577",
+        action: GameAction::Left,
+        goal: "merge the two 128s",
+        intro: "The board is almost full. The game ends when no move can \
+                change it: every cell taken and no two tiles side by side \
+                alike.",
+        outro: "The new tile filled the last empty cell and nothing can \
+                merge, so the game is over. Keep cells free to play on!",
+    },
+];
+
+// The tutorial played on the first launch: each lesson sets up a board and
+// waits for the move it asks for, then explains what the move did.
+#[derive(Default)]
+pub struct Tutorial {
+    index: usize,
+    // Whether the move of the lesson on the board has been played.
+    played: bool,
+}
+
+impl Tutorial {
+    pub fn lesson(&self) -> &'static Lesson {
+        &LESSONS[self.index]
+    }
+
+    // The number of the lesson on the board, from 1, and how many there are.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.index + 1, LESSONS.len())
+    }
+
+    pub fn is_played(&self) -> bool {
+        self.played
+    }
+
+    pub fn play(&mut self) {
+        self.played = true;
+    }
+
+    // The game of the lesson on the board, from before its move.
+    pub fn start(&self) -> Game {
+        self.lesson().start()
+    }
+
+    // Sets up the lesson on the board again.
+    pub fn restart(&mut self) -> Game {
+        self.played = false;
+        self.start()
+    }
+
+    // Moves on to the next lesson, returning its game, or `None` after the
+    // last one.
+    pub fn next(&mut self) -> Option<Game> {
+        if self.index + 1 == LESSONS.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.restart())
+    }
+}
+
+// Whether the tutorial was played through or skipped before. Without a data
+// directory there is nowhere to note it, so it is not offered.
+pub fn is_done() -> bool {
+    persistence::data_dir().is_none_or(|dir| dir.join(DONE_FILE).exists())
+}
+
+pub fn mark_done() -> Result<()> {
+    let Some(dir) = persistence::data_dir() else {
+        return Ok(());
+    };
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(DONE_FILE);
+    fs::write(&path, "")
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lessons_play_out_as_explained() {
+        let mut tutorial = Tutorial::default();
+        let mut games = vec![tutorial.start()];
+        games.extend(std::iter::from_fn(|| tutorial.next()));
+        assert_eq!(games.len(), LESSONS.len());
+
+        let outcomes: Vec<_> = games
+            .iter_mut()
+            .zip(&LESSONS)
+            .map(|(game, lesson)| game.apply_move(lesson.action).unwrap())
+            .collect();
+        assert!(outcomes.iter().all(|outcome| outcome.changed));
+        assert_eq!((games[0].max_tile(), games[0].score()), (4, 4));
+        assert_eq!(games[1].board().empty_cells().count(), 14);
+        assert!(!outcomes[1].game_over);
+        assert!(outcomes[2].game_over);
+    }
+
+    #[test]
+    fn each_lesson_starts_unplayed() {
+        let mut tutorial = Tutorial::default();
+        tutorial.play();
+        tutorial.next();
+        assert_eq!(tutorial.progress(), (2, 3));
+        assert!(!tutorial.is_played());
+    }
+}
@@ -0,0 +1,2353 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Margin, Offset, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::symbols::border;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Bar, BarChart, Block, BorderType, Clear, Padding, Paragraph, Row, Table,
+    TableState, Wrap,
+};
+
+use crate::ai::{self, Evaluation, SearchReport};
+use crate::board::{Board, Shape};
+use crate::challenge::Challenge;
+use crate::game::{
+    ActionOutcome, CellResult, Game, GameAction, Merge, MoveRecord, PowerUp,
+    TITLE,
+};
+use crate::glyph;
+use crate::history::{GameRecord, History, TOP_RUNS, format_duration};
+use crate::import::Import;
+use crate::keymap::{Command, Keymap};
+use crate::label::LabelMode;
+use crate::leaderboard::GlobalScores;
+use crate::menu::{MenuItem, PauseMenu, TitleMenu};
+use crate::puzzle::{Puzzle, PuzzleStatus};
+use crate::replay::ReplayFrame;
+use crate::rules::{Difficulty, MAX_COMBO, Scoring};
+use crate::share::Shared;
+use crate::slots::{Slot, SlotScreen};
+use crate::stats::GameStats;
+use crate::theme::Theme;
+use crate::tutorial::Tutorial;
+use crate::widget::{BoardWidget, Palette};
+
+// The AI's evaluation of each move from the position on the board, and how
+// the player's latest move compared with the move it preferred.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Analysis {
+    // `None` while the search is running.
+    pub evaluations: Option<[Evaluation; 4]>,
+    // The move played from the previous position and the AI's choice there.
+    pub last: Option<(GameAction, GameAction)>,
+}
+
+// A position of the game that just ended, shown in place of the game while
+// the game is reviewed.
+#[derive(Clone, Copy, Debug)]
+pub struct Review {
+    // The position's number from the first, and how many there are with the
+    // final board.
+    pub index: usize,
+    pub count: usize,
+    pub board: Board,
+    pub score: u32,
+    // The move played from the position, `None` on the final board.
+    pub played: Option<GameAction>,
+    // The move the AI prefers there, `None` until its search is done.
+    pub best: Option<GameAction>,
+}
+
+// Everything on screen at one moment of a game, borrowed from whatever holds
+// the game. The terminal fills one in before each frame, and any other
+// ratatui backend can do the same to draw the game exactly alike.
+pub struct GameView<'a> {
+    pub game: &'a Game,
+    // The latest move, whose merges and new tile are highlighted.
+    pub outcome: &'a ActionOutcome,
+    pub history: &'a History,
+    pub keymap: &'a Keymap,
+    pub labels: LabelMode,
+    // The theme with the tile markers already chosen.
+    pub theme: Theme,
+    // The settings listed in the pause menu and on the title screen.
+    pub report_settings: &'a [(&'static str, String)],
+    // The player profile, named in the board's title when it is not the
+    // default one.
+    pub profile: Option<&'a str>,
+    // The menu of the title screen, drawn in place of the game when set.
+    pub title: Option<&'a TitleMenu>,
+    // Notices of damaged files set aside at launch, shown on the title
+    // screen.
+    pub warnings: &'a [String],
+    pub mode: &'static str,
+    pub elapsed: Duration,
+    pub paused: bool,
+    // Time left to play, in blitz mode.
+    pub time_left: Option<Duration>,
+    // How far the restart key has been held, in hold mode.
+    pub hold_progress: Option<f64>,
+    pub hint: Option<char>,
+    pub autoplay: bool,
+    // The challenge changing what the move keys do, if any.
+    pub challenge: Option<Challenge>,
+    // Whether the border flashes after a move that changed nothing.
+    pub flash: bool,
+    // How far the tiles of the latest move have slid into place.
+    pub animation: Option<f64>,
+    // The points of the latest move and how far they have faded.
+    pub points: Option<(u32, f64)>,
+    // What became of the latest position export or load.
+    pub position: Option<Line<'static>>,
+    // The index in the history of the game that just ended.
+    pub finished_run: Option<usize>,
+    pub report: &'a Option<Result<PathBuf>>,
+    // Whether the summary of the finished run was copied.
+    pub shared: Option<Shared>,
+    // Where the moves of the finished run were written in move notation.
+    pub notation: Option<&'a Result<PathBuf>>,
+    // Whether a practice run or a puzzle just ended.
+    pub practice_over: bool,
+    // The puzzle on the board in puzzle mode, and whether it is the last of
+    // its pack.
+    pub puzzle: Option<(&'a Puzzle, bool)>,
+    // The tutorial, while its lessons are played.
+    pub tutorial: Option<&'a Tutorial>,
+    // The history snapshot browsed in place of the game.
+    pub gallery: Option<usize>,
+    // The position of the finished game reviewed in place of the game.
+    pub review: Option<Review>,
+    pub show_moves: bool,
+    pub moves_scroll: usize,
+    pub show_merges: bool,
+    pub show_stats: bool,
+    pub show_debug: bool,
+    pub show_heatmap: bool,
+    // The AI's evaluation of each move, while it is shown.
+    pub analysis: Option<Analysis>,
+    // How long the previous frame took to draw.
+    pub render_time: Option<Duration>,
+    // How the AI's latest search went, for autoplay or a hint.
+    pub search: Option<SearchReport>,
+    pub show_help: bool,
+    pub show_leaderboard: bool,
+    // The global top scores shown under the leaderboard, when a leaderboard
+    // server is configured.
+    pub global_scores: Option<&'a GlobalScores>,
+    pub show_statistics: bool,
+    pub confirming_restart: bool,
+    pub confirming_quit: bool,
+    pub celebrating: bool,
+    pub pasted: Option<&'a Result<Import>>,
+    pub menu: Option<PauseMenu>,
+    pub slot_screen: Option<&'a SlotScreen>,
+    pub slots: &'a [Slot],
+    // The power-up whose tiles are being picked, with the cells picked so far
+    // and the one under the cursor last.
+    pub targeting: Option<(PowerUp, Vec<(usize, usize)>)>,
+}
+
+const CELL_WIDTH: u16 = 11;
+const CELL_HEIGHT: u16 = 5;
+const SCORE_HEIGHT: u16 = 1;
+const CELL_PADDING_X: u16 = 1;
+const BORDER_WIDTH: u16 = 1;
+const SCOREBOARD_WIDTH: u16 = 42;
+const PANEL_WIDTH: u16 = 20;
+const TUTORIAL_WIDTH: u16 = 28;
+const STATS_HEIGHT: u16 = 5 + (BORDER_WIDTH * 2);
+// A bar for each move, then how the latest move compared.
+const ANALYSIS_HEIGHT: u16 = 5 + (BORDER_WIDTH * 2);
+const BAR_WIDTH: usize = 8;
+const SLOTS_WIDTH: u16 = 68;
+const MAX_SLOT_ROWS: usize = 10;
+const PREVIEW_CELL_WIDTH: u16 = 5;
+
+// Width of the gauge shown while the restart key is held.
+const HOLD_GAUGE_WIDTH: usize = 10;
+
+// Below this much time left in blitz mode, the clock turns red.
+const BLITZ_WARNING: Duration = Duration::from_secs(30);
+
+fn calculate_game_dimensions(shape: Shape) -> (u16, u16) {
+    let width = shape.cols() as u16 * (CELL_WIDTH + CELL_PADDING_X)
+        + CELL_PADDING_X
+        + (BORDER_WIDTH * 2);
+    let height =
+        shape.rows() as u16 * CELL_HEIGHT + SCORE_HEIGHT + (BORDER_WIDTH * 2);
+    (width, height)
+}
+
+// What the board's border shows besides the title.
+#[derive(Default)]
+struct BoardTitles<'a> {
+    // How far the restart key has been held, in hold mode.
+    hold_progress: Option<f64>,
+    seed: Option<u64>,
+    autoplay: bool,
+    hint: Option<char>,
+    // Time left to play, in blitz mode.
+    time_left: Option<Duration>,
+    // What became of the latest position export or load.
+    position: Option<Line<'static>>,
+    // The tile that wins the game, shown as the title.
+    target: Option<u32>,
+    // The player profile, shown after the title.
+    profile: Option<&'a str>,
+    // The difficulty, shown in the title unless normal.
+    difficulty: Difficulty,
+    // The puzzle being solved and how it stands.
+    puzzle: Option<(&'a Puzzle, PuzzleStatus)>,
+    // Whether the board's lines wrap around.
+    wrap: bool,
+    // Whether the border flashes after a move that changed nothing.
+    flash: bool,
+    // Whether the spawn heatmap is shown in place of the tiles.
+    heatmap: bool,
+    // The power-up whose tiles are being picked.
+    targeting: Option<PowerUp>,
+    // The moves in a row that merged, when they score by combo.
+    combo: Option<u32>,
+    // The challenge changing what the move keys do, so the player knows why
+    // the controls feel wrong.
+    challenge: Option<Challenge>,
+}
+
+// Render the border and title around the tiles area, with the seed, the
+// state of the helpers and, while the restart key is held, a gauge on the
+// bottom border
+fn render_board(
+    outcome: &ActionOutcome,
+    titles: &BoardTitles<'_>,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let style = if outcome.game_over || titles.flash {
+        theme.board_over
+    } else {
+        theme.board
+    };
+
+    // A puzzle takes the title with its name, objective and the moves left.
+    let title = match (titles.puzzle, titles.target) {
+        (Some((puzzle, status)), _) => {
+            let goal = match status {
+                PuzzleStatus::Playing { moves_left: 1 } => {
+                    "1 move left".to_string()
+                }
+                PuzzleStatus::Playing { moves_left } => {
+                    format!("{moves_left} moves left")
+                }
+                PuzzleStatus::Solved => "solved".to_string(),
+                PuzzleStatus::Failed => "failed".to_string(),
+            };
+            format!(" {}: {} · {goal} ", puzzle.name, puzzle.objective)
+        }
+        (None, Some(target)) if !titles.difficulty.is_normal() => {
+            format!(" {target} · {} ", difficulty_name(titles.difficulty))
+        }
+        (None, Some(target)) => format!(" {target} "),
+        (None, None) => TITLE.to_string(),
+    };
+    let mut block = Block::bordered()
+        .border_type(theme.border)
+        .border_style(style)
+        .title(title)
+        .title_style(theme.title);
+    // Players sharing the game can tell whose profile it is being played in.
+    if let Some(profile) = titles.profile {
+        block = block.title(Line::from(format!(" {profile} ")).dim());
+    }
+    if let Some(time_left) = titles.time_left {
+        // Round up so the clock shows 0:00 only once time is up.
+        let secs =
+            time_left.as_secs() + u64::from(time_left.subsec_nanos() > 0);
+        let clock = Line::from(format!(" ⏱ {} ", format_duration(secs)));
+        block = block.title(if time_left < BLITZ_WARNING {
+            clock.style(theme.alert).bold().centered()
+        } else {
+            clock.centered()
+        });
+    }
+    // The seed is shown so the game can be replayed with `--seed`.
+    if let Some(seed) = titles.seed {
+        block = block.title_bottom(Line::from(format!(" Seed {seed} ")).dim());
+    }
+    // The combo meter fills a notch for each move in a row that merged.
+    if let Some(combo) = titles.combo {
+        let filled = combo.min(MAX_COMBO) as usize;
+        let meter = format!(
+            " Combo x{} {}{} ",
+            Scoring::Combo.multiplier(combo),
+            "▰".repeat(filled),
+            "▱".repeat(MAX_COMBO as usize - filled)
+        );
+        let meter = Line::from(meter).style(theme.merged);
+        block = block.title_bottom(match combo {
+            0 => meter.dim(),
+            1 => meter,
+            _ => meter.bold(),
+        });
+    }
+    if titles.heatmap {
+        block = block
+            .title(Line::from(" Spawn heatmap ").magenta().right_aligned());
+    }
+    if let Some(power_up) = titles.targeting {
+        let tiles = match power_up.cells() {
+            1 => "a tile".to_string(),
+            n => format!("{n} tiles"),
+        };
+        block = block.title(
+            Line::from(format!(" {}: pick {tiles} ", power_up_name(power_up)))
+                .yellow()
+                .right_aligned(),
+        );
+    }
+    if let Some(arrow) = titles.hint {
+        block = block.title(
+            Line::from(format!(" Hint {arrow} "))
+                .green()
+                .right_aligned(),
+        );
+    }
+    if titles.wrap {
+        block =
+            block.title_bottom(Line::from(" ⟲ Wrap ").dim().right_aligned());
+    }
+    if titles.autoplay {
+        block =
+            block.title_bottom(Line::from(" Autoplay ").cyan().right_aligned());
+    }
+    if let Some(challenge) = titles.challenge {
+        block = block.title_bottom(
+            Line::from(format!(" {} ", challenge.label()))
+                .style(theme.alert)
+                .right_aligned(),
+        );
+    }
+    if let Some(position) = &titles.position {
+        block = block.title_bottom(position.clone().centered());
+    }
+    if let Some(progress) = titles.hold_progress {
+        let filled = (progress * HOLD_GAUGE_WIDTH as f64).round() as usize;
+        let gauge = format!(
+            " Restart {}{} ",
+            "█".repeat(filled),
+            "░".repeat(HOLD_GAUGE_WIDTH - filled)
+        );
+        block = block
+            .title_bottom(Line::from(gauge).style(theme.overlay).centered());
+    }
+
+    frame.render_widget(block, area);
+}
+
+// Splits the tiles area into the rectangle of each cell of a board with the
+// given rows and columns.
+// Draws tiles in the colors of the theme, labelled the way the player chose.
+struct Tiles<'a> {
+    theme: &'a Theme,
+    labels: LabelMode,
+}
+
+impl Palette for Tiles<'_> {
+    fn tile(&self, value: u32) -> Style {
+        self.theme.tile(value).style()
+    }
+
+    fn wall(&self) -> Style {
+        self.theme.wall().style()
+    }
+
+    fn cell(&self, merged: bool) -> Style {
+        if merged {
+            self.theme.merged
+        } else {
+            self.theme.cell
+        }
+    }
+
+    fn tile_border(&self, value: u32) -> border::Set<'static> {
+        self.theme.tile_border(value)
+    }
+
+    fn label(&self, value: u32) -> String {
+        self.labels.format(value)
+    }
+
+    fn large_label(
+        &self,
+        value: u32,
+        width: usize,
+        height: usize,
+    ) -> Option<Vec<String>> {
+        if self.labels != LabelMode::Large {
+            return None;
+        }
+        glyph::render(&self.labels.format(value), width, height)
+    }
+}
+
+// Renders the tiles of a move inside the border of the board, `slide` of the
+// way into place while they are still moving, with the `selected` cells
+// outlined.
+fn render_tiles(
+    board: &[Vec<CellResult>],
+    slide: Option<f64>,
+    selected: &[(usize, usize)],
+    labels: LabelMode,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let palette = Tiles { theme, labels };
+    let mut widget = BoardWidget::new(board).palette(&palette).select(selected);
+    if let Some(progress) = slide {
+        widget = widget.slide(progress);
+    }
+    let inside =
+        area.inner(Margin::new(BORDER_WIDTH + CELL_PADDING_X, BORDER_WIDTH));
+    frame.render_widget(widget, inside);
+}
+
+// Shades the cells of the spawn heatmap from cold to hot by how many tiles
+// spawned on them, relative to the cell most tiles spawned on.
+struct Heat {
+    most: u32,
+}
+
+impl Palette for Heat {
+    fn tile(&self, spawns: u32) -> Style {
+        let heat = f64::from(spawns) / f64::from(self.most.max(1));
+        let lerp = |cold: u8, hot: u8| {
+            (f64::from(cold) + (f64::from(hot) - f64::from(cold)) * heat)
+                .round() as u8
+        };
+        Style::new().fg(Color::White).bg(Color::Rgb(
+            lerp(40, 220),
+            lerp(60, 50),
+            lerp(140, 40),
+        ))
+    }
+}
+
+// Renders, in place of the tiles, how many tiles the game has spawned on each
+// cell so far, so players can see for themselves whether spawns favor some
+// cells. Cells no tile spawned on are left empty.
+fn render_heatmap(game: &Game, area: Rect, frame: &mut Frame) {
+    let board = game.board();
+    let mut cells =
+        vec![vec![CellResult::default(); board.cols()]; board.rows()];
+    let mut most = 0;
+    for ((row, col), _) in board.iter_cells() {
+        let spawns = game.spawns_at(row, col);
+        most = most.max(spawns);
+        cells[row][col] = CellResult {
+            value: Some(spawns).filter(|&spawns| spawns > 0),
+            wall: board.is_wall(row, col),
+            void: board.is_void(row, col),
+            ..Default::default()
+        };
+    }
+    let palette = Heat { most };
+    let inside =
+        area.inner(Margin::new(BORDER_WIDTH + CELL_PADDING_X, BORDER_WIDTH));
+    frame.render_widget(BoardWidget::new(&cells).palette(&palette), inside);
+}
+
+// Render the score next to the best one, highlighted while the game in
+// progress holds it.
+fn render_score(
+    score: u32,
+    best: u32,
+    new_best: bool,
+    points: Option<(u32, f64)>,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    const MIN_SCORE_WIDTH: usize = 6;
+    let best_style = if new_best { theme.best } else { theme.score };
+    // The points of the latest move fade out on the left, so the scores
+    // stay in place.
+    let points = points.map(|(points, progress)| {
+        let style = match progress {
+            ..0.4 => theme.merged.bold(),
+            ..0.7 => theme.merged,
+            _ => theme.merged.dim(),
+        };
+        Span::styled(format!("+{points}  "), style)
+    });
+    let score_text = Line::from_iter(points.into_iter().chain([
+        Span::styled(format!("Best: {best:>MIN_SCORE_WIDTH$}"), best_style),
+        Span::styled(
+            format!("  Score: {score:>MIN_SCORE_WIDTH$} "),
+            theme.score,
+        ),
+    ]));
+    frame.render_widget(Paragraph::new(score_text).right_aligned(), area);
+}
+
+fn difficulty_name(difficulty: Difficulty) -> &'static str {
+    match difficulty {
+        Difficulty::Normal => "Normal",
+        Difficulty::Hard => "Hard",
+        Difficulty::Expert => "Expert",
+    }
+}
+
+fn power_up_name(power_up: PowerUp) -> &'static str {
+    match power_up {
+        PowerUp::Swap => "Swap",
+        PowerUp::Delete => "Delete",
+        PowerUp::Upgrade => "Upgrade",
+    }
+}
+
+// Render the power-ups left to play on the left of the score line, each as
+// its symbol and count, dimmed while none is left.
+fn render_power_ups(game: &Game, theme: &Theme, area: Rect, frame: &mut Frame) {
+    let spans = PowerUp::ALL.into_iter().map(|power_up| {
+        let symbol = match power_up {
+            PowerUp::Swap => '⇄',
+            PowerUp::Delete => '✕',
+            PowerUp::Upgrade => '⇧',
+        };
+        let left = game.power_ups_left(power_up);
+        let span = Span::styled(format!(" {symbol} {left} "), theme.score);
+        if left == 0 { span.dim() } else { span }
+    });
+    frame.render_widget(Line::from_iter(spans), area);
+}
+
+// Render the end of a puzzle: solved, with the key to the next one, or failed.
+fn render_puzzle_over(
+    view: &GameView,
+    puzzle: &Puzzle,
+    last: bool,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let key = |command| {
+        view.keymap
+            .keys(command)
+            .first()
+            .map_or("unbound".to_string(), |key| key.to_string())
+    };
+    let (restart, quit) = (key(Command::Restart), key(Command::Quit));
+    let theme = &view.theme;
+    let keys = format!("{restart}: try again   {quit}: quit");
+    if puzzle.status(view.game) == PuzzleStatus::Solved {
+        let next = if last {
+            "Last puzzle of the pack"
+        } else {
+            "enter: next puzzle"
+        };
+        let text = format!(
+            "{} solved in {} of {} moves\n\n{next}\n{keys}",
+            puzzle.name,
+            view.game.moves().len(),
+            puzzle.moves
+        );
+        render_prompt(" Solved! ", &text, theme.win, theme, area, frame);
+    } else {
+        let text = format!(
+            "Goal: {} in {} moves\n\n{keys}",
+            puzzle.objective, puzzle.moves
+        );
+        let title = " Puzzle Failed ";
+        render_prompt(title, &text, theme.alert, theme, area, frame);
+    }
+}
+
+// Render the lesson of the tutorial: what it shows and the key to press, then
+// once the move is played what the move did and the key to go on.
+fn render_tutorial(
+    view: &GameView,
+    tutorial: &Tutorial,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let key = |command| {
+        view.keymap
+            .keys(command)
+            .first()
+            .map_or("unbound".to_string(), |key| key.to_string())
+    };
+    let lesson = tutorial.lesson();
+    let (number, count) = tutorial.progress();
+    let (text, next) = if !tutorial.is_played() {
+        let goal =
+            format!("Press {} to {}.", key(lesson.command()), lesson.goal);
+        (lesson.intro, goal)
+    } else if number < count {
+        (lesson.outro, "Press enter for the next lesson.".to_string())
+    } else {
+        (lesson.outro, "Press enter to start playing.".to_string())
+    };
+    let theme = &view.theme;
+    let skip = format!(" {}: skip ", key(Command::Pause));
+    let block = Block::bordered()
+        .title(format!(" Tutorial {number}/{count} "))
+        .title_style(theme.title)
+        .title_bottom(Line::from(skip).dim().right_aligned())
+        .padding(Padding::horizontal(1));
+    let lines = vec![
+        Line::from(lesson.name).bold(),
+        Line::default(),
+        Line::from(text),
+        Line::default(),
+        Line::from(next).bold(),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: true }).block(block),
+        area,
+    );
+}
+
+// The best finished runs ranked by score, with the run at `highlight` in the
+// history standing out.
+fn leaderboard_table(
+    top: Vec<(usize, &GameRecord)>,
+    highlight: Option<usize>,
+    theme: &Theme,
+) -> Table<'static> {
+    let rows = top.into_iter().enumerate().map(|(rank, (index, run))| {
+        let row = Row::new([
+            format!("{}", rank + 1),
+            run.score.to_string(),
+            match run.max_tile {
+                0 => "-".to_string(),
+                tile => tile.to_string(),
+            },
+            run.finished_at.format("%Y-%m-%d").to_string(),
+            format_duration(run.duration_secs),
+            // Games the AI finished or a tool played in rank alongside the
+            // player's, marked.
+            run.helper().unwrap_or_default().to_string(),
+        ]);
+        if Some(index) == highlight {
+            row.style(theme.best)
+        } else {
+            row
+        }
+    });
+
+    let widths = [
+        Constraint::Length(2),
+        Constraint::Length(7),
+        Constraint::Length(6),
+        Constraint::Length(10),
+        Constraint::Fill(1),
+        Constraint::Length(2),
+    ];
+    Table::new(rows, widths)
+        .header(Row::new(["#", "Score", "Tile", "Date", "Time", ""]).bold())
+}
+
+// Render the final stats of the game that just ended, the top scores with
+// its run highlighted and how to start over or quit.
+fn render_game_over(view: &GameView, area: Rect, frame: &mut Frame) {
+    let Some(finished) = view.finished_run.and_then(|i| view.history.run(i))
+    else {
+        return;
+    };
+    let top = view.history.top(TOP_RUNS, view.game.difficulty());
+    // Ties keep the earlier run first, so topping the board means beating
+    // every earlier score.
+    let new_best = top.first().map(|&(index, _)| index) == view.finished_run;
+    let table_height = top.len() as u16 + 1;
+    let stats_height = 2
+        + u16::from(new_best)
+        + u16::from(finished.handoff.is_some())
+        + u16::from(view.shared.is_some())
+        + u16::from(view.notation.is_some());
+    let height = stats_height + 1 + table_height + 1 + 1 + (BORDER_WIDTH * 2);
+    let area = area.centered(
+        Constraint::Length(SCOREBOARD_WIDTH),
+        Constraint::Length(height),
+    );
+
+    // The prompts name the keys of the player's keymap.
+    let key = |command| {
+        view.keymap
+            .keys(command)
+            .first()
+            .map_or("unbound".to_string(), |key| key.to_string())
+    };
+    let theme = &view.theme;
+    let block = Block::bordered()
+        .border_style(theme.alert)
+        .title(" Game Over ")
+        .title_style(theme.title)
+        .title(
+            Line::from(format!(
+                " {}: review · {}: moves ",
+                key(Command::ToggleReview),
+                key(Command::ExportNotation)
+            ))
+            .dim()
+            .right_aligned(),
+        )
+        .title_bottom(report_status(view.report).centered());
+    let [stats_area, _, table_area, _, prompt_area] = Layout::vertical([
+        Constraint::Length(stats_height),
+        Constraint::Length(1),
+        Constraint::Length(table_height),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(block.inner(area));
+
+    let mut stats = vec![
+        Line::from(format!(
+            "Score {}   Best tile {}",
+            finished.score, finished.max_tile
+        )),
+        Line::from(format!(
+            "Moves {}   Time {}",
+            view.game.moves().len(),
+            format_duration(finished.duration_secs)
+        )),
+    ];
+    if new_best {
+        stats.push(Line::from("New high score!").style(theme.best));
+    }
+    if let Some(handoff) = finished.handoff {
+        stats.push(
+            Line::from(format!(
+                "AI took it from {} to {} (+{})",
+                handoff.score,
+                finished.score,
+                finished.score.saturating_sub(handoff.score)
+            ))
+            .cyan(),
+        );
+    }
+    match view.shared {
+        Some(Shared::Copied) => {
+            stats.push(Line::from("Summary copied").green());
+        }
+        Some(Shared::Deferred) => {
+            stats.push(Line::from("No clipboard, summary printed on exit"));
+        }
+        None => {}
+    }
+    match view.notation {
+        Some(Ok(path)) => {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            stats.push(Line::from(format!("Moves saved to {name}")).green());
+        }
+        Some(Err(_)) => {
+            stats.push(Line::from("The moves cannot be written down").red());
+        }
+        None => {}
+    }
+    let stats = Paragraph::new(stats).centered().bold();
+    let table = leaderboard_table(top, view.finished_run, theme);
+
+    let prompt = format!(
+        "{}: restart   {}: copy   {}: quit",
+        key(Command::Restart),
+        key(Command::CopySummary),
+        key(Command::Quit)
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(stats, stats_area);
+    frame.render_widget(table, table_area);
+    frame.render_widget(Paragraph::new(prompt).centered().dim(), prompt_area);
+}
+
+// Render the end of a practice run, which has no place in the history.
+fn render_practice_over(view: &GameView, area: Rect, frame: &mut Frame) {
+    let key = |command| {
+        view.keymap
+            .keys(command)
+            .first()
+            .map_or("unbound".to_string(), |key| key.to_string())
+    };
+    let text = format!(
+        "Practice run over with {} points\n\n{}: try again   {}: quit",
+        view.game.score(),
+        key(Command::Restart),
+        key(Command::Quit)
+    );
+    let theme = &view.theme;
+    render_prompt(" Game Over ", &text, theme.alert, theme, area, frame);
+}
+
+// Render a past final board from the history in place of the game, with its
+// score and date below.
+fn render_gallery(
+    view: &GameView,
+    index: usize,
+    tiles_area: Rect,
+    scores_area: Rect,
+    frame: &mut Frame,
+) {
+    let snapshots = view.history.snapshots();
+    let Some((run, board)) = snapshots.get(index) else {
+        frame.render_widget(
+            Paragraph::new("No finished games yet")
+                .centered()
+                .block(Block::bordered().title(" Gallery ")),
+            tiles_area,
+        );
+        return;
+    };
+
+    frame.render_widget(
+        Block::bordered()
+            .border_type(BorderType::Thick)
+            .title(format!(" Gallery {}/{} ", index + 1, snapshots.len()))
+            .title_style(view.theme.title)
+            .title_bottom(Line::from(" ←/→ browse · G close ").centered()),
+        tiles_area,
+    );
+
+    render_tiles(
+        &still_cells(board),
+        None,
+        &[],
+        view.labels,
+        &view.theme,
+        tiles_area,
+        frame,
+    );
+
+    let date = run.finished_at.format("%Y-%m-%d");
+    let text = format!("{date}  Score: {} ", run.score);
+    frame.render_widget(Paragraph::new(text).right_aligned(), scores_area);
+}
+
+// Render a position of the game that just ended in place of the game, with
+// the move played from it and the move the AI prefers below.
+fn render_review(
+    view: &GameView,
+    review: &Review,
+    tiles_area: Rect,
+    scores_area: Rect,
+    frame: &mut Frame,
+) {
+    frame.render_widget(
+        Block::bordered()
+            .border_type(BorderType::Thick)
+            .title(format!(" Review {}/{} ", review.index + 1, review.count))
+            .title_style(view.theme.title)
+            .title_bottom(Line::from(" ←/→ step · R close ").centered()),
+        tiles_area,
+    );
+    render_tiles(
+        &still_cells(&review.board),
+        None,
+        &[],
+        view.labels,
+        &view.theme,
+        tiles_area,
+        frame,
+    );
+
+    let choice = match (review.played, review.best) {
+        (None, _) => Line::from(" Final board"),
+        (Some(played), Some(best)) if played == best => {
+            Line::from(format!(" Played {} as AI", played.arrow())).green()
+        }
+        (Some(played), Some(best)) => Line::from(format!(
+            " Played {} · AI {}",
+            played.arrow(),
+            best.arrow()
+        ))
+        .yellow(),
+        (Some(played), None) => {
+            Line::from(format!(" Played {} · AI …", played.arrow()))
+        }
+    };
+    let score = format!("Score: {} ", review.score);
+    frame.render_widget(choice, scores_area);
+    frame.render_widget(Paragraph::new(score).right_aligned(), scores_area);
+}
+
+// The cells of a board standing still, as no move left them.
+fn still_cells(board: &Board) -> Vec<Vec<CellResult>> {
+    let mut cells =
+        vec![vec![CellResult::default(); board.cols()]; board.rows()];
+    for ((row, col), value) in board.iter_cells() {
+        cells[row][col].value = value;
+    }
+    cells
+}
+
+// Describes the end-of-game report: how to save it, or where it went.
+fn report_status(report: &Option<Result<PathBuf>>) -> Line<'static> {
+    match report {
+        None => Line::from(" X: save a report ").dim(),
+        Some(Ok(path)) => {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            Line::from(format!(" Saved {name} ")).green()
+        }
+        Some(Err(_)) => Line::from(" Failed to save the report ").red(),
+    }
+}
+
+// Render a bordered message centered over the tiles area.
+fn render_prompt(
+    title: &str,
+    text: &str,
+    style: Style,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let area = area.centered(
+        Constraint::Length(area.width.saturating_sub(4)),
+        Constraint::Length(text.lines().count() as u16 + 2),
+    );
+
+    let prompt = Paragraph::new(text).centered().block(
+        Block::bordered()
+            .border_style(style)
+            .title(title)
+            .title_style(theme.title),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(prompt, area);
+}
+
+// Render the confirmation shown after a position is pasted, or why the paste
+// was rejected.
+fn render_paste_prompt(
+    pasted: &Result<Import>,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    match pasted {
+        Ok(_) => render_prompt(
+            " Paste ",
+            "Load the pasted position? (y/n)",
+            theme.overlay,
+            theme,
+            area,
+            frame,
+        ),
+        Err(e) => render_prompt(
+            " Invalid Paste ",
+            &format!("{e:#}\n\nPress any key"),
+            theme.alert,
+            theme,
+            area,
+            frame,
+        ),
+    }
+}
+
+// Render the most recent moves that fit in the panel, ending `scroll` moves
+// before the latest.
+fn render_moves(
+    moves: &[MoveRecord],
+    scroll: usize,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let visible = area.height.saturating_sub(BORDER_WIDTH * 2) as usize;
+    let end = moves.len().saturating_sub(scroll);
+    let start = end.saturating_sub(visible);
+
+    let lines: Vec<Line> = moves[start..end]
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let number = format!("{:>4}.", start + i + 1);
+            let points = match m.points {
+                0 => String::new(),
+                points => format!(" +{points}"),
+            };
+            Line::from(format!("{number} {}{points}", m.action.arrow()))
+        })
+        .collect();
+
+    let title = match scroll {
+        0 => " Moves ".to_string(),
+        _ => format!(" Moves (-{scroll}) "),
+    };
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::bordered().title(title).title_style(theme.title)),
+        area,
+    );
+}
+
+// Render the latest notable merges that fit in the panel.
+fn render_merges(
+    merges: &[Merge],
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let visible = area.height.saturating_sub(BORDER_WIDTH * 2) as usize;
+    let lines: Vec<Line> = merges[merges.len().saturating_sub(visible)..]
+        .iter()
+        .map(|merge| {
+            Line::from(format!("{} at move {}", merge.tile, merge.move_number))
+        })
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::bordered().title(" Merges ").title_style(theme.title),
+        ),
+        area,
+    );
+}
+
+// Render the stats of the current game, with its play time so far.
+fn render_stats(
+    stats: &GameStats,
+    elapsed: Duration,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let lines = [
+        ("Moves", stats.moves.to_string()),
+        ("Merges", stats.merges.to_string()),
+        ("Spawns", stats.spawns.to_string()),
+        ("Best tile", stats.highest_tile.to_string()),
+        ("Time", format_duration(elapsed.as_secs())),
+    ]
+    .map(|(name, value)| Line::from(format!("{name:<10}{value:>8}")));
+
+    frame.render_widget(
+        Paragraph::new(lines.to_vec())
+            .block(Block::bordered().title(" Stats ").title_style(theme.title)),
+        area,
+    );
+}
+
+// Render a bar for each move, as long as the AI thinks the move is good next
+// to the others, with the move it prefers highlighted, and below how the
+// player's latest move compared with the AI's choice.
+fn render_analysis(
+    analysis: &Analysis,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let mut lines: Vec<Line> = match &analysis.evaluations {
+        None => vec![Line::from("Searching…").dim()],
+        Some(evaluations) => {
+            let values = evaluations.iter().filter_map(|e| e.value);
+            let low = values.clone().fold(f64::INFINITY, f64::min);
+            let high = values.fold(f64::NEG_INFINITY, f64::max);
+            let best = ai::preferred(evaluations);
+            evaluations
+                .iter()
+                .map(|evaluation| {
+                    let arrow = evaluation.action.arrow();
+                    let Some(value) = evaluation.value else {
+                        return Line::from(format!("{arrow} {:>16}", "-"))
+                            .dim();
+                    };
+                    // Every legal move gets at least a notch, the best a
+                    // full bar.
+                    let share = if high > low {
+                        (value - low) / (high - low)
+                    } else {
+                        1.0
+                    };
+                    let filled =
+                        1 + (share * (BAR_WIDTH - 1) as f64).round() as usize;
+                    let line = Line::from(format!(
+                        "{arrow} {}{} {value:>7.1}",
+                        "█".repeat(filled),
+                        " ".repeat(BAR_WIDTH - filled)
+                    ));
+                    if best == Some(evaluation.action) {
+                        line.style(theme.merged).bold()
+                    } else {
+                        line
+                    }
+                })
+                .collect()
+        }
+    };
+    lines.push(match analysis.last {
+        Some((played, best)) if played == best => {
+            Line::from(format!("Played {} as AI", played.arrow())).green()
+        }
+        Some((played, best)) => Line::from(format!(
+            "Played {} · AI {}",
+            played.arrow(),
+            best.arrow()
+        ))
+        .yellow(),
+        None => Line::default(),
+    });
+
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::bordered()
+                .title(" Analysis ")
+                .title_style(theme.title),
+        ),
+        area,
+    );
+}
+
+// Height of the debug panel for a board with this many rows: the seed takes
+// two lines, then come the board, the empty cells, the evaluation, the AI's
+// depth and speed and the frame time.
+fn debug_height(rows: usize) -> u16 {
+    rows as u16 + 7 + (BORDER_WIDTH * 2)
+}
+
+// Render the internal state of the game for debugging: the seed in hex, the
+// rank of every cell as stored in the board, the number of empty cells, the
+// AI's evaluation of the position the last move left, the depth its latest
+// search reached and the positions it visited per second, and how long the
+// previous frame took to draw.
+fn render_debug(view: &GameView, theme: &Theme, area: Rect, frame: &mut Frame) {
+    let game = view.game;
+    let board = game.board();
+    let mut lines = vec![
+        Line::from("Seed"),
+        Line::from(format!("{:#018x}", game.seed())).right_aligned(),
+    ];
+    lines.extend((0..board.rows()).map(|row| {
+        let ranks: Vec<String> = (0..board.cols())
+            .map(|col| {
+                if board.is_wall(row, col) {
+                    "#".to_string()
+                } else if board.is_void(row, col) {
+                    "~".to_string()
+                } else {
+                    format!("{:x}", board.rank(row, col))
+                }
+            })
+            .collect();
+        Line::from(ranks.join(" ")).centered()
+    }));
+    let render_time = view.render_time.map_or("-".to_string(), |time| {
+        format!("{:.1}ms", time.as_secs_f64() * 1000.0)
+    });
+    // The depth the AI reached within its limits, and how fast it searched.
+    let (depth, speed) =
+        view.search
+            .map_or(("-".to_string(), "-".to_string()), |search| {
+                (
+                    search.depth.to_string(),
+                    format!("{:.0}k/s", search.nodes_per_sec() / 1000.0),
+                )
+            });
+    lines.extend(
+        [
+            ("Empty", board.empty_cells().count().to_string()),
+            ("Eval", format!("{:.1}", ai::heuristic(board))),
+            ("Depth", depth),
+            ("Nodes", speed),
+            ("Frame", render_time),
+        ]
+        .map(|(name, value)| Line::from(format!("{name:<6}{value:>12}"))),
+    );
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(Block::bordered().title(" Debug ").title_style(theme.title)),
+        area,
+    );
+}
+
+pub fn draw(frame: &mut Frame, view: &GameView) {
+    let theme = &view.theme;
+    if let Some(menu) = view.title {
+        if view.show_leaderboard {
+            return render_leaderboard(view, frame);
+        }
+        if view.show_statistics {
+            return render_statistics(view, frame);
+        }
+        return render_title(
+            menu,
+            view.report_settings,
+            view.warnings,
+            theme,
+            frame,
+        );
+    }
+    let (main_width, main_height) =
+        calculate_game_dimensions(view.game.board().shape());
+    if frame.area().width < main_width || frame.area().height < main_height {
+        return render_too_small((main_width, main_height), frame);
+    }
+    // The tutorial sits beside the board in place of the side panel, or over
+    // the bottom of the tiles when it does not fit there.
+    let tutorial_beside = view.tutorial.is_some()
+        && frame.area().width >= main_width + TUTORIAL_WIDTH;
+    let tutorial_width = if tutorial_beside { TUTORIAL_WIDTH } else { 0 };
+    // The side panel is left out when it does not fit beside the board.
+    let show_panel = (view.show_moves
+        || view.show_merges
+        || view.show_stats
+        || view.show_debug
+        || view.analysis.is_some())
+        && !tutorial_beside
+        && frame.area().width >= main_width + PANEL_WIDTH;
+    let panel_width = if show_panel { PANEL_WIDTH } else { 0 };
+
+    // Center the game area, and the side panel beside it, within the
+    // terminal frame
+    let area = frame.area().centered(
+        Constraint::Length(main_width + panel_width + tutorial_width),
+        Constraint::Length(main_height),
+    );
+    let [game_area, panel_area, tutorial_area] = Layout::horizontal([
+        Constraint::Length(main_width),
+        Constraint::Length(panel_width),
+        Constraint::Length(tutorial_width),
+    ])
+    .areas(area);
+
+    // The game stats, the move evaluations and the debug panel sit on top of
+    // the side panel, and the move history and merge log share the rest when
+    // both are shown
+    let stats_height = if view.show_stats { STATS_HEIGHT } else { 0 };
+    let analysis_height = if view.analysis.is_some() {
+        ANALYSIS_HEIGHT
+    } else {
+        0
+    };
+    let debug_height = if view.show_debug {
+        debug_height(view.game.board().rows())
+    } else {
+        0
+    };
+    let [
+        stats_area,
+        analysis_area,
+        debug_area,
+        moves_area,
+        merges_area,
+    ] = Layout::vertical([
+        Constraint::Length(stats_height),
+        Constraint::Length(analysis_height),
+        Constraint::Length(debug_height),
+        Constraint::Fill(u16::from(view.show_moves)),
+        Constraint::Fill(u16::from(view.show_merges)),
+    ])
+    .areas(panel_area);
+    if view.show_moves {
+        let (moves, scroll) = (view.game.moves(), view.moves_scroll);
+        render_moves(moves, scroll, theme, moves_area, frame);
+    }
+    if view.show_merges {
+        render_merges(view.game.merges(), theme, merges_area, frame);
+    }
+    if view.show_stats {
+        let elapsed = view.elapsed;
+        render_stats(view.game.stats(), elapsed, theme, stats_area, frame);
+    }
+    if let Some(analysis) = &view.analysis {
+        render_analysis(analysis, theme, analysis_area, frame);
+    }
+    if view.show_debug {
+        render_debug(view, theme, debug_area, frame);
+    }
+
+    // Split the game area into the tiles area and the score area
+    let game_layout = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(SCORE_HEIGHT),
+    ]);
+    let [tiles_area, scores_area] = game_layout.areas(game_area);
+
+    if let Some(index) = view.gallery {
+        render_gallery(view, index, tiles_area, scores_area, frame);
+    } else if let Some(review) = &view.review {
+        render_review(view, review, tiles_area, scores_area, frame);
+    } else {
+        let outcome = view.outcome;
+        let titles = BoardTitles {
+            hold_progress: view.hold_progress,
+            seed: Some(view.game.seed()),
+            autoplay: view.autoplay,
+            hint: view.hint,
+            time_left: view.time_left,
+            position: view.position.clone(),
+            target: Some(view.game.win_target()),
+            profile: view.profile,
+            difficulty: view.game.difficulty(),
+            puzzle: view
+                .puzzle
+                .map(|(puzzle, _)| (puzzle, puzzle.status(view.game))),
+            wrap: view.game.board().wraps(),
+            flash: view.flash,
+            heatmap: view.show_heatmap,
+            targeting: view.targeting.as_ref().map(|(power_up, _)| *power_up),
+            combo: (view.game.scoring() == Scoring::Combo)
+                .then(|| view.game.combo()),
+            challenge: view.challenge,
+        };
+        render_board(outcome, &titles, theme, tiles_area, frame);
+        if view.show_heatmap {
+            render_heatmap(view.game, tiles_area, frame);
+        } else {
+            let selected = view
+                .targeting
+                .as_ref()
+                .map_or(&[][..], |(_, cells)| cells.as_slice());
+            render_tiles(
+                &outcome.board,
+                view.animation,
+                selected,
+                view.labels,
+                theme,
+                tiles_area,
+                frame,
+            );
+        }
+        // The best score includes the game in progress once it pulls ahead,
+        // and stands out while it does.
+        let previous_best = view.history.best_score_at(view.game.difficulty());
+        let best = previous_best.max(outcome.score);
+        let new_best = view.finished_run.is_none()
+            && previous_best > 0
+            && outcome.score > previous_best;
+        if view.game.has_power_ups() {
+            render_power_ups(view.game, theme, scores_area, frame);
+        }
+        let points = view.points;
+        render_score(
+            outcome.score,
+            best,
+            new_best,
+            points,
+            theme,
+            scores_area,
+            frame,
+        );
+
+        if view.finished_run.is_some() {
+            render_game_over(view, tiles_area, frame);
+        } else if let Some((puzzle, last)) = view.puzzle
+            && view.practice_over
+        {
+            render_puzzle_over(view, puzzle, last, tiles_area, frame);
+        } else if view.practice_over {
+            render_practice_over(view, tiles_area, frame);
+        }
+
+        if let Some(tutorial) = view.tutorial {
+            let area = if tutorial_beside {
+                tutorial_area
+            } else {
+                let [_, bottom] = Layout::vertical([
+                    Constraint::Fill(1),
+                    Constraint::Percentage(60),
+                ])
+                .areas(tiles_area.inner(Margin::new(1, 1)));
+                frame.render_widget(Clear, bottom);
+                bottom
+            };
+            render_tutorial(view, tutorial, area, frame);
+        }
+    }
+
+    if view.confirming_restart {
+        render_prompt(
+            " Restart ",
+            "Abandon this game and start over? (y/n)",
+            theme.overlay,
+            theme,
+            tiles_area,
+            frame,
+        );
+    }
+
+    if view.celebrating {
+        render_prompt(
+            " You Win! ",
+            &format!(
+                "You made the {} tile!\n\n\
+                 c: keep playing   r: restart",
+                view.game.win_target()
+            ),
+            theme.win,
+            theme,
+            tiles_area,
+            frame,
+        );
+    }
+
+    if let Some(pasted) = view.pasted {
+        render_paste_prompt(pasted, theme, tiles_area, frame);
+    }
+
+    if view.confirming_quit {
+        render_prompt(
+            " Quit ",
+            "Quit the game in progress? (y/n)",
+            theme.overlay,
+            theme,
+            tiles_area,
+            frame,
+        );
+    }
+
+    if let Some(menu) = view.menu {
+        frame.buffer_mut().set_style(game_area, Style::new().dim());
+        render_menu(menu, view.report_settings, theme, tiles_area, frame);
+    } else if view.paused {
+        frame.buffer_mut().set_style(game_area, Style::new().dim());
+        let paused = Paragraph::new(" Paused ").centered().bold();
+        frame.render_widget(
+            paused,
+            tiles_area.centered_vertically(Constraint::Length(1)),
+        );
+    }
+
+    if let Some(screen) = view.slot_screen {
+        render_slots(screen, view.slots, theme, tiles_area, frame);
+    }
+
+    if view.show_leaderboard {
+        render_leaderboard(view, frame);
+    }
+
+    if view.show_help {
+        render_help(view.keymap, view.mode, view.game, theme, frame);
+    }
+}
+
+// Render in place of the game, while the terminal is too small to hold the
+// board, how large it needs to be. The game is drawn again once it fits.
+fn render_too_small((width, height): (u16, u16), frame: &mut Frame) {
+    let area = frame.area();
+    let text = vec![
+        Line::from("Terminal too small").bold().yellow(),
+        Line::from(format!("Needed:  {width} x {height}")),
+        Line::from(format!("Current: {} x {}", area.width, area.height)),
+    ];
+    let lines = text.len() as u16;
+    frame.render_widget(
+        Paragraph::new(text).centered(),
+        area.centered_vertically(Constraint::Length(lines)),
+    );
+}
+
+// Render the best finished games of the profile over the whole screen, with
+// the latest finished run highlighted.
+fn render_leaderboard(view: &GameView, frame: &mut Frame) {
+    let theme = &view.theme;
+    let close = view
+        .keymap
+        .keys(Command::ToggleLeaderboard)
+        .first()
+        .map_or("Esc".to_string(), |key| key.to_string());
+    // Each difficulty has its own leaderboard, the current game's shown.
+    let difficulty = view.game.difficulty();
+    let title = if difficulty.is_normal() {
+        " Leaderboard ".to_string()
+    } else {
+        format!(" Leaderboard · {} ", difficulty_name(difficulty))
+    };
+    let top = view.history.top(TOP_RUNS, difficulty);
+    if top.is_empty() && view.global_scores.is_none() {
+        let text = format!("No finished games yet\n\n{close}: close");
+        return render_prompt(
+            &title,
+            &text,
+            theme.overlay,
+            theme,
+            frame.area(),
+            frame,
+        );
+    }
+
+    let local_height = top.len().max(1) as u16 + 1;
+    // The global scores follow the player's own under a heading.
+    let global_height = match view.global_scores {
+        None => 0,
+        Some(GlobalScores::Loaded(scores)) if !scores.is_empty() => {
+            scores.len() as u16 + 3
+        }
+        Some(_) => 3,
+    };
+    let height = local_height + global_height + (BORDER_WIDTH * 2);
+    let area = frame.area().centered(
+        Constraint::Length(SCOREBOARD_WIDTH),
+        Constraint::Length(height),
+    );
+    let block = Block::bordered()
+        .border_style(theme.overlay)
+        .title(title)
+        .title_style(theme.title)
+        .title_bottom(Line::from(format!(" {close}: close ")).centered())
+        .padding(Padding::horizontal(1));
+    let [local_area, global_area] = Layout::vertical([
+        Constraint::Length(local_height),
+        Constraint::Length(global_height),
+    ])
+    .areas(block.inner(area));
+
+    frame.render_widget(Clear, area);
+    if top.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No finished games yet").centered(),
+            local_area,
+        );
+    } else {
+        let table = leaderboard_table(top, view.finished_run, theme);
+        frame.render_widget(table, local_area);
+    }
+    if let Some(scores) = view.global_scores {
+        render_global_scores(scores, theme, global_area, frame);
+    }
+    frame.render_widget(block, area);
+}
+
+// Render the global top scores fetched from the leaderboard server, below a
+// blank line and a heading.
+fn render_global_scores(
+    scores: &GlobalScores,
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let [heading_area, list_area] =
+        Layout::vertical([Constraint::Length(2), Constraint::Fill(1)])
+            .areas(area);
+    frame.render_widget(
+        Paragraph::new(vec![Line::default(), Line::from("Global").bold()])
+            .centered()
+            .style(theme.title),
+        heading_area,
+    );
+    let message = match scores {
+        GlobalScores::Loading => "Loading…",
+        GlobalScores::Failed => "The leaderboard server cannot be reached",
+        GlobalScores::Loaded(scores) if scores.is_empty() => "No scores yet",
+        GlobalScores::Loaded(scores) => {
+            let rows = scores.iter().enumerate().map(|(rank, score)| {
+                Row::new([
+                    format!("{}", rank + 1),
+                    score.score.to_string(),
+                    match score.max_tile {
+                        0 => "-".to_string(),
+                        tile => tile.to_string(),
+                    },
+                    score.name.clone(),
+                ])
+            });
+            let widths = [
+                Constraint::Length(2),
+                Constraint::Length(7),
+                Constraint::Length(6),
+                Constraint::Fill(1),
+            ];
+            let table = Table::new(rows, widths)
+                .header(Row::new(["#", "Score", "Tile", "Player"]).bold());
+            frame.render_widget(table, list_area);
+            return;
+        }
+    };
+    frame.render_widget(Paragraph::new(message).centered().dim(), list_area);
+}
+
+// Number of ranges the scores are split into on the statistics screen.
+const SCORE_RANGES: usize = 8;
+
+// Render the all-time statistics of the profile: games played, win rate and
+// scores above a bar chart of the best tile reached in each game and one of
+// the scores, counted in ranges.
+fn render_statistics(view: &GameView, frame: &mut Frame) {
+    let theme = &view.theme;
+    let Some(summary) = view.history.summary() else {
+        let text = "No finished games yet\n\nEsc: close";
+        return render_prompt(
+            " Statistics ",
+            text,
+            theme.overlay,
+            theme,
+            frame.area(),
+            frame,
+        );
+    };
+    let ranges = view.history.score_ranges(SCORE_RANGES);
+
+    let stats = vec![
+        Line::from(format!(
+            "Games {}   Won {} ({:.1}%)   Time {}",
+            summary.games,
+            summary.wins,
+            summary.win_rate() * 100.0,
+            format_duration(summary.total_secs)
+        )),
+        Line::from(format!(
+            "Average score {}   Best score {}   Best tile {}",
+            summary.average_score, summary.best_score, summary.best_tile
+        )),
+    ];
+    let chart_height = summary.tiles.len().max(ranges.len()) as u16;
+    let height = stats.len() as u16 + 1 + chart_height + 2 + BORDER_WIDTH * 2;
+    let area = frame.area().centered(
+        Constraint::Length(SCOREBOARD_WIDTH + PANEL_WIDTH * 2),
+        Constraint::Length(height),
+    );
+    let block = Block::bordered()
+        .border_style(theme.overlay)
+        .title(" Statistics ")
+        .title_style(theme.title)
+        .title_bottom(Line::from(" Esc: close ").centered())
+        .padding(Padding::horizontal(1));
+    let [stats_area, _, charts_area] = Layout::vertical([
+        Constraint::Length(stats.len() as u16),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(block.inner(area));
+    let [tiles_area, scores_area] =
+        Layout::horizontal([Constraint::Fill(1); 2])
+            .spacing(1)
+            .areas(charts_area);
+
+    // Each tile's bar takes the color of the tile.
+    let tile_bars: Vec<Bar> = summary
+        .tiles
+        .iter()
+        .rev()
+        .map(|&(tile, games)| {
+            Bar::with_label(tile.to_string(), games as u64)
+                .style(Style::new().fg(theme.tile(tile).bg))
+        })
+        .collect();
+    let tiles = BarChart::horizontal(tile_bars).bar_gap(0).block(
+        Block::bordered()
+            .title(" Best tile ")
+            .border_style(theme.cell),
+    );
+    let score_bars: Vec<Bar> = ranges
+        .iter()
+        .rev()
+        .map(|&(score, games)| {
+            Bar::with_label(format!("{}k", score / 1000), games as u64)
+        })
+        .collect();
+    let scores = BarChart::horizontal(score_bars)
+        .bar_gap(0)
+        .bar_style(theme.score)
+        .block(Block::bordered().title(" Score ").border_style(theme.cell));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(Paragraph::new(stats).centered().bold(), stats_area);
+    frame.render_widget(tiles, tiles_area);
+    frame.render_widget(scores, scores_area);
+}
+
+// Render the help screen: the rules, what the game is doing and every command
+// with the keys bound to it in the player's keymap.
+fn render_help(
+    keymap: &Keymap,
+    mode: &str,
+    game: &Game,
+    theme: &Theme,
+    frame: &mut Frame,
+) {
+    let target = game.win_target();
+    let variant = game.variant();
+    let mut lines = vec![
+        Line::from("Slide the tiles: matching tiles that touch merge into"),
+        Line::from("one, and a new tile appears after every move. Make the"),
+        Line::from(format!("{target} tile to win; the game ends when no move")),
+        Line::from("is left."),
+        Line::from(""),
+        Line::from(format!("Mode: {mode}")).style(theme.title),
+    ];
+    if !variant.is_classic() {
+        lines.push(
+            Line::from(format!("Variant: {variant}, {}", variant.summary()))
+                .style(theme.title),
+        );
+    }
+    lines.push(Line::from(""));
+    lines.extend(keymap.bindings().map(|(command, keys)| {
+        let keys = match keys {
+            [] => "unbound".to_string(),
+            keys => keys
+                .iter()
+                .map(|key| key.to_string())
+                .collect::<Vec<_>>()
+                .join("  "),
+        };
+        Line::from(vec![
+            Span::from(format!("{:<18}", command.description())).bold(),
+            Span::from(keys),
+        ])
+    }));
+
+    let area = frame.area().centered(
+        Constraint::Length(60),
+        Constraint::Length(lines.len() as u16 + 2),
+    );
+    let help = Paragraph::new(lines).block(
+        Block::bordered()
+            .border_style(theme.overlay)
+            .title(" Help ")
+            .title_style(theme.title)
+            .padding(Padding::horizontal(1)),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(help, area);
+}
+
+// Render the prompt for a slot's name, or the slots to load with a preview of
+// the selected one.
+fn render_slots(
+    screen: &SlotScreen,
+    slots: &[Slot],
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let selected = match screen {
+        SlotScreen::Naming(name) => {
+            let text = format!("Name: {name}▏\n\nEnter: save   Esc: cancel");
+            let style = theme.overlay;
+            return render_prompt(
+                " Save Game ",
+                &text,
+                style,
+                theme,
+                area,
+                frame,
+            );
+        }
+        SlotScreen::Choosing(_) if slots.is_empty() => {
+            let text = "No saved games yet\n\nEsc: close";
+            let style = theme.overlay;
+            return render_prompt(
+                " Load Game ",
+                text,
+                style,
+                theme,
+                area,
+                frame,
+            );
+        }
+        SlotScreen::Choosing(selected) => *selected,
+    };
+
+    // A few characters per tile, enough to tell the positions apart.
+    let board = slots[selected].board.parse().unwrap_or(Board::default());
+    let rows = slots.len().clamp(board.rows(), MAX_SLOT_ROWS) as u16 + 1;
+    let area = frame.area().centered(
+        Constraint::Length(SLOTS_WIDTH),
+        Constraint::Length(rows + 2),
+    );
+    let block = Block::bordered()
+        .border_style(theme.overlay)
+        .title(" Load Game ")
+        .title_style(theme.title)
+        .title_bottom(Line::from(" Enter: load   Esc: close ").centered())
+        .padding(Padding::horizontal(1));
+    let [table_area, preview_area] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Length(board.cols() as u16 * PREVIEW_CELL_WIDTH),
+    ])
+    .spacing(2)
+    .areas(block.inner(area));
+
+    let rows = slots.iter().map(|slot| {
+        Row::new([
+            slot.name.clone(),
+            slot.score.to_string(),
+            slot.saved_at.format("%Y-%m-%d %H:%M").to_string(),
+        ])
+    });
+    let widths = [
+        Constraint::Fill(1),
+        Constraint::Length(7),
+        Constraint::Length(16),
+    ];
+    let table = Table::new(rows, widths)
+        .header(Row::new(["Name", "Score", "Saved"]).bold())
+        .row_highlight_style(Style::new().reversed());
+    let mut state = TableState::default().with_selected(selected);
+
+    let preview: Vec<Line> = (0..board.rows())
+        .map(|row| {
+            let cells: String = (0..board.cols())
+                .map(|col| match board.cell(row, col) {
+                    Some(tile) => format!("{tile:>5}"),
+                    None => format!("{:>5}", "·"),
+                })
+                .collect();
+            Line::from(cells)
+        })
+        .collect();
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_stateful_widget(table, table_area, &mut state);
+    frame.render_widget(
+        Paragraph::new(preview),
+        preview_area.offset(Offset::new(0, 1)),
+    );
+}
+
+// Render the pause menu, or the settings in use once they are picked from it.
+fn render_menu(
+    menu: PauseMenu,
+    settings: &[(&'static str, String)],
+    theme: &Theme,
+    area: Rect,
+    frame: &mut Frame,
+) {
+    let lines: Vec<Line> = match menu {
+        PauseMenu::Choosing(selected) => MenuItem::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let line = Line::from(item.label());
+                if i == selected { line.reversed() } else { line }
+            })
+            .collect(),
+        PauseMenu::Settings => settings
+            .iter()
+            .map(|(name, value)| Line::from(format!("{name}: {value}")))
+            .chain([
+                Line::from(""),
+                Line::from("Edit settings.json to change these").dim(),
+            ])
+            .collect(),
+    };
+
+    let area = area.centered(
+        Constraint::Length(area.width.saturating_sub(4)),
+        Constraint::Length(lines.len() as u16 + 2),
+    );
+    let menu = Paragraph::new(lines).centered().block(
+        Block::bordered()
+            .border_style(theme.overlay)
+            .title(" Paused ")
+            .title_style(theme.title),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(menu, area);
+}
+
+// Render the title screen: the logo in block digits over the menu, or the
+// settings in use, and any warnings about damaged files.
+fn render_title(
+    menu: &TitleMenu,
+    settings: &[(&'static str, String)],
+    warnings: &[String],
+    theme: &Theme,
+    frame: &mut Frame,
+) {
+    const TITLE_WIDTH: u16 = 40;
+    const LOGO_HEIGHT: usize = 5;
+    let area = frame.area();
+    let width = TITLE_WIDTH.min(area.width);
+    let logo = glyph::render("2048", width as usize - 2, LOGO_HEIGHT)
+        .unwrap_or_else(|| vec![TITLE.trim().to_string()]);
+
+    let mut lines: Vec<Line> = logo
+        .into_iter()
+        .map(|line| Line::from(line).style(theme.title))
+        .collect();
+    lines.push(Line::from(""));
+    if !warnings.is_empty() {
+        lines.extend(
+            warnings
+                .iter()
+                .map(|warning| Line::from(warning.as_str()).red()),
+        );
+        lines.push(Line::from(""));
+    }
+    if let Some(score) = menu.resume_prompt() {
+        lines
+            .push(Line::from(format!("Resume previous game? (score {score})")));
+        lines.push(Line::from(""));
+        lines.push(Line::from("y: resume   n: new game   Esc: menu").dim());
+    } else if menu.is_showing_settings() {
+        lines.extend(
+            settings
+                .iter()
+                .map(|(name, value)| Line::from(format!("{name}: {value}"))),
+        );
+        lines.push(Line::from(""));
+        lines.push(Line::from("Edit settings.json to change these").dim());
+    } else {
+        lines.extend(menu.items().iter().enumerate().map(|(i, item)| {
+            let line = Line::from(item.label());
+            if i == menu.selected() {
+                line.reversed()
+            } else {
+                line
+            }
+        }));
+        lines.push(Line::from(""));
+        lines.push(Line::from("↑/↓: choose   Enter: select").dim());
+    }
+
+    let area = area.centered(
+        Constraint::Length(width),
+        Constraint::Length((lines.len() as u16 + 2).min(area.height)),
+    );
+    frame.render_widget(
+        Paragraph::new(lines)
+            .centered()
+            .block(Block::bordered().border_style(theme.overlay)),
+        area,
+    );
+}
+
+// Render the latest frame of a replay being watched.
+pub fn render_replay(
+    replay: Option<&ReplayFrame>,
+    status: Option<String>,
+    labels: LabelMode,
+    theme: &Theme,
+    frame: &mut Frame,
+) {
+    let shape = replay.map_or(Shape::default(), |replay| replay.board.shape());
+    let (main_width, main_height) = calculate_game_dimensions(shape);
+    if frame.area().width < main_width || frame.area().height < main_height {
+        return render_too_small((main_width, main_height), frame);
+    }
+    let game_area = frame.area().centered(
+        Constraint::Length(main_width),
+        Constraint::Length(main_height),
+    );
+    let [tiles_area, scores_area] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(SCORE_HEIGHT),
+    ])
+    .areas(game_area);
+
+    let Some(replay) = replay else {
+        frame.render_widget(
+            Paragraph::new("Waiting for the game to start")
+                .centered()
+                .block(Block::bordered().title(" Watching ")),
+            tiles_area,
+        );
+        return;
+    };
+
+    let mut outcome = Game::from_board(replay.board, replay.score).outcome();
+    outcome.game_over = replay.game_over;
+    let titles = BoardTitles {
+        wrap: replay.board.wraps(),
+        ..BoardTitles::default()
+    };
+    render_board(&outcome, &titles, theme, tiles_area, frame);
+    render_tiles(&outcome.board, None, &[], labels, theme, tiles_area, frame);
+
+    let last_move = replay.action.map_or(' ', |action| action.arrow());
+    let spawn = replay
+        .spawn
+        .map_or(String::new(), |spawn| format!(" +{}", spawn.tile));
+    let text = format!(
+        "Move {} {last_move}{spawn}  Score: {} ",
+        replay.moves, replay.score
+    );
+    frame.render_widget(Paragraph::new(text).right_aligned(), scores_area);
+    if let Some(status) = status {
+        frame.render_widget(Paragraph::new(status).dim(), scores_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::history::Handoff;
+    use crate::leaderboard::RemoteScore;
+
+    fn view<'a>(
+        game: &'a Game,
+        outcome: &'a ActionOutcome,
+        history: &'a History,
+        keymap: &'a Keymap,
+        report: &'a Option<Result<PathBuf>>,
+    ) -> GameView<'a> {
+        GameView {
+            game,
+            outcome,
+            history,
+            keymap,
+            labels: LabelMode::default(),
+            theme: Theme::CLASSIC,
+            report_settings: &[],
+            profile: None,
+            title: None,
+            warnings: &[],
+            mode: "Playing",
+            elapsed: Duration::ZERO,
+            paused: false,
+            time_left: None,
+            hold_progress: None,
+            hint: None,
+            autoplay: false,
+            challenge: None,
+            flash: false,
+            animation: None,
+            points: None,
+            position: None,
+            finished_run: None,
+            report,
+            shared: None,
+            notation: None,
+            practice_over: false,
+            puzzle: None,
+            tutorial: None,
+            gallery: None,
+            review: None,
+            show_moves: false,
+            moves_scroll: 0,
+            show_merges: false,
+            show_stats: false,
+            show_debug: false,
+            show_heatmap: false,
+            analysis: None,
+            render_time: None,
+            search: None,
+            show_help: false,
+            show_leaderboard: false,
+            global_scores: None,
+            show_statistics: false,
+            confirming_restart: false,
+            confirming_quit: false,
+            celebrating: false,
+            pasted: None,
+            menu: None,
+            slot_screen: None,
+            targeting: None,
+            slots: &[],
+        }
+    }
+
+    fn screen(terminal: &Terminal<TestBackend>) -> String {
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn draws_a_view_on_any_backend() {
+        let game = Game::from_board("1200000000000000".parse().unwrap(), 36);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = view(&game, &outcome, &history, &keymap, &None);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("Score:"));
+        assert!(drawn.contains("36"));
+
+        let debug = GameView {
+            show_debug: true,
+            search: Some(SearchReport {
+                depth: 4,
+                nodes: 30_000,
+                elapsed: Duration::from_millis(100),
+            }),
+            ..view
+        };
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &debug)).unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("1 2 0 0"));
+        assert!(drawn.contains("Depth            4"));
+        assert!(drawn.contains("Nodes       300k/s"));
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 10)).unwrap();
+        terminal.draw(|frame| draw(frame, &debug)).unwrap();
+        assert!(screen(&terminal).contains("Terminal too small"));
+    }
+
+    #[test]
+    fn power_ups_show_beside_the_score_while_picking_tiles() {
+        let board = "1200000000000000".parse().unwrap();
+        let mut game = Game::from_board(board, 2000);
+        game.set_power_ups(true);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = GameView {
+            targeting: Some((PowerUp::Swap, vec![(0, 0)])),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("⇄ 1"));
+        assert!(drawn.contains("⇧ 0"));
+        assert!(drawn.contains("Swap: pick 2 tiles"));
+    }
+
+    #[test]
+    fn combo_meter_fills_with_merging_moves() {
+        let board = "1200000000000000".parse().unwrap();
+        let mut game = Game::from_board(board, 0);
+        game.set_scoring(Scoring::Combo);
+        game.set_combo(3);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = view(&game, &outcome, &history, &keymap, &None);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        assert!(screen(&terminal).contains("Combo x3 ▰▰▰▱▱▱▱▱"));
+    }
+
+    #[test]
+    fn title_names_a_harder_difficulty() {
+        let mut game = Game::with_seed(3);
+        game.set_difficulty(Difficulty::Hard);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = view(&game, &outcome, &history, &keymap, &None);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        assert!(screen(&terminal).contains(" 2048 · Hard "));
+    }
+
+    #[test]
+    fn title_screen_warns_of_damaged_files() {
+        let game = Game::with_seed(3);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let menu = TitleMenu::new(None);
+        let warnings = ["history.json was damaged and set aside".to_string()];
+        let view = GameView {
+            title: Some(&menu),
+            warnings: &warnings,
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        assert!(screen(&terminal).contains(&warnings[0]));
+    }
+
+    #[test]
+    fn title_names_the_profile() {
+        let game = Game::with_seed(3);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = GameView {
+            profile: Some("alice"),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        assert!(screen(&terminal).contains(" 2048 ━ alice "));
+    }
+
+    #[test]
+    fn tutorial_names_the_key_to_press_beside_the_board() {
+        let tutorial = Tutorial::default();
+        let game = tutorial.start();
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = GameView {
+            tutorial: Some(&tutorial),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        let screen = screen(&terminal);
+        let left = keymap.keys(Command::MoveLeft)[0];
+        assert!(screen.contains(" Tutorial 1/3 "));
+        assert!(screen.contains(&format!("Press {left} to")), "{screen}");
+    }
+
+    #[test]
+    fn analysis_compares_the_move_played_with_the_ai() {
+        let game = Game::with_seed(3);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let evaluations = [(5.0, true), (1.0, true), (3.0, false), (9.0, true)];
+        let view = GameView {
+            analysis: Some(Analysis {
+                evaluations: Some(std::array::from_fn(|i| Evaluation {
+                    action: GameAction::ALL[i],
+                    value: evaluations[i].1.then_some(evaluations[i].0),
+                })),
+                last: Some((GameAction::Left, GameAction::Up)),
+            }),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("↑ █████        5.0"));
+        assert!(drawn.contains("↓ █            1.0"));
+        assert!(drawn.contains("←                -"));
+        assert!(drawn.contains("→ ████████     9.0"));
+        assert!(drawn.contains("Played ← · AI ↑"));
+    }
+
+    #[test]
+    fn border_names_the_challenge() {
+        let game = Game::with_seed(3);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = GameView {
+            challenge: Some(Challenge::Mirror),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        assert!(screen(&terminal).contains("Mirrored controls"));
+    }
+
+    #[test]
+    fn solved_puzzle_offers_the_next_one() {
+        let puzzle = &Puzzle::starter_pack()[0];
+        let mut game = puzzle.start();
+        for letter in "DLRU".chars() {
+            let action = GameAction::from_letter(letter).unwrap();
+            game.apply_move(action).unwrap();
+        }
+        game.end();
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let view = GameView {
+            practice_over: true,
+            puzzle: Some((puzzle, false)),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("First Steps: Reach 16 · solved"));
+        assert!(drawn.contains("First Steps solved in 4 of 5 moves"));
+        assert!(drawn.contains("enter: next puzzle"));
+    }
+
+    #[test]
+    fn game_over_tells_how_far_the_ai_took_the_game() {
+        let game = Game::with_seed(1);
+        let outcome = game.outcome();
+        let keymap = Keymap::default();
+        let mut history = History::default();
+        let handoff = Handoff {
+            score: 1200,
+            max_tile: 128,
+            moves: 150,
+        };
+        let run = GameRecord::new(3400, 256, Duration::from_secs(60))
+            .with_handoff(Some(handoff));
+        let index = history.record(run);
+        let view = GameView {
+            finished_run: Some(index),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal
+            .draw(|frame| render_game_over(&view, frame.area(), frame))
+            .unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("AI took it from 1200 to 3400 (+2200)"));
+    }
+
+    #[test]
+    fn review_shows_the_move_played_and_the_ai_choice() {
+        let game = Game::with_seed(1);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let review = Review {
+            index: 11,
+            count: 40,
+            board: "1200000000000000".parse().unwrap(),
+            score: 52,
+            played: Some(GameAction::Left),
+            best: Some(GameAction::Up),
+        };
+        let view = GameView {
+            review: Some(review),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &view)).unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("Review 12/40"));
+        assert!(drawn.contains("Played ← · AI ↑"));
+        assert!(drawn.contains("Score: 52"));
+    }
+
+    #[test]
+    fn leaderboard_lists_the_global_scores() {
+        let game = Game::with_seed(1);
+        let outcome = game.outcome();
+        let (history, keymap) = (History::default(), Keymap::default());
+        let scores = GlobalScores::Loaded(vec![RemoteScore {
+            name: "bob".to_string(),
+            score: 91234,
+            max_tile: 4096,
+        }]);
+        let view = GameView {
+            show_leaderboard: true,
+            global_scores: Some(&scores),
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 30)).unwrap();
+        terminal
+            .draw(|frame| render_leaderboard(&view, frame))
+            .unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("No finished games yet"));
+        assert!(drawn.contains("Global"));
+        assert!(drawn.contains("91234"));
+        assert!(drawn.contains("bob"));
+    }
+
+    #[test]
+    fn statistics_chart_the_best_tiles() {
+        let game = Game::with_seed(1);
+        let outcome = game.outcome();
+        let keymap = Keymap::default();
+        let mut history = History::default();
+        for (score, tile) in [(2000, 256), (5000, 512), (2500, 256)] {
+            let run = GameRecord::new(score, tile, Duration::from_secs(60));
+            history.record(run);
+        }
+        let view = GameView {
+            show_statistics: true,
+            ..view(&game, &outcome, &history, &keymap, &None)
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(90, 30)).unwrap();
+        terminal
+            .draw(|frame| render_statistics(&view, frame))
+            .unwrap();
+        let drawn = screen(&terminal);
+        assert!(drawn.contains("Games 3   Won 0 (0.0%)"));
+        assert!(drawn.contains("512"));
+        assert!(drawn.contains("5k"));
+    }
+}
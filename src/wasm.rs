@@ -0,0 +1,103 @@
+use wasm_bindgen::prelude::*;
+
+use crate::game::{Game, GameAction};
+
+// A game driven from JavaScript, with the same rules and tile spawns as the
+// terminal game.
+#[wasm_bindgen(js_name = Game)]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen(js_class = Game)]
+impl WasmGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self { game: Game::new() }
+    }
+
+    // Restores a game serialized with `toJson`.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<WasmGame, JsError> {
+        Ok(Self {
+            game: serde_json::from_str(json)?,
+        })
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.game)?)
+    }
+
+    // Slides the board "up", "down", "left" or "right" and returns whether
+    // the board changed.
+    #[wasm_bindgen(js_name = applyMove)]
+    pub fn apply_move(&mut self, direction: &str) -> Result<bool, JsError> {
+        let direction: GameAction = serde_json::from_value(direction.into())
+            .map_err(|_| {
+                JsError::new(&format!("Unknown direction '{direction}'"))
+            })?;
+        let outcome = self
+            .game
+            .apply_move(direction)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(outcome.changed)
+    }
+
+    pub fn restart(&mut self) {
+        self.game.restart();
+    }
+
+    // Returns the tiles in row-major order, with 0 for an empty cell.
+    pub fn cells(&self) -> Vec<u32> {
+        self.game
+            .board()
+            .iter_cells()
+            .map(|(_, cell)| cell.unwrap_or_default())
+            .collect()
+    }
+
+    // The board's size, which `cells` fills row by row.
+    pub fn rows(&self) -> usize {
+        self.game.board().rows()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.game.board().cols()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game.score()
+    }
+
+    #[wasm_bindgen(js_name = isGameOver)]
+    pub fn is_game_over(&self) -> bool {
+        self.game.is_game_over()
+    }
+}
+
+impl Default for WasmGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_round_trips_through_json() {
+        let mut game = WasmGame::new();
+        let moved = ["up", "down", "left", "right"]
+            .into_iter()
+            .any(|direction| game.apply_move(direction).unwrap());
+        assert!(moved);
+
+        let restored = WasmGame::from_json(&game.to_json().unwrap()).unwrap();
+
+        assert_eq!(restored.cells(), game.cells());
+        assert_eq!(restored.score(), game.score());
+        assert_eq!(restored.game.moves(), game.game.moves());
+    }
+}
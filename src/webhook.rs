@@ -0,0 +1,75 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::task::{JoinHandle, spawn_blocking};
+
+use crate::history::GameRecord;
+
+// The summary posted for a finished game: the run as the history keeps it,
+// and the seed it was played from.
+#[derive(Serialize)]
+struct Notification<'a> {
+    #[serde(flatten)]
+    run: &'a GameRecord,
+    seed: u64,
+}
+
+// Posts a JSON summary of a finished game to the configured URL.
+fn post(url: &str, run: &GameRecord, seed: u64) -> Result<()> {
+    let body = serde_json::to_string(&Notification { run, seed })?;
+    ureq::post(url)
+        .header("Content-Type", "application/json")
+        .send(body)
+        .with_context(|| format!("Failed to notify {url}"))?;
+    Ok(())
+}
+
+// Sends the notification in the background so a slow endpoint never stalls
+// the game. The handle tells whether it arrived.
+pub fn notify(
+    url: String,
+    run: GameRecord,
+    seed: u64,
+) -> JoinHandle<Result<()>> {
+    spawn_blocking(move || post(&url, &run, seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn post_sends_run_as_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/done", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            // Read until the whole JSON body has arrived.
+            while !request.ends_with(b"}") {
+                let len = stream.read(&mut buf).unwrap();
+                assert!(len > 0, "connection closed early");
+                request.extend_from_slice(&buf[..len]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let run = GameRecord::new(2048, 256, Duration::from_secs(90));
+        post(&url, &run, 7).unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("POST /done"));
+        assert!(request.contains("application/json"));
+        assert!(request.contains(r#""score":2048"#));
+        assert!(request.contains(r#""duration_secs":90"#));
+        assert!(request.contains(r#""seed":7"#));
+    }
+}
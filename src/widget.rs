@@ -0,0 +1,352 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Margin, Rect};
+use ratatui::style::Style;
+use ratatui::symbols::border;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, BorderType, Paragraph, Widget};
+
+use crate::game::CellResult;
+
+/// How a [`BoardWidget`] draws the tiles: their colors, borders and labels.
+pub trait Palette {
+    /// Returns the style filling a tile holding `value`.
+    fn tile(&self, value: u32) -> Style;
+
+    /// Returns the style filling a wall, which holds no tile.
+    fn wall(&self) -> Style {
+        Style::new().dim()
+    }
+
+    /// Returns the style of the border of a cell, which stands out if the
+    /// last move merged into it.
+    fn cell(&self, merged: bool) -> Style {
+        if merged {
+            Style::new().bold()
+        } else {
+            Style::new().dim()
+        }
+    }
+
+    /// Returns the style of the border drawn around a selected cell.
+    fn selection(&self) -> Style {
+        Style::new().bold().reversed()
+    }
+
+    /// Returns the symbols of the border of a tile holding `value`.
+    fn tile_border(&self, _value: u32) -> border::Set<'static> {
+        border::PLAIN
+    }
+
+    /// Returns the label of a tile holding `value`, drawn on its middle line.
+    fn label(&self, value: u32) -> String {
+        value.to_string()
+    }
+
+    /// Returns lines filling the inside of a tile of the given size in place
+    /// of its label, if the palette draws large labels and they fit.
+    fn large_label(
+        &self,
+        _value: u32,
+        _width: usize,
+        _height: usize,
+    ) -> Option<Vec<String>> {
+        None
+    }
+}
+
+/// Draws tiles in the colors of the terminal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Plain;
+
+impl Palette for Plain {
+    fn tile(&self, _value: u32) -> Style {
+        Style::new()
+    }
+}
+
+/// A ratatui widget drawing the cells of a board after a move, one bordered
+/// tile per cell filling the area it is given. Other terminal applications
+/// can embed a game with it:
+///
+/// ```
+/// use ratatui::buffer::Buffer;
+/// use ratatui::layout::Rect;
+/// use ratatui::widgets::Widget;
+/// use rust_2048::Game;
+/// use rust_2048::widget::BoardWidget;
+///
+/// let game = Game::with_seed(2048);
+/// let outcome = game.outcome();
+/// let area = Rect::new(0, 0, 48, 20);
+/// let mut buffer = Buffer::empty(area);
+/// BoardWidget::new(&outcome.board).render(area, &mut buffer);
+/// ```
+pub struct BoardWidget<'a> {
+    board: &'a [Vec<CellResult>],
+    palette: &'a dyn Palette,
+    slide: Option<f64>,
+    selected: &'a [(usize, usize)],
+}
+
+impl<'a> BoardWidget<'a> {
+    /// Creates a widget drawing the board of an [`ActionOutcome`] in the
+    /// [`Plain`] palette.
+    ///
+    /// [`ActionOutcome`]: crate::game::ActionOutcome
+    pub fn new(board: &'a [Vec<CellResult>]) -> Self {
+        Self {
+            board,
+            palette: &Plain,
+            slide: None,
+            selected: &[],
+        }
+    }
+
+    /// Draws the tiles with the given palette.
+    pub fn palette(mut self, palette: &'a dyn Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Draws the tiles of the move `progress` of the way, from 0 to 1, from
+    /// where they were to where they end up. Merging tiles slide into each
+    /// other with their old value, and the spawned tile only appears once the
+    /// slide is over.
+    pub fn slide(mut self, progress: f64) -> Self {
+        self.slide = Some(progress);
+        self
+    }
+
+    /// Draws a thick border around the given cells, by row and column, e.g.
+    /// to show the player which tiles they are picking.
+    pub fn select(mut self, cells: &'a [(usize, usize)]) -> Self {
+        self.selected = cells;
+        self
+    }
+
+    fn render_tile(&self, result: &CellResult, rect: Rect, buf: &mut Buffer) {
+        // Voids are not part of the board, so they are left blank
+        if result.void {
+            return;
+        }
+
+        // Walls are filled with a hatch in their own colors
+        if result.wall {
+            let hatch = vec![
+                Line::from("▚".repeat(rect.width as usize));
+                rect.height as usize
+            ];
+            Paragraph::new(hatch)
+                .style(self.palette.wall())
+                .render(rect, buf);
+            return;
+        }
+
+        // Fill the tile with its value's colors and render the cell border
+        // with the appropriate style
+        let mut block =
+            Block::bordered().border_style(self.palette.cell(result.merged));
+        if let Some(value) = result.value {
+            block = block
+                .style(self.palette.tile(value))
+                .border_set(self.palette.tile_border(value));
+        }
+        block.render(rect, buf);
+
+        // Render the cell value centered within the cell rectangle, in large
+        // lines filling the inside of the border if the palette draws them
+        let Some(value) = result.value else {
+            return;
+        };
+        let inside = rect.inner(Margin::new(1, 1));
+        if let Some(lines) = self.palette.large_label(
+            value,
+            inside.width as usize,
+            inside.height as usize,
+        ) {
+            let lines: Vec<Line> = lines.into_iter().map(Line::from).collect();
+            let height = lines.len() as u16;
+            Paragraph::new(lines).centered().render(
+                inside.centered_vertically(Constraint::Length(height)),
+                buf,
+            );
+            return;
+        }
+        Paragraph::new(self.palette.label(value))
+            .centered()
+            .render(rect.centered_vertically(Constraint::Length(1)), buf);
+    }
+
+    fn render_slide(
+        &self,
+        progress: f64,
+        rects: &[Vec<Rect>],
+        buf: &mut Buffer,
+    ) {
+        for (row, rects) in rects.iter().enumerate() {
+            for (col, rect) in rects.iter().enumerate() {
+                let cell = CellResult {
+                    wall: self.board[row][col].wall,
+                    void: self.board[row][col].void,
+                    ..Default::default()
+                };
+                self.render_tile(&cell, *rect, buf);
+            }
+        }
+
+        for (row, cells) in self.board.iter().enumerate() {
+            for (col, result) in cells.iter().enumerate() {
+                let Some(value) = result.value else {
+                    continue;
+                };
+                let value = if result.merged { value / 2 } else { value };
+                let tile = CellResult {
+                    value: Some(value),
+                    ..Default::default()
+                };
+                for &(from_row, from_col) in result.from.iter().flatten() {
+                    let rect = lerp_rect(
+                        rects[from_row][from_col],
+                        rects[row][col],
+                        progress,
+                    );
+                    self.render_tile(&tile, rect, buf);
+                }
+            }
+        }
+    }
+}
+
+impl Widget for BoardWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let cols = self.board.first().map_or(0, Vec::len);
+        let rects = tile_rects(area, self.board.len(), cols);
+        match self.slide {
+            Some(progress) if progress < 1.0 => {
+                self.render_slide(progress, &rects, buf);
+            }
+            _ => {
+                for (row, rects) in rects.iter().enumerate() {
+                    for (col, rect) in rects.iter().enumerate() {
+                        self.render_tile(&self.board[row][col], *rect, buf);
+                    }
+                }
+            }
+        }
+        for &(row, col) in self.selected {
+            if let Some(&rect) = rects.get(row).and_then(|rects| rects.get(col))
+            {
+                Block::bordered()
+                    .border_type(BorderType::Thick)
+                    .border_style(self.palette.selection())
+                    .render(rect, buf);
+            }
+        }
+    }
+}
+
+// Splits an area into the rectangle of each cell of a board, with a column of
+// space between neighboring cells.
+fn tile_rects(area: Rect, rows: usize, cols: usize) -> Vec<Vec<Rect>> {
+    // Split the area into rows
+    let rows_rects =
+        Layout::vertical(vec![Constraint::Fill(1); rows]).split(area);
+
+    // Each row is split into columns, with spacing between them
+    let cols_layout = Layout::horizontal((0..cols).flat_map(|i| {
+        if i < cols - 1 {
+            [Constraint::Fill(1), Constraint::Length(1)].iter()
+        } else {
+            [Constraint::Fill(1)].iter()
+        }
+    }));
+
+    rows_rects
+        .iter()
+        .map(|&row_rect| {
+            // Keep the tile rectangles and skip the spacing
+            cols_layout
+                .split(row_rect)
+                .iter()
+                .step_by(2)
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+// Returns the rectangle `progress` of the way from one rectangle to another.
+fn lerp_rect(from: Rect, to: Rect, progress: f64) -> Rect {
+    let lerp = |from: u16, to: u16| {
+        (f64::from(from) + (f64::from(to) - f64::from(from)) * progress).round()
+            as u16
+    };
+    Rect {
+        x: lerp(from.x, to.x),
+        y: lerp(from.y, to.y),
+        width: lerp(from.width, to.width),
+        height: lerp(from.height, to.height),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn draws_each_tile_with_its_label() {
+        let game = Game::from_board("1200000000000003".parse().unwrap(), 0);
+        let outcome = game.outcome();
+        let area = Rect::new(0, 0, 47, 20);
+        let mut buf = Buffer::empty(area);
+        BoardWidget::new(&outcome.board).render(area, &mut buf);
+
+        let rects = tile_rects(area, 4, 4);
+        let label = |rect: Rect| {
+            let y = rect.y + rect.height / 2;
+            let line: String = (rect.x..rect.right())
+                .map(|x| buf[(x, y)].symbol())
+                .collect();
+            line.trim_matches(['│', ' ']).to_string()
+        };
+        assert_eq!(label(rects[0][0]), "2");
+        assert_eq!(label(rects[0][1]), "4");
+        assert_eq!(label(rects[3][3]), "8");
+        assert_eq!(label(rects[1][1]), "");
+    }
+
+    #[test]
+    fn outlines_the_selected_cells() {
+        let game = Game::from_board("1200000000000003".parse().unwrap(), 0);
+        let outcome = game.outcome();
+        let area = Rect::new(0, 0, 47, 20);
+        let mut buf = Buffer::empty(area);
+        BoardWidget::new(&outcome.board)
+            .select(&[(0, 1)])
+            .render(area, &mut buf);
+
+        let rects = tile_rects(area, 4, 4);
+        let corner = |rect: Rect| buf[(rect.x, rect.y)].symbol().to_string();
+        assert_eq!(corner(rects[0][1]), "┏");
+        assert_eq!(corner(rects[0][0]), "┌");
+    }
+
+    #[test]
+    fn leaves_voids_blank() {
+        let game = Game::from_board("~200000000000003".parse().unwrap(), 0);
+        let outcome = game.outcome();
+        let area = Rect::new(0, 0, 47, 20);
+        let mut buf = Buffer::empty(area);
+        BoardWidget::new(&outcome.board).render(area, &mut buf);
+
+        let rects = tile_rects(area, 4, 4);
+        let blank = |rect: Rect| {
+            (rect.x..rect.right()).all(|x| {
+                (rect.y..rect.bottom()).all(|y| buf[(x, y)].symbol() == " ")
+            })
+        };
+        assert!(blank(rects[0][0]));
+        assert!(!blank(rects[1][1]));
+    }
+}